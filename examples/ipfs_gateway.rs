@@ -0,0 +1,42 @@
+//! Reads `ipfs://` feeds through a public HTTP gateway, with a custom scheme resolver.
+//!
+//! ```sh
+//! cargo run --example ipfs_gateway -- ipfs://<cid>/social.org
+//! ```
+
+use org_social_lib_rs::network::{fetch_feed, FetchOptions, HttpResolver, NetworkConfig, ResolveFuture, SchemeResolver, Validators};
+use std::sync::Arc;
+
+/// Resolves `ipfs://<cid>/<path>` URLs as `<gateway>/ipfs/<cid>/<path>`.
+#[derive(Debug)]
+struct GatewayResolver {
+    gateway: String,
+}
+
+impl SchemeResolver for GatewayResolver {
+    fn resolve<'a>(&'a self, url: &'a str, validators: Option<&'a Validators>, config: &'a NetworkConfig) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let path = url.strip_prefix("ipfs://").unwrap_or(url);
+            let gateway_url = format!("{}/ipfs/{path}", self.gateway.trim_end_matches('/'));
+            let mut resolved = HttpResolver.resolve(&gateway_url, validators, config).await?;
+            // Content on IPFS is addressed by its hash, so the posts keep the ipfs:// URL as their source
+            resolved.final_url = url.to_string();
+            Ok(resolved)
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let url = std::env::args().nth(1).ok_or("usage: ipfs_gateway ipfs://<cid>/social.org")?;
+
+    let mut network = NetworkConfig::default();
+    network.register_scheme("ipfs", Arc::new(GatewayResolver { gateway: "https://ipfs.io".to_string() }));
+    let feed = fetch_feed(&url, &FetchOptions { network, ..Default::default() }).await?;
+
+    println!("{} ({} posts)", feed.profile.title(), feed.posts.len());
+    for post in feed.posts.iter().take(5) {
+        println!("- {}: {}", post.id(), post.content().lines().next().unwrap_or_default());
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! Bookmarks module for starring posts and listing them later.
+//!
+//! Bookmarks are kept separately from the feed, so they survive refreshes,
+//! and are stored in a simple line-based file: one `<added-at> <full_id>` per line.
+
+use crate::util;
+use chrono::{DateTime, FixedOffset, Local};
+use std::io;
+use std::path::Path;
+
+/// A bookmarked post, by its full ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub full_id: String,
+    pub added_at: DateTime<FixedOffset>,
+}
+
+/// A set of bookmarked posts, see `Feed::bookmarked_posts`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bookmarks {
+    entries: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load bookmarks saved with [`Bookmarks::save`]. A missing file gives no bookmarks.
+    ///
+    /// # Errors
+    ///
+    /// Errors from reading the file, or `InvalidData` for a line that isn't a timestamp followed by a full ID.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(error) => return Err(error),
+        };
+
+        let mut bookmarks = Self::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Invalid bookmark on line {}: {line}", number + 1));
+            let (added_at, full_id) = line.split_once(' ').ok_or_else(invalid)?;
+            let added_at = util::parse_timestamp(added_at).map_err(|_| invalid())?;
+            bookmarks.add_at(full_id.trim(), added_at);
+        }
+        Ok(bookmarks)
+    }
+
+    /// Save the bookmarks, one `<added-at> <full_id>` line each, oldest first.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let content: String = self
+            .entries
+            .iter()
+            .map(|bookmark| format!("{} {}\n", bookmark.added_at.to_rfc3339(), bookmark.full_id))
+            .collect();
+        std::fs::write(path, content)
+    }
+
+    /// Bookmark a post, or remove its bookmark if it has one.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the post is now bookmarked.
+    pub fn toggle(&mut self, full_id: &str) -> bool {
+        if self.remove(full_id) {
+            false
+        } else {
+            self.add_at(full_id, Local::now().fixed_offset())
+        }
+    }
+
+    /// Bookmark a post at the given time.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the bookmark was added, `false` if the post was already bookmarked.
+    pub fn add_at(&mut self, full_id: &str, added_at: DateTime<FixedOffset>) -> bool {
+        if full_id.is_empty() || self.contains(full_id) {
+            return false;
+        }
+        let index = self.entries.partition_point(|bookmark| bookmark.added_at <= added_at);
+        self.entries.insert(index, Bookmark { full_id: full_id.to_string(), added_at });
+        true
+    }
+
+    /// Remove the bookmark of a post.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a bookmark was removed.
+    pub fn remove(&mut self, full_id: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|bookmark| bookmark.full_id != full_id);
+        self.entries.len() != before
+    }
+
+    pub fn contains(&self, full_id: &str) -> bool {
+        self.entries.iter().any(|bookmark| bookmark.full_id == full_id)
+    }
+
+    /// Get the bookmark of a post.
+    pub fn get(&self, full_id: &str) -> Option<&Bookmark> {
+        self.entries.iter().find(|bookmark| bookmark.full_id == full_id)
+    }
+
+    /// Iterate over the bookmarks, most recently added first.
+    pub fn iter(&self) -> impl Iterator<Item = &Bookmark> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(timestamp: &str) -> DateTime<FixedOffset> {
+        util::parse_timestamp(timestamp).unwrap()
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut bookmarks = Bookmarks::new();
+        assert!(bookmarks.toggle("https://alice.example/social.org#2025-01-01T12:00:00+0000"));
+        assert!(bookmarks.contains("https://alice.example/social.org#2025-01-01T12:00:00+0000"));
+
+        assert!(!bookmarks.toggle("https://alice.example/social.org#2025-01-01T12:00:00+0000"));
+        assert!(bookmarks.is_empty());
+    }
+
+    #[test]
+    fn test_iter_newest_first() {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.add_at("b", time("2025-02-01T12:00:00+0000"));
+        bookmarks.add_at("c", time("2025-03-01T12:00:00+0100"));
+        bookmarks.add_at("a", time("2025-01-01T12:00:00+0000"));
+        assert!(!bookmarks.add_at("a", time("2025-04-01T12:00:00+0000")));
+
+        let ids: Vec<&str> = bookmarks.iter().map(|bookmark| bookmark.full_id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("org-social-bookmarks-test-{}", std::process::id()));
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.add_at("https://alice.example/social.org#2025-01-01T12:00:00+0000", time("2025-02-01T12:00:00+0100"));
+        bookmarks.add_at("https://bob.example/social.org#2025-01-02T12:00:00+0000", time("2025-02-02T12:00:00+0000"));
+
+        bookmarks.save(&path).unwrap();
+        let loaded = Bookmarks::load(&path).unwrap();
+        assert_eq!(loaded, bookmarks);
+        assert_eq!(loaded.get("https://alice.example/social.org#2025-01-01T12:00:00+0000").unwrap().added_at, time("2025-02-01T12:00:00+0100"));
+
+        std::fs::write(&path, "2025-02-01T12:00:00+0000\n").unwrap();
+        assert_eq!(Bookmarks::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(Bookmarks::load(&path).unwrap().is_empty());
+    }
+}
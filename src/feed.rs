@@ -4,165 +4,4607 @@
 //! feeds of org-social posts from multiple sources.
 //! The feed represantation is by default sorted chronologically with newest posts first.
 
-use crate::profile::Profile;
-use crate::post::Post;
-use crate::network;
-use chrono::{DateTime, FixedOffset};
+use crate::profile::{self, Profile};
+use crate::post::{self, Post};
+use crate::util;
+use crate::network::{
+    self, AvatarData, DiskCache, FeedCache, FeedFetchResult, FeedResult, FetchOptions, FetchWarning, Freshness, NetworkConfig, NetworkError, NetworkHandle,
+    RetryPolicy,
+};
+use crate::poll::Poll;
+use crate::notifications::MentionIndex;
+use crate::bookmarks::{Bookmark, Bookmarks};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default maximum number of `reply_to` links followed when resolving a thread root.
+pub const DEFAULT_MAX_THREAD_DEPTH: usize = 100;
+
+/// Timeout for each followed feed when creating or refreshing a feed.
+const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Number of tags kept in `AuthorStats::top_tags`.
+const TOP_TAGS_COUNT: usize = 5;
+
+/// Posting statistics of an author, see `Feed::author_stats`.
+///
+/// Posts with unparseable timestamps are counted in `post_count` and `top_tags`,
+/// but not in the time-based fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorStats {
+    pub nick: String,
+    pub source: Option<String>,
+    pub post_count: usize,
+    pub first_post: Option<DateTime<FixedOffset>>,
+    pub last_post: Option<DateTime<FixedOffset>>,
+    /// Average over the time between the first and last post, counting at least one week.
+    pub posts_per_week: Option<f64>,
+    /// Most used tags with their counts, most used first.
+    pub top_tags: Vec<(String, usize)>,
+}
+
+/// Accumulates the statistics of one author while going over the posts.
+struct AuthorStatsBuilder {
+    nick: String,
+    source: Option<String>,
+    post_count: usize,
+    timed_post_count: usize,
+    first_post: Option<DateTime<FixedOffset>>,
+    last_post: Option<DateTime<FixedOffset>>,
+    tag_counts: HashMap<String, usize>,
+}
+
+impl AuthorStatsBuilder {
+    fn new(nick: String, source: Option<String>) -> Self {
+        Self {
+            nick,
+            source,
+            post_count: 0,
+            timed_post_count: 0,
+            first_post: None,
+            last_post: None,
+            tag_counts: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, post: &Post) {
+        self.post_count += 1;
+        if let Some(time) = post.time() {
+            self.timed_post_count += 1;
+            self.first_post = Some(self.first_post.map_or(time, |first| first.min(time)));
+            self.last_post = Some(self.last_post.map_or(time, |last| last.max(time)));
+        }
+        for tag in post.all_tags() {
+            *self.tag_counts.entry(normalize_tag(&tag)).or_default() += 1;
+        }
+    }
+
+    fn build(self) -> AuthorStats {
+        let posts_per_week = match (self.first_post, self.last_post) {
+            (Some(first), Some(last)) => {
+                let weeks = ((last - first).num_seconds() as f64 / (7.0 * 24.0 * 3600.0)).max(1.0);
+                Some(self.timed_post_count as f64 / weeks)
+            }
+            _ => None,
+        };
+
+        let mut top_tags = sort_by_count(self.tag_counts);
+        top_tags.truncate(TOP_TAGS_COUNT);
+
+        AuthorStats {
+            nick: self.nick,
+            source: self.source,
+            post_count: self.post_count,
+            first_post: self.first_post,
+            last_post: self.last_post,
+            posts_per_week,
+            top_tags,
+        }
+    }
+}
+
+/// Which nick is used as the author of posts from followed feeds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttributionPolicy {
+    /// The nick from the user's `#+FOLLOW` entry, falling back to the feed's own nick.
+    ///
+    /// Only a nick the user wrote counts, a follow with just a URL uses the feed's own nick.
+    #[default]
+    LocalNickFirst,
+    /// The feed's own `#+NICK`, ignoring the nick the user gave it.
+    RemoteNickFirst,
+}
+
+/// Nick to attribute posts from a followed feed with.
+fn attributed_nick(user_profile: &Profile, profile: &Profile, policy: AttributionPolicy) -> String {
+    let local_nick = match policy {
+        AttributionPolicy::LocalNickFirst => profile.source().and_then(|source| user_profile.nick_for_url(source)),
+        AttributionPolicy::RemoteNickFirst => None,
+    };
+    local_nick.unwrap_or(profile.display_name()).to_string()
+}
+
+/// Whether posts with an unparseable timestamp are kept when cutting a feed at a time, see [`Feed::retain_since`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UntimedPostPolicy {
+    /// Keep them, so user data isn't dropped just because its ID isn't a timestamp.
+    #[default]
+    Keep,
+    Drop,
+}
+
+/// Whether a post is at or after `cutoff`.
+fn is_since(post: &Post, cutoff: DateTime<FixedOffset>, untimed_posts: UntimedPostPolicy) -> bool {
+    match post.time() {
+        Some(time) => time >= cutoff,
+        None => untimed_posts == UntimedPostPolicy::Keep,
+    }
+}
+
+/// Options for [`Feed::refresh`].
+#[derive(Clone, Default)]
+pub struct RefreshOptions {
+    /// Remove posts that are no longer in their source. Sources that failed to fetch are left untouched.
+    pub remove_missing: bool,
+    /// Feed URLs or author nicks to leave out, in addition to the profile's mutes.
+    pub extra_mutes: Vec<String>,
+    /// Cache used to make conditional requests. Sources that didn't change since the last refresh
+    /// are skipped, so the cache should only be shared by refreshes of this feed.
+    pub cache: Option<Arc<dyn FeedCache>>,
+    /// Only keep posts from this time on. Older posts are neither added nor kept in the feed.
+    pub since: Option<DateTime<FixedOffset>>,
+    /// What to do with posts without a parseable timestamp when `since` is set.
+    pub untimed_posts: UntimedPostPolicy,
+    /// How failed requests are retried. If None, each feed gets a single request.
+    pub retry: Option<RetryPolicy>,
+    /// How requests are made, e.g. with a preconfigured client. If None, the feed's [`Feed::network`] is used,
+    /// so refreshes reuse its client and connections.
+    pub network: Option<NetworkConfig>,
+    /// Bounds of the time between checks of each source, see [`Feed::refresh_stale`].
+    pub schedule: RefreshSchedule,
+}
+
+impl std::fmt::Debug for RefreshOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefreshOptions")
+            .field("remove_missing", &self.remove_missing)
+            .field("extra_mutes", &self.extra_mutes)
+            .field("cache", &self.cache.as_ref().map(|_| "FeedCache"))
+            .field("since", &self.since)
+            .field("untimed_posts", &self.untimed_posts)
+            .field("retry", &self.retry)
+            .field("network", &self.network)
+            .field("schedule", &self.schedule)
+            .finish()
+    }
+}
+
+/// Bounds of the adaptive time between checks of a source, see [`Feed::refresh_stale`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshSchedule {
+    /// Shortest time between two checks of a source, 5 minutes by default.
+    /// Sources that failed to fetch are checked again after this time.
+    pub min_interval: Duration,
+    /// Longest time between two checks of a source however rarely it posts, a day by default.
+    pub max_interval: Duration,
+}
+
+impl Default for RefreshSchedule {
+    fn default() -> Self {
+        Self { min_interval: Duration::from_secs(5 * 60), max_interval: Duration::from_secs(24 * 60 * 60) }
+    }
+}
+
+impl RefreshSchedule {
+    /// Time until the next check of a source posting every `cadence` on average, and active `quiet` ago:
+    /// about as often as it posts, less often the longer it's been quiet.
+    fn interval(&self, cadence: Option<Duration>, quiet: Option<Duration>) -> Duration {
+        let expected = match (cadence, quiet) {
+            (Some(cadence), Some(quiet)) => cadence.max(quiet / 2),
+            (Some(cadence), None) => cadence,
+            (None, Some(quiet)) => quiet / 2,
+            (None, None) => self.max_interval,
+        };
+        expected.clamp(self.min_interval, self.max_interval.max(self.min_interval))
+    }
+}
+
+/// Number of recent posts the posting cadence of a source is computed from.
+const CADENCE_POSTS: usize = 10;
+
+/// Average time between the most recent of `times`, None if there are fewer than two.
+fn posting_cadence(mut times: Vec<DateTime<FixedOffset>>) -> Option<Duration> {
+    times.sort_unstable_by(|a, b| b.cmp(a));
+    times.truncate(CADENCE_POSTS);
+    let span = (*times.first()? - *times.last()?).to_std().ok()?;
+    (times.len() > 1).then(|| span / (times.len() as u32 - 1))
+}
+
+/// Changes to the posts of one source during a refresh.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceRefresh {
+    /// The fetched feed URL, or the user's own source (empty if the user's profile has none).
+    pub source: String,
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+/// Result of [`Feed::refresh`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// Changes per refreshed source, the user's own posts first.
+    pub sources: Vec<SourceRefresh>,
+}
+
+impl RefreshReport {
+    /// Total number of added posts.
+    pub fn added(&self) -> usize {
+        self.sources.iter().map(|source| source.added).sum()
+    }
+
+    /// Total number of posts that changed.
+    pub fn updated(&self) -> usize {
+        self.sources.iter().map(|source| source.updated).sum()
+    }
+
+    /// Total number of removed posts.
+    pub fn removed(&self) -> usize {
+        self.sources.iter().map(|source| source.removed).sum()
+    }
+
+    /// Whether the refresh changed any post.
+    pub fn is_empty(&self) -> bool {
+        self.added() == 0 && self.updated() == 0 && self.removed() == 0
+    }
+}
+
+/// Keep only the last fetched copy of each profile, see [`Profile::identity_key`].
+fn latest_per_identity(followed_feeds: Vec<(Profile, Vec<Post>, String)>) -> Vec<(Profile, Vec<Post>, String)> {
+    let mut seen = HashSet::new();
+    let mut latest: Vec<(Profile, Vec<Post>, String)> = followed_feeds
+        .into_iter()
+        .rev()
+        .filter(|(profile, _, _)| seen.insert(profile.identity_key()))
+        .collect();
+    latest.reverse();
+    latest
+}
+
+/// Whether posts mirrored in several followed feeds are collapsed into one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupPolicy {
+    /// Posts with the same ID and content from different sources are kept once. The user's own
+    /// copy is kept first, then the copy from the author's canonical source (the URL the user
+    /// follows under the author's own nick), then the one with the smallest source URL, so the
+    /// result doesn't depend on follow order.
+    #[default]
+    Collapse,
+    /// Keep every copy.
+    KeepAll,
+}
+
+/// Order of the posts in a feed, see [`Feed::sort`].
+///
+/// Posts without a valid timestamp sort last, and posts that would otherwise be equal
+/// are ordered by their full ID, so sorting is deterministic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedOrder {
+    #[default]
+    NewestFirst,
+    /// Oldest first, e.g. to catch up on what happened since the last visit.
+    OldestFirst,
+    /// Grouped by author (case-insensitively, posts without an author last), newest first within each author.
+    ByAuthorThenTime,
+}
+
+impl FeedOrder {
+    /// Compare two posts according to this order.
+    pub fn compare(self, a: &Post, b: &Post) -> std::cmp::Ordering {
+        match self {
+            FeedOrder::NewestFirst => a.cmp_newest_first(b),
+            FeedOrder::OldestFirst => a.cmp_by_time(b),
+            FeedOrder::ByAuthorThenTime => {
+                let author = |post: &Post| post.author().as_deref().map(str::to_lowercase);
+                match (author(a), author(b)) {
+                    (Some(author_a), Some(author_b)) => author_a.cmp(&author_b),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                }
+                .then_with(|| a.cmp_newest_first(b))
+            }
+        }
+    }
+}
+
+/// Options for [`Feed::create_combined_feed_with_options`].
+#[derive(Debug, Clone)]
+pub struct FeedOptions {
+    /// Feed URLs or author nicks to leave out, in addition to the profile's mutes.
+    pub extra_mutes: Vec<String>,
+    pub attribution_policy: AttributionPolicy,
+    pub dedup_policy: DedupPolicy,
+    /// Order of the posts, newest first by default.
+    pub order: FeedOrder,
+    /// Only keep posts from this time on, see [`Feed::retain_since`].
+    pub since: Option<DateTime<FixedOffset>>,
+    /// What to do with posts without a parseable timestamp when `since` is set.
+    pub untimed_posts: UntimedPostPolicy,
+    /// How failed requests are retried. If None, each feed gets a single request.
+    pub retry: Option<RetryPolicy>,
+    /// How requests are made, e.g. with a preconfigured client.
+    pub network: NetworkConfig,
+    /// Timeout for the requests of each followed feed, 30 seconds by default. If None, no timeout is applied.
+    pub per_feed_timeout: Option<Duration>,
+    /// Time after which the feed is built with the followed feeds loaded so far, none by default.
+    /// The others are in the [`Feed::fetch_report`] with [`NetworkError::DeadlineExceeded`].
+    /// [`Feed::stream`] doesn't have a deadline, its snapshots can be taken at any time instead.
+    pub overall_deadline: Option<Duration>,
+    /// Where the body of each followed feed is saved, for `offline_fallback`.
+    pub raw_store: Option<DiskCache>,
+    /// Use the stored copy of the followed feeds that can't be fetched because of a network failure,
+    /// see [`FetchOptions::offline_fallback`]. Their status has [`FetchStats::stale`] set.
+    pub offline_fallback: bool,
+}
+
+impl FeedOptions {
+    /// The options of the followed feeds' requests.
+    fn fetch_options(&self) -> FetchOptions {
+        FetchOptions {
+            per_feed_timeout: self.per_feed_timeout,
+            overall_deadline: self.overall_deadline,
+            retry: self.retry,
+            network: self.network.clone(),
+            raw_store: self.raw_store.clone(),
+            offline_fallback: self.offline_fallback,
+            since: self.since,
+        }
+    }
+}
+
+impl Default for FeedOptions {
+    fn default() -> Self {
+        Self {
+            extra_mutes: Vec::new(),
+            attribution_policy: AttributionPolicy::default(),
+            dedup_policy: DedupPolicy::default(),
+            order: FeedOrder::default(),
+            since: None,
+            untimed_posts: UntimedPostPolicy::default(),
+            retry: None,
+            network: NetworkConfig::default(),
+            per_feed_timeout: Some(DEFAULT_FETCH_TIMEOUT),
+            overall_deadline: None,
+            raw_store: None,
+            offline_fallback: false,
+        }
+    }
+}
+
+/// What was fetched from a source, see [`SourceStatus`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchStats {
+    /// Number of posts in the fetched feed, before mutes are applied.
+    pub post_count: usize,
+    /// The feed didn't change since it was cached, so it wasn't merged.
+    pub not_modified: bool,
+    /// The feed couldn't be fetched, its copy stored at this time was used instead.
+    pub stale: Option<DateTime<Utc>>,
+    /// The download stopped at the first post older than the `since` cutoff, see [`FeedFetchResult::early_stop`].
+    pub early_stop: bool,
+}
+
+/// Outcome of fetching one followed feed, see [`Feed::fetch_report`].
+#[derive(Debug)]
+pub struct SourceStatus {
+    /// Nick the user follows the feed under, empty if none was given.
+    pub nick: String,
+    /// URL of the feed, as followed.
+    pub url: String,
+    /// URL the feed was read from after redirects, the source of its posts.
+    pub final_url: String,
+    /// What was fetched, or why the fetch failed.
+    pub result: Result<FetchStats, NetworkError>,
+    /// Time taken by the requests.
+    pub latency: Duration,
+    /// Number of requests made, more than one if failed requests were retried.
+    /// Left at 0 for feeds cancelled at the [`FeedOptions::overall_deadline`].
+    pub attempts: u32,
+    /// Problems with the feed that didn't prevent reading it, e.g. invalid characters or a permanent redirect.
+    pub warnings: Vec<FetchWarning>,
+    /// Time spent waiting for the [`NetworkConfig::rate_limit`] by the requests, not counted in the latency.
+    pub queued: Duration,
+}
+
+/// Fetch history of one source, kept across refreshes, see [`Feed::source_health`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceHealth {
+    /// When the source was last fetched successfully.
+    pub last_success: Option<DateTime<Utc>>,
+    /// When the source last failed to fetch, and why.
+    pub last_error: Option<(DateTime<Utc>, String)>,
+    /// Number of posts in the source the last time it was fetched with changes.
+    pub posts: usize,
+    /// Time taken by the last request to the source.
+    pub last_latency: Option<Duration>,
+    /// When a refresh last found new, changed or removed posts in the source.
+    pub last_change: Option<DateTime<Utc>>,
+    /// Average time between the recent posts of the source, None if it has fewer than two with a timestamp.
+    pub cadence: Option<Duration>,
+    /// When [`Feed::refresh_stale`] checks the source next. If None, it's checked at the next one.
+    pub next_check: Option<DateTime<Utc>>,
+}
+
+impl SourceHealth {
+    /// Whether the source was fetched successfully more recently than it failed.
+    pub fn is_healthy(&self) -> bool {
+        match (&self.last_success, &self.last_error) {
+            (Some(success), Some((failure, _))) => success >= failure,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    fn record(&mut self, status: &SourceStatus, at: DateTime<Utc>) {
+        self.last_latency = Some(status.latency);
+        match &status.result {
+            // A stored copy was used, the source wasn't reached
+            Ok(stats) if stats.stale.is_some() => {}
+            Ok(stats) => {
+                self.last_success = Some(at);
+                if !stats.not_modified {
+                    self.posts = stats.post_count;
+                }
+            }
+            Err(error) => self.last_error = Some((at, error.to_string())),
+        }
+    }
+}
+
+/// Fetched feeds as (profile, posts, URL), as returned by [`network::get_feeds_with_config`].
+type FetchedFeeds = Vec<(Profile, Vec<Post>, String)>;
+
+/// Split fetch outcomes into the fetched feeds and the status of each source.
+fn collect_fetched(outcomes: Vec<network::FeedFetch>) -> (FetchedFeeds, Vec<SourceStatus>) {
+    let mut fetched = Vec::new();
+    let mut statuses = Vec::new();
+    for outcome in outcomes {
+        let (feed, status) = source_status(outcome);
+        fetched.extend(feed);
+        statuses.push(status);
+    }
+    (fetched, statuses)
+}
+
+/// Split a fetch outcome into the fetched feed, if any, and the status of its source.
+fn source_status(outcome: network::FeedFetch) -> (Option<(Profile, Vec<Post>, String)>, SourceStatus) {
+    let network::FeedFetch { identifier, url, final_url, result, elapsed, attempts, warnings, queued, freshness, early_stop } = outcome;
+    let mut fetched = None;
+    let result = match result {
+        Ok(FeedResult::Fetched(profile, posts)) => {
+            let post_count = posts.len();
+            fetched = Some((profile, posts, final_url.clone()));
+            Ok(FetchStats { post_count, not_modified: false, stale: stale_since(freshness), early_stop })
+        }
+        Ok(FeedResult::NotModified) => Ok(FetchStats { post_count: 0, not_modified: true, stale: None, early_stop: false }),
+        Err(error) => Err(error),
+    };
+    (fetched, SourceStatus { nick: identifier, url, final_url, result, latency: elapsed, attempts, warnings, queued })
+}
+
+/// When the stored copy of a stale feed was fetched.
+fn stale_since(freshness: Freshness) -> Option<DateTime<Utc>> {
+    match freshness {
+        Freshness::Fresh => None,
+        Freshness::Stale { fetched_at } => Some(fetched_at),
+    }
+}
+
+/// Options for [`Feed::create_extended_feed`].
+#[derive(Debug, Clone)]
+pub struct ExtendedOptions {
+    /// How many follow hops to go: 1 only fetches the direct follows, 2 adds their follows, and so on.
+    pub depth: u8,
+    /// Maximum number of feeds fetched beyond the direct follows.
+    pub max_feeds: usize,
+    /// Timeout for each feed request. If None, no timeout is applied.
+    pub timeout: Option<Duration>,
+    /// How failed requests are retried. If None, each feed gets a single request.
+    pub retry: Option<RetryPolicy>,
+    /// How requests are made, e.g. with a preconfigured client.
+    pub network: NetworkConfig,
+}
+
+impl Default for ExtendedOptions {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            max_feeds: 20,
+            timeout: Some(DEFAULT_FETCH_TIMEOUT),
+            retry: None,
+            network: NetworkConfig::default(),
+        }
+    }
+}
+
+/// Progress of a feed built with [`Feed::stream`].
+#[derive(Debug)]
+pub enum FeedEvent {
+    /// The request for a followed feed started.
+    SourceStarted(String),
+    /// A followed feed was fetched, its posts are in the next [`FeedHandle::snapshot`].
+    SourceLoaded { url: String, post_count: usize },
+    /// A followed feed couldn't be fetched. The error itself is in the completed feed's [`Feed::fetch_report`].
+    SourceFailed { url: String, error: String },
+    /// Every followed feed was fetched or failed. This is the last event.
+    Completed(Feed),
+}
+
+/// Sources loaded so far by [`Feed::stream`], shared with its [`FeedHandle`].
+#[derive(Default)]
+struct StreamState {
+    /// Fetched feeds with their position in the follow list, so snapshots don't depend on which came first.
+    loaded: Vec<(usize, Profile, Vec<Post>, String)>,
+    completed: bool,
+}
+
+/// Access to a feed being built by [`Feed::stream`].
+#[derive(Clone)]
+pub struct FeedHandle {
+    user_profile: Profile,
+    user_posts: Vec<Post>,
+    options: FeedOptions,
+    state: Arc<Mutex<StreamState>>,
+}
+
+impl FeedHandle {
+    /// Build a feed from the user's posts and the followed feeds loaded so far.
+    pub fn snapshot(&self) -> Feed {
+        let mut loaded = self.state.lock().unwrap().loaded.clone();
+        loaded.sort_by_key(|(index, _, _, _)| *index);
+        let fetched = loaded.into_iter().map(|(_, profile, posts, url)| (profile, posts, url)).collect();
+        Feed::from_fetched_feeds(&self.user_profile, self.user_posts.clone(), fetched, &self.options)
+    }
+
+    /// Number of followed feeds loaded so far.
+    pub fn loaded_sources(&self) -> usize {
+        self.state.lock().unwrap().loaded.len()
+    }
+
+    /// Whether every followed feed was fetched or failed.
+    pub fn is_completed(&self) -> bool {
+        self.state.lock().unwrap().completed
+    }
+}
+
+/// Events of a feed built with [`Feed::stream`], see [`FeedEvent`].
+///
+/// Implements [`futures_core::Stream`], or can be read with [`FeedEventStream::next_event`].
+pub struct FeedEventStream {
+    receiver: tokio::sync::mpsc::UnboundedReceiver<FeedEvent>,
+}
+
+impl FeedEventStream {
+    /// Wait for the next event. Returns None after [`FeedEvent::Completed`].
+    pub async fn next_event(&mut self) -> Option<FeedEvent> {
+        self.receiver.recv().await
+    }
+}
+
+impl futures_core::Stream for FeedEventStream {
+    type Item = FeedEvent;
+
+    fn poll_next(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<FeedEvent>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Progress of the fetch tasks of [`Feed::stream`].
+enum StreamProgress {
+    Started(String),
+    Finished(usize, Option<Box<network::FeedFetch>>),
+}
+
+/// A feed that couldn't be fetched by [`Feed::from_urls`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchFailure {
+    pub url: String,
+    pub message: String,
+}
+
+/// Error returned by [`Feed::from_urls`] when some feeds couldn't be fetched.
+#[derive(Debug)]
+pub enum FeedError {
+    /// Some feeds failed, the feed holds the ones that were fetched.
+    PartialFailure { feed: Feed, failures: Vec<FetchFailure> },
+    /// No feed could be fetched.
+    AllFailed { failures: Vec<FetchFailure> },
+}
+
+impl FeedError {
+    pub fn failures(&self) -> &[FetchFailure] {
+        match self {
+            FeedError::PartialFailure { failures, .. } | FeedError::AllFailed { failures } => failures,
+        }
+    }
+}
+
+impl std::fmt::Display for FeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let failures = self.failures();
+        match self {
+            FeedError::PartialFailure { .. } => write!(f, "Failed to fetch {} feed(s)", failures.len())?,
+            FeedError::AllFailed { .. } => write!(f, "Failed to fetch all feeds")?,
+        }
+        for failure in failures {
+            write!(f, "\n  {}: {}", failure.url, failure.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+/// Key identifying the copies of a mirrored post - its ID and a hash of its content.
+fn mirror_key(post: &Post) -> (String, u64) {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    post.content().hash(&mut hasher);
+    (post.id().to_string(), hasher.finish())
+}
+
+/// Canonical URLs of the feeds the user follows under their profile's own nick,
+/// e.g. `alice` at her own site rather than `alice-mirror` at a mirror.
+fn canonical_sources<'a>(user_profile: &Profile, feeds: impl IntoIterator<Item = (&'a Profile, &'a str)>) -> HashSet<String> {
+    feeds
+        .into_iter()
+        .map(|(profile, source)| (profile, util::canonical_feed_url(source)))
+        .filter(|(profile, source)| {
+            user_profile.url_for_nick(profile.nick()).is_some_and(|url| util::canonical_feed_url(url) == *source)
+        })
+        .map(|(_, source)| source)
+        .collect()
+}
+
+/// Rank of a copy of a mirrored post, the lowest one is kept - see [`DedupPolicy::Collapse`].
+fn mirror_rank(post: &Post, own_source: Option<&str>, canonical_sources: &HashSet<String>) -> (u8, String) {
+    let Some(source) = post.source().as_deref().map(util::canonical_feed_url) else {
+        return (0, String::new());
+    };
+    if own_source == Some(source.as_str()) {
+        (0, source)
+    } else if canonical_sources.contains(&source) {
+        (1, source)
+    } else {
+        (2, source)
+    }
+}
+
+/// Which copy wins when [`Feed::merge`] finds a post or profile in both feeds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedMergePolicy {
+    /// Keep the copy already in the feed.
+    KeepExisting,
+    /// Replace it with the copy from the merged feed, e.g. a fresher fetch.
+    #[default]
+    TakeIncoming,
+}
+
+/// Result of [`Feed::merge`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Posts that were only in the merged feed.
+    pub added: usize,
+    /// Posts in both feeds whose copy was replaced.
+    pub replaced: usize,
+    /// Posts in both feeds that were kept as they were, because they're identical or by policy.
+    pub skipped: usize,
+}
+
+/// A poll post with the votes found in the feed, see [`Feed::polls`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TalliedPoll<'a> {
+    pub post: &'a Post,
+    pub poll: Poll,
+}
+
+/// Whether votes cast after a poll's `:POLL_END:` are counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LateVotePolicy {
+    #[default]
+    Exclude,
+    Include,
+}
+
+/// Whether [`Feed::root_posts_with_policy`] treats replies to posts missing from the feed as root posts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingParentPolicy {
+    /// Replies to unknown posts are shown as root posts, so they don't disappear from both views.
+    #[default]
+    AsRoot,
+    AsReply,
+}
+
+/// Session-only filters for [`Feed::filtered_view`], separate from the mutes stored in the profile.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedFilterSet {
+    /// Feed URLs whose posts are hidden, compared with [`util::same_feed_url`].
+    pub sources: Vec<String>,
+    /// Author nicks whose posts are hidden, case-insensitive, with or without a leading `@`.
+    pub authors: Vec<String>,
+    /// Full IDs of posts whose whole thread is hidden - the post itself and every reply below it.
+    pub threads: Vec<String>,
+    /// Words hiding the posts whose plain-text content contains them, case-insensitive.
+    pub keywords: Vec<String>,
+}
+
+impl FeedFilterSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mute_source(&mut self, source: impl Into<String>) -> &mut Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    pub fn mute_author(&mut self, nick: impl Into<String>) -> &mut Self {
+        self.authors.push(nick.into());
+        self
+    }
+
+    pub fn mute_thread(&mut self, root_full_id: impl Into<String>) -> &mut Self {
+        self.threads.push(root_full_id.into());
+        self
+    }
+
+    pub fn mute_keyword(&mut self, keyword: impl Into<String>) -> &mut Self {
+        self.keywords.push(keyword.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty() && self.authors.is_empty() && self.threads.is_empty() && self.keywords.is_empty()
+    }
+
+    /// Whether a post is hidden by the source, author or keyword filters.
+    fn hides_post(&self, post: &Post) -> bool {
+        if let Some(source) = post.source() {
+            if self.sources.iter().any(|muted| util::same_feed_url(source, muted)) {
+                return true;
+            }
+        }
+
+        if let Some(author) = post.author() {
+            let author = author.trim_start_matches('@');
+            if self.authors.iter().any(|muted| muted.trim_start_matches('@').eq_ignore_ascii_case(author)) {
+                return true;
+            }
+        }
+
+        if self.keywords.iter().any(|keyword| !keyword.trim().is_empty()) {
+            let text = post.plain_text().to_lowercase();
+            if self.keywords.iter().any(|keyword| !keyword.trim().is_empty() && text.contains(&keyword.trim().to_lowercase())) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Posts of a feed belonging to the same thread, see [`Feed::conversations`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversation<'a> {
+    /// Full ID of the topmost post of the thread present in the feed.
+    pub root_full_id: String,
+    /// The posts of the thread, oldest first.
+    pub posts: Vec<&'a Post>,
+    /// Time of the newest post of the thread.
+    pub latest_activity: Option<DateTime<FixedOffset>>,
+}
+
+/// Lightweight record of the posts a feed held, to find what changed since, see [`Feed::diff_since`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedSnapshotRef {
+    /// Content hash of each post, by full ID.
+    content_hashes: HashMap<String, u64>,
+}
+
+impl FeedSnapshotRef {
+    pub fn contains(&self, full_id: &str) -> bool {
+        self.content_hashes.contains_key(full_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.content_hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content_hashes.is_empty()
+    }
+}
+
+/// Changes of a feed since a [`FeedSnapshotRef`], see [`Feed::diff_since`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedDelta<'a> {
+    /// Posts not in the snapshot, in feed order.
+    pub new_posts: Vec<&'a Post>,
+    /// Posts whose content changed since the snapshot, in feed order.
+    pub edited_posts: Vec<&'a Post>,
+    /// Full IDs of the snapshot's posts no longer in the feed, sorted.
+    pub removed_full_ids: Vec<String>,
+}
+
+impl FeedDelta<'_> {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.new_posts.is_empty() && self.edited_posts.is_empty() && self.removed_full_ids.is_empty()
+    }
+}
+
+/// Hash of a post's content, ignoring differences in whitespace.
+fn content_hash(post: &Post) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for word in post.content().split_whitespace() {
+        word.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Index of a feed's posts to resolve `reply_to` references, with the same rules as [`Feed::find_post`].
+struct PostLookup<'a> {
+    by_full_id: HashMap<String, usize>,
+    by_id: HashMap<&'a str, usize>,
+}
+
+impl<'a> PostLookup<'a> {
+    fn new(posts: &'a [Post]) -> Self {
+        let mut by_full_id = HashMap::new();
+        let mut by_id = HashMap::new();
+        for (index, post) in posts.iter().enumerate() {
+            by_full_id.entry(post.full_id()).or_insert(index);
+            by_id.entry(post.id()).or_insert(index);
+        }
+        Self { by_full_id, by_id }
+    }
+
+    fn resolve(&self, reference: &str) -> Option<usize> {
+        if let Some(&index) = self.by_full_id.get(reference) {
+            return Some(index);
+        }
+        let id = reference.rsplit('#').next().unwrap_or(reference);
+        self.by_id.get(id).copied()
+    }
+}
+
+/// Whether [`Feed::posts_in_lang`] keeps posts without a `:LANG:` property.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnsetLangPolicy {
+    #[default]
+    Include,
+    Exclude,
+}
 
 /// Represents a collection of org-social posts from various sources.
 ///
 /// A feed can contain posts from the user and followed users,
 /// sorted chronologically with metadata preserved.
+#[derive(Debug, Default)]
 pub struct Feed {
     pub posts: Vec<Post>,
+    /// Profiles of the feed's sources - the user's own first, then the fetched ones.
+    pub profiles: Vec<Arc<Profile>>,
+    /// How posts from followed feeds were attributed, see [`Feed::set_attribution_policy`].
+    pub attribution_policy: AttributionPolicy,
+    /// Whether mirrored posts are collapsed, also applied by [`Feed::refresh`].
+    pub dedup_policy: DedupPolicy,
+    /// Order of the posts, kept when posts are added, see [`Feed::sort`].
+    pub order: FeedOrder,
+    /// Number of follow hops to each source, by canonical feed URL, see [`Feed::attribution_distance`].
+    pub source_distances: HashMap<String, u8>,
+    /// Outcome of fetching each followed feed when the feed was created or last refreshed.
+    ///
+    /// Lets clients tell the user which follows failed to load, while the feed holds the others.
+    /// Empty for feeds built without fetching.
+    pub fetch_report: Vec<SourceStatus>,
+    /// Fetch history of each followed feed, by canonical feed URL, see [`Feed::source_health`].
+    ///
+    /// Unlike [`Feed::fetch_report`], this is updated rather than replaced by [`Feed::refresh`],
+    /// and is kept in snapshots.
+    pub source_health: HashMap<String, SourceHealth>,
+    /// Transport of the requests made for the feed, like [`Feed::fetch_avatars`] and [`Feed::refresh`],
+    /// created from the [`FeedOptions::network`] it was built with.
+    pub network: NetworkHandle,
 }
 
-impl Feed {
-    pub async fn create_combined_feed(
-        user_profile: &Profile,
-        user_posts: Vec<Post>,
-    ) -> Result<Feed, Box<dyn std::error::Error>> {
-        let mut all_posts = Vec::new();
+impl Feed {
+    /// Create a feed from the user's posts and the posts of the feeds they follow.
+    ///
+    /// Posts from feeds or authors muted in the user's profile are left out.
+    pub async fn create_combined_feed(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        Self::create_combined_feed_with_mutes(user_profile, user_posts, &[]).await
+    }
+
+    /// Create a combined feed, leaving out posts matching the profile's mutes or the additional `extra_mutes`.
+    ///
+    /// `extra_mutes` are feed URLs or author nicks, matched like `#+MUTE` entries (see [`profile::mute_matches`]).
+    pub async fn create_combined_feed_with_mutes(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        extra_mutes: &[String],
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        let options = FeedOptions { extra_mutes: extra_mutes.to_vec(), ..Default::default() };
+        Self::create_combined_feed_with_options(user_profile, user_posts, options).await
+    }
+
+    /// Create a combined feed with the given mutes, attribution and deduplication policies.
+    pub async fn create_combined_feed_with_options(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        options: FeedOptions,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        Self::create_combined_feed_with_hooks(user_profile, user_posts, options, network::FetchHooks::default()).await
+    }
+
+    /// Blocking version of [`Feed::create_combined_feed`], see [`network::blocking`].
+    #[cfg(feature = "blocking")]
+    pub fn create_combined_feed_blocking(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        Self::create_combined_feed_with_options_blocking(user_profile, user_posts, FeedOptions::default())
+    }
+
+    /// Blocking version of [`Feed::create_combined_feed_with_options`], see [`network::blocking`].
+    #[cfg(feature = "blocking")]
+    pub fn create_combined_feed_with_options_blocking(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        options: FeedOptions,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        let options = FeedOptions { network: network::blocking::config(&options.network), ..options };
+        network::blocking::block_on(Self::create_combined_feed_with_options(user_profile, user_posts, options))
+    }
+
+    /// Create a combined feed like [`Feed::create_combined_feed_with_options`], sending a
+    /// [`network::FetchEvent`] to `events` as each followed feed starts and finishes loading.
+    ///
+    /// See [`network::get_feeds_with_progress`] for how events are sent.
+    pub async fn create_combined_feed_with_progress(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        options: FeedOptions,
+        events: tokio::sync::mpsc::Sender<network::FetchEvent>,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        let hooks = network::FetchHooks { events: Some(events), ..Default::default() };
+        Self::create_combined_feed_with_hooks(user_profile, user_posts, options, hooks).await
+    }
+
+    async fn create_combined_feed_with_hooks(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        options: FeedOptions,
+        hooks: network::FetchHooks,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        // The feed keeps the transport of the fetch, for its later requests
+        let options = FeedOptions { network: NetworkHandle::new(options.network).into(), ..options };
+        // Fetch posts from followed users
+        let follows = user_profile.follow_urls_normalized();
+        let outcomes = network::fetch_feeds_with_hooks(follows, &options.fetch_options(), None, hooks).await;
+        let results = outcomes.into_iter().map(FeedFetchResult::from).collect();
+        Ok(Self::from_fetch_results(user_profile, user_posts, results, &options))
+    }
+
+    /// Create a combined feed from feeds fetched with [`network::get_feeds_detailed`].
+    ///
+    /// The outcome of each fetch, successful or not, is recorded in the [`Feed::fetch_report`].
+    pub fn from_fetch_results(user_profile: &Profile, user_posts: Vec<Post>, results: Vec<FeedFetchResult>, options: &FeedOptions) -> Feed {
+        let mut followed_feeds = Vec::new();
+        let mut fetch_report = Vec::new();
+        for result in results {
+            let FeedFetchResult { nick, url, outcome, elapsed, queued, attempts, warnings, freshness, early_stop } = result;
+            let (final_url, result) = match outcome {
+                Ok((profile, posts)) => {
+                    let final_url = profile.source().cloned().unwrap_or_else(|| url.clone());
+                    let stats = FetchStats { post_count: posts.len(), not_modified: false, stale: stale_since(freshness), early_stop };
+                    followed_feeds.push((profile, posts, final_url.clone()));
+                    (final_url, Ok(stats))
+                }
+                Err(error) => (url.clone(), Err(error)),
+            };
+            fetch_report.push(SourceStatus { nick, url, final_url, result, latency: elapsed, attempts, warnings, queued });
+        }
+
+        let mut feed = Self::from_fetched_feeds(user_profile, user_posts, followed_feeds, options);
+        feed.record_fetch_report(fetch_report);
+        feed
+    }
+
+    /// Build a combined feed progressively, so fast feeds can be shown without waiting for slow ones.
+    ///
+    /// The followed feeds are fetched in the background - this must be called within a Tokio runtime.
+    /// The returned handle gives the posts loaded so far with [`FeedHandle::snapshot`], and the stream
+    /// reports each source as it starts, loads or fails, ending with the complete feed.
+    pub fn stream(user_profile: &Profile, user_posts: Vec<Post>, options: FeedOptions) -> (FeedHandle, FeedEventStream) {
+        let state = Arc::new(Mutex::new(StreamState::default()));
+        let handle = FeedHandle { user_profile: user_profile.clone(), user_posts, options, state: state.clone() };
+        let (events, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (progress, mut progress_receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        let started = progress.clone();
+        let on_start: network::OnFetchStart = Arc::new(move |url: &str| {
+            let _ = started.send(StreamProgress::Started(url.to_string()));
+        });
+        let follows = user_profile.follow_urls_normalized();
+        let fetches = network::spawn_feed_fetches(
+            follows,
+            &handle.options.fetch_options(),
+            None,
+            network::FetchHooks { on_start: Some(on_start), ..Default::default() },
+        );
+        for (index, fetch) in fetches.into_iter().enumerate() {
+            let progress = progress.clone();
+            tokio::spawn(async move {
+                let outcome = fetch.await.ok().flatten().map(Box::new);
+                let _ = progress.send(StreamProgress::Finished(index, outcome));
+            });
+        }
+        // The channel closes once every fetch task is done
+        drop(progress);
+
+        let final_handle = handle.clone();
+        tokio::spawn(async move {
+            let mut fetch_report = Vec::new();
+            while let Some(message) = progress_receiver.recv().await {
+                let (index, outcome) = match message {
+                    StreamProgress::Started(url) => {
+                        let _ = events.send(FeedEvent::SourceStarted(url));
+                        continue;
+                    }
+                    StreamProgress::Finished(index, Some(outcome)) => (index, *outcome),
+                    StreamProgress::Finished(_, None) => continue,
+                };
+
+                let (fetched, status) = source_status(outcome);
+                if let Some((profile, posts, url)) = fetched {
+                    let post_count = posts.len();
+                    state.lock().unwrap().loaded.push((index, profile, posts, url.clone()));
+                    let _ = events.send(FeedEvent::SourceLoaded { url, post_count });
+                } else if let Err(error) = &status.result {
+                    let _ = events.send(FeedEvent::SourceFailed { url: status.url.clone(), error: error.to_string() });
+                }
+                fetch_report.push((index, status));
+            }
+
+            state.lock().unwrap().completed = true;
+            let mut feed = final_handle.snapshot();
+            fetch_report.sort_by_key(|(index, _)| *index);
+            feed.record_fetch_report(fetch_report.into_iter().map(|(_, status)| status).collect());
+            let _ = events.send(FeedEvent::Completed(feed));
+        });
+
+        (handle, FeedEventStream { receiver })
+    }
+
+    /// Create a combined feed that also includes feeds followed by the user's follows, to discover new people.
+    ///
+    /// After fetching the direct follows, the union of their follow lists is fetched, and so on up to
+    /// `options.depth` hops. Feeds already fetched and the user's own feed are skipped, so follow cycles
+    /// end there. When there are more candidates than `options.max_feeds` allows, the ones followed by
+    /// the most fetched feeds are preferred. See [`Feed::attribution_distance`] to tell the posts apart.
+    pub async fn create_extended_feed(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        options: ExtendedOptions,
+    ) -> Result<Feed, Box<dyn std::error::Error>> {
+        let direct_follows = user_profile.follow_urls_normalized();
+        let mut visited: HashSet<String> = direct_follows.iter().map(|(_, url)| util::canonical_feed_url(url)).collect();
+        visited.extend(user_profile.source().map(|source| util::canonical_feed_url(source)));
+
+        let outcomes = network::fetch_feeds_with_errors(direct_follows, options.timeout, None, options.retry, &options.network).await;
+        let (mut fetched, mut fetch_report) = collect_fetched(outcomes);
+        let mut distances = HashMap::new();
+        let mut level_start = 0;
+        let mut remaining = options.max_feeds;
+
+        for distance in 2..=options.depth {
+            // Follows of the feeds fetched at the previous level, with the number of feeds following them
+            let mut candidates: HashMap<String, (String, usize)> = HashMap::new();
+            for (profile, _, url) in &fetched[level_start..] {
+                visited.insert(util::canonical_feed_url(url));
+                visited.extend(profile.source().map(|source| util::canonical_feed_url(source)));
+                for (_, followed) in profile.follow_urls_normalized() {
+                    let key = util::canonical_feed_url(&followed);
+                    candidates.entry(key).or_insert((followed, 0)).1 += 1;
+                }
+            }
+            candidates.retain(|key, _| !visited.contains(key));
+
+            let mut candidates: Vec<(String, (String, usize))> = candidates.into_iter().collect();
+            candidates.sort_by(|(key_a, (_, count_a)), (key_b, (_, count_b))| count_b.cmp(count_a).then_with(|| key_a.cmp(key_b)));
+            candidates.truncate(remaining);
+            if candidates.is_empty() {
+                break;
+            }
+            remaining -= candidates.len();
+
+            let targets = candidates
+                .into_iter()
+                .map(|(key, (url, _))| {
+                    visited.insert(key);
+                    (String::new(), url)
+                })
+                .collect();
+            let (level_feeds, level_report) = collect_fetched(network::fetch_feeds_with_errors(targets, options.timeout, None, options.retry, &options.network).await);
+            level_start = fetched.len();
+            for (_, _, url) in &level_feeds {
+                distances.insert(util::canonical_feed_url(url), distance);
+            }
+            fetched.extend(level_feeds);
+            fetch_report.extend(level_report);
+        }
+
+        let mut feed = Self::from_fetched_feeds(user_profile, user_posts, fetched, &FeedOptions::default());
+        feed.source_distances.extend(distances);
+        feed.record_fetch_report(fetch_report);
+        Ok(feed)
+    }
+
+    /// Number of follow hops from the user to the author of a post.
+    ///
+    /// 0 for the user's own posts, 1 for direct follows, 2 for follows of follows (see
+    /// [`Feed::create_extended_feed`]) and so on. Posts without a source are the user's own.
+    /// None if the post's source isn't part of the feed.
+    pub fn attribution_distance(&self, post: &Post) -> Option<u8> {
+        match post.source().as_deref() {
+            Some(source) => self.source_distances.get(&util::canonical_feed_url(source)).copied(),
+            None => Some(0),
+        }
+    }
+
+    /// The entries of [`Feed::fetch_report`] for sources that failed to fetch.
+    pub fn failed_sources(&self) -> impl Iterator<Item = &SourceStatus> {
+        self.fetch_report.iter().filter(|status| status.result.is_err())
+    }
+
+    /// The entries of [`Feed::fetch_report`] for sources shown from a stored copy, see [`FeedOptions::offline_fallback`].
+    pub fn stale_sources(&self) -> impl Iterator<Item = &SourceStatus> {
+        self.fetch_report.iter().filter(|status| status.result.as_ref().is_ok_and(|stats| stats.stale.is_some()))
+    }
+
+    /// Fetch history of a followed feed, matched by canonical URL.
+    pub fn source_health(&self, url: &str) -> Option<&SourceHealth> {
+        self.source_health.get(&util::canonical_feed_url(url))
+    }
+
+    /// Sources whose last fetch failed, or that never fetched successfully, as (canonical URL, health), sorted by URL.
+    pub fn unhealthy_sources(&self) -> Vec<(&str, &SourceHealth)> {
+        let mut unhealthy: Vec<(&str, &SourceHealth)> = self
+            .source_health
+            .iter()
+            .filter(|(_, health)| !health.is_healthy())
+            .map(|(url, health)| (url.as_str(), health))
+            .collect();
+        unhealthy.sort_by_key(|(url, _)| *url);
+        unhealthy
+    }
+
+    /// Replace the fetch report and update the health of the reported sources.
+    fn record_fetch_report(&mut self, fetch_report: Vec<SourceStatus>) {
+        let now = Utc::now();
+        for status in &fetch_report {
+            self.source_health.entry(util::canonical_feed_url(&status.url)).or_default().record(status, now);
+        }
+        self.fetch_report = fetch_report;
+    }
+
+    /// Build a combined feed from already fetched feeds, as returned by [`network::get_feeds_with_config`].
+    ///
+    /// Feeds of the same person (same [`Profile::identity_key`], e.g. fetched under both `http://`
+    /// and `https://`) are only included once, keeping the copy fetched last.
+    /// Mirrored posts are collapsed according to [`FeedOptions::dedup_policy`].
+    pub(crate) fn from_fetched_feeds(
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        followed_feeds: Vec<(Profile, Vec<Post>, String)>,
+        options: &FeedOptions,
+    ) -> Feed {
+        let mut all_posts = Vec::new();
+        let mut profiles = vec![Arc::new(user_profile.clone())];
+        let mut source_distances: HashMap<String, u8> =
+            followed_feeds.iter().map(|(_, _, url)| (util::canonical_feed_url(url), 1)).collect();
+        if let Some(source) = user_profile.source() {
+            source_distances.insert(util::canonical_feed_url(source), 0);
+        }
+        let canonical = canonical_sources(user_profile, followed_feeds.iter().map(|(profile, _, url)| (profile, url.as_str())));
+
+        // Add user's own posts with their nick as author
+        for mut post in user_posts {
+            post.set_author(user_profile.nick().to_string());
+            all_posts.push(post);
+        }
+
+        // Add posts from followed users with their nick as author
+        for (profile, posts, _source) in latest_per_identity(followed_feeds) {
+            let author_nick = attributed_nick(user_profile, &profile, options.attribution_policy);
+            
+            for mut post in posts {
+                post.set_author(author_nick.clone());
+                if !is_muted(&post, user_profile, &options.extra_mutes) {
+                    all_posts.push(post);
+                }
+            }
+            profiles.push(Arc::new(profile));
+        }
+
+        if options.dedup_policy == DedupPolicy::Collapse {
+            let own_source = user_profile.source().map(|source| util::canonical_feed_url(source));
+            let rank = |post: &Post| mirror_rank(post, own_source.as_deref(), &canonical);
+            let mut best: HashMap<(String, u64), (u8, String)> = HashMap::new();
+            for post in &all_posts {
+                let rank = rank(post);
+                best.entry(mirror_key(post))
+                    .and_modify(|best| *best = std::cmp::min(best.clone(), rank.clone()))
+                    .or_insert(rank);
+            }
+            all_posts.retain(|post| best[&mirror_key(post)] == rank(post));
+        }
+
+        if let Some(cutoff) = options.since {
+            all_posts.retain(|post| is_since(post, cutoff, options.untimed_posts));
+        }
+        
+        all_posts.sort_by(|a, b| options.order.compare(a, b));
+
+        Feed {
+            posts: all_posts,
+            profiles,
+            attribution_policy: options.attribution_policy,
+            dedup_policy: options.dedup_policy,
+            order: options.order,
+            source_distances,
+            fetch_report: Vec::new(),
+            source_health: HashMap::new(),
+            network: NetworkHandle::new(options.network.clone()),
+        }
+    }
+    
+    /// Create a feed from a list of feed URLs, without a user profile - e.g. to preview a feed before following it.
+    ///
+    /// Posts are attributed with the nick of their feed's profile. As there's no user,
+    /// the first of the feed's profiles is empty.
+    ///
+    /// # Errors
+    ///
+    /// `FeedError::PartialFailure` (holding the feed built from the other URLs) if some URLs
+    /// couldn't be fetched, `FeedError::AllFailed` if none could.
+    pub async fn from_urls(urls: &[String], timeout: Option<Duration>) -> Result<Feed, FeedError> {
+        let targets = urls.iter().map(|url| (String::new(), url.clone())).collect();
+
+        let handle = NetworkHandle::shared();
+        let (fetched, fetch_report) = collect_fetched(network::fetch_feeds_with_errors(targets, timeout, None, None, handle.config()).await);
+        let failures: Vec<FetchFailure> = fetch_report
+            .iter()
+            .filter_map(|status| {
+                let message = status.result.as_ref().err()?.to_string();
+                Some(FetchFailure { url: status.url.clone(), message })
+            })
+            .collect();
+
+        if !failures.is_empty() && fetched.is_empty() {
+            return Err(FeedError::AllFailed { failures });
+        }
+        let mut feed = Self::from_fetched_feeds(&Profile::default(), Vec::new(), fetched, &FeedOptions::default());
+        feed.record_fetch_report(fetch_report);
+        if failures.is_empty() {
+            Ok(feed)
+        } else {
+            Err(FeedError::PartialFailure { feed, failures })
+        }
+    }
+
+    pub fn create_user_feed(user_profile: &Profile, user_posts: Vec<Post>) -> Feed {
+        let mut posts = Vec::new();
+        
+        // Set author for user's own posts
+        for mut post in user_posts {
+            post.set_author(user_profile.nick().to_string());
+            posts.push(post);
+        }
+        
+        // Sort posts chronologically (newest first)
+        post::sort_newest_first(&mut posts);
+        
+        Feed {
+            posts,
+            profiles: vec![Arc::new(user_profile.clone())],
+            attribution_policy: AttributionPolicy::default(),
+            dedup_policy: DedupPolicy::default(),
+            order: FeedOrder::default(),
+            source_distances: user_profile.source().map(|source| (util::canonical_feed_url(source), 0)).into_iter().collect(),
+            fetch_report: Vec::new(),
+            source_health: HashMap::new(),
+            network: NetworkHandle::shared(),
+        }
+    }
+
+    /// Re-fetch the followed feeds and merge the changes into the feed.
+    ///
+    /// Unlike rebuilding the feed with [`Feed::create_combined_feed`], posts and profiles that didn't
+    /// change keep their place in memory. Posts are matched by their full ID: new ones are added,
+    /// changed ones are replaced, and with [`RefreshOptions::remove_missing`] posts gone from their
+    /// source are removed. The feed is sorted again afterwards.
+    ///
+    /// With a [`RefreshOptions::cache`], sources that didn't change are not merged and don't appear in the report.
+    /// The outcome of each fetch replaces the feed's [`Feed::fetch_report`].
+    ///
+    /// The time each source is checked next by [`Feed::refresh_stale`] is updated as well.
+    pub async fn refresh(&mut self, user_profile: &Profile, user_posts: Vec<Post>, options: RefreshOptions) -> RefreshReport {
+        let follows = user_profile.follow_urls_normalized();
+        self.refresh_follows(follows, user_profile, user_posts, &options).await
+    }
+
+    /// Like [`Feed::refresh`], but only re-fetch the followed feeds that are due for a check, saving
+    /// requests to people who post rarely.
+    ///
+    /// Each source is checked about as often as it posts, from the timestamps of its recent posts
+    /// ([`SourceHealth::cadence`]), and less often the longer it's been quiet, within the bounds of
+    /// [`RefreshOptions::schedule`]. Sources never checked before are due right away, failed ones after
+    /// the minimum interval. The schedule is in the [`Feed::source_health`], so it's kept in snapshots.
+    ///
+    /// The fetch report only holds the sources that were checked.
+    pub async fn refresh_stale(&mut self, user_profile: &Profile, user_posts: Vec<Post>, options: RefreshOptions) -> RefreshReport {
+        let now = Utc::now();
+        let due = user_profile
+            .follow_urls_normalized()
+            .into_iter()
+            .filter(|(_, url)| {
+                let next_check = self.source_health.get(&util::canonical_feed_url(url)).and_then(|health| health.next_check);
+                next_check.is_none_or(|next_check| next_check <= now)
+            })
+            .collect();
+        self.refresh_follows(due, user_profile, user_posts, &options).await
+    }
+
+    /// Fetch the given follows and merge them into the feed, see [`Feed::refresh`].
+    async fn refresh_follows(
+        &mut self,
+        follows: Vec<(String, String)>,
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        options: &RefreshOptions,
+    ) -> RefreshReport {
+        let fetch_options = FetchOptions {
+            per_feed_timeout: Some(DEFAULT_FETCH_TIMEOUT),
+            retry: options.retry,
+            network: options.network.clone().unwrap_or_else(|| self.network.config().clone()),
+            since: options.since,
+            ..Default::default()
+        };
+        let outcomes = network::fetch_feeds_with_hooks(follows, &fetch_options, options.cache.clone(), network::FetchHooks::default()).await;
+        let (followed_feeds, fetch_report) = collect_fetched(outcomes);
+        self.record_fetch_report(fetch_report);
+        let report = self.merge_fetched(user_profile, user_posts, followed_feeds, options);
+        self.schedule_checks(&report, &options.schedule, Utc::now());
+        report
+    }
+
+    /// Set when the sources of the fetch report are checked next, see [`Feed::refresh_stale`].
+    fn schedule_checks(&mut self, report: &RefreshReport, schedule: &RefreshSchedule, now: DateTime<Utc>) {
+        for status in &self.fetch_report {
+            let urls = [util::canonical_feed_url(&status.url), util::canonical_feed_url(&status.final_url)];
+            let is_source = |source: &str| urls.contains(&util::canonical_feed_url(source));
+            let changed = report
+                .sources
+                .iter()
+                .any(|changes| is_source(&changes.source) && changes.added + changes.updated + changes.removed > 0);
+            let times: Vec<DateTime<FixedOffset>> = self
+                .posts
+                .iter()
+                .filter(|post| post.source().as_deref().is_some_and(is_source))
+                .filter_map(Post::time)
+                .collect();
+
+            let health = self.source_health.entry(urls[0].clone()).or_default();
+            if changed {
+                health.last_change = Some(now);
+            }
+            let last_activity = times.iter().max().map(|time| time.with_timezone(&Utc)).max(health.last_change);
+            health.cadence = posting_cadence(times);
+            let interval = match &status.result {
+                Ok(_) => schedule.interval(health.cadence, last_activity.and_then(|at| (now - at).to_std().ok())),
+                Err(_) => schedule.min_interval,
+            };
+            let next_check = chrono::Duration::from_std(interval).ok().and_then(|interval| now.checked_add_signed(interval));
+            health.next_check = Some(next_check.unwrap_or(DateTime::<Utc>::MAX_UTC));
+        }
+    }
+
+    /// Merge fetched feeds into the feed, see [`Feed::refresh`].
+    pub(crate) fn merge_fetched(
+        &mut self,
+        user_profile: &Profile,
+        user_posts: Vec<Post>,
+        followed_feeds: Vec<(Profile, Vec<Post>, String)>,
+        options: &RefreshOptions,
+    ) -> RefreshReport {
+        let mut report = RefreshReport::default();
+
+        // Update the profiles, keeping the ones that didn't change
+        match self.profiles.first_mut() {
+            Some(own) if **own == *user_profile => {}
+            Some(own) => *own = Arc::new(user_profile.clone()),
+            None => self.profiles.push(Arc::new(user_profile.clone())),
+        }
+
+        let mut sources = vec![(
+            user_profile.source().cloned().unwrap_or_default(),
+            user_profile.nick().to_string(),
+            user_posts,
+        )];
+        for (profile, posts, source) in latest_per_identity(followed_feeds) {
+            let author_nick = attributed_nick(user_profile, &profile, self.attribution_policy);
+            let key = profile.identity_key();
+            match self.profiles.iter_mut().skip(1).find(|existing| existing.identity_key() == key) {
+                Some(existing) if **existing == profile => {}
+                Some(existing) => *existing = Arc::new(profile),
+                None => self.profiles.push(Arc::new(profile)),
+            }
+            self.source_distances.entry(util::canonical_feed_url(&source)).or_insert(1);
+            sources.push((source, author_nick, posts));
+        }
+
+        let mut positions: HashMap<String, usize> =
+            self.posts.iter().enumerate().map(|(index, post)| (post.full_id(), index)).collect();
+        let own_source = user_profile.source().map(|source| util::canonical_feed_url(source));
+        let canonical = canonical_sources(
+            user_profile,
+            self.profiles.iter().skip(1).filter_map(|profile| Some((profile.as_ref(), profile.source()?.as_str()))),
+        );
+        let rank = |post: &Post| mirror_rank(post, own_source.as_deref(), &canonical);
+        // Best copy of each mirrored post in the feed, and its position
+        let mut mirrors: HashMap<(String, u64), ((u8, String), usize)> = HashMap::new();
+        for (index, post) in self.posts.iter().enumerate() {
+            let rank = rank(post);
+            mirrors
+                .entry(mirror_key(post))
+                .and_modify(|best| *best = std::cmp::min(best.clone(), (rank.clone(), index)))
+                .or_insert((rank, index));
+        }
+        let mut fetched_ids = HashSet::new();
+        let mut refreshed_sources = HashSet::new();
+
+        for (source, author_nick, posts) in sources {
+            refreshed_sources.insert(Some(source.as_str()).filter(|source| !source.is_empty()).map(util::canonical_feed_url));
+            let mut changes = SourceRefresh { source, ..Default::default() };
+
+            for mut post in posts {
+                post.set_author(author_nick.clone());
+                if is_muted(&post, user_profile, &options.extra_mutes) {
+                    continue;
+                }
+                let full_id = post.full_id();
+                fetched_ids.insert(full_id.clone());
+                if options.since.is_some_and(|cutoff| !is_since(&post, cutoff, options.untimed_posts)) {
+                    continue;
+                }
+
+                match positions.get(&full_id) {
+                    Some(&index) if self.posts[index] == post => {}
+                    Some(&index) => {
+                        self.posts[index] = post;
+                        changes.updated += 1;
+                    }
+                    None => {
+                        if self.dedup_policy == DedupPolicy::Collapse {
+                            let rank = rank(&post);
+                            match mirrors.get_mut(&mirror_key(&post)) {
+                                Some((best, _)) if *best < rank => continue,
+                                // A better copy than the one in the feed replaces it
+                                Some((best, index)) if *best > rank => {
+                                    positions.remove(&self.posts[*index].full_id());
+                                    positions.insert(full_id, *index);
+                                    self.posts[*index] = post;
+                                    *best = rank;
+                                    changes.updated += 1;
+                                    continue;
+                                }
+                                Some(_) => {}
+                                None => {
+                                    mirrors.insert(mirror_key(&post), (rank, self.posts.len()));
+                                }
+                            }
+                        }
+                        positions.insert(full_id, self.posts.len());
+                        self.posts.push(post);
+                        changes.added += 1;
+                    }
+                }
+            }
+
+            report.sources.push(changes);
+        }
+
+        if options.remove_missing {
+            // Posts without a source are the user's own
+            let own_source = user_profile.source().filter(|source| !source.is_empty()).map(|source| util::canonical_feed_url(source));
+            let mut removed_per_source: HashMap<Option<String>, usize> = HashMap::new();
+            self.posts.retain(|post| {
+                let source = post.source().as_deref().map(util::canonical_feed_url).or_else(|| own_source.clone());
+                let keep = !refreshed_sources.contains(&source) || fetched_ids.contains(&post.full_id());
+                if !keep {
+                    *removed_per_source.entry(source).or_default() += 1;
+                }
+                keep
+            });
+            for changes in &mut report.sources {
+                let source = Some(changes.source.as_str()).filter(|source| !source.is_empty()).map(util::canonical_feed_url);
+                changes.removed = removed_per_source.remove(&source).unwrap_or_default();
+            }
+        }
+
+        // Posts that got too old are dropped without being reported as removed from their source
+        if let Some(cutoff) = options.since {
+            self.retain_since_with_policy(cutoff, options.untimed_posts);
+        }
+
+        self.sort(self.order);
+        report
+    }
+
+    /// Remove the posts older than `cutoff`, keeping the ones without a parseable timestamp.
+    ///
+    /// # Returns
+    ///
+    /// The number of removed posts.
+    pub fn retain_since(&mut self, cutoff: DateTime<FixedOffset>) -> usize {
+        self.retain_since_with_policy(cutoff, UntimedPostPolicy::Keep)
+    }
+
+    /// Remove the posts older than `cutoff`, see [`Feed::retain_since`].
+    pub fn retain_since_with_policy(&mut self, cutoff: DateTime<FixedOffset>, untimed_posts: UntimedPostPolicy) -> usize {
+        let before = self.posts.len();
+        self.posts.retain(|post| is_since(post, cutoff, untimed_posts));
+        before - self.posts.len()
+    }
+
+    /// Time of the oldest post with a parseable timestamp.
+    pub fn oldest_post_time(&self) -> Option<DateTime<FixedOffset>> {
+        self.posts.iter().filter_map(Post::time).min()
+    }
+
+    /// Time of the newest post with a parseable timestamp.
+    pub fn newest_post_time(&self) -> Option<DateTime<FixedOffset>> {
+        self.posts.iter().filter_map(Post::time).max()
+    }
+
+    /// Sort the posts, and keep them in this order when posts are added or refreshed.
+    ///
+    /// Pagination by time ([`Feed::posts_before`], [`Feed::posts_after`]) follows the order.
+    pub fn sort(&mut self, order: FeedOrder) {
+        self.order = order;
+        self.posts.sort_by(|a, b| order.compare(a, b));
+    }
+
+    /// Insert a post, keeping the feed sorted (see [`Feed::sort`]), e.g. after the user wrote it.
+    ///
+    /// If given, the profile of the post's feed is added to the feed's profiles,
+    /// unless a profile with the same [`Profile::identity_key`] is already there.
+    pub fn add_post(&mut self, post: Post, profile: Option<Arc<Profile>>) {
+        if let Some(profile) = profile {
+            let key = profile.identity_key();
+            if !self.profiles.iter().any(|existing| existing.identity_key() == key) {
+                self.profiles.push(profile);
+            }
+        }
+
+        let index = self.posts.partition_point(|existing| self.order.compare(existing, &post) == std::cmp::Ordering::Less);
+        self.posts.insert(index, post);
+    }
+
+    /// Merge another feed into this one, e.g. a freshly fetched partial feed into one restored from disk.
+    ///
+    /// Posts are matched by full ID and profiles by [`Profile::identity_key`], the policy decides
+    /// which copy is kept when both feeds have one. The feed is sorted again afterwards.
+    pub fn merge(&mut self, other: Feed, policy: FeedMergePolicy) -> MergeReport {
+        let mut report = MergeReport::default();
+
+        for profile in other.profiles {
+            let key = profile.identity_key();
+            match self.profiles.iter_mut().find(|existing| existing.identity_key() == key) {
+                Some(existing) if policy == FeedMergePolicy::TakeIncoming => *existing = profile,
+                Some(_) => {}
+                None => self.profiles.push(profile),
+            }
+        }
+
+        let mut positions: HashMap<String, usize> =
+            self.posts.iter().enumerate().map(|(index, post)| (post.full_id(), index)).collect();
+        for post in other.posts {
+            match positions.get(&post.full_id()) {
+                Some(&index) if policy == FeedMergePolicy::TakeIncoming && self.posts[index] != post => {
+                    self.posts[index] = post;
+                    report.replaced += 1;
+                }
+                Some(_) => report.skipped += 1,
+                None => {
+                    positions.insert(post.full_id(), self.posts.len());
+                    self.posts.push(post);
+                    report.added += 1;
+                }
+            }
+        }
+
+        self.sort(self.order);
+        report
+    }
+
+    /// Remove a post by its full ID, e.g. after the user deleted it. Profiles are left as they are.
+    pub fn remove_post(&mut self, full_id: &str) -> Option<Post> {
+        let index = self.posts.iter().position(|post| post.full_id() == full_id)?;
+        Some(self.posts.remove(index))
+    }
+
+    /// Modify a post in place, moving it if its place in the feed changed.
+    ///
+    /// # Returns
+    ///
+    /// `false` if no post has this full ID.
+    pub fn update_post(&mut self, full_id: &str, f: impl FnOnce(&mut Post)) -> bool {
+        let Some(index) = self.posts.iter().position(|post| post.full_id() == full_id) else {
+            return false;
+        };
+
+        let time = self.posts[index].time();
+        f(&mut self.posts[index]);
+        let post = &self.posts[index];
+
+        if post.time() != time || post.full_id() != full_id {
+            let post = self.posts.remove(index);
+            self.add_post(post, None);
+        }
+        true
+    }
+
+    /// Change which nick is used as the author of posts from followed feeds, and re-attribute them.
+    ///
+    /// Posts are matched to the fetched profiles by source, the user's own posts are left as they are.
+    pub fn set_attribution_policy(&mut self, policy: AttributionPolicy) {
+        self.attribution_policy = policy;
+        let Some((user_profile, followed)) = self.profiles.split_first() else {
+            return;
+        };
+
+        for post in &mut self.posts {
+            let Some(source) = post.source().clone() else {
+                continue;
+            };
+            let profile = followed
+                .iter()
+                .find(|profile| profile.source().map(|profile_source| util::same_feed_url(profile_source, &source)).unwrap_or(false));
+            if let Some(profile) = profile {
+                post.set_author(attributed_nick(user_profile, profile, policy));
+            }
+        }
+    }
+    
+    /// Filter posts by a specific time range.
+    ///
+    /// Returns posts that fall within the specified start and end times.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The start time of the range (inclusive)
+    /// * `end` - The end time of the range (inclusive)
+    ///
+    /// # Returns
+    ///
+    /// A vector of references to posts within the time range.threading
+    pub fn posts_in_range(
+        &self,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> Vec<&Post> {
+        self.posts
+            .iter()
+            .filter(|post| {
+                if let Some(post_time) = post.time() {
+                    post_time >= start && post_time <= end
+                } else {
+                    false
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_recent_posts(&self, limit: usize) -> Vec<&Post> {
+        self.posts.iter().take(limit).collect()
+    }
+
+    /// Get the posts tagged with `tag`, in feed order.
+    ///
+    /// Matches property tags and inline hashtags (see [`Post::all_tags`]) case-insensitively,
+    /// `#tag` and `tag` are considered equal.
+    pub fn posts_with_tag(&self, tag: &str) -> Vec<&Post> {
+        let tag = normalize_tag(tag);
+        self.posts
+            .iter()
+            .filter(|post| post.all_tags().iter().any(|post_tag| normalize_tag(post_tag) == tag))
+            .collect()
+    }
+
+    /// Get all tags used in the feed with the number of posts using them, most used first.
+    ///
+    /// Tags are lowercased and without the leading `#`.
+    pub fn tags(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for post in &self.posts {
+            let tags: HashSet<String> = post.all_tags().iter().map(|tag| normalize_tag(tag)).collect();
+            for tag in tags {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        sort_by_count(counts)
+    }
+
+    /// Get the posts with the mood `mood`, in feed order.
+    ///
+    /// Moods are compared whole and case-insensitively, so an emoji only matches the same emoji
+    /// and not a longer mood starting with it.
+    pub fn posts_with_mood(&self, mood: &str) -> Vec<&Post> {
+        let mood = normalize_mood(mood);
+        if mood.is_empty() {
+            return Vec::new();
+        }
+        self.filter(|post| post.mood().as_deref().is_some_and(|post_mood| normalize_mood(post_mood) == mood))
+    }
+
+    /// Get all moods used in the feed with the number of posts using them, most used first.
+    ///
+    /// Moods are lowercased, so moods only differing in case are counted together.
+    pub fn moods(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for mood in self.posts.iter().filter_map(|post| post.mood().as_deref()).map(normalize_mood) {
+            if !mood.is_empty() {
+                *counts.entry(mood).or_default() += 1;
+            }
+        }
+        sort_by_count(counts)
+    }
+
+    /// Get the posts written in any of `langs`, in feed order. Languages are compared case-insensitively.
+    ///
+    /// Posts without a language are kept or left out depending on `unset`.
+    pub fn posts_in_lang(&self, langs: &[&str], unset: UnsetLangPolicy) -> Vec<&Post> {
+        let langs: Vec<String> = langs.iter().map(|lang| lang.trim().to_lowercase()).collect();
+        self.posts
+            .iter()
+            .filter(|post| {
+                let post_langs = post.langs();
+                if post_langs.is_empty() {
+                    unset == UnsetLangPolicy::Include
+                } else {
+                    post_langs.iter().any(|lang| langs.contains(lang))
+                }
+            })
+            .collect()
+    }
+
+    /// Get all languages used in the feed with the number of posts in them, most used first.
+    pub fn languages(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for post in &self.posts {
+            let langs: HashSet<String> = post.langs().into_iter().collect();
+            for lang in langs {
+                *counts.entry(lang).or_default() += 1;
+            }
+        }
+        sort_by_count(counts)
+    }
+
+    /// Get a page of posts, counting pages from 0. Pages past the end are empty.
+    pub fn page(&self, page: usize, per_page: usize) -> Vec<&Post> {
+        match page.checked_mul(per_page) {
+            Some(start) if start < self.len() => self.posts[start..].iter().take(per_page).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get up to `limit` posts older than `time`, the closest ones to it, in feed order (see [`Feed::sort`]).
+    ///
+    /// In a feed sorted newest first, this is the next page when scrolling down.
+    /// Posts without a valid timestamp are left out. Posts sharing the cursor's timestamp are skipped,
+    /// use [`Feed::posts_before_post`] to page through them.
+    pub fn posts_before(&self, time: DateTime<FixedOffset>, limit: usize) -> Vec<&Post> {
+        match self.order {
+            FeedOrder::NewestFirst => {
+                let start = self.posts.partition_point(|post| post.time().is_some_and(|post_time| post_time >= time));
+                self.posts[start..].iter().take_while(|post| post.time().is_some()).take(limit).collect()
+            }
+            FeedOrder::OldestFirst => {
+                let end = self.posts.partition_point(|post| post.time().is_some_and(|post_time| post_time < time));
+                self.posts[end.saturating_sub(limit)..end].iter().collect()
+            }
+            FeedOrder::ByAuthorThenTime => self.closest_posts(time, limit, true),
+        }
+    }
+
+    /// Get up to `limit` posts newer than `time`, the closest ones to it, in feed order, see [`Feed::posts_before`].
+    pub fn posts_after(&self, time: DateTime<FixedOffset>, limit: usize) -> Vec<&Post> {
+        match self.order {
+            FeedOrder::NewestFirst => {
+                let end = self.posts.partition_point(|post| post.time().is_some_and(|post_time| post_time > time));
+                self.posts[end.saturating_sub(limit)..end].iter().collect()
+            }
+            FeedOrder::OldestFirst => {
+                let start = self.posts.partition_point(|post| post.time().is_some_and(|post_time| post_time <= time));
+                self.posts[start..].iter().take_while(|post| post.time().is_some()).take(limit).collect()
+            }
+            FeedOrder::ByAuthorThenTime => self.closest_posts(time, limit, false),
+        }
+    }
+
+    /// Get up to `limit` posts older (or newer) than `time`, the closest ones to it, in feed order.
+    ///
+    /// Used for orders that aren't sorted by time.
+    fn closest_posts(&self, time: DateTime<FixedOffset>, limit: usize, older: bool) -> Vec<&Post> {
+        let mut matching: Vec<(usize, DateTime<FixedOffset>)> = self.posts.iter()
+            .enumerate()
+            .filter_map(|(index, post)| Some((index, post.time()?)))
+            .filter(|&(_, post_time)| if older { post_time < time } else { post_time > time })
+            .collect();
+        // Closest to the cursor first, then keep the feed order
+        matching.sort_by(|a, b| if older { b.1.cmp(&a.1) } else { a.1.cmp(&b.1) }.then(a.0.cmp(&b.0)));
+        matching.truncate(limit);
+        matching.sort_by_key(|&(index, _)| index);
+        matching.into_iter().map(|(index, _)| &self.posts[index]).collect()
+    }
+
+    /// Get up to `limit` posts coming after `cursor` in the feed, in feed order.
+    ///
+    /// Posts sharing a timestamp are ordered by full ID (see [`FeedOrder`]), so paging
+    /// with the last post of the previous page never skips or repeats posts.
+    pub fn posts_before_post(&self, cursor: &Post, limit: usize) -> Vec<&Post> {
+        let start = self.posts.partition_point(|post| self.order.compare(post, cursor) != std::cmp::Ordering::Greater);
+        self.posts[start..].iter().take(limit).collect()
+    }
+
+    /// Get up to `limit` posts coming before `cursor` in the feed, in feed order, see [`Feed::posts_before_post`].
+    pub fn posts_after_post(&self, cursor: &Post, limit: usize) -> Vec<&Post> {
+        let end = self.posts.partition_point(|post| self.order.compare(post, cursor) == std::cmp::Ordering::Less);
+        self.posts[end.saturating_sub(limit)..end].iter().collect()
+    }
+
+    /// Get the posts matching a predicate, in feed order.
+    pub fn filter(&self, pred: impl Fn(&Post) -> bool) -> Vec<&Post> {
+        self.posts.iter().filter(|post| pred(post)).collect()
+    }
+
+    /// Get the posts of an author, matched case-insensitively.
+    ///
+    /// Posts without an `author` are matched by the nick of the profile they come from.
+    pub fn posts_by_author(&self, nick: &str) -> Vec<&Post> {
+        self.filter(|post| self.is_by_author(post, nick))
+    }
+
+    /// Get the posts not written by any of `nicks`, see [`Feed::posts_by_author`] for the matching rules.
+    pub fn posts_excluding_authors(&self, nicks: &[&str]) -> Vec<&Post> {
+        self.filter(|post| !nicks.iter().any(|nick| self.is_by_author(post, nick)))
+    }
+
+    /// Get the posts not hidden by the session filters, in feed order.
+    ///
+    /// A muted thread hides the post with that full ID and every reply below it.
+    /// If the muted post is missing from the feed, the replies referencing it are hidden too.
+    pub fn filtered_view(&self, filters: &FeedFilterSet) -> Vec<&Post> {
+        if filters.is_empty() {
+            return self.posts.iter().collect();
+        }
+
+        let muted = self.in_muted_threads(&filters.threads);
+        self.posts
+            .iter()
+            .zip(muted)
+            .filter(|(post, muted)| !muted && !filters.hides_post(post))
+            .map(|(post, _)| post)
+            .collect()
+    }
+
+    /// Whether each post of the feed is in one of the muted threads, by index.
+    ///
+    /// Each `reply_to` chain is walked once, the posts met on the way get the result of the whole chain.
+    fn in_muted_threads(&self, threads: &[String]) -> Vec<bool> {
+        let mut muted: Vec<Option<bool>> = vec![if threads.is_empty() { Some(false) } else { None }; self.posts.len()];
+        let threads: HashSet<&str> = threads.iter().map(String::as_str).collect();
+        let lookup = PostLookup::new(&self.posts);
+
+        for start in 0..self.posts.len() {
+            let mut chain = Vec::new();
+            let mut on_chain = HashSet::new();
+            let mut current = start;
+            let result = loop {
+                if let Some(result) = muted[current] {
+                    break result;
+                }
+                let post = &self.posts[current];
+                if threads.contains(post.full_id().as_str()) {
+                    break true;
+                }
+                chain.push(current);
+                on_chain.insert(current);
+                let Some(reply_to) = post.reply_to().as_deref() else {
+                    break false;
+                };
+                match lookup.resolve(reply_to) {
+                    // A loop in the chain, no muted post on it
+                    Some(parent) if on_chain.contains(&parent) => break false,
+                    Some(parent) => current = parent,
+                    // The thread root may be missing from the feed, the topmost post then still references it
+                    None => break threads.contains(reply_to),
+                }
+            };
+            for index in chain {
+                muted[index] = Some(result);
+            }
+            muted[current].get_or_insert(result);
+        }
+
+        muted.into_iter().map(Option::unwrap_or_default).collect()
+    }
+
+    /// Group the posts by the thread they belong to, most recently active thread first.
+    ///
+    /// Each post's `reply_to` chain is followed (see [`Feed::find_post`] for how references are resolved)
+    /// up to the topmost post present in the feed, stopping at loops and after [`DEFAULT_MAX_THREAD_DEPTH`] links.
+    /// A reply to a post missing from the feed starts its own conversation.
+    pub fn conversations(&self) -> Vec<Conversation<'_>> {
+        let lookup = PostLookup::new(&self.posts);
+        let mut by_root: HashMap<usize, Vec<&Post>> = HashMap::new();
+
+        for (index, post) in self.posts.iter().enumerate() {
+            let mut root = index;
+            let mut visited = HashSet::from([index]);
+            while let Some(parent) = self.posts[root].reply_to().as_deref().and_then(|reply_to| lookup.resolve(reply_to)) {
+                if visited.len() > DEFAULT_MAX_THREAD_DEPTH || !visited.insert(parent) {
+                    break;
+                }
+                root = parent;
+            }
+            by_root.entry(root).or_default().push(post);
+        }
+
+        let mut conversations: Vec<Conversation> = by_root
+            .into_iter()
+            .map(|(root, mut posts)| {
+                posts.sort_by(|a, b| a.cmp_by_time(b));
+                Conversation {
+                    root_full_id: self.posts[root].full_id(),
+                    latest_activity: posts.iter().filter_map(|post| post.time()).max(),
+                    posts,
+                }
+            })
+            .collect();
+        conversations.sort_by(|a, b| {
+            util::cmp_optional_times(a.latest_activity, b.latest_activity, true).then_with(|| a.root_full_id.cmp(&b.root_full_id))
+        });
+        conversations
+    }
+
+    /// Capture the posts currently in the feed, to tell later what's new with [`Feed::diff_since`].
+    ///
+    /// Only keeps the full IDs and content hashes, so it's cheap to take after each render.
+    /// The hashes are only meant to be compared within the same process.
+    pub fn snapshot_ref(&self) -> FeedSnapshotRef {
+        FeedSnapshotRef { content_hashes: self.posts.iter().map(|post| (post.full_id(), content_hash(post))).collect() }
+    }
+
+    /// Compare the feed with an earlier [`Feed::snapshot_ref`], e.g. to show the number of new posts.
+    ///
+    /// Changes to a post's content that only affect whitespace don't count as edits.
+    pub fn diff_since(&self, previous: &FeedSnapshotRef) -> FeedDelta<'_> {
+        let mut delta = FeedDelta::default();
+        let mut seen = HashSet::new();
+        for post in &self.posts {
+            let full_id = post.full_id();
+            match previous.content_hashes.get(&full_id) {
+                None => delta.new_posts.push(post),
+                Some(&hash) if hash != content_hash(post) => delta.edited_posts.push(post),
+                Some(_) => {}
+            }
+            seen.insert(full_id);
+        }
+        delta.removed_full_ids = previous.content_hashes.keys().filter(|full_id| !seen.contains(*full_id)).cloned().collect();
+        delta.removed_full_ids.sort();
+        delta
+    }
+
+    /// Get the posts starting a conversation, with replies to posts missing from the feed counted as roots.
+    pub fn root_posts(&self) -> Vec<&Post> {
+        self.root_posts_with_policy(MissingParentPolicy::default())
+    }
+
+    /// Get the posts starting a conversation, in feed order.
+    ///
+    /// A post is a root if it has no `reply_to`, or - with [`MissingParentPolicy::AsRoot`] -
+    /// if the post it replies to isn't in the feed. References are resolved like [`Feed::find_post`].
+    pub fn root_posts_with_policy(&self, missing_parent: MissingParentPolicy) -> Vec<&Post> {
+        let lookup = PostLookup::new(&self.posts);
+        self.filter(|post| Self::is_root(post, &lookup, missing_parent))
+    }
+
+    /// Get the replies, with replies to posts missing from the feed counted as roots (see [`Feed::root_posts`]).
+    pub fn replies(&self) -> Vec<&Post> {
+        self.replies_with_policy(MissingParentPolicy::default())
+    }
+
+    /// Get the replies in feed order - every post not returned by [`Feed::root_posts_with_policy`].
+    pub fn replies_with_policy(&self, missing_parent: MissingParentPolicy) -> Vec<&Post> {
+        let lookup = PostLookup::new(&self.posts);
+        self.filter(|post| !Self::is_root(post, &lookup, missing_parent))
+    }
+
+    /// Get the direct replies to a post, in feed order.
+    ///
+    /// `full_id` and the `reply_to` references are resolved like [`Feed::find_post`]. If the post
+    /// isn't in the feed, the replies referencing exactly `full_id` are returned.
+    pub fn replies_to(&self, full_id: &str) -> Vec<&Post> {
+        let lookup = PostLookup::new(&self.posts);
+        match lookup.resolve(full_id) {
+            Some(target) => self.filter(|post| post.reply_to().as_deref().is_some_and(|reply_to| lookup.resolve(reply_to) == Some(target))),
+            None => self.filter(|post| post.reply_to().as_deref() == Some(full_id)),
+        }
+    }
+
+    fn is_root(post: &Post, lookup: &PostLookup, missing_parent: MissingParentPolicy) -> bool {
+        match post.reply_to().as_deref() {
+            None => true,
+            Some(reply_to) => missing_parent == MissingParentPolicy::AsRoot && lookup.resolve(reply_to).is_none(),
+        }
+    }
+
+    fn is_by_author(&self, post: &Post, nick: &str) -> bool {
+        match post.author() {
+            Some(author) => author.to_lowercase() == nick.to_lowercase(),
+            None => self.profile_for_post(post).is_some_and(|profile| profile.nick().to_lowercase() == nick.to_lowercase()),
+        }
+    }
+
+    pub fn posts_from_source(&self, source: &str) -> Vec<&Post> {
+        self.posts
+            .iter()
+            .filter(|post| {
+                post.source()
+                    .as_ref()
+                    .map(|s| s == source)
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    pub fn sources(&self) -> Vec<String> {
+        let mut sources: Vec<String> = self.posts
+            .iter()
+            .filter_map(|post| post.source().as_ref())
+            .cloned()
+            .collect();
+        
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Fetch the avatars of all profiles in the feed concurrently.
+    ///
+    /// Uses the same concurrency limit as feed fetching, a 30-second timeout and
+    /// `network::DEFAULT_MAX_AVATAR_BYTES` as the size limit. Profiles without an avatar are skipped.
+    /// The requests are made with the [`Feed::network`] handle.
+    pub async fn fetch_avatars(&self) -> Vec<(Arc<Profile>, Result<AvatarData, NetworkError>)> {
+        network::fetch_avatars_with_config(
+            self.profiles.clone(),
+            network::DEFAULT_MAX_AVATAR_BYTES,
+            Some(Duration::from_secs(30)),
+            self.network.config(),
+        )
+        .await
+    }
+
+    /// Get the posts from the feeds a profile follows in a group, see [`Profile::follows_in_group`].
+    pub fn posts_from_group(&self, profile: &Profile, group: &str) -> Vec<&Post> {
+        let urls: Vec<String> = profile.follows_in_group(group).into_iter().map(|follow| follow.url).collect();
+        self.posts
+            .iter()
+            .filter(|post| {
+                post.source()
+                    .as_ref()
+                    .map(|source| urls.iter().any(|url| util::same_feed_url(url, source)))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Compute the posting statistics of an author, identified by nick (case-insensitive) or feed URL.
+    ///
+    /// Returns None if no post in the feed matches.
+    pub fn author_stats(&self, nick_or_source: &str) -> Option<AuthorStats> {
+        let mut builder: Option<AuthorStatsBuilder> = None;
+
+        for post in &self.posts {
+            let nick = self.display_name_for_post(post);
+            let matches_source = post.source().as_ref().map(|source| util::same_feed_url(source, nick_or_source)).unwrap_or(false);
+            if !matches_source && nick.to_lowercase() != nick_or_source.to_lowercase() {
+                continue;
+            }
+            builder
+                .get_or_insert_with(|| AuthorStatsBuilder::new(nick, post.source().clone()))
+                .add(post);
+        }
+
+        builder.map(AuthorStatsBuilder::build)
+    }
+
+    /// Compute the posting statistics of every author in the feed, in a single pass over the posts.
+    ///
+    /// Authors are grouped by feed URL (or by nick for posts without a source),
+    /// and sorted by post count, then nick.
+    pub fn all_author_stats(&self) -> Vec<AuthorStats> {
+        let mut builders: HashMap<String, AuthorStatsBuilder> = HashMap::new();
+
+        for post in &self.posts {
+            let nick = self.display_name_for_post(post);
+            let key = post.source().as_deref().map(util::normalize_url).unwrap_or_else(|| nick.clone());
+            builders
+                .entry(key)
+                .or_insert_with(|| AuthorStatsBuilder::new(nick, post.source().clone()))
+                .add(post);
+        }
+
+        let mut stats: Vec<AuthorStats> = builders.into_values().map(AuthorStatsBuilder::build).collect();
+        stats.sort_by(|a, b| b.post_count.cmp(&a.post_count).then_with(|| a.nick.cmp(&b.nick)));
+        stats
+    }
+
+    /// Find the fetched profiles that follow a user.
+    ///
+    /// A profile follows the user if one of its `#+FOLLOW` URLs points to the user's source,
+    /// ignoring trailing slashes, host case and `http`/`https` differences.
+    /// Returns nothing if the user's profile has no source.
+    pub fn followers_of(&self, me: &Profile) -> Vec<&Arc<Profile>> {
+        let Some(my_source) = me.source() else {
+            return Vec::new();
+        };
+        self.profiles
+            .iter()
+            .filter(|profile| profile.source().map(|source| !util::same_feed_url(source, my_source)).unwrap_or(true))
+            .filter(|profile| profile.follows_feed(my_source))
+            .collect()
+    }
+
+    /// Find the profile of the feed a post comes from, by matching its source URL.
+    ///
+    /// Posts are never matched by their bare ID, which is just a timestamp shared by posts
+    /// of different people. Posts without a source (e.g. the user's own) are matched by author nick.
+    /// Returns None when nothing matches, posts of unknown authors are never attributed to the user.
+    pub fn profile_for_post(&self, post: &Post) -> Option<&Arc<Profile>> {
+        match post.source() {
+            Some(source) => self
+                .profiles
+                .iter()
+                .find(|profile| profile.source().map(|profile_source| util::same_feed_url(profile_source, source)).unwrap_or(false)),
+            None => {
+                let author = post.author().as_deref()?.to_lowercase();
+                if author.is_empty() || author == post::UNKNOWN_AUTHOR {
+                    return None;
+                }
+                self.profiles.iter().find(|profile| profile.nick().to_lowercase() == author)
+            }
+        }
+    }
+
+    /// Name to show as the author of a post.
+    ///
+    /// Uses the post's `author` if set (and not the [`post::UNKNOWN_AUTHOR`] placeholder), then the display name
+    /// of the profile the post comes from, then the host of its source, and finally [`post::UNKNOWN_AUTHOR`].
+    pub fn display_name_for_post(&self, post: &Post) -> String {
+        if let Some(author) = post.author().as_deref().filter(|author| !author.is_empty() && *author != post::UNKNOWN_AUTHOR) {
+            return author.to_string();
+        }
+
+        if let Some(profile) = self.profile_for_post(post) {
+            return profile.display_name().to_string();
+        }
+
+        post.source()
+            .as_deref()
+            .and_then(util::url_host)
+            .unwrap_or(post::UNKNOWN_AUTHOR)
+            .to_string()
+    }
+
+    /// Find the original post shared by a repost, if it's present in the feed.
+    ///
+    /// Matches the full ID first, falling back to the bare post ID if the
+    /// shared reference doesn't include a source.
+    pub fn resolve_share(&self, post: &Post) -> Option<&Post> {
+        let share = post.shared_post_id().as_ref()?;
+
+        if let Some(original) = self.posts.iter().find(|candidate| &candidate.full_id() == share) {
+            return Some(original);
+        }
+
+        if share.contains('#') {
+            None
+        } else {
+            self.posts.iter().find(|candidate| candidate.id() == share)
+        }
+    }
+
+    /// Tally every poll in the feed, ending soonest first. Votes after the deadline are left out.
+    ///
+    /// See [`Feed::polls_with_late_votes`] for how votes are counted.
+    pub fn polls(&self) -> Vec<TalliedPoll<'_>> {
+        self.polls_with_late_votes(LateVotePolicy::Exclude)
+    }
+
+    /// Tally every poll in the feed, ending soonest first.
+    ///
+    /// Votes are the posts replying to the poll with a `:POLL_OPTION:`. Each author
+    /// (or source, for posts without an author) gets one vote - their latest one.
+    /// Votes without a valid timestamp are never considered late.
+    pub fn polls_with_late_votes(&self, late_votes: LateVotePolicy) -> Vec<TalliedPoll<'_>> {
+        let lookup = PostLookup::new(&self.posts);
+
+        // Latest counted vote of each voter, per poll post index. Late votes are dropped
+        // first, so a late vote doesn't replace a voter's earlier valid one.
+        let mut votes: HashMap<usize, HashMap<String, &Post>> = HashMap::new();
+        for vote in self.posts.iter().filter(|post| post.is_poll_vote()) {
+            let Some(index) = vote.reply_to().as_deref().and_then(|reference| lookup.resolve(reference)) else {
+                continue;
+            };
+            let deadline = self.posts[index].poll_end().as_deref().and_then(|end| util::parse_timestamp(end).ok());
+            let is_late = matches!((vote.time(), deadline), (Some(time), Some(deadline)) if time > deadline);
+            if is_late && late_votes == LateVotePolicy::Exclude {
+                continue;
+            }
+            let voter = vote
+                .author()
+                .clone()
+                .or_else(|| vote.source().clone())
+                .unwrap_or_else(|| vote.full_id())
+                .to_lowercase();
+            let latest = votes.entry(index).or_default().entry(voter).or_insert(vote);
+            if vote.cmp_by_time(latest) == std::cmp::Ordering::Greater {
+                *latest = vote;
+            }
+        }
+
+        let mut polls: Vec<TalliedPoll<'_>> = self
+            .posts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, post)| {
+                let mut poll = post.get_poll()?;
+                for vote in votes.get(&index).into_iter().flat_map(HashMap::values) {
+                    poll.add_vote_from_reply(vote);
+                }
+                Some(TalliedPoll { post, poll })
+            })
+            .collect();
+
+        polls.sort_by(|a, b| {
+            let end = |tallied: &TalliedPoll| tallied.poll.poll_end.as_deref().and_then(|end| util::parse_timestamp(end).ok());
+            util::cmp_optional_times(end(a), end(b), false)
+        });
+        polls
+    }
+
+    /// Tally a single poll post, see [`Feed::polls`]. Returns None if the post is not a poll.
+    pub fn tally_poll(&self, poll_post: &Post) -> Option<Poll> {
+        let full_id = poll_post.full_id();
+        self.polls().into_iter().find(|tallied| tallied.post.full_id() == full_id).map(|tallied| tallied.poll)
+    }
+
+    /// Get the bookmarked posts still in the feed, most recently bookmarked first.
+    ///
+    /// Bookmarks are matched by full ID, see [`Feed::dangling_bookmarks`] for the ones that didn't match.
+    pub fn bookmarked_posts(&self, bookmarks: &Bookmarks) -> Vec<&Post> {
+        let by_full_id: HashMap<String, &Post> = self.posts.iter().map(|post| (post.full_id(), post)).collect();
+        bookmarks.iter().filter_map(|bookmark| by_full_id.get(&bookmark.full_id).copied()).collect()
+    }
+
+    /// Get the bookmarks whose posts are no longer in the feed, most recently bookmarked first.
+    pub fn dangling_bookmarks<'a>(&self, bookmarks: &'a Bookmarks) -> Vec<&'a Bookmark> {
+        let full_ids: HashSet<String> = self.posts.iter().map(Post::full_id).collect();
+        bookmarks.iter().filter(|bookmark| !full_ids.contains(&bookmark.full_id)).collect()
+    }
+
+    /// Index the mentions of all posts in the feed in a single pass.
+    ///
+    /// Pass the index to `NotificationFeed::create_notification_feed_with_index` to avoid rescanning the posts,
+    /// and keep it up to date with `MentionIndex::add_post` as posts are added.
+    pub fn build_mention_index(&self) -> MentionIndex {
+        let mut index = MentionIndex::new();
+        for post in &self.posts {
+            index.add_post(post);
+        }
+        index
+    }
+
+    /// Find a post in the feed by a reference as used in `reply_to`.
+    ///
+    /// Matches the full ID (`url#id`) first, then falls back to matching only the
+    /// post ID (the part after the last `#`), the same way the thread view does.
+    pub fn find_post(&self, reference: &str) -> Option<&Post> {
+        if let Some(post) = self.posts.iter().find(|post| post.full_id() == reference) {
+            return Some(post);
+        }
+
+        let id = reference.rsplit('#').next().unwrap_or(reference);
+        self.posts.iter().find(|post| post.id() == id)
+    }
+
+    /// Find the root of the conversation a post belongs to, see `thread_root_of_with_max_depth`.
+    pub fn thread_root_of(&self, post: &Post) -> Option<&Post> {
+        self.thread_root_of_with_max_depth(post, DEFAULT_MAX_THREAD_DEPTH)
+    }
+
+    /// Find the root of the conversation a post belongs to.
+    ///
+    /// Walks the `reply_to` chain through posts present in the feed, following at most `max_depth` links.
+    /// If the chain is broken (a parent is missing from the feed), loops back on itself or is too long,
+    /// the topmost post reached is returned.
+    ///
+    /// # Returns
+    ///
+    /// The root post, or None if the post is neither in the feed nor replying to a post in the feed.
+    pub fn thread_root_of_with_max_depth(&self, post: &Post, max_depth: usize) -> Option<&Post> {
+        self.ancestors_of(post, max_depth)
+            .last()
+            .copied()
+            .or_else(|| self.posts.iter().find(|candidate| candidate.full_id() == post.full_id()))
+    }
+
+    /// Collect the ancestors of a post present in the feed, nearest parent first.
+    ///
+    /// Stops at a missing parent, at a post that was already visited (cycle) or after `max_depth` links.
+    pub(crate) fn ancestors_of(&self, post: &Post, max_depth: usize) -> Vec<&Post> {
+        let mut ancestors = Vec::new();
+        let mut visited = HashSet::new();
+        visited.insert(post.full_id());
+
+        let mut reply_to = post.reply_to().clone();
+        while let Some(target) = reply_to {
+            if ancestors.len() >= max_depth {
+                break;
+            }
+
+            match self.find_post(&target) {
+                Some(parent) if visited.insert(parent.full_id()) => {
+                    ancestors.push(parent);
+                    reply_to = parent.reply_to().clone();
+                }
+                _ => break,
+            }
+        }
+
+        ancestors
+    }
+
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+
+    /// The posts, in feed order (newest first unless changed with [`Feed::sort`]).
+    pub fn posts(&self) -> &[Post] {
+        &self.posts
+    }
+
+    /// Iterate over the posts, in feed order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Post> {
+        self.posts.iter()
+    }
+
+    /// Iterate over the posts with the profile of the feed they come from, see [`Feed::profile_for_post`].
+    pub fn iter_with_profiles(&self) -> impl Iterator<Item = (&Post, Option<&Arc<Profile>>)> {
+        self.posts.iter().map(|post| (post, self.profile_for_post(post)))
+    }
+
+    /// Take the posts out of the feed, in feed order.
+    pub fn into_posts(self) -> Vec<Post> {
+        self.posts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.posts.is_empty()
+    }
+
+}
+
+impl<'a> IntoIterator for &'a Feed {
+    type Item = &'a Post;
+    type IntoIter = std::slice::Iter<'a, Post>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.posts.iter()
+    }
+}
+
+impl std::fmt::Display for Feed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Feed with {} posts:", self.posts.len())?;
+        for (i, post) in self.posts.iter().enumerate() {
+            writeln!(f, "--- Post {} ---", i + 1)?;
+            if let Some(time) = post.time() {
+                writeln!(f, "Time: {time}")?;
+            }
+            if let Some(source) = post.source() {
+                writeln!(f, "Source: {source}")?;
+            }
+            writeln!(f, "{post}")?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tags are compared case-insensitively, with or without a leading `#`.
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Moods are compared case-insensitively, ignoring surrounding whitespace.
+fn normalize_mood(mood: &str) -> String {
+    mood.trim().to_lowercase()
+}
+
+/// Sort `(value, count)` pairs by count, most used first, then by value.
+fn sort_by_count(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// First line of a feed snapshot, followed by the format version.
+#[cfg(feature = "json")]
+const SNAPSHOT_MAGIC: &str = "org-social-feed-snapshot";
+
+/// Version of the snapshot format written by [`Feed::save_snapshot`].
+#[cfg(feature = "json")]
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Errors that can occur while loading a feed snapshot. Available with the `json` feature.
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    /// The file doesn't start with the snapshot header.
+    NotASnapshot,
+    /// The snapshot was written in a format version this library can't read.
+    UnsupportedVersion(u32),
+    /// The snapshot body is not valid JSON, or doesn't have the expected structure.
+    Malformed(String),
+}
+
+#[cfg(feature = "json")]
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "Failed to read snapshot: {error}"),
+            SnapshotError::NotASnapshot => write!(f, "Not a feed snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "Unsupported snapshot version {version}"),
+            SnapshotError::Malformed(reason) => write!(f, "Malformed snapshot: {reason}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl Feed {
+    /// Save the feed to a file, to show it right away on the next start. Available with the `json` feature.
+    ///
+    /// The file starts with a versioned header line, followed by a JSON body with the profiles
+    /// and the posts in org-social format. Fails with [`std::io::ErrorKind::InvalidData`] if a
+    /// profile can't be serialized, rather than writing a snapshot that can't be loaded back.
+    pub fn save_snapshot(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        use serde_json::{json, Value};
+
+        let profiles: Vec<Value> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                serde_json::from_str(&profile.to_json()).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+            })
+            .collect::<std::io::Result<Vec<Value>>>()?;
+        let posts: Vec<Value> = self
+            .posts
+            .iter()
+            .map(|post| json!({ "org": post.to_org_social(), "source": post.source(), "author": post.author() }))
+            .collect();
+        let source_health: serde_json::Map<String, Value> = self
+            .source_health
+            .iter()
+            .map(|(url, health)| {
+                let entry = json!({
+                    "last_success": health.last_success.map(|at| at.to_rfc3339()),
+                    "last_error": health.last_error.as_ref().map(|(at, message)| json!({ "at": at.to_rfc3339(), "message": message })),
+                    "posts": health.posts,
+                    "last_latency_ms": health.last_latency.map(|latency| latency.as_millis() as u64),
+                    "last_change": health.last_change.map(|at| at.to_rfc3339()),
+                    "cadence_secs": health.cadence.map(|cadence| cadence.as_secs()),
+                    "next_check": health.next_check.map(|at| at.to_rfc3339()),
+                });
+                (url.clone(), entry)
+            })
+            .collect();
+        let body = json!({
+            "profiles": profiles,
+            "posts": posts,
+            "attribution_policy": format!("{:?}", self.attribution_policy),
+            "dedup_policy": format!("{:?}", self.dedup_policy),
+            "order": format!("{:?}", self.order),
+            "source_health": source_health,
+        });
+
+        std::fs::write(path, format!("{SNAPSHOT_MAGIC} {SNAPSHOT_VERSION}\n{body}\n"))
+    }
+
+    /// Load a feed saved with [`Feed::save_snapshot`]. Available with the `json` feature.
+    pub fn load_snapshot(path: impl AsRef<std::path::Path>) -> Result<Feed, SnapshotError> {
+        use serde_json::{Map, Value};
+
+        let content = std::fs::read_to_string(path).map_err(SnapshotError::Io)?;
+        let (header, body) = content.split_once('\n').ok_or(SnapshotError::NotASnapshot)?;
+        let version = header
+            .strip_prefix(SNAPSHOT_MAGIC)
+            .and_then(|version| version.trim().parse::<u32>().ok())
+            .ok_or(SnapshotError::NotASnapshot)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let malformed = |reason: &str| SnapshotError::Malformed(reason.to_string());
+        let body: Map<String, Value> = serde_json::from_str(body).map_err(|error| SnapshotError::Malformed(error.to_string()))?;
+
+        let profiles = body
+            .get("profiles")
+            .and_then(Value::as_array)
+            .ok_or_else(|| malformed("missing profiles"))?
+            .iter()
+            .map(|profile| {
+                Profile::from_json(&profile.to_string())
+                    .map(Arc::new)
+                    .map_err(|error| SnapshotError::Malformed(error.to_string()))
+            })
+            .collect::<Result<Vec<Arc<Profile>>, SnapshotError>>()?;
+
+        let posts = body
+            .get("posts")
+            .and_then(Value::as_array)
+            .ok_or_else(|| malformed("missing posts"))?
+            .iter()
+            .map(|entry| {
+                let org = entry.get("org").and_then(Value::as_str).ok_or_else(|| malformed("post without content"))?;
+                let mut post = Post::from(org.lines().map(String::from).collect::<Vec<String>>());
+                post.set_source(entry.get("source").and_then(Value::as_str).map(str::to_string));
+                if let Some(author) = entry.get("author").and_then(Value::as_str) {
+                    post.set_author(author.to_string());
+                }
+                Ok(post)
+            })
+            .collect::<Result<Vec<Post>, SnapshotError>>()?;
+
+        let policy = |key: &str| body.get(key).and_then(Value::as_str).unwrap_or_default();
+        let attribution_policy = match policy("attribution_policy") {
+            "RemoteNickFirst" => AttributionPolicy::RemoteNickFirst,
+            _ => AttributionPolicy::LocalNickFirst,
+        };
+        let dedup_policy = match policy("dedup_policy") {
+            "KeepAll" => DedupPolicy::KeepAll,
+            _ => DedupPolicy::Collapse,
+        };
+        let order = match policy("order") {
+            "OldestFirst" => FeedOrder::OldestFirst,
+            "ByAuthorThenTime" => FeedOrder::ByAuthorThenTime,
+            _ => FeedOrder::NewestFirst,
+        };
+
+        // Older snapshots have no source health.
+        let time = |value: Option<&Value>| {
+            value
+                .and_then(Value::as_str)
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .map(|at| at.with_timezone(&Utc))
+        };
+        let source_health = body
+            .get("source_health")
+            .and_then(Value::as_object)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|(url, entry)| {
+                        let last_error = entry.get("last_error").and_then(|error| {
+                            let message = error.get("message").and_then(Value::as_str)?;
+                            Some((time(error.get("at"))?, message.to_string()))
+                        });
+                        let health = SourceHealth {
+                            last_success: time(entry.get("last_success")),
+                            last_error,
+                            posts: entry.get("posts").and_then(Value::as_u64).unwrap_or_default() as usize,
+                            last_latency: entry.get("last_latency_ms").and_then(Value::as_u64).map(Duration::from_millis),
+                            last_change: time(entry.get("last_change")),
+                            cadence: entry.get("cadence_secs").and_then(Value::as_u64).map(Duration::from_secs),
+                            next_check: time(entry.get("next_check")),
+                        };
+                        (url.clone(), health)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Feed { posts, profiles, attribution_policy, dedup_policy, order, source_health, ..Default::default() })
+    }
+}
+
+/// Check whether a post matches the profile's mutes or any of the extra mutes.
+fn is_muted(post: &Post, user_profile: &Profile, extra_mutes: &[String]) -> bool {
+    user_profile.is_muted(post) || extra_mutes.iter().any(|mute| profile::mute_matches(mute, post))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::NotificationFeed;
+
+    fn reply(id: &str, reply_to: &str) -> Post {
+        let mut post = Post::new(id.to_string(), format!("Post {id}"));
+        post.set_reply_to(Some(reply_to.to_string()));
+        post
+    }
+
+    #[test]
+    fn test_thread_root_of_chain() {
+        let root = Post::new("2025-05-01T10:00:00+00:00".to_string(), "Root".to_string());
+        let middle = reply("2025-05-01T11:00:00+00:00", "2025-05-01T10:00:00+00:00");
+        let leaf = reply("2025-05-01T12:00:00+00:00", "2025-05-01T11:00:00+00:00");
+        let feed = Feed { posts: vec![leaf.clone(), middle, root.clone()], ..Default::default() };
+
+        assert_eq!(feed.thread_root_of(&leaf).map(|p| p.id()), Some(root.id()));
+        assert_eq!(feed.thread_root_of(&root).map(|p| p.id()), Some(root.id()));
+        assert_eq!(
+            leaf.reply_chain_ids(&feed),
+            vec!["2025-05-01T10:00:00+00:00", "2025-05-01T11:00:00+00:00", "2025-05-01T12:00:00+00:00"]
+        );
+
+        // Limited depth stops at the parent
+        assert_eq!(feed.thread_root_of_with_max_depth(&leaf, 1).map(|p| p.id()), Some("2025-05-01T11:00:00+00:00"));
+    }
+
+    #[test]
+    fn test_thread_root_of_broken_chain() {
+        let root = Post::new("2025-05-01T10:00:00+00:00".to_string(), "Root".to_string());
+        let leaf = reply("2025-05-01T12:00:00+00:00", "2025-05-01T11:00:00+00:00");
+        let feed = Feed { posts: vec![leaf.clone(), root], ..Default::default() };
+
+        // The missing middle post stops the walk at the leaf itself
+        assert_eq!(feed.thread_root_of(&leaf).map(|p| p.id()), Some(leaf.id()));
+        assert_eq!(leaf.reply_chain_ids(&feed), vec!["2025-05-01T12:00:00+00:00"]);
+    }
+
+    #[test]
+    fn test_thread_root_of_cycle() {
+        let a = reply("2025-05-01T10:00:00+00:00", "2025-05-01T11:00:00+00:00");
+        let b = reply("2025-05-01T11:00:00+00:00", "2025-05-01T10:00:00+00:00");
+        let feed = Feed { posts: vec![a.clone(), b], ..Default::default() };
+
+        assert_eq!(feed.thread_root_of(&a).map(|p| p.id()), Some("2025-05-01T11:00:00+00:00"));
+        assert_eq!(a.reply_chain_ids(&feed).len(), 2);
+    }
+
+    #[test]
+    fn test_resolve_share() {
+        let mut original = Post::new("2025-05-01T12:00:00+0100".to_string(), "Original".to_string());
+        original.set_source(Some("https://example.com/social.org".to_string()));
+        let repost = Post::repost_of(&original, None);
+        let mut dangling = Post::new("2025-05-03T12:00:00+0100".to_string(), String::new());
+        dangling.set_shared_post_id(Some("https://other.example/social.org#2025-01-01T00:00:00+0000".to_string()));
+
+        let feed = Feed { posts: vec![repost.clone(), original, dangling.clone()], ..Default::default() };
+
+        assert_eq!(feed.resolve_share(&repost).map(|p| p.content()), Some("Original"));
+        assert!(feed.resolve_share(&dangling).is_none());
+        assert!(feed.resolve_share(&feed.posts[1]).is_none());
+    }
+
+    #[test]
+    fn test_display_name_for_post() {
+        let mut remote = Profile::from(vec!["#+TITLE: Bob's feed".to_string()]);
+        remote.set_source(Some("https://bob.example/social.org".to_string()));
+        let feed = Feed { profiles: vec![Arc::new(remote)], ..Default::default() };
+
+        let mut post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Hi".to_string());
+        assert_eq!(feed.display_name_for_post(&post), "unknown");
+
+        post.set_source(Some("https://carol.example/social.org".to_string()));
+        assert_eq!(feed.display_name_for_post(&post), "carol.example");
+
+        post.set_source(Some("http://bob.example/social.org/".to_string()));
+        assert_eq!(feed.display_name_for_post(&post), "Bob's feed");
+
+        post.set_author("unknown".to_string());
+        assert_eq!(feed.display_name_for_post(&post), "Bob's feed");
+
+        post.set_author("bobby".to_string());
+        assert_eq!(feed.display_name_for_post(&post), "bobby");
+    }
+
+    #[test]
+    fn test_posts_from_group() {
+        let profile = Profile::from(vec![
+            "#+NICK: me".to_string(),
+            "#+FOLLOW: alice https://alice.example/social.org rust".to_string(),
+            "#+FOLLOW: bob https://bob.example/social.org friends".to_string(),
+        ]);
+
+        let post_from = |source: &str| {
+            let mut post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Hi".to_string());
+            post.set_source(Some(source.to_string()));
+            post
+        };
+        let feed = Feed {
+            posts: vec![post_from("http://alice.example/social.org/"), post_from("https://bob.example/social.org")],
+            ..Default::default()
+        };
+
+        let rust = feed.posts_from_group(&profile, "rust");
+        assert_eq!(rust.len(), 1);
+        assert_eq!(rust[0].source().as_deref(), Some("http://alice.example/social.org/"));
+        assert!(feed.posts_from_group(&profile, "unknown-group").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_muted_feeds_are_left_out() {
+        use crate::test_support::{http_response, stub_server};
+        use crate::threading::ThreadView;
+        use std::sync::OnceLock;
+
+        // Alice's reply needs the stub's address, which is only known once it's started
+        let base_url = Arc::new(OnceLock::<String>::new());
+        let handler_base_url = base_url.clone();
+        let base = stub_server(move |path| {
+            let base = handler_base_url.get().unwrap();
+            let body = match path {
+                "/spam.org" => "#+NICK: spammer\n\n* Posts\n** 2025-05-01T10:00:00+0000\n:PROPERTIES:\n:END:\nBuy now\n".to_string(),
+                "/alice.org" => format!(
+                    "#+NICK: alice\n\n* Posts\n** 2025-05-01T11:00:00+0000\n:PROPERTIES:\n\
+                     :REPLY_TO: {base}/spam.org#2025-05-01T10:00:00+0000\n:END:\nNo thanks\n"
+                ),
+                _ => return http_response("404 Not Found", &[], b""),
+            };
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+        base_url.set(base.clone()).unwrap();
+
+        let user_profile = Profile::from(vec![
+            "#+NICK: me".to_string(),
+            format!("#+FOLLOW: spammer {base}/spam.org"),
+            format!("#+FOLLOW: alice {base}/alice.org"),
+            format!("#+MUTE: {base}/spam.org"),
+        ]);
+
+        let feed = Feed::create_combined_feed(&user_profile, Vec::new()).await.unwrap();
+        assert_eq!(feed.posts.len(), 1);
+        assert_eq!(feed.posts[0].author().as_deref(), Some("alice"));
+
+        // The reply to the muted post is threaded against a placeholder
+        let thread_view = ThreadView::from_posts(feed.posts.clone());
+        assert_eq!(thread_view.roots.len(), 1);
+        assert_eq!(thread_view.roots[0].post.content(), "[Post not available]");
+        assert_eq!(thread_view.roots[0].replies[0].post.author().as_deref(), Some("alice"));
+
+        // Runtime mutes work on top of the profile's
+        let feed = Feed::create_combined_feed_with_mutes(&user_profile, Vec::new(), &["Alice".to_string()]).await.unwrap();
+        assert!(feed.posts.is_empty());
+    }
+
+    #[test]
+    fn test_followers_of() {
+        let profile = |lines: &[&str], source: &str| {
+            let mut profile = Profile::from(lines.iter().map(|line| line.to_string()).collect::<Vec<String>>());
+            profile.set_source(Some(source.to_string()));
+            Arc::new(profile)
+        };
+        let me = profile(&["#+NICK: me", "#+FOLLOW: me https://me.example/social.org"], "https://me.example/social.org");
+        let feed = Feed {
+            profiles: vec![
+                me.clone(),
+                profile(&["#+NICK: a", "#+FOLLOW: me http://me.example/social.org"], "https://a.example/social.org"),
+                profile(&["#+NICK: b", "#+FOLLOW: me https://Me.example/social.org/"], "https://b.example/social.org"),
+                profile(&["#+NICK: c", "#+FOLLOW: me https://me.example/other.org"], "https://c.example/social.org"),
+                profile(&["#+NICK: d"], "https://d.example/social.org"),
+            ],
+            ..Default::default()
+        };
+
+        let followers: Vec<&str> = feed.followers_of(&me).iter().map(|profile| profile.nick()).collect();
+        assert_eq!(followers, vec!["a", "b"]);
+
+        assert!(feed.followers_of(&Profile::default()).is_empty());
+    }
+
+    fn stats_fixture() -> Feed {
+        let post = |id: &str, author: &str, source: &str, tags: &str| {
+            let mut post = Post::new(id.to_string(), format!("Post {id}"));
+            post.set_author(author.to_string());
+            post.set_source(Some(source.to_string()));
+            if !tags.is_empty() {
+                post.set_tags(Some(tags.split(' ').map(String::from).collect()));
+            }
+            post
+        };
+
+        Feed {
+            posts: vec![
+                post("2025-01-01T12:00:00+0000", "alice", "https://alice.example/social.org", "rust org"),
+                post("2025-01-15T12:00:00+0000", "alice", "https://alice.example/social.org", "rust"),
+                post("2025-01-29T12:00:00+0000", "alice", "https://alice.example/social.org", "Rust emacs"),
+                post("not-a-timestamp", "alice", "https://alice.example/social.org", ""),
+                post("2025-01-10T08:00:00+0000", "bob", "https://bob.example/social.org", "cats"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_author_stats() {
+        let feed = stats_fixture();
+
+        let alice = feed.author_stats("Alice").unwrap();
+        assert_eq!(alice.post_count, 4);
+        assert_eq!(alice.first_post, util::parse_timestamp("2025-01-01T12:00:00+0000").ok());
+        assert_eq!(alice.last_post, util::parse_timestamp("2025-01-29T12:00:00+0000").ok());
+        // 3 timed posts over 4 weeks
+        assert_eq!(alice.posts_per_week, Some(0.75));
+        assert_eq!(alice.top_tags, vec![("rust".to_string(), 3), ("emacs".to_string(), 1), ("org".to_string(), 1)]);
+
+        let by_source = feed.author_stats("http://alice.example/social.org/").unwrap();
+        assert_eq!(by_source, alice);
+
+        // A single post counts as one week
+        let bob = feed.author_stats("bob").unwrap();
+        assert_eq!(bob.posts_per_week, Some(1.0));
+
+        assert!(feed.author_stats("carol").is_none());
+    }
+
+    #[test]
+    fn test_all_author_stats() {
+        let stats = stats_fixture().all_author_stats();
+        let summary: Vec<(&str, usize)> = stats.iter().map(|stats| (stats.nick.as_str(), stats.post_count)).collect();
+        assert_eq!(summary, vec![("alice", 4), ("bob", 1)]);
+        assert_eq!(stats[1].source.as_deref(), Some("https://bob.example/social.org"));
+    }
+
+    #[test]
+    fn test_profiles_deduplicated_by_identity() {
+        let fetched = |source: &str, title: &str| {
+            let mut profile = Profile::from(vec!["#+NICK: alice".to_string(), format!("#+TITLE: {title}")]);
+            profile.set_source(Some(source.to_string()));
+            let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), "Hello".to_string());
+            post.set_source(Some(source.to_string()));
+            (profile, vec![post], source.to_string())
+        };
+
+        let older = fetched("http://alice.example/social.org/", "Old");
+        let newer = fetched("https://alice.example/social.org", "New");
+        assert_eq!(older.0.identity_key(), newer.0.identity_key());
+
+        let feed = Feed::from_fetched_feeds(&Profile::default(), Vec::new(), vec![older, newer], &FeedOptions::default());
+        assert_eq!(feed.profiles.len(), 2);
+        assert_eq!(feed.profiles[1].title(), "New");
+        assert_eq!(feed.posts.len(), 1);
+    }
+
+    #[test]
+    fn test_attribution_policy() {
+        let source = "https://alice.example/social.org";
+        let mut user = Profile::default();
+        user.add_follow("mum".to_string(), source.to_string());
+
+        let mut remote = Profile::from(vec!["#+NICK: alice".to_string()]);
+        remote.set_source(Some(source.to_string()));
+        let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), "Hello".to_string());
+        post.set_source(Some(source.to_string()));
+
+        let mut feed = Feed::from_fetched_feeds(&user, Vec::new(), vec![(remote, vec![post], source.to_string())], &FeedOptions::default());
+        assert_eq!(feed.attribution_policy, AttributionPolicy::LocalNickFirst);
+        assert_eq!(feed.posts[0].author().as_deref(), Some("mum"));
+        assert_eq!(feed.profile_for_post(&feed.posts[0]).unwrap().nick(), "alice");
+
+        feed.set_attribution_policy(AttributionPolicy::RemoteNickFirst);
+        assert_eq!(feed.posts[0].author().as_deref(), Some("alice"));
+
+        feed.set_attribution_policy(AttributionPolicy::LocalNickFirst);
+        assert_eq!(feed.posts[0].author().as_deref(), Some("mum"));
+    }
+
+    #[test]
+    fn test_attribution_url_only_follow() {
+        let source = "https://bob.example/social.org";
+        let user = Profile::from(vec!["#+NICK: me".to_string(), format!("#+FOLLOW: {source}")]);
+        assert_eq!(user.nick_for_url(source), None);
+
+        let mut remote = Profile::from(vec!["#+NICK: bob".to_string()]);
+        remote.set_source(Some(source.to_string()));
+        let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), "Hello".to_string());
+        post.set_source(Some(source.to_string()));
+
+        let feed = Feed::from_fetched_feeds(&user, Vec::new(), vec![(remote, vec![post], source.to_string())], &FeedOptions::default());
+        assert_eq!(feed.attribution_policy, AttributionPolicy::LocalNickFirst);
+        assert_eq!(feed.posts[0].author().as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn test_merge_fetched() {
+        let source = "https://alice.example/social.org";
+        let fetched = |posts: &[(&str, &str)]| {
+            let mut profile = Profile::from(vec!["#+NICK: alice".to_string()]);
+            profile.set_source(Some(source.to_string()));
+            let posts = posts
+                .iter()
+                .map(|(id, content)| {
+                    let mut post = Post::new(id.to_string(), content.to_string());
+                    post.set_source(Some(source.to_string()));
+                    post
+                })
+                .collect();
+            vec![(profile, posts, source.to_string())]
+        };
+        let user = Profile::from(vec!["#+NICK: me".to_string()]);
+        let own_post = Post::new("2025-01-01T10:00:00+0000".to_string(), "Mine".to_string());
+
+        let mut feed = Feed::from_fetched_feeds(
+            &user,
+            vec![own_post.clone()],
+            fetched(&[("2025-01-01T12:00:00+0000", "First"), ("2025-01-02T12:00:00+0000", "Second")]),
+            &FeedOptions::default(),
+        );
+        let alice_profile = feed.profiles[1].clone();
+
+        let report = feed.merge_fetched(
+            &user,
+            vec![own_post.clone()],
+            fetched(&[("2025-01-01T12:00:00+0000", "First, edited"), ("2025-01-03T12:00:00+0000", "Third")]),
+            &RefreshOptions::default(),
+        );
+        assert_eq!(report.sources.len(), 2);
+        assert_eq!(report.sources[0], SourceRefresh::default());
+        assert_eq!(
+            report.sources[1],
+            SourceRefresh { source: source.to_string(), added: 1, updated: 1, removed: 0 }
+        );
+        assert!(Arc::ptr_eq(&alice_profile, &feed.profiles[1]));
+        let contents: Vec<&str> = feed.posts.iter().map(|post| post.content()).collect();
+        assert_eq!(contents, vec!["Third", "Second", "First, edited", "Mine"]);
+
+        let options = RefreshOptions { remove_missing: true, ..Default::default() };
+        let report = feed.merge_fetched(
+            &user,
+            vec![own_post],
+            fetched(&[("2025-01-01T12:00:00+0000", "First, edited"), ("2025-01-03T12:00:00+0000", "Third")]),
+            &options,
+        );
+        assert_eq!((report.added(), report.updated(), report.removed()), (0, 0, 1));
+        let contents: Vec<&str> = feed.posts.iter().map(|post| post.content()).collect();
+        assert_eq!(contents, vec!["Third", "First, edited", "Mine"]);
+
+        // Nothing changed
+        assert!(feed.merge_fetched(&user, Vec::new(), Vec::new(), &RefreshOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_merge_fetched_removes_deleted_own_posts() {
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.set_source(Some("https://me.example/social.org".to_string()));
+        let own_post = |id: &str| Post::new(id.to_string(), format!("Mine {id}"));
+        let mut feed = Feed::create_user_feed(&user, vec![own_post("2025-01-01T10:00:00+0000"), own_post("2025-01-02T10:00:00+0000")]);
+
+        let options = RefreshOptions { remove_missing: true, ..Default::default() };
+        let report = feed.merge_fetched(&user, vec![own_post("2025-01-02T10:00:00+0000")], Vec::new(), &options);
+        assert_eq!(report.sources[0].removed, 1);
+        let ids: Vec<&str> = feed.posts.iter().map(|post| post.id()).collect();
+        assert_eq!(ids, vec!["2025-01-02T10:00:00+0000"]);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_skips_unchanged_sources() {
+        use crate::network::MemoryFeedCache;
+        use crate::test_support::{http_response, stub_server};
+
+        let base = stub_server(|_| {
+            http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n** 2025-01-01T12:00:00+0000\nHello\n")
+        })
+        .await;
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/social.org"));
+        let options = RefreshOptions { cache: Some(Arc::new(MemoryFeedCache::new())), ..Default::default() };
+
+        let mut feed = Feed::create_user_feed(&user, Vec::new());
+        let report = feed.refresh(&user, Vec::new(), options.clone()).await;
+        assert_eq!(report.added(), 1);
+        assert_eq!(feed.posts.len(), 1);
+
+        let report = feed.refresh(&user, Vec::new(), options).await;
+        assert_eq!(report.sources.len(), 1);
+        assert!(report.is_empty());
+        assert_eq!(feed.posts.len(), 1);
+    }
+
+    fn pagination_fixture() -> Feed {
+        let mut posts: Vec<Post> = [
+            ("2025-01-03T12:00:00+0000", "https://a.example/social.org"),
+            ("2025-01-02T12:00:00+0000", "https://c.example/social.org"),
+            ("2025-01-02T12:00:00+0000", "https://a.example/social.org"),
+            ("2025-01-02T12:00:00+0000", "https://b.example/social.org"),
+            ("2025-01-01T12:00:00+0000", "https://a.example/social.org"),
+            ("no-timestamp", "https://a.example/social.org"),
+        ]
+        .iter()
+        .map(|(id, source)| {
+            let mut post = Post::new(id.to_string(), String::new());
+            post.set_source(Some(source.to_string()));
+            post
+        })
+        .collect();
+        post::sort_newest_first(&mut posts);
+        Feed { posts, ..Default::default() }
+    }
+
+    fn full_ids(posts: Vec<&Post>) -> Vec<String> {
+        posts.iter().map(|post| post.full_id()).collect()
+    }
+
+    #[test]
+    fn test_page() {
+        let feed = pagination_fixture();
+        assert_eq!(feed.page(0, 4).len(), 4);
+        assert_eq!(feed.page(1, 4).len(), 2);
+        assert!(feed.page(2, 4).is_empty());
+        assert!(feed.page(0, 0).is_empty());
+        assert!(feed.page(usize::MAX, 2).is_empty());
+    }
+
+    #[test]
+    fn test_time_cursors() {
+        let feed = pagination_fixture();
+        let time = |id: &str| util::parse_timestamp(id).unwrap();
+
+        // Same-timestamp posts are returned in full ID order
+        assert_eq!(
+            full_ids(feed.posts_before(time("2025-01-03T12:00:00+0000"), 3)),
+            vec![
+                "https://a.example/social.org#2025-01-02T12:00:00+0000",
+                "https://b.example/social.org#2025-01-02T12:00:00+0000",
+                "https://c.example/social.org#2025-01-02T12:00:00+0000",
+            ]
+        );
+        // Posts without a timestamp are left out
+        assert_eq!(feed.posts_before(time("2025-01-02T12:00:00+0000"), 10).len(), 1);
+        assert!(feed.posts_before(time("2025-01-01T00:00:00+0000"), 10).is_empty());
+
+        assert_eq!(
+            full_ids(feed.posts_after(time("2025-01-01T12:00:00+0000"), 2)),
+            vec![
+                "https://b.example/social.org#2025-01-02T12:00:00+0000",
+                "https://c.example/social.org#2025-01-02T12:00:00+0000",
+            ]
+        );
+        assert_eq!(feed.posts_after(time("2025-01-01T12:00:00+0000"), 10).len(), 4);
+        assert!(feed.posts_after(time("2025-01-03T12:00:00+0000"), 10).is_empty());
+    }
+
+    #[test]
+    fn test_time_cursors_other_orders() {
+        let time = |id: &str| util::parse_timestamp(id).unwrap();
+        let mut feed = Feed::default();
+        for (day, author) in [(1, "bob"), (2, "alice"), (3, "bob"), (4, "alice"), (5, "bob")] {
+            let mut post = Post::new(format!("2025-01-0{day}T12:00:00+0000"), "Hello".to_string());
+            post.set_source(Some("https://a.example/social.org".to_string()));
+            post.set_author(author.to_string());
+            feed.posts.push(post);
+        }
+        let ids = |posts: Vec<&Post>| posts.iter().map(|post| post.id().to_string()).collect::<Vec<_>>();
+        let cursor = time("2025-01-03T12:00:00+0000");
+
+        feed.sort(FeedOrder::OldestFirst);
+        assert_eq!(ids(feed.posts_before(cursor, 10)), ["2025-01-01T12:00:00+0000", "2025-01-02T12:00:00+0000"]);
+        assert_eq!(ids(feed.posts_after(cursor, 10)), ["2025-01-04T12:00:00+0000", "2025-01-05T12:00:00+0000"]);
+        // The closest posts to the cursor
+        assert_eq!(ids(feed.posts_before(cursor, 1)), ["2025-01-02T12:00:00+0000"]);
+        assert_eq!(ids(feed.posts_after(cursor, 1)), ["2025-01-04T12:00:00+0000"]);
+
+        // Grouped by author: alice (4, 2), then bob (5, 3, 1)
+        feed.sort(FeedOrder::ByAuthorThenTime);
+        assert_eq!(ids(feed.posts_before(cursor, 10)), ["2025-01-02T12:00:00+0000", "2025-01-01T12:00:00+0000"]);
+        assert_eq!(ids(feed.posts_after(cursor, 10)), ["2025-01-04T12:00:00+0000", "2025-01-05T12:00:00+0000"]);
+        assert_eq!(ids(feed.posts_after(cursor, 1)), ["2025-01-04T12:00:00+0000"]);
+        assert_eq!(ids(feed.posts_before(cursor, 1)), ["2025-01-02T12:00:00+0000"]);
+    }
+
+    #[test]
+    fn test_post_cursors_are_stable() {
+        let feed = pagination_fixture();
+
+        // Paging two at a time visits every post exactly once
+        let mut seen = full_ids(feed.page(0, 2));
+        loop {
+            let cursor = feed.find_post(seen.last().unwrap()).unwrap();
+            let next = full_ids(feed.posts_before_post(cursor, 2));
+            if next.is_empty() {
+                break;
+            }
+            seen.extend(next);
+        }
+        assert_eq!(seen, full_ids(feed.posts.iter().collect()));
+
+        let cursor = &feed.posts[3];
+        assert_eq!(full_ids(feed.posts_after_post(cursor, 2)), full_ids(feed.posts[1..3].iter().collect()));
+        assert!(feed.posts_after_post(&feed.posts[0], 2).is_empty());
+    }
+
+    #[test]
+    fn test_posts_with_tag() {
+        let post = |id: &str, tags: Option<&str>, content: &str| {
+            let mut post = Post::new(id.to_string(), content.to_string());
+            post.set_tags(tags.map(|tags| tags.split(' ').map(String::from).collect()));
+            post
+        };
+        let feed = Feed {
+            posts: vec![
+                post("2025-01-03T12:00:00+0000", Some("Rust emacs"), "Hello"),
+                post("2025-01-02T12:00:00+0000", None, "Learning #rust today"),
+                post("2025-01-01T12:00:00+0000", None, "No tags at all"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(feed.posts_with_tag("rust").len(), 2);
+        assert_eq!(feed.posts_with_tag("#RUST").len(), 2);
+        assert_eq!(feed.posts_with_tag("emacs")[0].id(), "2025-01-03T12:00:00+0000");
+        assert!(feed.posts_with_tag("vim").is_empty());
+
+        assert_eq!(feed.tags(), vec![("rust".to_string(), 2), ("emacs".to_string(), 1)]);
+        assert!(Feed::default().tags().is_empty());
+    }
+
+    #[test]
+    fn test_posts_in_lang() {
+        let post = |id: &str, lang: Option<&str>| {
+            let mut post = Post::new(id.to_string(), String::new());
+            post.set_lang(lang.map(String::from));
+            post
+        };
+        let feed = Feed {
+            posts: vec![
+                post("2025-01-04T12:00:00+0000", Some("en")),
+                post("2025-01-03T12:00:00+0000", Some("ES")),
+                post("2025-01-02T12:00:00+0000", Some("en es")),
+                post("2025-01-01T12:00:00+0000", None),
+            ],
+            ..Default::default()
+        };
+        let ids = |posts: Vec<&Post>| posts.iter().map(|post| &post.id()[8..10]).collect::<Vec<&str>>().join(",");
+
+        assert_eq!(ids(feed.posts_in_lang(&["es"], UnsetLangPolicy::Exclude)), "03,02");
+        assert_eq!(ids(feed.posts_in_lang(&["EN"], UnsetLangPolicy::Include)), "04,02,01");
+        assert_eq!(ids(feed.posts_in_lang(&["de"], UnsetLangPolicy::Exclude)), "");
+
+        assert_eq!(feed.languages(), vec![("en".to_string(), 2), ("es".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_posts_by_author() {
+        let source = "https://alice.example/social.org";
+        let mut alice = Profile::from(vec!["#+NICK: Alice".to_string()]);
+        alice.set_source(Some(source.to_string()));
+
+        let mut unattributed = Post::new("2025-01-03T12:00:00+0000".to_string(), String::new());
+        unattributed.set_source(Some(source.to_string()));
+        let mut by_alice = Post::new("2025-01-02T12:00:00+0000".to_string(), String::new());
+        by_alice.set_author("alice".to_string());
+        let mut by_bob = Post::new("2025-01-01T12:00:00+0000".to_string(), String::new());
+        by_bob.set_author("bob".to_string());
+
+        let feed = Feed {
+            posts: vec![unattributed, by_alice, by_bob],
+            profiles: vec![Arc::new(alice)],
+            ..Default::default()
+        };
+
+        assert_eq!(feed.posts_by_author("ALICE").len(), 2);
+        assert_eq!(feed.posts_by_author("bob").len(), 1);
+        assert!(feed.posts_by_author("carol").is_empty());
+
+        let others = feed.posts_excluding_authors(&["alice"]);
+        assert_eq!(others.len(), 1);
+        assert_eq!(others[0].author().as_deref(), Some("bob"));
+
+        let recent_by_alice =
+            feed.filter(|post| feed.is_by_author(post, "alice") && post.id() > "2025-01-02T13:00:00+0000");
+        assert_eq!(recent_by_alice.len(), 1);
+    }
+
+    #[test]
+    fn test_mirrored_posts_collapsed() {
+        let primary = "https://alice.example/social.org";
+        let mirror = "https://mirror.example/alice.org";
+        let fetched = |source: &str| {
+            let mut profile = Profile::from(vec!["#+NICK: alice".to_string()]);
+            profile.set_source(Some(source.to_string()));
+            let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), "Hello".to_string());
+            post.set_source(Some(source.to_string()));
+            (profile, vec![post], source.to_string())
+        };
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), primary.to_string());
+        user.add_follow("alice-mirror".to_string(), mirror.to_string());
+
+        let feed = Feed::from_fetched_feeds(&user, Vec::new(), vec![fetched(primary), fetched(mirror)], &FeedOptions::default());
+        assert_eq!(feed.posts.len(), 1);
+        assert_eq!(feed.posts[0].source().as_deref(), Some(primary));
+
+        // The canonical copy is kept whatever the follow order
+        let reversed = Feed::from_fetched_feeds(&user, Vec::new(), vec![fetched(mirror), fetched(primary)], &FeedOptions::default());
+        assert_eq!(reversed.posts.len(), 1);
+        assert_eq!(reversed.posts[0].source().as_deref(), Some(primary));
+
+        // A refresh replaces a mirrored copy with the canonical one
+        let mut from_mirror = Feed::from_fetched_feeds(&user, Vec::new(), vec![fetched(mirror)], &FeedOptions::default());
+        let report = from_mirror.merge_fetched(&user, Vec::new(), vec![fetched(mirror), fetched(primary)], &RefreshOptions::default());
+        assert!(!report.is_empty());
+        assert_eq!(from_mirror.posts.len(), 1);
+        assert_eq!(from_mirror.posts[0].source().as_deref(), Some(primary));
+        assert_eq!(feed.profile_for_post(&feed.posts[0]).unwrap().source().map(String::as_str), Some(primary));
+        assert_eq!(crate::threading::ThreadView::from_posts(feed.posts.clone()).total_posts(), 1);
+
+        // A refresh doesn't bring the mirrored copy back
+        let mut feed = feed;
+        let report = feed.merge_fetched(&user, Vec::new(), vec![fetched(primary), fetched(mirror)], &RefreshOptions::default());
+        assert!(report.is_empty());
+        assert_eq!(feed.posts.len(), 1);
+
+        let options = FeedOptions { dedup_policy: DedupPolicy::KeepAll, ..Default::default() };
+        let feed = Feed::from_fetched_feeds(&user, Vec::new(), vec![fetched(primary), fetched(mirror)], &options);
+        assert_eq!(feed.posts.len(), 2);
+    }
+
+    #[test]
+    fn test_profile_for_same_second_posts() {
+        let profile = |nick: &str, source: Option<&str>| {
+            let mut profile = Profile::from(vec![format!("#+NICK: {nick}")]);
+            profile.set_source(source.map(String::from));
+            Arc::new(profile)
+        };
+        let post = |source: Option<&str>, author: &str| {
+            let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), String::new());
+            post.set_source(source.map(String::from));
+            post.set_author(author.to_string());
+            post
+        };
+        let alice = "https://alice.example/social.org";
+        let bob = "https://bob.example/social.org";
+        let feed = Feed {
+            posts: vec![post(Some(alice), "alice"), post(Some(bob), "bob"), post(None, "me")],
+            profiles: vec![profile("me", None), profile("alice", Some(alice)), profile("bob", Some(bob))],
+            ..Default::default()
+        };
+
+        let nicks: Vec<&str> = feed.posts.iter().map(|post| feed.profile_for_post(post).unwrap().nick()).collect();
+        assert_eq!(nicks, vec!["alice", "bob", "me"]);
+    }
+
+    #[test]
+    fn test_post_mutations_keep_order() {
+        let post = |id: &str, source: &str| {
+            let mut post = Post::new(id.to_string(), String::new());
+            post.set_source(Some(source.to_string()));
+            post
+        };
+        let is_sorted =
+            |feed: &Feed| feed.posts.windows(2).all(|pair| pair[0].cmp_newest_first(&pair[1]) != std::cmp::Ordering::Greater);
+        let alice = "https://alice.example/social.org";
+        let bob = "https://bob.example/social.org";
+
+        let mut feed = Feed::default();
+        let mut bob_profile = Profile::from(vec!["#+NICK: bob".to_string()]);
+        bob_profile.set_source(Some(bob.to_string()));
+        let bob_profile = Arc::new(bob_profile);
+
+        feed.add_post(post("2025-01-02T12:00:00+0000", alice), None);
+        feed.add_post(post("2025-01-04T12:00:00+0000", bob), Some(bob_profile.clone()));
+        feed.add_post(post("2025-01-03T12:00:00+0000", alice), None);
+        feed.add_post(post("2025-01-02T12:00:00+0000", bob), Some(bob_profile));
+        feed.add_post(post("not-a-timestamp", alice), None);
+        assert!(is_sorted(&feed));
+        assert_eq!(feed.profiles.len(), 1);
+        assert_eq!(feed.profile_for_post(&feed.posts[0]).unwrap().nick(), "bob");
+
+        let removed = feed.remove_post(&format!("{alice}#2025-01-03T12:00:00+0000")).unwrap();
+        assert_eq!(removed.id(), "2025-01-03T12:00:00+0000");
+        assert!(feed.remove_post(&format!("{alice}#2025-01-03T12:00:00+0000")).is_none());
+
+        assert!(feed.update_post(&format!("{alice}#2025-01-02T12:00:00+0000"), |post| {
+            post.set_id("2025-01-05T12:00:00+0000".to_string())
+        }));
+        assert!(feed.update_post(&format!("{bob}#2025-01-04T12:00:00+0000"), |post| post.set_content("Edited".to_string())));
+        assert!(!feed.update_post("missing", |_| {}));
+        assert!(is_sorted(&feed));
+
+        let ids: Vec<&str> = feed.posts.iter().map(|post| post.id()).collect();
+        assert_eq!(
+            ids,
+            vec!["2025-01-05T12:00:00+0000", "2025-01-04T12:00:00+0000", "2025-01-02T12:00:00+0000", "not-a-timestamp"]
+        );
+        assert_eq!(feed.posts[1].content(), "Edited");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_snapshot_round_trip() {
+        let alice = "https://alice.example/social.org";
+        let bob = "https://bob.example/social.org";
+        let fetched = |nick: &str, source: &str, ids: &[&str]| {
+            let mut profile = Profile::from(vec![format!("#+NICK: {nick}"), "#+LINK: [[https://blog.example][Blog]]".to_string()]);
+            profile.set_source(Some(source.to_string()));
+            let posts = ids
+                .iter()
+                .map(|id| {
+                    let mut post = Post::new(id.to_string(), format!("Hello from {nick}\nsecond line"));
+                    post.set_source(Some(source.to_string()));
+                    post.set_tags(Some(vec!["test".to_string()]));
+                    post
+                })
+                .collect();
+            (profile, posts, source.to_string())
+        };
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), alice.to_string());
+        let feed = Feed::from_fetched_feeds(
+            &user,
+            vec![Post::new("2025-01-05T12:00:00+0000".to_string(), "Mine".to_string())],
+            vec![
+                fetched("alice", alice, &["2025-01-01T12:00:00+0000", "2025-01-03T12:00:00+0000"]),
+                fetched("bob", bob, &["2025-01-01T12:00:00+0000"]),
+            ],
+            &FeedOptions { attribution_policy: AttributionPolicy::RemoteNickFirst, ..Default::default() },
+        );
+
+        let path = std::env::temp_dir().join(format!("org-social-snapshot-test-{}", std::process::id()));
+        feed.save_snapshot(&path).unwrap();
+        let loaded = Feed::load_snapshot(&path).unwrap();
+
+        let summary = |feed: &Feed| -> Vec<(String, String, String, Vec<String>)> {
+            feed.posts
+                .iter()
+                .map(|post| {
+                    let profile = feed.profile_for_post(post).unwrap();
+                    (post.full_id(), profile.nick().to_string(), post.content().to_string(), post.all_tags())
+                })
+                .collect()
+        };
+        assert_eq!(summary(&loaded), summary(&feed));
+        assert_eq!(loaded.profiles, feed.profiles);
+        assert_eq!(loaded.attribution_policy, AttributionPolicy::RemoteNickFirst);
+
+        std::fs::write(&path, "org-social-feed-snapshot 99\n{}").unwrap();
+        assert!(matches!(Feed::load_snapshot(&path), Err(SnapshotError::UnsupportedVersion(99))));
+        std::fs::write(&path, "{}").unwrap();
+        assert!(matches!(Feed::load_snapshot(&path), Err(SnapshotError::NotASnapshot)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_from_urls() {
+        use crate::test_support::{http_response, stub_server};
+
+        let base = stub_server(|path| {
+            let body = match path {
+                "/alice.org" => "#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n",
+                "/bob.org" => "#+NICK: bob\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-02T12:00:00+0000\n:END:\n\nHi\n",
+                _ => return http_response("404 Not Found", &[], b""),
+            };
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+        let url = |path: &str| format!("{base}{path}");
+
+        let feed = Feed::from_urls(&[url("/alice.org"), url("/bob.org")], None).await.unwrap();
+        let authors: Vec<&str> = feed.posts.iter().filter_map(|post| post.author().as_deref()).collect();
+        assert_eq!(authors, vec!["bob", "alice"]);
+        assert_eq!(feed.profile_for_post(&feed.posts[0]).unwrap().nick(), "bob");
+
+        match Feed::from_urls(&[url("/alice.org"), url("/missing.org")], None).await {
+            Err(FeedError::PartialFailure { feed, failures }) => {
+                assert_eq!(feed.posts.len(), 1);
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].url, url("/missing.org"));
+            }
+            other => panic!("Expected a partial failure, got {other:?}"),
+        }
+
+        assert!(matches!(
+            Feed::from_urls(&[url("/missing.org")], None).await,
+            Err(FeedError::AllFailed { failures }) if failures.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_merge_feeds() {
+        let alice = "https://alice.example/social.org";
+        let post = |id: &str, content: &str| {
+            let mut post = Post::new(id.to_string(), content.to_string());
+            post.set_source(Some(alice.to_string()));
+            post
+        };
+        let profile = |title: &str| {
+            let mut profile = Profile::from(vec!["#+NICK: alice".to_string(), format!("#+TITLE: {title}")]);
+            profile.set_source(Some(alice.to_string()));
+            Arc::new(profile)
+        };
+        let cached = || Feed {
+            posts: vec![post("2025-01-02T12:00:00+0000", "Same"), post("2025-01-01T12:00:00+0000", "Old content")],
+            profiles: vec![profile("Cached")],
+            ..Default::default()
+        };
+        let fresh = || Feed {
+            posts: vec![
+                post("2025-01-03T12:00:00+0000", "New"),
+                post("2025-01-02T12:00:00+0000", "Same"),
+                post("2025-01-01T12:00:00+0000", "Edited content"),
+            ],
+            profiles: vec![profile("Fresh")],
+            ..Default::default()
+        };
+
+        let mut feed = cached();
+        let report = feed.merge(fresh(), FeedMergePolicy::TakeIncoming);
+        assert_eq!(report, MergeReport { added: 1, replaced: 1, skipped: 1 });
+        let contents: Vec<&str> = feed.posts.iter().map(|post| post.content()).collect();
+        assert_eq!(contents, vec!["New", "Same", "Edited content"]);
+        assert_eq!(feed.profiles.len(), 1);
+        assert_eq!(feed.profiles[0].title(), "Fresh");
+
+        let mut feed = cached();
+        let report = feed.merge(fresh(), FeedMergePolicy::KeepExisting);
+        assert_eq!(report, MergeReport { added: 1, replaced: 0, skipped: 2 });
+        assert_eq!(feed.posts[2].content(), "Old content");
+        assert_eq!(feed.profiles[0].title(), "Cached");
+    }
+
+    #[test]
+    fn test_feed_iterators() {
+        let alice = "https://alice.example/social.org";
+        let mut profile = Profile::from(vec!["#+NICK: alice".to_string()]);
+        profile.set_source(Some(alice.to_string()));
+        let mut from_alice = Post::new("2025-01-02T12:00:00+0000".to_string(), String::new());
+        from_alice.set_source(Some(alice.to_string()));
+        let unknown = Post::new("2025-01-01T12:00:00+0000".to_string(), String::new());
+
+        let feed = Feed { posts: vec![from_alice, unknown], profiles: vec![Arc::new(profile)], ..Default::default() };
+
+        fn count<'a>(posts: impl IntoIterator<Item = &'a Post>) -> usize {
+            posts.into_iter().count()
+        }
+        assert_eq!(count(&feed), 2);
+        assert_eq!(feed.iter().next().unwrap().id(), "2025-01-02T12:00:00+0000");
+
+        let nicks: Vec<Option<&str>> = feed.iter_with_profiles().map(|(_, profile)| profile.map(|profile| profile.nick())).collect();
+        assert_eq!(nicks, vec![Some("alice"), None]);
+
+        assert_eq!(feed.posts().len(), 2);
+        assert_eq!(feed.into_posts().len(), 2);
+    }
+
+    #[test]
+    fn test_polls() {
+        let source = "https://alice.example/social.org";
+        let mut poll_post = Post::new(
+            "2025-01-01T12:00:00+0000".to_string(),
+            "Favourite editor?\n- [ ] Emacs\n- [ ] Vim".to_string(),
+        );
+        poll_post.set_source(Some(source.to_string()));
+        poll_post.set_poll_end(Some("2025-01-02T12:00:00+0000".to_string()));
+        let poll_id = poll_post.full_id();
+
+        let vote = |id: &str, author: &str, option: &str| {
+            let mut vote = Post::new(id.to_string(), String::new());
+            vote.set_author(author.to_string());
+            vote.set_reply_to(Some(poll_id.clone()));
+            vote.set_poll_option(Some(option.to_string()));
+            vote
+        };
+
+        let mut posts = vec![
+            poll_post,
+            vote("2025-01-01T13:00:00+0000", "bob", "Emacs"),
+            vote("2025-01-01T14:00:00+0000", "carol", "Vim"),
+            // Dave changed their mind, only the latest vote counts
+            vote("2025-01-01T15:00:00+0000", "dave", "Vim"),
+            vote("2025-01-01T16:00:00+0000", "Dave", "Emacs"),
+            // After the deadline
+            vote("2025-01-03T12:00:00+0000", "erin", "Vim"),
+            // A late change of mind doesn't cancel Carol's valid vote
+            vote("2025-01-03T13:00:00+0000", "carol", "Emacs"),
+        ];
+        post::sort_newest_first(&mut posts);
+        let feed = Feed { posts, ..Default::default() };
+
+        let polls = feed.polls();
+        assert_eq!(polls.len(), 1);
+        assert_eq!(polls[0].post.full_id(), poll_id);
+        let votes: Vec<(String, usize)> = polls[0].poll.options.iter().map(|option| (option.text.clone(), option.votes)).collect();
+        assert_eq!(votes, vec![("Emacs".to_string(), 2), ("Vim".to_string(), 1)]);
+
+        let with_late = feed.polls_with_late_votes(LateVotePolicy::Include);
+        assert_eq!(with_late[0].poll.total_votes, 4);
+        let votes: Vec<usize> = with_late[0].poll.options.iter().map(|option| option.votes).collect();
+        assert_eq!(votes, vec![3, 1]);
+
+        let poll_post = feed.find_post(&poll_id).unwrap();
+        assert_eq!(feed.tally_poll(poll_post).unwrap().total_votes, 3);
+        assert!(feed.tally_poll(&feed.posts[0]).is_none());
+    }
+
+    #[test]
+    fn test_build_mention_index() {
+        let mut user = Profile::default();
+        user.set_nick("Alice".to_string());
+        user.set_source(Some("https://alice.example/social.org".to_string()));
+
+        let mut posts = Vec::new();
+        for i in 0..1000 {
+            let content = match i % 4 {
+                0 => "Hi [[org-social:https://alice.example/social.org][someone]]".to_string(),
+                1 => "Hi [[org-social:https://other.example/social.org][Alice]]".to_string(),
+                2 => "Hi [[org-social:https://bob.example/social.org][bob]]".to_string(),
+                _ => "Nothing to see here".to_string(),
+            };
+            let mut post = Post::new(format!("2025-01-01T12:00:{i:04}+0000"), content);
+            post.parse_content();
+            post.set_source(Some("https://carol.example/social.org".to_string()));
+            posts.push(post);
+        }
+        let feed = Feed { posts, ..Default::default() };
+
+        let index = feed.build_mention_index();
+        assert_eq!(index.indexed_posts(), feed.posts.len());
+        assert_eq!(index.posts_mentioning_profile(&user).len(), 500);
+
+        let indexed = NotificationFeed::create_notification_feed_with_index(&user, &[], feed.posts.clone(), Some(&index));
+        let scanned = NotificationFeed::create_notification_feed(&user, &[], feed.posts.clone());
+        let ids = |notifications: &NotificationFeed| notifications.notifications.iter().map(|n| n.post.full_id()).collect::<Vec<_>>();
+        assert_eq!(indexed.len(), 500);
+        assert_eq!(ids(&indexed), ids(&scanned));
+    }
+
+    #[test]
+    fn test_mention_index_add_post() {
+        let mut user = Profile::default();
+        user.set_nick("@bob".to_string());
+
+        let mut index = Feed::default().build_mention_index();
+        assert!(index.posts_mentioning_profile(&user).is_empty());
+
+        let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), "Hi [[org-social:https://bob.example/social.org][bob]]".to_string());
+        post.parse_content();
+        post.set_source(Some("https://carol.example/social.org".to_string()));
+        index.add_post(&post);
+
+        assert_eq!(index.indexed_posts(), 1);
+        assert_eq!(index.posts_mentioning_profile(&user), vec![post.full_id().as_str()]);
+
+        // A mention link whose username is also written as plain "@username" notifies anyone
+        let mut other = Profile::default();
+        other.set_nick("dave".to_string());
+        let mut plain = Post::new("2025-01-01T12:01:00+0000".to_string(), "Hi [[org-social:https://erin.example/social.org][erin]], cc @erin".to_string());
+        plain.parse_content();
+        plain.set_source(Some("https://carol.example/social.org".to_string()));
+        index.add_post(&plain);
+
+        assert_eq!(index.posts_mentioning_profile(&other), vec![plain.full_id().as_str()]);
+        let indexed = NotificationFeed::create_notification_feed_with_index(&other, &[], vec![post.clone(), plain.clone()], Some(&index));
+        let scanned = NotificationFeed::create_notification_feed(&other, &[], vec![post.clone(), plain.clone()]);
+        assert_eq!(indexed.len(), 1);
+        assert_eq!(scanned.len(), 1);
+    }
+
+    #[test]
+    fn test_root_posts_and_replies() {
+        let root = Post::new("2025-01-01T10:00:00+0000".to_string(), "Root".to_string());
+        let by_full_id = reply("2025-01-01T11:00:00+0000", "https://alice.example/social.org#2025-01-01T10:00:00+0000");
+        let by_id = reply("2025-01-01T12:00:00+0000", "2025-01-01T10:00:00+0000");
+        let nested = reply("2025-01-01T13:00:00+0000", "2025-01-01T11:00:00+0000");
+        let orphan = reply("2025-01-01T14:00:00+0000", "https://bob.example/social.org#2024-12-31T00:00:00+0000");
+        let mut posts = vec![root, by_full_id, by_id, nested, orphan];
+        for post in &mut posts {
+            post.set_source(Some("https://alice.example/social.org".to_string()));
+        }
+        posts.reverse();
+        let feed = Feed { posts, ..Default::default() };
+        let ids = |posts: Vec<&Post>| posts.iter().map(|post| post.id().to_string()).collect::<Vec<_>>();
+
+        assert_eq!(ids(feed.root_posts()), vec!["2025-01-01T14:00:00+0000", "2025-01-01T10:00:00+0000"]);
+        assert_eq!(
+            ids(feed.replies()),
+            vec!["2025-01-01T13:00:00+0000", "2025-01-01T12:00:00+0000", "2025-01-01T11:00:00+0000"]
+        );
+
+        assert_eq!(ids(feed.root_posts_with_policy(MissingParentPolicy::AsReply)), vec!["2025-01-01T10:00:00+0000"]);
+        assert_eq!(feed.replies_with_policy(MissingParentPolicy::AsReply).len(), 4);
+
+        assert_eq!(
+            ids(feed.replies_to("https://alice.example/social.org#2025-01-01T10:00:00+0000")),
+            vec!["2025-01-01T12:00:00+0000", "2025-01-01T11:00:00+0000"]
+        );
+        assert_eq!(ids(feed.replies_to("2025-01-01T11:00:00+0000")), vec!["2025-01-01T13:00:00+0000"]);
+        assert_eq!(
+            ids(feed.replies_to("https://bob.example/social.org#2024-12-31T00:00:00+0000")),
+            vec!["2025-01-01T14:00:00+0000"]
+        );
+        assert!(feed.replies_to("https://alice.example/social.org#2025-01-01T14:00:00+0000").is_empty());
+    }
+
+    fn filter_fixture() -> Feed {
+        let mut posts = Vec::new();
+        let mut add = |id: &str, source: &str, author: &str, content: &str, reply_to: Option<&str>| {
+            let mut post = Post::new(id.to_string(), content.to_string());
+            post.set_source(Some(source.to_string()));
+            post.set_author(author.to_string());
+            post.set_reply_to(reply_to.map(str::to_string));
+            posts.push(post);
+        };
+        add("2025-01-01T10:00:00+0000", "https://alice.example/social.org", "alice", "Thread about *Rust*", None);
+        add("2025-01-01T11:00:00+0000", "https://bob.example/social.org", "bob", "Agreed!", Some("https://alice.example/social.org#2025-01-01T10:00:00+0000"));
+        add("2025-01-01T12:00:00+0000", "https://carol.example/social.org", "carol", "Me too", Some("https://bob.example/social.org#2025-01-01T11:00:00+0000"));
+        add("2025-01-01T13:00:00+0000", "https://bob.example/social.org", "bob", "Read [[https://example.com][the SPOILER]]", None);
+        add("2025-01-01T14:00:00+0000", "https://carol.example/social.org", "carol", "Reply to a missing post", Some("https://dave.example/social.org#2024-12-01T00:00:00+0000"));
+        posts.reverse();
+        Feed { posts, ..Default::default() }
+    }
+
+    fn visible_ids(feed: &Feed, filters: &FeedFilterSet) -> Vec<String> {
+        feed.filtered_view(filters).iter().map(|post| post.id()[11..13].to_string()).collect()
+    }
+
+    #[test]
+    fn test_filtered_view_by_source_and_author() {
+        let feed = filter_fixture();
+        assert_eq!(visible_ids(&feed, &FeedFilterSet::new()), vec!["14", "13", "12", "11", "10"]);
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_source("http://bob.example/social.org");
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "12", "10"]);
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_author("@Carol");
+        assert_eq!(visible_ids(&feed, &filters), vec!["13", "11", "10"]);
+    }
+
+    #[test]
+    fn test_filtered_view_by_thread() {
+        let feed = filter_fixture();
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_thread("https://alice.example/social.org#2025-01-01T10:00:00+0000");
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "13"]);
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_thread("https://dave.example/social.org#2024-12-01T00:00:00+0000");
+        assert_eq!(visible_ids(&feed, &filters), vec!["13", "12", "11", "10"]);
+
+        // Muting a reply hides it and the replies below it, not its parent
+        let mut filters = FeedFilterSet::new();
+        filters.mute_thread("https://bob.example/social.org#2025-01-01T11:00:00+0000");
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "13", "10"]);
+
+        // Replies looping back on each other end the walk
+        let mut feed = filter_fixture();
+        feed.posts[4].set_reply_to(Some("https://carol.example/social.org#2025-01-01T12:00:00+0000".to_string()));
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "13"]);
+    }
 
-        // Add user's own posts with their nick as author
-        for mut post in user_posts {
-            post.set_author(user_profile.nick().to_string());
-            all_posts.push(post);
-        }
-        
-        // Fetch posts from followed users
-        let followed_feeds = network::get_feeds_from_profile_with_timeout(user_profile).await;
-        
-        // Add posts from followed users with their nick as author
-        for (profile, posts, _source) in followed_feeds {
-            let author_nick = if profile.nick().is_empty() {
-                "unknown".to_string() // Fallback if no nick is set
+    #[test]
+    fn test_filtered_view_by_keyword() {
+        let feed = filter_fixture();
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_keyword("spoiler");
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "12", "11", "10"]);
+
+        // Markup is not part of the plain text
+        let mut filters = FeedFilterSet::new();
+        filters.mute_keyword("*rust*").mute_keyword("example.com");
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "13", "12", "11", "10"]);
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_keyword("RUST");
+        assert_eq!(visible_ids(&feed, &filters), vec!["14", "13", "12", "11"]);
+    }
+
+    #[test]
+    fn test_filtered_view_combined() {
+        let feed = filter_fixture();
+
+        let mut filters = FeedFilterSet::new();
+        filters
+            .mute_author("carol")
+            .mute_keyword("spoiler")
+            .mute_thread("https://bob.example/social.org#2025-01-01T11:00:00+0000");
+        assert_eq!(visible_ids(&feed, &filters), vec!["10"]);
+    }
+
+    fn mixed_age_posts(source: &str) -> Vec<Post> {
+        ["2025-01-01T12:00:00+0000", "2025-01-10T12:00:00+0000", "2025-01-20T12:00:00+0000", "not-a-timestamp"]
+            .iter()
+            .map(|id| {
+                let mut post = Post::new(id.to_string(), format!("Post {id}"));
+                post.set_source(Some(source.to_string()));
+                post
+            })
+            .collect()
+    }
+
+    fn cutoff() -> DateTime<FixedOffset> {
+        util::parse_timestamp("2025-01-10T12:00:00+0000").unwrap()
+    }
+
+    #[test]
+    fn test_retain_since() {
+        let mut feed = Feed { posts: mixed_age_posts("https://alice.example/social.org"), ..Default::default() };
+        post::sort_newest_first(&mut feed.posts);
+        assert_eq!(feed.len(), 4);
+        assert_eq!(feed.oldest_post_time(), util::parse_timestamp("2025-01-01T12:00:00+0000").ok());
+        assert_eq!(feed.newest_post_time(), util::parse_timestamp("2025-01-20T12:00:00+0000").ok());
+
+        assert_eq!(feed.retain_since(cutoff()), 1);
+        assert_eq!(feed.len(), 3);
+        assert_eq!(feed.oldest_post_time(), Some(cutoff()));
+        assert!(feed.posts.iter().any(|post| post.id() == "not-a-timestamp"));
+
+        assert_eq!(feed.retain_since_with_policy(cutoff(), UntimedPostPolicy::Drop), 1);
+        assert_eq!(feed.len(), 2);
+
+        assert_eq!(Feed::default().oldest_post_time(), None);
+    }
+
+    #[test]
+    fn test_feed_options_since() {
+        let source = "https://alice.example/social.org";
+        let remote = Profile::from(vec!["#+NICK: alice".to_string(), format!("#+SOURCE: {source}")]);
+        let fetched = || vec![(remote.clone(), mixed_age_posts(source), source.to_string())];
+
+        let all = Feed::from_fetched_feeds(&Profile::default(), Vec::new(), fetched(), &FeedOptions::default());
+        assert_eq!(all.len(), 4);
+
+        let options = FeedOptions { since: Some(cutoff()), ..Default::default() };
+        let recent = Feed::from_fetched_feeds(&Profile::default(), Vec::new(), fetched(), &options);
+        assert_eq!(recent.len(), 3);
+
+        let options = FeedOptions { since: Some(cutoff()), untimed_posts: UntimedPostPolicy::Drop, ..Default::default() };
+        let timed = Feed::from_fetched_feeds(&Profile::default(), Vec::new(), fetched(), &options);
+        assert_eq!(timed.len(), 2);
+    }
+
+    #[test]
+    fn test_refresh_options_since() {
+        let source = "https://alice.example/social.org";
+        let remote = Profile::from(vec!["#+NICK: alice".to_string(), format!("#+SOURCE: {source}")]);
+        let mut feed = Feed::from_fetched_feeds(
+            &Profile::default(),
+            Vec::new(),
+            vec![(remote.clone(), mixed_age_posts(source), source.to_string())],
+            &FeedOptions::default(),
+        );
+        assert_eq!(feed.len(), 4);
+
+        let mut posts = mixed_age_posts(source);
+        let mut newer = Post::new("2025-01-30T12:00:00+0000".to_string(), "Newer".to_string());
+        newer.set_source(Some(source.to_string()));
+        let mut older = Post::new("2024-12-01T12:00:00+0000".to_string(), "Older".to_string());
+        older.set_source(Some(source.to_string()));
+        posts.push(newer);
+        posts.push(older);
+
+        let options = RefreshOptions { since: Some(cutoff()), remove_missing: true, ..Default::default() };
+        let report = feed.merge_fetched(&Profile::default(), Vec::new(), vec![(remote, posts, source.to_string())], &options);
+
+        assert_eq!(report.added(), 1);
+        assert_eq!(report.removed(), 0);
+        assert_eq!(feed.len(), 4);
+        assert!(feed.posts.iter().all(|post| post.time().is_none_or(|time| time >= cutoff())));
+    }
+
+    #[test]
+    fn test_moods() {
+        let posts = [("00", "😀"), ("01", "Happy"), ("02", "happy "), ("03", "😀😀"), ("04", "😀"), ("05", "")]
+            .iter()
+            .map(|(second, mood)| {
+                let mut post = Post::new(format!("2025-01-01T12:00:{second}+0000"), String::new());
+                post.set_mood(Some(mood.to_string()));
+                post
+            })
+            .rev()
+            .collect();
+        let feed = Feed { posts, ..Default::default() };
+        let seconds = |posts: Vec<&Post>| posts.iter().map(|post| post.id()[17..19].to_string()).collect::<Vec<_>>();
+
+        assert_eq!(seconds(feed.posts_with_mood("😀")), vec!["04", "00"]);
+        assert_eq!(seconds(feed.posts_with_mood("😀😀")), vec!["03"]);
+        assert_eq!(seconds(feed.posts_with_mood("HAPPY")), vec!["02", "01"]);
+        assert!(feed.posts_with_mood("").is_empty());
+
+        assert_eq!(
+            feed.moods(),
+            vec![("happy".to_string(), 2), ("😀".to_string(), 2), ("😀😀".to_string(), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_report_records_failures() {
+        use crate::test_support::{http_response, stub_server};
+
+        let base = stub_server(|_| {
+            http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n")
+        })
+        .await;
+        // Nothing listens on the port once the listener is dropped
+        let unreachable = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/social.org", listener.local_addr().unwrap())
+        };
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/social.org"));
+        user.add_follow("bob".to_string(), unreachable.clone());
+
+        let feed = Feed::create_combined_feed(&user, Vec::new()).await.unwrap();
+
+        assert_eq!(feed.len(), 1);
+        assert_eq!(feed.fetch_report.len(), 2);
+        let alice = feed.fetch_report.iter().find(|status| status.nick == "alice").unwrap();
+        assert_eq!(alice.result.as_ref().unwrap(), &FetchStats { post_count: 1, not_modified: false, stale: None, early_stop: false });
+
+        let failed: Vec<&SourceStatus> = feed.failed_sources().collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].nick, "bob");
+        assert_eq!(failed[0].url, unreachable);
+        assert!(matches!(failed[0].result, Err(NetworkError::Transport { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_feed_uses_injected_client() {
+        use crate::test_support::{http_response, stub_server_with_request};
+
+        let base = stub_server_with_request(|_, request| {
+            if request.to_lowercase().contains("x-client-marker: injected") {
+                http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n")
             } else {
-                profile.nick().to_string()
-            };
-            
-            for mut post in posts {
-                post.set_author(author_nick.clone());
-                all_posts.push(post);
+                http_response("403 Forbidden", &[], b"")
             }
+        })
+        .await;
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-Client-Marker", reqwest::header::HeaderValue::from_static("injected"));
+        let network = NetworkConfig::with_client(reqwest::Client::builder().default_headers(headers).build().unwrap());
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/social.org"));
+
+        let options = FeedOptions { network: network.clone(), ..Default::default() };
+        let mut feed = Feed::create_combined_feed_with_options(&user, Vec::new(), options).await.unwrap();
+        assert_eq!(feed.len(), 1);
+
+        // Refreshes use the feed's client unless given another configuration
+        feed.refresh(&user, Vec::new(), RefreshOptions::default()).await;
+        assert!(feed.fetch_report[0].result.is_ok());
+        feed.refresh(&user, Vec::new(), RefreshOptions { network: Some(NetworkConfig::default()), ..Default::default() }).await;
+        assert!(matches!(feed.fetch_report[0].result, Err(NetworkError::Http { status: 403, .. })));
+        feed.refresh(&user, Vec::new(), RefreshOptions { network: Some(network), ..Default::default() }).await;
+        assert!(feed.fetch_report[0].result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_combined_feed_with_progress() {
+        use crate::test_support::{http_response, stub_server};
+
+        let base = stub_server(|path| {
+            let nick = path.trim_start_matches('/').trim_end_matches(".org");
+            let body = format!("#+NICK: {nick}\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello from {nick}\n");
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/alice.org"));
+        user.add_follow("bob".to_string(), format!("{base}/bob.org"));
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+        let feed = Feed::create_combined_feed_with_progress(&user, Vec::new(), FeedOptions::default(), sender).await.unwrap();
+        assert_eq!(feed.len(), 2);
+
+        let mut events = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
         }
-        
-        // Sort posts chronologically (newest first)
-        all_posts.sort_by(|a, b| {
-            match (a.time(), b.time()) {
-                (Some(time_a), Some(time_b)) => time_b.cmp(&time_a), // Reverse order for newest first
-                (Some(_), None) => std::cmp::Ordering::Less,         // Posts with time come before posts without
-                (None, Some(_)) => std::cmp::Ordering::Greater,      // Posts without time come after posts with time
-                (None, None) => std::cmp::Ordering::Equal,           // Equal if both don't have time
+        assert_eq!(events.iter().filter(|event| matches!(event, network::FetchEvent::Started(_))).count(), 2);
+        assert_eq!(events.iter().filter(|event| matches!(event, network::FetchEvent::Finished { posts: 1, .. })).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_report_records_retries() {
+        use crate::test_support::{http_response, stub_server};
+
+        let failures = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = failures.clone();
+        let base = stub_server(move |_| {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                http_response("500 Internal Server Error", &[], b"")
+            } else {
+                http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n")
             }
-        });
-        
-        Ok(Feed { posts: all_posts })
+        })
+        .await;
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/social.org"));
+        let retry = RetryPolicy { base_delay: Duration::from_millis(10), ..Default::default() };
+
+        let feed = Feed::create_combined_feed_with_options(&user, Vec::new(), FeedOptions { retry: Some(retry), ..Default::default() })
+            .await
+            .unwrap();
+
+        assert_eq!(feed.len(), 1);
+        assert_eq!(feed.fetch_report[0].attempts, 3);
+        assert!(feed.fetch_report[0].result.is_ok());
     }
-    
-    pub fn create_user_feed(user_profile: &Profile, user_posts: Vec<Post>) -> Feed {
+
+    #[tokio::test]
+    async fn test_source_health() {
+        use crate::test_support::{http_response, stub_server};
+
+        let base = stub_server(|path| match path {
+            "/alice.org" => {
+                http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n")
+            }
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let alice = format!("{base}/alice.org");
+        let bob = format!("{base}/bob.org");
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), alice.clone());
+        user.add_follow("bob".to_string(), bob.clone());
+
+        let mut feed = Feed::create_combined_feed(&user, Vec::new()).await.unwrap();
+
+        let healthy = feed.source_health(&alice).unwrap();
+        assert!(healthy.is_healthy());
+        assert!(healthy.last_success.is_some());
+        assert_eq!(healthy.last_error, None);
+        assert_eq!(healthy.posts, 1);
+        assert!(healthy.last_latency.is_some());
+
+        let failing = feed.source_health(&bob).unwrap();
+        assert!(!failing.is_healthy());
+        assert_eq!(failing.last_success, None);
+        assert!(failing.last_error.is_some());
+
+        let unhealthy = feed.unhealthy_sources();
+        assert_eq!(unhealthy.len(), 1);
+        assert_eq!(unhealthy[0].0, util::canonical_feed_url(&bob));
+
+        // A later failure of a healthy source keeps its last success
+        let alice_success = healthy.last_success;
+        feed.record_fetch_report(vec![SourceStatus {
+            nick: "alice".to_string(),
+            url: alice.clone(),
+            final_url: alice.clone(),
+            result: Err(NetworkError::Timeout { url: alice.clone(), after: Duration::from_secs(30) }),
+            latency: Duration::from_secs(30),
+            attempts: 1,
+            warnings: Vec::new(),
+            queued: Duration::ZERO,
+        }]);
+        let alice_health = feed.source_health(&alice).unwrap();
+        assert_eq!(alice_health.last_success, alice_success);
+        assert_eq!(alice_health.posts, 1);
+        assert!(!alice_health.is_healthy());
+        assert_eq!(feed.unhealthy_sources().len(), 2);
+        assert!(feed.source_health(&bob).is_some());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_snapshot_keeps_source_health() {
+        let at = |timestamp: &str| DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc);
+        let mut feed = Feed::default();
+        feed.source_health.insert(
+            "https://alice.example/social.org".to_string(),
+            SourceHealth {
+                last_success: Some(at("2025-01-02T12:00:00Z")),
+                last_error: Some((at("2025-01-01T12:00:00Z"), "timed out".to_string())),
+                posts: 3,
+                last_latency: Some(Duration::from_millis(250)),
+                last_change: Some(at("2025-01-02T12:00:00Z")),
+                cadence: Some(Duration::from_secs(3600)),
+                next_check: Some(at("2025-01-02T13:00:00Z")),
+            },
+        );
+        feed.source_health.insert("https://bob.example/social.org".to_string(), SourceHealth::default());
+
+        let path = std::env::temp_dir().join(format!("org-social-health-snapshot-test-{}", std::process::id()));
+        feed.save_snapshot(&path).unwrap();
+        let loaded = Feed::load_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.source_health, feed.source_health);
+        assert_eq!(loaded.unhealthy_sources().len(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_snapshot_versions() {
+        let path = std::env::temp_dir().join(format!("org-social-version-snapshot-test-{}", std::process::id()));
+        Feed::default().save_snapshot(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with(&format!("{SNAPSHOT_MAGIC} {SNAPSHOT_VERSION}\n")));
+
+        // Snapshots without source health still load
+        std::fs::write(&path, format!("{SNAPSHOT_MAGIC} {SNAPSHOT_VERSION}\n{{\"profiles\": [], \"posts\": []}}\n")).unwrap();
+        let loaded = Feed::load_snapshot(&path).unwrap();
+        assert!(loaded.source_health.is_empty());
+
+        std::fs::write(&path, format!("{SNAPSHOT_MAGIC} {}\n{{}}\n", SNAPSHOT_VERSION + 1)).unwrap();
+        let error = Feed::load_snapshot(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(error, SnapshotError::UnsupportedVersion(version) if version == SNAPSHOT_VERSION + 1));
+    }
+
+    fn order_fixture() -> Feed {
         let mut posts = Vec::new();
-        
-        // Set author for user's own posts
-        for mut post in user_posts {
-            post.set_author(user_profile.nick().to_string());
+        for (id, source, author) in [
+            ("2025-01-02T12:00:00+0000", "https://bob.example/social.org", Some("bob")),
+            ("2025-01-01T12:00:00+0000", "https://alice.example/social.org", Some("Alice")),
+            ("2025-01-03T12:00:00+0000", "https://alice.example/social.org", Some("alice")),
+            ("2025-01-02T12:00:00+0000", "https://alice.example/social.org", Some("alice")),
+            ("untimed", "https://carol.example/social.org", None),
+        ] {
+            let mut post = Post::new(id.to_string(), String::new());
+            post.set_source(Some(source.to_string()));
+            if let Some(author) = author {
+                post.set_author(author.to_string());
+            }
             posts.push(post);
         }
-        
-        // Sort posts chronologically (newest first)
-        posts.sort_by(|a, b| {
-            match (a.time(), b.time()) {
-                (Some(time_a), Some(time_b)) => time_b.cmp(&time_a), // Reverse order for newest first
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
-        });
-        
-        Feed { posts }
+        Feed { posts, ..Default::default() }
     }
-    
-    /// Filter posts by a specific time range.
-    ///
-    /// Returns posts that fall within the specified start and end times.
-    ///
-    /// # Arguments
-    ///
-    /// * `start` - The start time of the range (inclusive)
-    /// * `end` - The end time of the range (inclusive)
-    ///
-    /// # Returns
-    ///
-    /// A vector of references to posts within the time range.threading
-    pub fn posts_in_range(
-        &self,
-        start: DateTime<FixedOffset>,
-        end: DateTime<FixedOffset>,
-    ) -> Vec<&Post> {
-        self.posts
+
+    /// Host of the source and day of the month of each post, in feed order.
+    fn order_keys(feed: &Feed) -> Vec<(&str, Option<u32>)> {
+        use chrono::Datelike;
+
+        feed.posts
             .iter()
-            .filter(|post| {
-                if let Some(post_time) = post.time() {
-                    post_time >= start && post_time <= end
-                } else {
-                    false
-                }
-            })
+            .map(|post| (util::url_host(post.source().as_deref().unwrap()).unwrap(), post.time().map(|time| time.day())))
             .collect()
     }
 
-    pub fn get_recent_posts(&self, limit: usize) -> Vec<&Post> {
-        self.posts.iter().take(limit).collect()
+    #[test]
+    fn test_sort_orders() {
+        let mut feed = order_fixture();
+
+        feed.sort(FeedOrder::NewestFirst);
+        assert_eq!(order_keys(&feed), vec![("alice.example", Some(3)), ("alice.example", Some(2)), ("bob.example", Some(2)), ("alice.example", Some(1)), ("carol.example", None)]);
+
+        feed.sort(FeedOrder::OldestFirst);
+        assert_eq!(order_keys(&feed), vec![("alice.example", Some(1)), ("alice.example", Some(2)), ("bob.example", Some(2)), ("alice.example", Some(3)), ("carol.example", None)]);
+
+        feed.sort(FeedOrder::ByAuthorThenTime);
+        assert_eq!(order_keys(&feed), vec![("alice.example", Some(3)), ("alice.example", Some(2)), ("alice.example", Some(1)), ("bob.example", Some(2)), ("carol.example", None)]);
+        assert_eq!(feed.order, FeedOrder::ByAuthorThenTime);
+
+        // Added posts keep the chosen order
+        let mut post = Post::new("2025-01-05T12:00:00+0000".to_string(), String::new());
+        post.set_source(Some("https://bob.example/social.org".to_string()));
+        post.set_author("bob".to_string());
+        feed.add_post(post, None);
+        assert_eq!(order_keys(&feed), vec![("alice.example", Some(3)), ("alice.example", Some(2)), ("alice.example", Some(1)), ("bob.example", Some(5)), ("bob.example", Some(2)), ("carol.example", None)]);
     }
 
-    pub fn posts_from_source(&self, source: &str) -> Vec<&Post> {
-        self.posts
-            .iter()
-            .filter(|post| {
-                post.source()
-                    .as_ref()
-                    .map(|s| s == source)
-                    .unwrap_or(false)
-            })
-            .collect()
+    #[test]
+    fn test_feed_options_order() {
+        let source = "https://alice.example/social.org";
+        let remote = Profile::from(vec!["#+NICK: alice".to_string(), format!("#+SOURCE: {source}")]);
+        let options = FeedOptions { order: FeedOrder::OldestFirst, ..Default::default() };
+
+        let feed = Feed::from_fetched_feeds(&Profile::default(), Vec::new(), vec![(remote, mixed_age_posts(source), source.to_string())], &options);
+
+        assert_eq!(feed.order, FeedOrder::OldestFirst);
+        let ids: Vec<&str> = feed.posts.iter().map(Post::id).collect();
+        assert_eq!(ids, vec!["2025-01-01T12:00:00+0000", "2025-01-10T12:00:00+0000", "2025-01-20T12:00:00+0000", "not-a-timestamp"]);
     }
 
-    pub fn sources(&self) -> Vec<String> {
-        let mut sources: Vec<String> = self.posts
+    #[test]
+    fn test_attribution_distance_of_own_posts() {
+        let user = Profile::from(vec!["#+NICK: me".to_string()]);
+        let feed = Feed::create_user_feed(&user, vec![Post::new("2025-01-02T12:00:00+0000".to_string(), "Mine".to_string())]);
+        assert_eq!(feed.attribution_distance(&feed.posts[0]), Some(0));
+
+        let mut unknown = Post::new("2025-01-02T12:00:00+0000".to_string(), "Elsewhere".to_string());
+        unknown.set_source(Some("https://unknown.example/social.org".to_string()));
+        assert_eq!(feed.attribution_distance(&unknown), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_extended_feed() {
+        use crate::test_support::{http_response, stub_server};
+        use std::sync::OnceLock;
+
+        let base_url = Arc::new(OnceLock::<String>::new());
+        let handler_base_url = base_url.clone();
+        let base = stub_server(move |path| {
+            let base = handler_base_url.get().unwrap();
+            let (nick, follows) = match path {
+                // Alice follows the user back, herself, and two new people
+                "/alice.org" => ("alice", vec!["me", "alice", "bob", "carol"]),
+                // Bob follows Alice back, and Dave
+                "/bob.org" => ("bob", vec!["alice", "dave"]),
+                "/carol.org" => ("carol", vec!["bob"]),
+                "/dave.org" => ("dave", vec!["me"]),
+                _ => return http_response("404 Not Found", &[], b""),
+            };
+            let follows: String = follows.iter().map(|follow| format!("#+FOLLOW: {follow} {base}/{follow}.org\n")).collect();
+            let body = format!("#+NICK: {nick}\n{follows}\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHi from {nick}\n");
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+        base_url.set(base.clone()).unwrap();
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.set_source(Some(format!("{base}/me.org")));
+        user.add_follow("alice".to_string(), format!("{base}/alice.org"));
+        let mut own_post = Post::new("2025-01-02T12:00:00+0000".to_string(), "Mine".to_string());
+        own_post.set_source(Some(format!("{base}/me.org")));
+
+        let distances = |feed: &Feed| {
+            let mut distances: Vec<(String, Option<u8>)> =
+                feed.posts.iter().map(|post| (post.author().clone().unwrap_or_default(), feed.attribution_distance(post))).collect();
+            distances.sort();
+            distances
+        };
+
+        let direct = Feed::create_extended_feed(&user, vec![own_post.clone()], ExtendedOptions { depth: 1, ..Default::default() }).await.unwrap();
+        assert_eq!(distances(&direct), vec![("alice".to_string(), Some(1)), ("me".to_string(), Some(0))]);
+
+        let extended = Feed::create_extended_feed(&user, vec![own_post.clone()], ExtendedOptions::default()).await.unwrap();
+        assert_eq!(
+            distances(&extended),
+            vec![
+                ("alice".to_string(), Some(1)),
+                ("bob".to_string(), Some(2)),
+                ("carol".to_string(), Some(2)),
+                ("me".to_string(), Some(0)),
+            ]
+        );
+        assert_eq!(extended.fetch_report.len(), 3);
+
+        // Dave is only reached through Bob, the cycles back to Alice and the user are not followed
+        let deep = Feed::create_extended_feed(&user, vec![own_post.clone()], ExtendedOptions { depth: 5, ..Default::default() }).await.unwrap();
+        assert_eq!(deep.len(), 5);
+        assert_eq!(deep.fetch_report.len(), 4);
+        assert!(deep.posts.iter().any(|post| post.author().as_deref() == Some("dave") && deep.attribution_distance(post) == Some(3)));
+
+        let limited = Feed::create_extended_feed(&user, vec![own_post], ExtendedOptions { depth: 5, max_feeds: 1, ..Default::default() }).await.unwrap();
+        let authors: Vec<&str> = limited.posts.iter().filter_map(|post| post.author().as_deref()).collect();
+        assert_eq!(authors.len(), 3);
+        assert!(authors.contains(&"bob"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_loads_fast_feeds_first() {
+        use crate::test_support::{delayed_stub_server, http_response};
+
+        let base = delayed_stub_server(
+            |path| if path == "/slow.org" { Duration::from_millis(500) } else { Duration::ZERO },
+            |path, _| {
+                let nick = match path {
+                    "/fast.org" => "fast",
+                    "/slow.org" => "slow",
+                    _ => return http_response("404 Not Found", &[], b""),
+                };
+                let body = format!("#+NICK: {nick}\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHi from {nick}\n");
+                http_response("200 OK", &[], body.as_bytes())
+            },
+        )
+        .await;
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("slow".to_string(), format!("{base}/slow.org"));
+        user.add_follow("fast".to_string(), format!("{base}/fast.org"));
+        user.add_follow("gone".to_string(), format!("{base}/gone.org"));
+
+        let (handle, mut events) = Feed::stream(&user, Vec::new(), FeedOptions::default());
+        assert_eq!(handle.snapshot().len(), 0);
+
+        let mut started = 0;
+        let mut loaded = Vec::new();
+        let mut failed = Vec::new();
+        let feed = loop {
+            match events.next_event().await.unwrap() {
+                FeedEvent::SourceStarted(_) => started += 1,
+                FeedEvent::SourceLoaded { url, post_count } => {
+                    assert_eq!(post_count, 1);
+                    if url.ends_with("/fast.org") {
+                        // The slow feed is still loading
+                        assert!(!handle.is_completed());
+                        assert_eq!(handle.snapshot().posts[0].author().as_deref(), Some("fast"));
+                    }
+                    loaded.push(url);
+                }
+                FeedEvent::SourceFailed { url, .. } => failed.push(url),
+                FeedEvent::Completed(feed) => break feed,
+            }
+        };
+
+        assert_eq!(started, 3);
+        assert_eq!(loaded, vec![format!("{base}/fast.org"), format!("{base}/slow.org")]);
+        assert_eq!(failed, vec![format!("{base}/gone.org")]);
+        assert!(handle.is_completed());
+        assert_eq!(handle.loaded_sources(), 2);
+        assert!(events.next_event().await.is_none());
+
+        assert_eq!(feed.len(), 2);
+        let report: Vec<&str> = feed.fetch_report.iter().map(|status| status.nick.as_str()).collect();
+        assert_eq!(report, vec!["slow", "fast", "gone"]);
+        assert!(matches!(feed.fetch_report[2].result, Err(NetworkError::Http { status: 404, .. })));
+    }
+
+    #[test]
+    fn test_bookmarked_posts() {
+        let source = "https://alice.example/social.org";
+        let mut posts: Vec<Post> = mixed_age_posts(source);
+        post::sort_newest_first(&mut posts);
+        let feed = Feed { posts, ..Default::default() };
+
+        let mut bookmarks = Bookmarks::new();
+        let at = |timestamp: &str| util::parse_timestamp(timestamp).unwrap();
+        bookmarks.add_at(&format!("{source}#2025-01-01T12:00:00+0000"), at("2025-03-01T12:00:00+0000"));
+        bookmarks.add_at(&format!("{source}#2025-01-20T12:00:00+0000"), at("2025-02-01T12:00:00+0000"));
+        bookmarks.add_at(&format!("{source}#2024-06-01T12:00:00+0000"), at("2025-04-01T12:00:00+0000"));
+
+        let ids: Vec<&str> = feed.bookmarked_posts(&bookmarks).iter().map(|post| post.id()).collect();
+        assert_eq!(ids, vec!["2025-01-01T12:00:00+0000", "2025-01-20T12:00:00+0000"]);
+
+        let dangling = feed.dangling_bookmarks(&bookmarks);
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0].full_id, format!("{source}#2024-06-01T12:00:00+0000"));
+    }
+
+    #[test]
+    fn test_conversations() {
+        let source = "https://alice.example/social.org";
+        let post = |id: &str, reply_to: Option<&str>| {
+            let mut post = Post::new(id.to_string(), format!("Post {id}"));
+            post.set_source(Some(source.to_string()));
+            post.set_reply_to(reply_to.map(str::to_string));
+            post
+        };
+        let mut posts = vec![
+            post("2025-01-01T10:00:00+0000", None),
+            post("2025-01-01T11:00:00+0000", Some(&format!("{source}#2025-01-01T10:00:00+0000"))),
+            post("2025-01-01T15:00:00+0000", Some("2025-01-01T11:00:00+0000")),
+            post("2025-01-01T12:00:00+0000", Some("https://bob.example/social.org#2024-12-31T00:00:00+0000")),
+            post("2025-01-01T13:00:00+0000", None),
+        ];
+        post::sort_newest_first(&mut posts);
+        let feed = Feed { posts, ..Default::default() };
+
+        let conversations = feed.conversations();
+        let summary: Vec<(&str, Vec<&str>)> = conversations
             .iter()
-            .filter_map(|post| post.source().as_ref())
-            .cloned()
+            .map(|conversation| {
+                let root_id = conversation.root_full_id.rsplit('#').next().unwrap();
+                (&root_id[11..13], conversation.posts.iter().map(|post| &post.id()[11..13]).collect())
+            })
             .collect();
-        
-        sources.sort();
-        sources.dedup();
-        sources
+        assert_eq!(summary, vec![("10", vec!["10", "11", "15"]), ("13", vec!["13"]), ("12", vec!["12"])]);
+        assert_eq!(conversations[0].latest_activity, util::parse_timestamp("2025-01-01T15:00:00+0000").ok());
     }
 
-    pub fn len(&self) -> usize {
-        self.posts.len()
+    #[test]
+    fn test_conversations_reply_loop() {
+        let a = reply("2025-01-01T10:00:00+0000", "2025-01-01T11:00:00+0000");
+        let b = reply("2025-01-01T11:00:00+0000", "2025-01-01T10:00:00+0000");
+        let feed = Feed { posts: vec![b, a], ..Default::default() };
+
+        let conversations = feed.conversations();
+        assert_eq!(conversations.iter().map(|conversation| conversation.posts.len()).sum::<usize>(), 2);
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.posts.is_empty()
+    #[test]
+    fn test_profile_for_post_with_empty_nick() {
+        let source = "https://anon.example/social.org";
+        let mut user = Profile::default();
+        user.add_follow(String::new(), source.to_string());
+        let mut remote = Profile::from(vec!["#+TITLE: Anonymous feed".to_string()]);
+        remote.set_source(Some(source.to_string()));
+        let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), "Hello".to_string());
+        post.set_source(Some(source.to_string()));
+
+        let feed = Feed::from_fetched_feeds(&user, Vec::new(), vec![(remote, vec![post], source.to_string())], &FeedOptions::default());
+
+        let profile = feed.profile_for_post(&feed.posts[0]).unwrap();
+        assert!(!Arc::ptr_eq(profile, &feed.profiles[0]));
+        assert_eq!(profile.source().map(String::as_str), Some(source));
+        assert_eq!(feed.display_name_for_post(&feed.posts[0]), "Anonymous feed");
+
+        // Without a source, an empty author must not match the user's empty nick
+        let mut sourceless = Post::new("2025-01-01T13:00:00+0000".to_string(), "Hi".to_string());
+        sourceless.set_author(String::new());
+        assert!(feed.profile_for_post(&sourceless).is_none());
+        let mut unknown = sourceless.clone();
+        unknown.set_author("unknown".to_string());
+        assert!(feed.profile_for_post(&unknown).is_none());
     }
 
-}
+    #[test]
+    fn test_diff_since() {
+        let source = "https://alice.example/social.org";
+        let post = |id: &str, content: &str| {
+            let mut post = Post::new(id.to_string(), content.to_string());
+            post.set_source(Some(source.to_string()));
+            post
+        };
+        let previous = Feed {
+            posts: vec![
+                post("2025-01-01T12:00:00+0000", "Hello world"),
+                post("2025-01-02T12:00:00+0000", "First draft"),
+                post("2025-01-03T12:00:00+0000", "Deleted later"),
+            ],
+            ..Default::default()
+        };
+        let snapshot = previous.snapshot_ref();
+        assert_eq!(snapshot.len(), 3);
+        assert!(snapshot.contains(&format!("{source}#2025-01-03T12:00:00+0000")));
+        assert!(previous.diff_since(&snapshot).is_empty());
 
-impl std::fmt::Display for Feed {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Feed with {} posts:", self.posts.len())?;
-        for (i, post) in self.posts.iter().enumerate() {
-            writeln!(f, "--- Post {} ---", i + 1)?;
-            if let Some(time) = post.time() {
-                writeln!(f, "Time: {time}")?;
-            }
-            if let Some(source) = post.source() {
-                writeln!(f, "Source: {source}")?;
+        let current = Feed {
+            posts: vec![
+                post("2025-01-04T12:00:00+0000", "Brand new"),
+                post("2025-01-01T12:00:00+0000", "  Hello\n   world\n"),
+                post("2025-01-02T12:00:00+0000", "Second draft"),
+            ],
+            ..Default::default()
+        };
+        let delta = current.diff_since(&snapshot);
+
+        let ids = |posts: &[&Post]| posts.iter().map(|post| post.id().to_string()).collect::<Vec<_>>();
+        assert_eq!(ids(&delta.new_posts), vec!["2025-01-04T12:00:00+0000"]);
+        assert_eq!(ids(&delta.edited_posts), vec!["2025-01-02T12:00:00+0000"]);
+        assert_eq!(delta.removed_full_ids, vec![format!("{source}#2025-01-03T12:00:00+0000")]);
+
+        // Everything is new against an empty snapshot
+        let delta = current.diff_since(&FeedSnapshotRef::default());
+        assert_eq!(delta.new_posts.len(), 3);
+        assert!(delta.edited_posts.is_empty() && delta.removed_full_ids.is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_create_combined_feed_blocking() {
+        use crate::test_support::{http_response, stub_server};
+
+        let server_runtime = tokio::runtime::Runtime::new().unwrap();
+        let base = server_runtime.block_on(stub_server(|path| match path {
+            "/alice.org" => http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n"),
+            _ => http_response("404 Not Found", &[], b""),
+        }));
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/alice.org"));
+        user.add_follow("bob".to_string(), format!("{base}/bob.org"));
+
+        let feed = Feed::create_combined_feed_blocking(&user, Vec::new()).unwrap();
+        assert_eq!(feed.len(), 1);
+        assert_eq!(feed.posts[0].author().as_deref(), Some("alice"));
+        assert_eq!(feed.failed_sources().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_from_fetch_results() {
+        use crate::test_support::{http_response, stub_server};
+
+        let base = stub_server(|path| match path {
+            "/alice.org" => http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n"),
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/alice.org"));
+        user.add_follow("bob".to_string(), format!("{base}/bob.org"));
+
+        let results = network::get_feeds_detailed(user.follow_urls_normalized(), &network::FetchOptions::default()).await;
+        let feed = Feed::from_fetch_results(&user, Vec::new(), results, &FeedOptions::default());
+        assert_eq!(feed.len(), 1);
+        assert_eq!(feed.fetch_report.len(), 2);
+        let alice = feed.fetch_report.iter().find(|status| status.nick == "alice").unwrap();
+        assert_eq!(alice.result.as_ref().unwrap().post_count, 1);
+        assert_eq!(alice.final_url, format!("{base}/alice.org"));
+        let failed: Vec<_> = feed.failed_sources().collect();
+        assert_eq!(failed.len(), 1);
+        assert!(feed.source_health(&format!("{base}/bob.org")).unwrap().last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_create_combined_feed_deadline() {
+        use crate::test_support::{delayed_stub_server, http_response};
+
+        let base = delayed_stub_server(
+            |path| if path == "/slow.org" { Duration::from_secs(3600) } else { Duration::ZERO },
+            |_, _| http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n"),
+        )
+        .await;
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/alice.org"));
+        user.add_follow("slow".to_string(), format!("{base}/slow.org"));
+
+        let options = FeedOptions { overall_deadline: Some(Duration::from_millis(300)), ..Default::default() };
+        let feed = Feed::create_combined_feed_with_options(&user, Vec::new(), options).await.unwrap();
+        assert_eq!(feed.len(), 1);
+        let slow = feed.fetch_report.iter().find(|status| status.nick == "slow").unwrap();
+        assert!(matches!(slow.result, Err(NetworkError::DeadlineExceeded { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_offline_fallback() {
+        use crate::test_support::{abortable_stub_server, http_response};
+
+        let directory = std::env::temp_dir().join(format!("org-social-offline-feed-test-{}", std::process::id()));
+        let (base, server) = abortable_stub_server(|_| {
+            http_response("200 OK", &[], b"#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n")
+        })
+        .await;
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/alice.org"));
+        let options = FeedOptions { raw_store: Some(DiskCache::new(&directory, u64::MAX)), offline_fallback: true, ..Default::default() };
+
+        let feed = Feed::create_combined_feed_with_options(&user, Vec::new(), options.clone()).await.unwrap();
+        assert_eq!(feed.stale_sources().count(), 0);
+
+        server.abort();
+        let _ = server.await;
+        let feed = Feed::create_combined_feed_with_options(&user, Vec::new(), options).await.unwrap();
+        assert_eq!(feed.len(), 1);
+        let stale: Vec<_> = feed.stale_sources().collect();
+        assert_eq!(stale.len(), 1);
+        assert!(stale[0].result.as_ref().unwrap().stale.is_some());
+        assert_eq!(feed.failed_sources().count(), 0);
+        assert!(feed.source_health(&format!("{base}/alice.org")).unwrap().last_success.is_none());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_stale() {
+        use crate::transport::{MockResponse, MockTransport};
+
+        // Alice posts hourly, Bob monthly
+        let feed_with_posts = |nick: &str, gap: chrono::Duration, newest: chrono::Duration| {
+            let mut content = format!("#+NICK: {nick}\n\n* Posts\n");
+            for index in 0..5 {
+                let time = Utc::now() - newest - gap * index;
+                content.push_str(&format!("**\n:PROPERTIES:\n:ID: {}\n:END:\n\nPost {index}\n", time.format("%Y-%m-%dT%H:%M:%S+0000")));
             }
-            writeln!(f, "{post}")?;
-            writeln!(f)?;
+            content
+        };
+        let (alice, bob) = ("https://alice.example/social.org", "https://bob.example/social.org");
+        let transport = Arc::new(MockTransport::new());
+        transport.respond(alice, MockResponse::ok(feed_with_posts("alice", chrono::Duration::hours(1), chrono::Duration::minutes(30))));
+        transport.respond(bob, MockResponse::ok(feed_with_posts("bob", chrono::Duration::days(30), chrono::Duration::days(10))));
+
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), alice.to_string());
+        user.add_follow("bob".to_string(), bob.to_string());
+        let options = || RefreshOptions {
+            network: Some(NetworkConfig { transport: Some(transport.clone()), ..Default::default() }),
+            ..Default::default()
+        };
+
+        // Sources never checked are due right away
+        let mut feed = Feed::default();
+        let report = feed.refresh_stale(&user, Vec::new(), options()).await;
+        assert_eq!(report.added(), 10);
+        assert_eq!((transport.request_count(alice), transport.request_count(bob)), (1, 1));
+
+        let until_next_check = |feed: &Feed, url: &str| {
+            let health = &feed.source_health[url];
+            assert!(health.last_change.is_some());
+            (health.next_check.unwrap() - Utc::now()).num_minutes()
+        };
+        assert_eq!(feed.source_health[alice].cadence, Some(Duration::from_secs(3600)));
+        assert!((58..=60).contains(&until_next_check(&feed, alice)));
+        assert_eq!(feed.source_health[bob].cadence, Some(Duration::from_secs(30 * 24 * 3600)));
+        assert!((24 * 60 - 2..=24 * 60).contains(&until_next_check(&feed, bob)));
+
+        // Nothing is due yet
+        let report = feed.refresh_stale(&user, Vec::new(), options()).await;
+        assert!(report.is_empty());
+        assert!(feed.fetch_report.is_empty());
+        assert_eq!(transport.requests().len(), 2);
+
+        // Two hours later, only Alice is checked again
+        for health in feed.source_health.values_mut() {
+            health.next_check = health.next_check.map(|next_check| next_check - chrono::Duration::hours(2));
         }
-        Ok(())
+        feed.refresh_stale(&user, Vec::new(), options()).await;
+        assert_eq!((transport.request_count(alice), transport.request_count(bob)), (2, 1));
+        assert_eq!(feed.fetch_report.len(), 1);
+
+        // The bounds are configurable
+        let schedule = RefreshSchedule { min_interval: Duration::from_secs(2 * 3600), max_interval: Duration::from_secs(4 * 3600) };
+        feed.refresh(&user, Vec::new(), RefreshOptions { schedule, ..options() }).await;
+        assert_eq!((transport.request_count(alice), transport.request_count(bob)), (3, 2));
+        assert!((118..=120).contains(&until_next_check(&feed, alice)));
+        assert!((238..=240).contains(&until_next_check(&feed, bob)));
+    }
+
+    #[tokio::test]
+    async fn test_network_handle_reuses_connections() {
+        use crate::test_support::{keep_alive_response, keep_alive_stub_server};
+
+        let (base, connections) = keep_alive_stub_server(|path| match path {
+            "/social.org" => keep_alive_response(
+                "200 OK",
+                &[],
+                b"#+NICK: alice\n#+AVATAR: /avatar.png\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n",
+            ),
+            "/avatar.png" => keep_alive_response("200 OK", &[("Content-Type", "image/png")], b"PNGDATA"),
+            _ => keep_alive_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let mut user = Profile::from(vec!["#+NICK: me".to_string()]);
+        user.add_follow("alice".to_string(), format!("{base}/social.org"));
+
+        // A client of its own, so no connection is left from other tests
+        let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+        let options = FeedOptions { network: NetworkConfig::with_client(client), ..Default::default() };
+        let mut feed = Feed::create_combined_feed_with_options(&user, Vec::new(), options).await.unwrap();
+        assert_eq!(feed.posts.len(), 1);
+        for _ in 0..3 {
+            feed.refresh(&user, Vec::new(), RefreshOptions::default()).await;
+            assert!(feed.fetch_report[0].result.is_ok());
+        }
+        let avatars = feed.fetch_avatars().await;
+        assert_eq!(avatars[0].1.as_ref().unwrap().bytes, b"PNGDATA");
+
+        // The feed, its refreshes and the avatar all went through the first connection
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
     }
 }
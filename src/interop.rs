@@ -0,0 +1,602 @@
+//! Interop module for exporting org-social posts to other formats.
+//!
+//! This module provides conversion of feeds into [JSON Feed 1.1](https://www.jsonfeed.org/version/1.1/),
+//! Atom 1.0 and RSS 2.0 documents and of posts into ActivityStreams notes, so they can be consumed
+//! by existing readers and tools. The JSON formats are available with the `json` feature.
+
+use chrono::{DateTime, FixedOffset, Utc};
+#[cfg(feature = "json")]
+use serde_json::{json, Map, Value};
+
+use crate::feed::Feed;
+use crate::post::Post;
+#[cfg(feature = "json")]
+use crate::profile::Profile;
+use crate::tokenizer::{Token, Tokenizer};
+use crate::util;
+
+#[cfg(feature = "json")]
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+#[cfg(feature = "json")]
+const ACTIVITYSTREAMS_CONTEXT: &str = "https://www.w3.org/ns/activitystreams";
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>";
+/// Maximum length of entry titles, in characters.
+const ENTRY_TITLE_LENGTH: usize = 80;
+
+/// Information about an exported feed as a whole, see [`feed_to_atom`] and [`feed_to_rss2`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedMeta {
+    pub title: String,
+    /// URL the exported document is published at.
+    pub self_url: String,
+    /// Name of the feed's author, e.g. the user's nick.
+    pub author: String,
+}
+
+/// Convert a feed into a JSON Feed 1.1 document.
+///
+/// Every post becomes an item with its `full_id` as the ID, the content rendered
+/// to HTML, the publishing date taken from the post's timestamp and its tags.
+/// The author is taken from the profile the post comes from (see [`Feed::profile_for_post`]):
+/// its display name, the URL of its feed and its avatar. Available with the `json` feature.
+#[cfg(feature = "json")]
+pub fn feed_to_json_feed(feed: &Feed) -> String {
+    let items: Vec<Value> = feed.posts.iter().map(|post| post_to_json_feed_item(feed, post)).collect();
+
+    let document = json!({
+        "version": JSON_FEED_VERSION,
+        "title": "org-social feed",
+        "items": items,
+    });
+
+    document.to_string()
+}
+
+/// Convert a single post into a JSON Feed item.
+#[cfg(feature = "json")]
+fn post_to_json_feed_item(feed: &Feed, post: &Post) -> Value {
+    let mut item = Map::new();
+    item.insert("id".to_string(), json!(post.full_id()));
+    item.insert("content_html".to_string(), json!(render_html(post)));
+
+    if let Some(time) = post.time() {
+        item.insert("date_published".to_string(), json!(time.to_rfc3339()));
+    }
+
+    let tags = post.all_tags();
+    if !tags.is_empty() {
+        item.insert("tags".to_string(), json!(tags));
+    }
+
+    if let Some(lang) = post.lang() {
+        item.insert("language".to_string(), json!(lang));
+    }
+
+    if let Some(warning) = post.content_warning() {
+        item.insert("summary".to_string(), json!(warning));
+    }
+
+    let mut author = Map::new();
+    author.insert("name".to_string(), json!(feed.display_name_for_post(post)));
+    if let Some(url) = post_author_url(feed, post) {
+        author.insert("url".to_string(), json!(url));
+    }
+    if let Some(avatar) = feed.profile_for_post(post).and_then(|profile| profile.avatar().cloned()) {
+        author.insert("avatar".to_string(), json!(avatar));
+    }
+    item.insert("authors".to_string(), json!([Value::Object(author)]));
+
+    if !post.media().is_empty() {
+        let attachments: Vec<Value> = post
+            .media()
+            .iter()
+            .map(|url| json!({ "url": url, "mime_type": guess_mime_type(url) }))
+            .collect();
+        item.insert("attachments".to_string(), json!(attachments));
+    }
+
+    Value::Object(item)
+}
+
+/// Convert a post into an ActivityStreams `Note` object.
+///
+/// The profile is used as the author (`attributedTo`) - its source URL if known, its nick otherwise.
+/// Available with the `json` feature.
+#[cfg(feature = "json")]
+pub fn post_to_activitystreams_note(post: &Post, profile: &Profile) -> Value {
+    let mut note = Map::new();
+    note.insert("@context".to_string(), json!(ACTIVITYSTREAMS_CONTEXT));
+    note.insert("type".to_string(), json!("Note"));
+    note.insert("id".to_string(), json!(post.full_id()));
+
+    let attributed_to = profile.source().cloned().unwrap_or_else(|| profile.nick().to_string());
+    note.insert("attributedTo".to_string(), json!(attributed_to));
+    note.insert("content".to_string(), json!(render_html(post)));
+
+    if let Some(time) = post.time() {
+        note.insert("published".to_string(), json!(time.to_rfc3339()));
+    }
+
+    if let Some(reply_to) = post.reply_to() {
+        note.insert("inReplyTo".to_string(), json!(reply_to));
+    }
+
+    if let Some(warning) = post.content_warning() {
+        note.insert("summary".to_string(), json!(warning));
+        note.insert("sensitive".to_string(), json!(true));
+    }
+
+    let tags: Vec<Value> = post
+        .all_tags()
+        .iter()
+        .map(|tag| json!({ "type": "Hashtag", "name": format!("#{tag}") }))
+        .collect();
+    if !tags.is_empty() {
+        note.insert("tag".to_string(), json!(tags));
+    }
+
+    if !post.media().is_empty() {
+        let attachments: Vec<Value> = post
+            .media()
+            .iter()
+            .map(|url| json!({ "type": "Document", "url": url, "mediaType": guess_mime_type(url) }))
+            .collect();
+        note.insert("attachment".to_string(), json!(attachments));
+    }
+
+    Value::Object(note)
+}
+
+/// Convert a feed into an Atom 1.0 document.
+///
+/// Every post becomes an entry identified by its `full_id`, a `tag:` URI for posts with a timestamp, with the content
+/// rendered to HTML and the author taken from the post's profile. Posts without a timestamp
+/// get the time of the newest post as their update time.
+pub fn feed_to_atom(feed: &Feed, meta: &FeedMeta) -> String {
+    let updated = feed_updated(feed);
+    let mut xml = format!("{XML_DECLARATION}\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&meta.title)));
+    xml.push_str(&format!("  <id>{}</id>\n", escape_xml(&meta.self_url)));
+    xml.push_str(&format!("  <link rel=\"self\" href=\"{}\"/>\n", escape_xml(&meta.self_url)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+    xml.push_str(&format!("  <author><name>{}</name></author>\n", escape_xml(&meta.author)));
+
+    for post in &feed.posts {
+        let time = post.time().unwrap_or(updated);
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>{}</id>\n", escape_xml(&entry_id(post, &meta.self_url))));
+        xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&entry_title(post))));
+        xml.push_str(&format!("    <updated>{}</updated>\n", time.to_rfc3339()));
+        xml.push_str(&format!("    <author><name>{}</name>", escape_xml(&feed.display_name_for_post(post))));
+        if let Some(source) = post_author_url(feed, post) {
+            xml.push_str(&format!("<uri>{}</uri>", escape_xml(&source)));
+        }
+        xml.push_str("</author>\n");
+        if let Some(source) = post.source() {
+            xml.push_str(&format!("    <link href=\"{}\"/>\n", escape_xml(source)));
+        }
+        for tag in post.all_tags() {
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", escape_xml(&tag)));
+        }
+        xml.push_str(&format!("    <content type=\"html\">{}</content>\n", escape_xml(&render_html(post))));
+        xml.push_str("  </entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Convert a feed into an RSS 2.0 document.
+///
+/// Every post becomes an item with its `full_id` as the GUID, the content rendered to HTML
+/// as the description and the author's name as `dc:creator`.
+pub fn feed_to_rss2(feed: &Feed, meta: &FeedMeta) -> String {
+    let mut xml = format!(
+        "{XML_DECLARATION}\n<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<channel>\n"
+    );
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&meta.title)));
+    xml.push_str(&format!("  <link>{}</link>\n", escape_xml(&meta.self_url)));
+    xml.push_str(&format!("  <description>{}</description>\n", escape_xml(&meta.title)));
+    xml.push_str(&format!(
+        "  <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        escape_xml(&meta.self_url)
+    ));
+    xml.push_str(&format!("  <lastBuildDate>{}</lastBuildDate>\n", feed_updated(feed).to_rfc2822()));
+
+    for post in &feed.posts {
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <guid isPermaLink=\"false\">{}</guid>\n", escape_xml(&post.full_id())));
+        xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&entry_title(post))));
+        if let Some(source) = post.source() {
+            xml.push_str(&format!("    <link>{}</link>\n", escape_xml(source)));
+        }
+        if let Some(time) = post.time() {
+            xml.push_str(&format!("    <pubDate>{}</pubDate>\n", time.to_rfc2822()));
+        }
+        xml.push_str(&format!("    <dc:creator>{}</dc:creator>\n", escape_xml(&feed.display_name_for_post(post))));
+        for tag in post.all_tags() {
+            xml.push_str(&format!("    <category>{}</category>\n", escape_xml(&tag)));
+        }
+        xml.push_str(&format!("    <description>{}</description>\n", escape_xml(&render_html(post))));
+        xml.push_str("  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// Time of the newest post of the feed, or the current time if no post has one.
+fn feed_updated(feed: &Feed) -> DateTime<FixedOffset> {
+    feed.newest_post_time().unwrap_or_else(|| Utc::now().fixed_offset())
+}
+
+/// Build the ID of an Atom entry from the post's `full_id`, so it's the same in every export.
+///
+/// Posts with a timestamp get a `tag:` URI (RFC 4151): the authority is the host of the post's source,
+/// the date is the day the post was published. Posts without one are identified by their full ID.
+/// Posts without a source are taken to come from `fallback_url`, without its query and fragment.
+fn entry_id(post: &Post, fallback_url: &str) -> String {
+    let fallback = fallback_url.split(['?', '#']).next().unwrap_or(fallback_url);
+    let source = post.source().as_deref().unwrap_or(fallback);
+    let Some(time) = post.time() else {
+        return format!("{}#{}", source, post.id());
+    };
+    let host = util::url_host(source).unwrap_or("localhost");
+    let rest = source.split_once("://").map_or(source, |(_, rest)| rest);
+    let path = rest.find('/').map_or("", |start| &rest[start..]);
+    format!("tag:{},{}:{}#{}", host, time.format("%Y-%m-%d"), path, post.id())
+}
+
+/// URL identifying the author of a post - the source of its profile, or of the post itself.
+fn post_author_url(feed: &Feed, post: &Post) -> Option<String> {
+    feed.profile_for_post(post)
+        .and_then(|profile| profile.source().cloned())
+        .or_else(|| post.source().clone())
+}
+
+/// Title of a post in Atom and RSS documents - its content warning, or the start of its first line.
+fn entry_title(post: &Post) -> String {
+    if let Some(warning) = post.content_warning() {
+        return warning.to_string();
+    }
+    let text = post.plain_text();
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+    if line.chars().count() > ENTRY_TITLE_LENGTH {
+        format!("{}…", line.chars().take(ENTRY_TITLE_LENGTH).collect::<String>().trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Render the content of a post to HTML.
+///
+/// Uses the parsed tokens if available, tokenizing the content otherwise.
+/// Newlines are rendered as `<br>`. Only http, https, mailto and relative links become `<a>` elements,
+/// other targets (e.g. `javascript:` or `data:`) are rendered as plain text.
+pub fn render_html(post: &Post) -> String {
+    let tokens = if post.tokens().is_empty() && !post.content().is_empty() {
+        Tokenizer::new(post.content().to_string()).tokenize()
+    } else {
+        post.tokens().to_vec()
+    };
+
+    let mut html = String::new();
+    for token in &tokens {
+        match token {
+            Token::PlainText(text) => html.push_str(&escape_html(text)),
+            Token::Bold(text) => html.push_str(&format!("<strong>{}</strong>", escape_html(text))),
+            Token::Italic(text) => html.push_str(&format!("<em>{}</em>", escape_html(text))),
+            Token::BoldItalic(text) => html.push_str(&format!("<strong><em>{}</em></strong>", escape_html(text))),
+            Token::Strikethrough(text) => html.push_str(&format!("<del>{}</del>", escape_html(text))),
+            Token::Underline(text) => html.push_str(&format!("<u>{}</u>", escape_html(text))),
+            Token::InlineCode(text) => html.push_str(&format!("<code>{}</code>", escape_html(text))),
+            Token::Link { url, description } => {
+                let text = description.as_deref().unwrap_or(url);
+                if is_safe_href(url) {
+                    html.push_str(&format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text)));
+                } else {
+                    html.push_str(&escape_html(text));
+                }
+            }
+            Token::Mention { url, username } => {
+                let username = escape_html(username.trim_start_matches('@'));
+                if is_safe_href(url) {
+                    html.push_str(&format!("<a href=\"{}\" class=\"mention\">@{}</a>", escape_html(url), username));
+                } else {
+                    html.push_str(&format!("@{username}"));
+                }
+            }
+            Token::Hashtag(tag) => html.push_str(&format!("<span class=\"hashtag\">#{}</span>", escape_html(tag))),
+        }
+    }
+
+    html.replace('\n', "<br>")
+}
+
+/// Whether a link target is safe to put in an `href`: http, https, mailto or a relative URL.
+///
+/// Browsers ignore whitespace and control characters inside the scheme, so they are removed
+/// before looking at it.
+fn is_safe_href(url: &str) -> bool {
+    let cleaned: String = url.chars().filter(|c| !c.is_ascii_whitespace() && !c.is_control()).collect();
+    let scheme_end = cleaned.find([':', '/', '?', '#']);
+    match scheme_end {
+        Some(end) if cleaned[end..].starts_with(':') => {
+            matches!(cleaned[..end].to_ascii_lowercase().as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+/// Escape the characters that have a special meaning in HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escape text for XML character data and attribute values, dropping characters XML 1.0 doesn't allow.
+///
+/// Escaping `>` also keeps `]]>` out of the output.
+fn escape_xml(text: &str) -> String {
+    let allowed: String = text
+        .chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || (*c >= ' ' && !matches!(c, '\u{FFFE}' | '\u{FFFF}')))
+        .collect();
+    escape_html(&allowed)
+}
+
+/// Guess a MIME type from the extension of a media URL.
+#[cfg(feature = "json")]
+fn guess_mime_type(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    match path.rsplit('.').next() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") | Some("oga") => "audio/ogg",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_post() -> Post {
+        let mut post = Post::new("2025-05-01T12:00:00+01:00".to_string(), "Hello *world* & <friends> #rust".to_string());
+        post.set_source(Some("https://example.com/social.org".to_string()));
+        post.set_author("alice".to_string());
+        post.set_tags(Some(vec!["intro".to_string()]));
+        post.add_media("https://example.com/cat.png".to_string());
+        post
+    }
+
+    #[test]
+    fn test_render_html_escapes_and_formats() {
+        let html = render_html(&sample_post());
+        assert_eq!(html, "Hello <strong>world</strong> &amp; &lt;friends&gt; <span class=\"hashtag\">#rust</span>");
+    }
+
+    #[test]
+    fn test_render_html_drops_unsafe_links() {
+        let post = Post::new(
+            "2025-05-01T12:00:00+01:00".to_string(),
+            "[[javascript:alert(1)][x]] [[ JaVa\tScript:alert(2)]] [[data:text/html,<b>hi</b>][data]] [[https://example.com][ok]] [[mailto:me@example.com][mail]] [[/about][about]]".to_string(),
+        );
+        let html = render_html(&post);
+        assert!(!html.to_lowercase().contains("javascript"), "{html}");
+        assert!(!html.contains("href=\"data:"), "{html}");
+        assert!(html.starts_with("x "), "{html}");
+        assert!(html.contains("<a href=\"https://example.com\">ok</a>"));
+        assert!(html.contains("<a href=\"mailto:me@example.com\">mail</a>"));
+        assert!(html.contains("<a href=\"/about\">about</a>"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_feed_shape() {
+        let feed = Feed { posts: vec![sample_post()], ..Default::default() };
+        let document: Value = serde_json::from_str(&feed_to_json_feed(&feed)).unwrap();
+
+        assert_eq!(document["version"], JSON_FEED_VERSION);
+        assert!(document["title"].is_string());
+
+        let item = &document["items"][0];
+        assert_eq!(item["id"], "https://example.com/social.org#2025-05-01T12:00:00+01:00");
+        assert!(item["content_html"].as_str().unwrap().contains("<strong>world</strong>"));
+        assert_eq!(item["date_published"], "2025-05-01T12:00:00+01:00");
+        assert_eq!(item["tags"], json!(["intro", "rust"]));
+        assert_eq!(item["authors"][0]["name"], "alice");
+        assert_eq!(item["authors"][0]["url"], "https://example.com/social.org");
+        assert!(item["authors"][0].get("avatar").is_none());
+        assert_eq!(item["attachments"][0]["mime_type"], "image/png");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_feed_author_from_profile() {
+        let mut post = sample_post();
+        post.set_author(String::new());
+        post.set_source(Some("http://example.com/social.org/".to_string()));
+        let mut profile = Profile::from(vec![
+            "#+NICK: alice".to_string(),
+            "#+TITLE: Alice's feed".to_string(),
+            "#+AVATAR: https://example.com/alice.png".to_string(),
+        ]);
+        profile.set_source(Some("https://example.com/social.org".to_string()));
+        let feed = Feed { posts: vec![post], profiles: vec![std::sync::Arc::new(profile)], ..Default::default() };
+        let document: Value = serde_json::from_str(&feed_to_json_feed(&feed)).unwrap();
+
+        let author = &document["items"][0]["authors"][0];
+        assert_eq!(author["name"], "alice");
+        assert_eq!(author["url"], "https://example.com/social.org");
+        assert_eq!(author["avatar"], "https://example.com/alice.png");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_activitystreams_note_shape() {
+        let mut post = sample_post();
+        post.set_reply_to(Some("https://other.example/social.org#2025-04-30T10:00:00+00:00".to_string()));
+        post.set_content_warning(Some("Cats".to_string()));
+
+        let mut profile = Profile::default();
+        profile.set_nick("alice".to_string());
+        profile.set_source(Some("https://example.com/social.org".to_string()));
+
+        let note = post_to_activitystreams_note(&post, &profile);
+        assert_eq!(note["@context"], ACTIVITYSTREAMS_CONTEXT);
+        assert_eq!(note["type"], "Note");
+        assert_eq!(note["attributedTo"], "https://example.com/social.org");
+        assert_eq!(note["inReplyTo"], "https://other.example/social.org#2025-04-30T10:00:00+00:00");
+        assert_eq!(note["summary"], "Cats");
+        assert_eq!(note["sensitive"], true);
+        assert_eq!(note["tag"][1]["name"], "#rust");
+        assert_eq!(note["attachment"][0]["type"], "Document");
+    }
+
+    /// Minimal XML well-formedness check: balanced tags, quoted attributes and valid entity references.
+    fn assert_well_formed(xml: &str) {
+        let check_text = |text: &str| {
+            assert!(!text.contains("]]>"), "]]> in character data: {text}");
+            let mut rest = text;
+            while let Some(start) = rest.find('&') {
+                let end = rest[start..].find(';').unwrap_or_else(|| panic!("unterminated entity in {text}")) + start;
+                let entity = &rest[start + 1..end];
+                let valid = matches!(entity, "amp" | "lt" | "gt" | "quot" | "apos")
+                    || entity.strip_prefix('#').is_some_and(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()));
+                assert!(valid, "invalid entity &{entity}; in {text}");
+                rest = &rest[end + 1..];
+            }
+        };
+
+        let body = xml.strip_prefix(XML_DECLARATION).expect("missing XML declaration");
+        let mut stack: Vec<&str> = Vec::new();
+        let mut rest = body;
+        let mut roots = 0;
+        while let Some(start) = rest.find('<') {
+            check_text(&rest[..start]);
+            if stack.is_empty() {
+                assert!(rest[..start].trim().is_empty(), "text outside the root element");
+            }
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop(), Some(name.trim()), "mismatched closing tag");
+            } else {
+                let self_closing = tag.ends_with('/');
+                let tag = tag.trim_end_matches('/');
+                let (name, mut attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                assert!(!name.is_empty() && !name.contains(['<', '&', '"']), "invalid tag name {name}");
+                while !attributes.trim().is_empty() {
+                    let (_, value) = attributes.split_once("=\"").unwrap_or_else(|| panic!("unquoted attribute in <{tag}>"));
+                    let (value, after) = value.split_once('"').expect("unterminated attribute");
+                    assert!(!value.contains('<'), "< in attribute value");
+                    check_text(value);
+                    attributes = after;
+                }
+                if stack.is_empty() {
+                    roots += 1;
+                }
+                if !self_closing {
+                    stack.push(name);
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        assert!(rest.trim().is_empty(), "text after the root element");
+        assert!(stack.is_empty(), "unclosed tags: {stack:?}");
+        assert_eq!(roots, 1);
+    }
+
+    fn tricky_feed() -> Feed {
+        let mut tricky = Post::new(
+            "2025-05-02T08:30:00+00:00".to_string(),
+            "Fish & chips <b>bold</b> ]]> <![CDATA[ x ]]> \u{1} \"quoted\" 'single'".to_string(),
+        );
+        tricky.set_source(Some("https://bob.example:8080/feeds/social.org".to_string()));
+        tricky.set_author("bob & co".to_string());
+        Feed { posts: vec![sample_post(), tricky, Post::new("untimed".to_string(), "No time".to_string())], ..Default::default() }
+    }
+
+    fn meta() -> FeedMeta {
+        FeedMeta {
+            title: "Alice's <timeline> & friends".to_string(),
+            self_url: "https://example.com/feed.xml?a=1&b=2".to_string(),
+            author: "alice".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_assert_well_formed_rejects_broken_xml() {
+        for broken in ["<a><b></a></b>", "<a>x & y</a>", "<a>]]></a>", "<a href=x/>", "<a/><b/>"] {
+            let document = format!("{XML_DECLARATION}{broken}");
+            assert!(std::panic::catch_unwind(|| assert_well_formed(&document)).is_err(), "accepted {broken}");
+        }
+    }
+
+    #[test]
+    fn test_atom_export() {
+        let xml = feed_to_atom(&tricky_feed(), &meta());
+        assert_well_formed(&xml);
+
+        assert!(xml.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(xml.contains("<title>Alice&#39;s &lt;timeline&gt; &amp; friends</title>"));
+        assert!(xml.contains("<link rel=\"self\" href=\"https://example.com/feed.xml?a=1&amp;b=2\"/>"));
+        assert!(xml.contains("<updated>2025-05-02T08:30:00+00:00</updated>"));
+        assert!(xml.contains("<id>tag:example.com,2025-05-01:/social.org#2025-05-01T12:00:00+01:00</id>"));
+        assert!(xml.contains("<id>tag:bob.example,2025-05-02:/feeds/social.org#2025-05-02T08:30:00+00:00</id>"));
+        assert!(xml.contains("<id>https://example.com/feed.xml#untimed</id>"));
+        assert!(xml.contains("<author><name>bob &amp; co</name><uri>https://bob.example:8080/feeds/social.org</uri></author>"));
+        assert!(xml.contains("<category term=\"rust\"/>"));
+        // The rendered HTML is escaped once more
+        assert!(xml.contains("Fish &amp;amp; chips &amp;lt;b&amp;gt;bold&amp;lt;/b&amp;gt; ]]&amp;gt;"));
+        assert!(!xml.contains('\u{1}'));
+        assert_eq!(xml.matches("<entry>").count(), 3);
+
+        // The IDs don't depend on the other posts
+        let mut feed = tricky_feed();
+        let mut untimed = Post::new("untimed".to_string(), "Still no time".to_string());
+        untimed.set_source(Some("https://carol.example/social.org".to_string()));
+        feed.posts.push(untimed);
+        feed.posts.push(Post::new("2025-06-01T00:00:00+00:00".to_string(), "Newer".to_string()));
+        let newer = feed_to_atom(&feed, &meta());
+        assert!(newer.contains("<updated>2025-06-01T00:00:00+00:00</updated>"));
+        assert!(newer.contains("<id>https://example.com/feed.xml#untimed</id>"));
+        assert!(newer.contains("<id>https://carol.example/social.org#untimed</id>"));
+    }
+
+    #[test]
+    fn test_rss2_export() {
+        let xml = feed_to_rss2(&tricky_feed(), &meta());
+        assert_well_formed(&xml);
+
+        assert!(xml.contains("<rss version=\"2.0\""));
+        assert!(xml.contains("<guid isPermaLink=\"false\">https://example.com/social.org#2025-05-01T12:00:00+01:00</guid>"));
+        assert!(xml.contains("<pubDate>Thu, 1 May 2025 12:00:00 +0100</pubDate>"));
+        assert!(xml.contains("<dc:creator>alice</dc:creator>"));
+        assert!(xml.contains("<lastBuildDate>Fri, 2 May 2025 08:30:00 +0000</lastBuildDate>"));
+        assert_eq!(xml.matches("<item>").count(), 3);
+        assert_eq!(xml.matches("<pubDate>").count(), 2);
+    }
+
+    #[test]
+    fn test_entry_title() {
+        let mut post = Post::new("2025-05-01T12:00:00+00:00".to_string(), format!("\n*Bold* start\n{}", "x".repeat(100)));
+        assert_eq!(entry_title(&post), "Bold start");
+
+        post = Post::new("2025-05-01T12:00:00+00:00".to_string(), "y".repeat(100));
+        assert_eq!(entry_title(&post).chars().count(), ENTRY_TITLE_LENGTH + 1);
+
+        post.set_content_warning(Some("Spoilers".to_string()));
+        assert_eq!(entry_title(&post), "Spoilers");
+    }
+}
@@ -37,7 +37,10 @@
 //! ```
 
 pub mod blocks;
+pub mod bookmarks;
 pub mod feed;
+pub mod interop;
+pub mod metrics;
 pub mod network;
 pub mod new_post;
 pub mod notifications;
@@ -45,6 +48,11 @@ pub mod parser;
 pub mod poll;
 pub mod post;
 pub mod profile;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "signing")]
+pub mod signing;
 pub mod threading;
 pub mod tokenizer;
+pub mod transport;
 pub mod util;
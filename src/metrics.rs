@@ -0,0 +1,349 @@
+//! Metrics of the network activity, e.g. to export to the metrics of an application.
+//!
+//! A [`NetworkMetrics`] set in [`crate::network::NetworkConfig::metrics`] is told about every request
+//! handed to the transport - feeds, avatars, publishing - including each redirect and retry.
+//! [`AtomicMetrics`] keeps counters and a latency summary; other sinks can implement the trait.
+
+use crate::transport::{BodyStream, FetchRequest, FetchResponse, Transport, TransportError};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Receives the network activity, see the [module documentation](self).
+///
+/// The methods are called from the tasks making the requests, so they should return quickly.
+pub trait NetworkMetrics: std::fmt::Debug + Send + Sync {
+    /// A request is handed to the transport.
+    fn on_request_start(&self, _url: &str) {}
+
+    /// A response was received and its body read to the end, or dropped before, e.g. when a feed is
+    /// cut off at its `since` cutoff or a request times out while reading the body.
+    fn on_response(&self, _response: &ResponseMetrics) {}
+
+    /// A request failed, before or while reading the body.
+    fn on_error(&self, _error: &ErrorMetrics) {}
+}
+
+/// A response, see [`NetworkMetrics::on_response`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMetrics {
+    /// URL of the request, the target of a redirect for the requests following one.
+    pub url: String,
+    pub status: u16,
+    /// Size of the body read, as received from the transport (decompressed, for reqwest).
+    pub bytes: u64,
+    /// Time from the start of the request until the body was read or dropped.
+    pub elapsed: Duration,
+}
+
+/// A failed request, see [`NetworkMetrics::on_error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorMetrics {
+    pub url: String,
+    pub kind: RequestErrorKind,
+    /// Time from the start of the request until it failed.
+    pub elapsed: Duration,
+}
+
+/// How a request failed, see [`ErrorMetrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestErrorKind {
+    /// No response was received, e.g. the connection was refused.
+    Request,
+    /// The response was received, reading its body failed.
+    Body,
+    /// The request was dropped before its response was received, e.g. at its timeout.
+    Cancelled,
+}
+
+/// Counts the network activity with atomics, see [`AtomicMetrics::snapshot`].
+///
+/// Share it with an `Arc` between the [`crate::network::NetworkConfig`] and the code reading it.
+#[derive(Debug, Default)]
+pub struct AtomicMetrics {
+    requests: AtomicU64,
+    responses: AtomicU64,
+    error_responses: AtomicU64,
+    errors: AtomicU64,
+    bytes: AtomicU64,
+    latency_total_micros: AtomicU64,
+    latency_max_micros: AtomicU64,
+}
+
+impl AtomicMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The counts so far.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let responses = self.responses.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            responses,
+            error_responses: self.error_responses.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            latency: LatencySummary {
+                count: responses,
+                total: Duration::from_micros(self.latency_total_micros.load(Ordering::Relaxed)),
+                max: Duration::from_micros(self.latency_max_micros.load(Ordering::Relaxed)),
+            },
+        }
+    }
+}
+
+impl NetworkMetrics for AtomicMetrics {
+    fn on_request_start(&self, _url: &str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_response(&self, response: &ResponseMetrics) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        if response.status >= 400 {
+            self.error_responses.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes.fetch_add(response.bytes, Ordering::Relaxed);
+        let micros = u64::try_from(response.elapsed.as_micros()).unwrap_or(u64::MAX);
+        self.latency_total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.latency_max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, _error: &ErrorMetrics) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts of an [`AtomicMetrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Requests handed to the transport.
+    pub requests: u64,
+    /// Responses received, whatever their status.
+    pub responses: u64,
+    /// Responses with an error status (`4xx` or `5xx`).
+    pub error_responses: u64,
+    /// Requests that failed without a complete response, see [`RequestErrorKind`].
+    pub errors: u64,
+    /// Bytes of the bodies read.
+    pub bytes: u64,
+    /// Latency of the responses.
+    pub latency: LatencySummary,
+}
+
+impl MetricsSnapshot {
+    /// Share of the requests that failed or got an error status, 0 if none was made.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        (self.errors + self.error_responses) as f64 / self.requests as f64
+    }
+}
+
+/// Latency of responses, from the start of the request until the body was read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    /// The average latency, None if there was no response.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count).ok().filter(|count| *count > 0)?;
+        Some(self.total / count)
+    }
+}
+
+/// Make a request with `transport`, reporting it to `metrics`.
+pub(crate) async fn send_metered(
+    transport: &dyn Transport,
+    request: FetchRequest,
+    metrics: Arc<dyn NetworkMetrics>,
+) -> Result<FetchResponse, TransportError> {
+    metrics.on_request_start(&request.url);
+    let mut report = Report { metrics: Some(metrics), url: request.url.clone(), started: Instant::now() };
+    let mut response = match transport.send(request).await {
+        Ok(response) => response,
+        Err(error) => {
+            report.error(RequestErrorKind::Request);
+            return Err(error);
+        }
+    };
+
+    let headers = std::mem::take(&mut response.headers);
+    let (status, url) = (response.status, response.url.clone());
+    let body = MeteredBody { body: response.into_body(), report, status, bytes: 0 };
+    Ok(FetchResponse::streaming(status, &url, headers, Box::pin(body)))
+}
+
+/// The report of a request still running. If it's dropped before being made, the request was cancelled.
+struct Report {
+    metrics: Option<Arc<dyn NetworkMetrics>>,
+    url: String,
+    started: Instant,
+}
+
+impl Report {
+    fn error(&mut self, kind: RequestErrorKind) {
+        if let Some(metrics) = self.metrics.take() {
+            metrics.on_error(&ErrorMetrics { url: std::mem::take(&mut self.url), kind, elapsed: self.started.elapsed() });
+        }
+    }
+
+    fn response(&mut self, status: u16, bytes: u64) {
+        if let Some(metrics) = self.metrics.take() {
+            let url = std::mem::take(&mut self.url);
+            metrics.on_response(&ResponseMetrics { url, status, bytes, elapsed: self.started.elapsed() });
+        }
+    }
+}
+
+impl Drop for Report {
+    fn drop(&mut self) {
+        self.error(RequestErrorKind::Cancelled);
+    }
+}
+
+/// A body counting its bytes, reporting the response once it's read or dropped.
+struct MeteredBody {
+    body: BodyStream,
+    report: Report,
+    status: u16,
+    bytes: u64,
+}
+
+impl futures_core::Stream for MeteredBody {
+    type Item = Result<Vec<u8>, TransportError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.body.as_mut().poll_next(context);
+        match &polled {
+            Poll::Ready(Some(Ok(chunk))) => self.bytes += chunk.len() as u64,
+            Poll::Ready(Some(Err(_))) => self.report.error(RequestErrorKind::Body),
+            Poll::Ready(None) => {
+                let (status, bytes) = (self.status, self.bytes);
+                self.report.response(status, bytes);
+            }
+            Poll::Pending => {}
+        }
+        polled
+    }
+}
+
+impl Drop for MeteredBody {
+    fn drop(&mut self) {
+        // Dropped before the end, what was read is reported
+        self.report.response(self.status, self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{self, FetchOptions, NetworkConfig, NetworkError, PublishAuth, PublishOptions, PublishTarget};
+    use crate::profile::Profile;
+    use crate::transport::{MockResponse, MockTransport};
+    use std::sync::Mutex;
+
+    const FEED: &str = "#+NICK: alice\n#+AVATAR: avatar.png\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n";
+
+    /// Records the calls, and counts them with an [`AtomicMetrics`].
+    #[derive(Debug, Default)]
+    struct Recorder {
+        calls: Mutex<Vec<String>>,
+        counts: AtomicMetrics,
+    }
+
+    impl NetworkMetrics for Recorder {
+        fn on_request_start(&self, url: &str) {
+            self.calls.lock().unwrap().push(format!("start {url}"));
+            self.counts.on_request_start(url);
+        }
+
+        fn on_response(&self, response: &ResponseMetrics) {
+            self.calls.lock().unwrap().push(format!("{} {} {}", response.status, response.url, response.bytes));
+            self.counts.on_response(response);
+        }
+
+        fn on_error(&self, error: &ErrorMetrics) {
+            self.calls.lock().unwrap().push(format!("{:?} {}", error.kind, error.url));
+            self.counts.on_error(error);
+        }
+    }
+
+    impl Recorder {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.calls.lock().unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let (old, feed, avatar) =
+            ("https://old.example/social.org", "https://example.com/social.org", "https://example.com/avatar.png");
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(old, MockResponse::status(301).with_header("Location", feed))
+            .respond(feed, MockResponse::ok(FEED))
+            .respond(avatar, MockResponse::ok(vec![0; 100]).with_header("Content-Type", "image/png"))
+            .respond("https://down.example/social.org", MockResponse::error("connection refused"))
+            .respond("https://slow.example/social.org", MockResponse::ok(FEED).with_delay(Duration::from_secs(5)))
+            .respond("https://example.com/upload.org", MockResponse::status(201));
+        let recorder = Arc::new(Recorder::default());
+        let config = NetworkConfig { transport: Some(transport), metrics: Some(recorder.clone()), ..Default::default() };
+        let options = FetchOptions { network: config.clone(), ..Default::default() };
+
+        // Each redirect is a request of its own
+        let fetched = network::fetch_feed(old, &options).await.unwrap();
+        assert_eq!(recorder.take(), [format!("start {old}"), format!("301 {old} 0"), format!("start {feed}"), format!("200 {feed} {}", FEED.len())]);
+
+        let profile: Profile = fetched.profile;
+        network::fetch_avatar_with_config(&profile, 1000, None, &config).await.unwrap();
+        assert_eq!(recorder.take(), [format!("start {avatar}"), format!("200 {avatar} 100")]);
+
+        let error = network::fetch_feed("https://down.example/social.org", &options).await.unwrap_err();
+        assert!(matches!(error, NetworkError::Transport { .. }));
+        assert_eq!(recorder.take(), ["start https://down.example/social.org", "Request https://down.example/social.org"]);
+
+        let timeout = FetchOptions { per_feed_timeout: Some(Duration::from_millis(20)), ..options.clone() };
+        let error = network::fetch_feed("https://slow.example/social.org", &timeout).await.unwrap_err();
+        assert!(matches!(error, NetworkError::Timeout { .. }));
+        assert_eq!(recorder.take(), ["start https://slow.example/social.org", "Cancelled https://slow.example/social.org"]);
+
+        let target = PublishTarget::HttpPut { url: "https://example.com/upload.org".to_string(), auth: PublishAuth::None };
+        network::publish(FEED, &target, &PublishOptions { network: config.clone(), ..Default::default() }).await.unwrap();
+        assert_eq!(recorder.take(), ["start https://example.com/upload.org", "201 https://example.com/upload.org 0"]);
+
+        let snapshot = recorder.counts.snapshot();
+        assert_eq!(
+            (snapshot.requests, snapshot.responses, snapshot.error_responses, snapshot.errors, snapshot.bytes),
+            (6, 4, 0, 2, FEED.len() as u64 + 100)
+        );
+        assert_eq!(snapshot.latency.count, 4);
+        assert!(snapshot.latency.mean().unwrap() <= snapshot.latency.max);
+        assert!((snapshot.error_rate() - 2.0 / 6.0).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_partial_body() {
+        // A body dropped before the end reports what was read
+        let transport = Arc::new(MockTransport::new());
+        transport.respond("https://example.com/a", MockResponse::ok("some body"));
+        let recorder = Arc::new(Recorder::default());
+        let request = FetchRequest::get("https://example.com/a");
+        let response = send_metered(transport.as_ref(), request, recorder.clone()).await.unwrap();
+        drop(response);
+        assert_eq!(recorder.take(), ["start https://example.com/a", "200 https://example.com/a 0"]);
+
+        let request = FetchRequest::get("https://example.com/missing");
+        let mut response = send_metered(transport.as_ref(), request, recorder.clone()).await.unwrap();
+        assert_eq!(response.chunk().await.unwrap(), None);
+        assert_eq!(recorder.take(), ["start https://example.com/missing", "404 https://example.com/missing 0"]);
+        assert_eq!(recorder.counts.snapshot().error_responses, 1);
+    }
+}
@@ -3,133 +3,4409 @@
 //! This module provides functionality to fetch org-social files from remote URLs
 //! and parse them into profiles and posts using concurrent HTTP requests.
 
-use crate::profile::Profile;
+use crate::metrics::{send_metered, NetworkMetrics};
+use crate::profile::{Profile, ProfileDiff};
 use crate::post::Post;
-use crate::parser::parse_file;
+use crate::parser::{parse_file, parse_profile, serialize_file, StreamingParser};
+use crate::transport::{single_chunk, BodyStream, FetchRequest, FetchResponse, ReqwestTransport, Transport, TransportError};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
+use tokio::sync::Semaphore;
 
-/// Fetches and parses org-social files from followed users concurrently.
-/// # Arguments
-///
-/// * `followed_users` - A vector of tuples containing (identifier, url) pairs
-/// * `timeout` - Optional timeout duration for each feed request. If None, no timeout is applied.
-/// # Returns
-///
-/// A vector of tuples containing (Profile, Vec\<Post\>, String), where the String is the URL, for successfully fetched feeds
-pub async fn get_feeds(followed_users: Vec<(String, String)>, timeout: Option<Duration>) -> Vec<(Profile, Vec<Post>, String)> {
-    let client = std::sync::Arc::new(reqwest::Client::new());
+/// Default maximum number of requests running at the same time when fetching feeds or avatars,
+/// see [`NetworkConfig::max_concurrency`].
+pub const MAX_CONCURRENT_FETCHES: usize = 8;
 
-    let fetch_futures: Vec<_> = followed_users
-        .into_iter()
-        .map(|(identifier, url)| {
-            let client = client.clone();
-            let identifier = identifier.clone();
-            let url = url.clone();
-            let timeout = timeout;
-            
-            tokio::spawn(async move {
-                match fetch_and_parse_feed(&client, &url, timeout).await {
-                    Ok((profile, posts)) => Some((profile, posts, url)),
-                    Err(e) => {
-                        eprintln!("Failed to fetch feed for {identifier} at {url}: {e}");
-                        None
-                    }
-                }
-            })
+/// Default size limit for avatars and other binary resources, in bytes, see [`FetchBytesOptions::max_bytes`].
+pub const DEFAULT_MAX_AVATAR_BYTES: usize = 2 * 1024 * 1024;
+
+/// Default size limit for feeds, in bytes, see [`NetworkConfig::max_body_bytes`].
+pub const DEFAULT_MAX_FEED_BYTES: usize = 8 * 1024 * 1024;
+
+/// Default for [`NetworkConfig::max_redirects`].
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// `User-Agent` sent with requests by default.
+pub const DEFAULT_USER_AGENT: &str =
+    concat!("org-social-lib-rs/", env!("CARGO_PKG_VERSION"), " (+https://github.com/AdsanTheGreat/org-social-lib-rs)");
+
+/// How requests are made, see [`get_feeds_with_config`].
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Client used for the requests, e.g. one built with TLS settings or default headers.
+    /// If None, each operation builds one for its requests, or all the operations of a
+    /// [`NetworkHandle`] share the one it built.
+    ///
+    /// Like the connections it keeps, a client should only be used within a single Tokio runtime.
+    /// Build it with `reqwest::redirect::Policy::none()` so redirects are followed as set in
+    /// [`NetworkConfig::max_redirects`] and permanent ones are reported, otherwise the client's policy applies.
+    pub client: Option<reqwest::Client>,
+    /// `User-Agent` header sent with every request, [`DEFAULT_USER_AGENT`] by default.
+    /// If None, the client's own is used.
+    pub user_agent: Option<String>,
+    /// Maximum number of requests running at the same time, [`MAX_CONCURRENT_FETCHES`] by default.
+    /// Other requests wait for one to finish, their timeout only starts once they do.
+    pub max_concurrency: usize,
+    /// Limit on the rate of feed requests to each host, none by default.
+    ///
+    /// Each request takes a token, including HEAD requests and retries. Fetches wait for the turn of
+    /// their first request before taking one of the [`NetworkConfig::max_concurrency`] slots,
+    /// so a throttled host doesn't hold back the others, and their timeout only starts once they got it.
+    /// The following requests wait in their slot, within their timeout.
+    pub rate_limit: Option<RateLimiter>,
+    /// Size limit for feeds and other text resources, in bytes, [`DEFAULT_MAX_FEED_BYTES`] by default.
+    /// Bigger responses fail with [`NetworkError::TooLarge`] without being read to the end.
+    pub max_body_bytes: usize,
+    /// Maximum number of redirects followed for a request, [`DEFAULT_MAX_REDIRECTS`] by default.
+    /// More fail with [`NetworkError::TooManyRedirects`].
+    pub max_redirects: usize,
+    /// Before downloading again a cached feed from a server without `ETag`s, ask with a `HEAD` request
+    /// whether it changed, see [`check_modified`]. Off by default.
+    ///
+    /// Applies to the fetches using a [`FeedCache`], like [`crate::feed::Feed::refresh`] and [`FeedWatcher`].
+    /// Once a server answered a `HEAD` request differently than the `GET` that followed, the feed is
+    /// marked in the cache with [`CacheEntry::head_unreliable`] and always downloaded.
+    pub head_precheck: bool,
+    /// Proxy for `http://` URLs, e.g. `http://proxy.example:3128`.
+    ///
+    /// The proxy settings only apply when no [`NetworkConfig::client`] is set. Connecting through
+    /// the proxy is part of the request, so it counts toward the timeout of the request.
+    pub http_proxy: Option<String>,
+    /// Proxy for `https://` URLs, tunneled with `CONNECT`.
+    pub https_proxy: Option<String>,
+    /// SOCKS5 proxy for the URLs the other proxies don't apply to, e.g. `socks5h://127.0.0.1:9050` for Tor.
+    /// With `socks5h://`, host names are resolved by the proxy.
+    pub socks5: Option<String>,
+    /// Use the proxies of the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables,
+    /// true by default. They're ignored when any of the proxies above is set.
+    pub proxy_from_env: bool,
+    /// Transport the requests are made with, see [`crate::transport`].
+    /// If None, a [`ReqwestTransport`] with the [`NetworkConfig::client`] is used.
+    ///
+    /// Redirects, timeouts, retries and size limits are handled on top of the transport, as set here.
+    pub transport: Option<Arc<dyn Transport>>,
+    /// Resolvers of the feeds of other URL schemes than `http`, `https` and `file`, by lowercase scheme,
+    /// see [`NetworkConfig::register_scheme`]. They take precedence over the built-in ones.
+    pub scheme_resolvers: HashMap<String, Arc<dyn SchemeResolver>>,
+    /// Receives every request made with the transport, see [`crate::metrics`]. None by default.
+    pub metrics: Option<Arc<dyn NetworkMetrics>>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            client: None,
+            user_agent: Some(DEFAULT_USER_AGENT.to_string()),
+            max_concurrency: MAX_CONCURRENT_FETCHES,
+            rate_limit: None,
+            max_body_bytes: DEFAULT_MAX_FEED_BYTES,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            head_precheck: false,
+            http_proxy: None,
+            https_proxy: None,
+            socks5: None,
+            proxy_from_env: true,
+            transport: None,
+            scheme_resolvers: HashMap::new(),
+            metrics: None,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// A configuration using the given client.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client: Some(client), ..Self::default() }
+    }
+
+    /// Add the name of the application to the user agent, like the `:CLIENT:` of its posts,
+    /// e.g. `org-social-lib-rs/0.4.3 (+https://github.com/AdsanTheGreat/org-social-lib-rs) my-client/1.0`.
+    pub fn with_client_name(mut self, client_name: &str) -> Self {
+        let client_name = client_name.trim();
+        if !client_name.is_empty() {
+            let user_agent = self.user_agent.get_or_insert_with(|| DEFAULT_USER_AGENT.to_string());
+            user_agent.push(' ');
+            user_agent.push_str(client_name);
+        }
+        self
+    }
+
+    /// Read the feeds with URLs of `scheme` (e.g. `ipfs` for `ipfs://` URLs) with `resolver`,
+    /// replacing the resolver registered for it, or the built-in one.
+    pub fn register_scheme(&mut self, scheme: &str, resolver: Arc<dyn SchemeResolver>) {
+        self.scheme_resolvers.insert(scheme.to_ascii_lowercase(), resolver);
+    }
+
+    /// The resolver of the scheme of `url`, the built-in [`HttpResolver`] and [`FileResolver`] unless
+    /// another one is registered. Absolute paths are read with the resolver of `file`.
+    ///
+    /// Fails with [`NetworkError::UnsupportedScheme`] if there's none.
+    fn resolver(&self, url: &str) -> Result<Arc<dyn SchemeResolver>, NetworkError> {
+        let scheme = match url.split_once("://") {
+            Some((scheme, _)) => scheme.to_ascii_lowercase(),
+            None if local_feed_path(url).is_some() => "file".to_string(),
+            None => return Err(NetworkError::InvalidUrl { url: url.to_string() }),
+        };
+        if let Some(resolver) = self.scheme_resolvers.get(&scheme) {
+            return Ok(resolver.clone());
+        }
+        match scheme.as_str() {
+            "http" | "https" => Ok(Arc::new(HttpResolver)),
+            "file" => Ok(Arc::new(FileResolver)),
+            _ => Err(NetworkError::UnsupportedScheme { url: url.to_string(), scheme }),
+        }
+    }
+
+    /// A semaphore limiting the number of requests running at the same time, allowing at least one.
+    fn concurrency_limit(&self) -> Arc<Semaphore> {
+        Arc::new(Semaphore::new(self.max_concurrency.max(1)))
+    }
+
+    /// The client to make requests with. If none is set, a new one is built for the proxy settings.
+    ///
+    /// Fails with [`NetworkError::InvalidProxy`] if no client is set and a proxy can't be used.
+    pub fn client(&self) -> Result<reqwest::Client, NetworkError> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            None => build_client(&self.proxy_settings()),
+        }
+    }
+
+    /// The configuration with its transport created if it has none, so the requests of an operation
+    /// share a client. Clients aren't shared between operations, which may run in different runtimes.
+    fn with_transport(&self) -> NetworkConfig {
+        NetworkHandle::new(self.clone()).into()
+    }
+
+    /// The proxy settings, which the clients built by the library are shared by.
+    fn proxy_settings(&self) -> ProxySettings {
+        ProxySettings {
+            http: self.http_proxy.clone(),
+            https: self.https_proxy.clone(),
+            socks5: self.socks5.clone(),
+            from_env: self.proxy_from_env,
+        }
+    }
+
+    /// Start a request with the configured client and headers.
+    fn request(&self, method: reqwest::Method, url: &str) -> Result<reqwest::RequestBuilder, NetworkError> {
+        let request = self.client()?.request(method, url);
+        Ok(match &self.user_agent {
+            Some(user_agent) => request.header(reqwest::header::USER_AGENT, user_agent),
+            None => request,
         })
-        .collect();
+    }
+
+    /// Make a request started with [`NetworkConfig::request`] with the transport, without following redirects.
+    async fn execute(&self, request: reqwest::RequestBuilder, url: &str) -> Result<FetchResponse, NetworkError> {
+        let transport_error = |source| NetworkError::Transport { url: url.to_string(), source };
+        let request = request.build().map_err(|source| transport_error(source.into()))?;
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => Arc::new(ReqwestTransport::new(self.client()?)),
+        };
+        let request = FetchRequest::from_reqwest(&request);
+        match &self.metrics {
+            Some(metrics) => send_metered(transport.as_ref(), request, metrics.clone()).await.map_err(transport_error),
+            None => transport.send(request).await.map_err(transport_error),
+        }
+    }
+
+    /// Make a GET request with `headers`, following up to `max_redirects` redirects, see [`NetworkConfig::send_request`].
+    async fn send(&self, url: &str, headers: reqwest::header::HeaderMap) -> Result<(FetchResponse, Option<String>), NetworkError> {
+        self.send_request(reqwest::Method::GET, url, headers).await
+    }
+
+    /// Make a request with `headers`, following up to `max_redirects` redirects with the same method.
+    ///
+    /// A redirect without a usable `Location` isn't followed, its response is returned.
+    ///
+    /// # Returns
+    ///
+    /// The final response, and where the resource moved to if the redirects leading to it
+    /// started with permanent ones (`301`, `308`): the target of the last of those
+    async fn send_request(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        headers: reqwest::header::HeaderMap,
+    ) -> Result<(FetchResponse, Option<String>), NetworkError> {
+        let mut response = self.execute(self.request(method.clone(), url)?.headers(headers.clone()), url).await?;
+        let mut moved_to = None;
+        let mut permanent = true;
+        let mut redirects = 0;
 
-    // Wait for all futures to complete and collect successful results
-    let mut results = Vec::new();
-    for future in fetch_futures {
-        if let Ok(Some((profile, posts, url))) = future.await {
-            results.push((profile, posts, url));
+        while matches!(response.status, 301 | 302 | 303 | 307 | 308) {
+            let location = response
+                .header(reqwest::header::LOCATION)
+                .and_then(|location| reqwest::Url::parse(&response.url).ok()?.join(location).ok());
+            let Some(location) = location else {
+                break;
+            };
+            if redirects == self.max_redirects {
+                return Err(NetworkError::TooManyRedirects { url: url.to_string(), limit: self.max_redirects });
+            }
+            redirects += 1;
+            permanent &= matches!(response.status, 301 | 308);
+            if permanent {
+                moved_to = Some(location.to_string());
+            }
+            // Done with the redirect before following it, so its connection can be reused
+            drop(response);
+            response = self.execute(self.request(method.clone(), location.as_str())?.headers(headers.clone()), url).await?;
         }
+        Ok((response, moved_to))
     }
-    
-    results
 }
 
-/// Fetches a single org-social file and parses it.
-///
-/// # Arguments
-/// * `client` - The HTTP client to use for the request
-/// * `url` - The URL of the org-social file to fetch
-/// * `timeout` - Optional timeout duration for the request. If None, no timeout is applied.
-/// # Returns
+/// A [`NetworkConfig`] with its transport created once, so the operations given the handle or its clones
+/// share one client and its connections. Cheap to clone.
 ///
-/// A Result containing a tuple of (Profile, Vec<Post>) on success, or an error on failure
-async fn fetch_and_parse_feed(
-    client: &reqwest::Client,
-    url: &str,
-    timeout: Option<Duration>,
-) -> Result<(Profile, Vec<Post>), Box<dyn std::error::Error>> {
-    let request_future = async {
-        let response = client.get(url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(format!("HTTP error {}: {}", response.status(), url).into());
-        }
-        
-        let content = response.text().await?;
-        let (profile, posts) = parse_file(&content, Some(url.to_string()));
-        
-        Ok((profile, posts))
-    };
+/// A [`NetworkConfig`] taken from a handle (with [`NetworkHandle::config`] or `From`) keeps its transport,
+/// so it can be set in the options of any operation, e.g. [`FetchOptions::network`] or [`PublishOptions::network`].
+/// The functions that don't take a configuration use [`NetworkHandle::shared`].
+#[derive(Debug, Clone)]
+pub struct NetworkHandle {
+    config: Arc<NetworkConfig>,
+}
 
-    match timeout {
-        Some(duration) => {
-            match tokio::time::timeout(duration, request_future).await {
-                Ok(result) => result,
-                Err(_) => Err(format!("Timeout after {:?} while fetching {}", duration, url).into()),
+impl NetworkHandle {
+    /// Create the transport of `config`, a [`ReqwestTransport`] with its client unless one is set.
+    ///
+    /// If its client can't be built, e.g. because of an invalid proxy, the handle is still created
+    /// and its requests fail with [`NetworkError::InvalidProxy`].
+    pub fn new(config: NetworkConfig) -> Self {
+        let mut config = config;
+        if config.transport.is_none() {
+            if let Ok(client) = config.client() {
+                config.transport = Some(Arc::new(ReqwestTransport::new(client.clone())));
+                config.client = Some(client);
             }
         }
-        None => request_future.await,
+        Self { config: Arc::new(config) }
+    }
+
+    /// The handle of the default configuration, created on first use and shared by the whole process.
+    ///
+    /// Like the client it holds, it should only be used within a single Tokio runtime: its pooled
+    /// connections belong to the runtime of the first requests, and fail once that runtime is shut down.
+    /// Applications with several runtimes (or tests, each with its own) should create a handle per runtime
+    /// with [`NetworkHandle::new`] and pass its configuration to the functions taking one.
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<NetworkHandle> = OnceLock::new();
+        SHARED.get_or_init(|| NetworkHandle::new(NetworkConfig::default())).clone()
+    }
+
+    /// The configuration of the handle, with its transport.
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
     }
 }
 
-/// Fetches and parses org-social files from a profile's follow list.
+impl Default for NetworkHandle {
+    fn default() -> Self {
+        Self::shared()
+    }
+}
+
+impl From<NetworkHandle> for NetworkConfig {
+    fn from(handle: NetworkHandle) -> Self {
+        Arc::unwrap_or_clone(handle.config)
+    }
+}
+
+/// Limits the rate of requests to each host with a token bucket, see [`NetworkConfig::rate_limit`].
 ///
-/// This is a convenient wrapper around `get_feeds` that extracts the follow list
-/// from a profile and fetches all followed users' feeds.
-/// # Arguments
-/// * `profile` - The profile containing the follow list
-/// * `timeout` - Optional timeout duration for each feed request. If None, no timeout is applied.
+/// Clones share their buckets, so the limit holds across all the fetches made with a configuration
+/// and its clones, e.g. the refreshes of a feed or the polls of a [`FeedWatcher`].
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: u32,
+    /// Tokens left for each host, and when they were counted.
+    buckets: Arc<Mutex<HashMap<String, (f64, std::time::Instant)>>>,
+}
+
+impl RateLimiter {
+    /// Allow `requests_per_second` requests to each host on average, and up to `burst` (at least 1) at once.
+    ///
+    /// A rate that isn't a positive number doesn't limit anything.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self { requests_per_second, burst: burst.max(1), buckets: Arc::default() }
+    }
+
+    /// Wait for the turn of a request to the host of `url`.
+    ///
+    /// # Returns
+    ///
+    /// How long the request waited
+    pub(crate) async fn acquire(&self, url: &str) -> Duration {
+        let rate = self.requests_per_second;
+        let Some(host) = crate::util::url_host(url).filter(|_| rate.is_finite() && rate > 0.0) else {
+            return Duration::ZERO;
+        };
+
+        // Requests take a token even if there's none left, so the ones waiting are spaced out
+        let wait = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let now = std::time::Instant::now();
+            let (tokens, counted_at) = buckets.entry(host.to_ascii_lowercase()).or_insert((f64::from(self.burst), now));
+            *tokens = (*tokens + now.duration_since(*counted_at).as_secs_f64() * rate).min(f64::from(self.burst)) - 1.0;
+            *counted_at = now;
+            Duration::from_secs_f64((-*tokens).max(0.0) / rate)
+        };
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+        wait
+    }
+}
+
+/// The [`NetworkConfig::rate_limit`] of the requests for one feed, each request taking a token.
 ///
-/// # Returns
+/// The token of the first request is taken before the feed gets one of the [`NetworkConfig::max_concurrency`] slots,
+/// see [`Throttle::first_request`].
+#[derive(Debug, Default)]
+struct Throttle {
+    limiter: Option<RateLimiter>,
+    /// Whether the token of the next request was already taken.
+    prepaid: AtomicBool,
+    /// Time waited for tokens so far, in nanoseconds.
+    waited: AtomicU64,
+}
+
+impl Throttle {
+    /// The limit of `config` for `url`, local files aren't limited.
+    fn new(config: &NetworkConfig, url: &str) -> Self {
+        let limiter = config.rate_limit.clone().filter(|_| local_feed_path(url).is_none());
+        Self { limiter, ..Default::default() }
+    }
+
+    /// Take the token of the first request ahead of it.
+    ///
+    /// # Returns
+    ///
+    /// How long it waited
+    async fn first_request(&self, url: &str) -> Duration {
+        let waited = self.acquire(url).await;
+        self.prepaid.store(true, Ordering::Relaxed);
+        waited
+    }
+
+    /// Wait for the turn of a request to `url`, unless its token was taken ahead.
+    async fn request(&self, url: &str) {
+        if !self.prepaid.swap(false, Ordering::Relaxed) {
+            self.acquire(url).await;
+        }
+    }
+
+    async fn acquire(&self, url: &str) -> Duration {
+        let Some(limiter) = &self.limiter else {
+            return Duration::ZERO;
+        };
+        let waited = limiter.acquire(url).await;
+        self.waited.fetch_add(waited.as_nanos() as u64, Ordering::Relaxed);
+        waited
+    }
+
+    /// Total time waited for tokens.
+    fn waited(&self) -> Duration {
+        Duration::from_nanos(self.waited.load(Ordering::Relaxed))
+    }
+}
+
+/// The proxy settings of a [`NetworkConfig`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ProxySettings {
+    http: Option<String>,
+    https: Option<String>,
+    socks5: Option<String>,
+    from_env: bool,
+}
+
+/// Builds a client using the proxy settings. It doesn't follow redirects, [`NetworkConfig::send`] does.
+fn build_client(settings: &ProxySettings) -> Result<reqwest::Client, NetworkError> {
+    let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+    let explicit = settings.http.is_some() || settings.https.is_some() || settings.socks5.is_some();
+    if explicit || !settings.from_env {
+        builder = builder.no_proxy();
+    }
+    // Proxies are tried in order, so the SOCKS one only gets the URLs the others don't take
+    for (proxy, scheme) in [(&settings.http, "http"), (&settings.https, "https"), (&settings.socks5, "all")] {
+        let Some(proxy) = proxy else {
+            continue;
+        };
+        let built = match scheme {
+            "http" => reqwest::Proxy::http(proxy),
+            "https" => reqwest::Proxy::https(proxy),
+            _ => reqwest::Proxy::all(proxy),
+        };
+        builder = builder.proxy(built.map_err(|source| NetworkError::InvalidProxy { proxy: proxy.clone(), source })?);
+    }
+    builder.build().map_err(|source| NetworkError::InvalidProxy {
+        proxy: [&settings.http, &settings.https, &settings.socks5].into_iter().flatten().cloned().collect::<Vec<_>>().join(", "),
+        source,
+    })
+}
+
+/// Errors that can occur while fetching remote resources.
+#[derive(Debug)]
+pub enum NetworkError {
+    /// The profile has no `#+AVATAR`.
+    NoAvatar,
+    /// The URL can't be parsed, or is relative without a base to resolve it against.
+    InvalidUrl { url: String },
+    /// No resolver is registered for the scheme of the URL, see [`NetworkConfig::register_scheme`].
+    UnsupportedScheme { url: String, scheme: String },
+    /// A proxy of the [`NetworkConfig`] can't be used, e.g. because its URL is invalid.
+    InvalidProxy { proxy: String, source: reqwest::Error },
+    /// The server answered with a non-success status.
+    Http { url: String, status: u16 },
+    /// The request didn't complete in time.
+    Timeout { url: String, after: Duration },
+    /// The request was cancelled because the overall deadline of the fetch was reached,
+    /// see [`FetchOptions::overall_deadline`].
+    DeadlineExceeded { url: String },
+    /// The response is bigger than the allowed size.
+    TooLarge { url: String, limit: usize },
+    /// The request was redirected more than the allowed number of times.
+    TooManyRedirects { url: String, limit: usize },
+    /// The file changed on the server since its ETag was read (`412 Precondition Failed`), see [`publish`].
+    Conflict { url: String },
+    /// The resource isn't of a type in [`FetchBytesOptions::accept`], None if its type is unknown.
+    UnexpectedType { url: String, content_type: Option<String> },
+    /// The request failed before a response was received (DNS, connection, TLS...).
+    Transport { url: String, source: TransportError },
+    /// The feed isn't valid UTF-8. With the `charsets` feature, feeds are decoded with their charset instead,
+    /// replacing invalid bytes, see [`FetchWarning::LossyDecode`].
+    InvalidUtf8 { url: String, source: std::str::Utf8Error },
+    /// The response isn't an org-social file, e.g. an HTML error page served with a success status.
+    Parse { url: String, reason: String },
+    /// The local feed file doesn't exist.
+    NotFound { url: String },
+    /// The local feed file couldn't be read.
+    Io { url: String, source: std::io::Error },
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::NoAvatar => write!(f, "Profile has no avatar"),
+            NetworkError::InvalidUrl { url } => write!(f, "Invalid URL: {url}"),
+            NetworkError::UnsupportedScheme { url, scheme } => write!(f, "Unsupported URL scheme {scheme}: {url}"),
+            NetworkError::InvalidProxy { proxy, source } => write!(f, "Invalid proxy {proxy}: {source}"),
+            NetworkError::Http { url, status } => write!(f, "HTTP error {status}: {url}"),
+            NetworkError::Timeout { url, after } => write!(f, "Timeout after {after:?} while fetching {url}"),
+            NetworkError::DeadlineExceeded { url } => write!(f, "Fetching {url} was cancelled at the deadline"),
+            NetworkError::TooLarge { url, limit } => write!(f, "Response from {url} is larger than {limit} bytes"),
+            NetworkError::TooManyRedirects { url, limit } => write!(f, "Request to {url} was redirected more than {limit} times"),
+            NetworkError::Conflict { url } => write!(f, "{url} was changed by someone else since it was fetched"),
+            NetworkError::UnexpectedType { url, content_type } => {
+                write!(f, "{url} has an unexpected content type: {}", content_type.as_deref().unwrap_or("unknown"))
+            }
+            NetworkError::Transport { url, source } => write!(f, "Request to {url} failed: {source}"),
+            NetworkError::InvalidUtf8 { url, source } => write!(f, "Feed {url} is not valid UTF-8: {source}"),
+            NetworkError::Parse { url, reason } => write!(f, "Feed {url} could not be parsed: {reason}"),
+            NetworkError::NotFound { url } => write!(f, "Feed file {url} does not exist"),
+            NetworkError::Io { url, source } => write!(f, "Could not read feed file {url}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NetworkError::Transport { source, .. } => Some(source),
+            NetworkError::InvalidProxy { source, .. } => Some(source),
+            NetworkError::InvalidUtf8 { source, .. } => Some(source),
+            NetworkError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl NetworkError {
+    /// Whether the error may go away on its own, so the request is worth retrying.
+    ///
+    /// True for timeouts and exceeded deadlines, connection failures, rate limiting (`429`) and server errors (`5xx`).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NetworkError::Timeout { .. } | NetworkError::DeadlineExceeded { .. } => true,
+            NetworkError::Http { status, .. } => *status == 429 || *status >= 500,
+            NetworkError::Transport { source, .. } => source.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+/// A problem with a fetched feed that didn't prevent reading it, see [`crate::feed::SourceStatus::warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchWarning {
+    /// The body wasn't valid in its encoding, invalid bytes were replaced with `U+FFFD`.
+    LossyDecode { encoding: String },
+    /// The feed permanently moved to this URL (`301` or `308` redirect), the follow can be updated.
+    MovedPermanently { to: String },
+}
+
+impl std::fmt::Display for FetchWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchWarning::LossyDecode { encoding } => write!(f, "Invalid {encoding} characters were replaced"),
+            FetchWarning::MovedPermanently { to } => write!(f, "Moved permanently to {to}"),
+        }
+    }
+}
+
+/// How failed feed requests are retried, see [`get_feeds_with_retry`].
 ///
-/// A vector of tuples containing (Profile, Vec<Post>, String), where the String is the URL, for successfully fetched feeds
-pub async fn get_feeds_from_profile(profile: &Profile, timeout: Option<Duration>) -> Vec<(Profile, Vec<Post>, String)> {
-    match profile.follow() {
-        Some(followed_users) => {
-            get_feeds(followed_users.clone(), timeout).await
+/// The delay before each retry doubles from `base_delay` up to `max_delay`, with a random part
+/// so feeds failing together aren't retried together.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Maximum number of requests per feed, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Whether a failed request is retried, [`NetworkError::is_transient`] by default.
+    pub retry_on: fn(&NetworkError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            retry_on: NetworkError::is_transient,
         }
-        None => Vec::new(),
     }
 }
 
-/// Fetches and parses org-social files from followed users concurrently with a default 30-second timeout.
-/// 
-/// This is a convenience function that applies a reasonable default timeout.
-/// # Arguments
+impl RetryPolicy {
+    /// A policy making a single request.
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Delay before the given retry (1 for the first one): between half and all of the exponential backoff.
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1))).min(self.max_delay);
+        backoff.mul_f64(0.5 + random_fraction() / 2.0)
+    }
+}
+
+/// A random number between 0 and 1, for jitter.
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    // RandomState is seeded randomly, which is enough for jitter
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (random % 1000) as f64 / 1000.0
+}
+
+/// A downloaded avatar image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvatarData {
+    pub bytes: Vec<u8>,
+    /// Content type of the image, detected from its first bytes or reported by the server, see [`fetch_bytes`].
+    pub content_type: Option<String>,
+    /// The resolved avatar URL the request was made to.
+    pub source_url: String,
+}
+
+/// Validators of a previous fetch of a feed, used to make conditional requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The `ETag` response header.
+    pub etag: Option<String>,
+    /// The `Last-Modified` response header.
+    pub last_modified: Option<String>,
+    /// The `Content-Length` response header.
+    pub content_length: Option<u64>,
+    /// Hash of the response body, to detect unchanged feeds on servers without validators.
+    pub body_hash: u64,
+    pub fetched_at: DateTime<Utc>,
+    /// The server answered a `HEAD` request differently than a `GET`, so [`NetworkConfig::head_precheck`]
+    /// is skipped for this feed.
+    pub head_unreliable: bool,
+    /// The decoded body, only kept by caches that outlive the feeds built with them, see [`FeedCache::keeps_bodies`].
+    pub body: Option<String>,
+}
+
+/// Validators of a response, sent back in a conditional request to skip unchanged content.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// The `ETag` response header.
+    pub etag: Option<String>,
+    /// The `Last-Modified` response header.
+    pub last_modified: Option<String>,
+    /// The `Content-Length` response header. It isn't sent back, only compared by [`check_modified`].
+    pub content_length: Option<u64>,
+}
+
+impl Validators {
+    /// Whether there's nothing to make a conditional request with, the content length doesn't count.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Outcome of a conditional request, see [`fetch_conditional`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchOutcome {
+    /// The content was downloaded, with the validators to use for the next request.
+    Fresh(String, Validators),
+    /// The content didn't change since the validators were received (`304 Not Modified`).
+    NotModified,
+}
+
+/// Storage for [`CacheEntry`]s, keyed by feed URL.
 ///
-/// * `followed_users` - A vector of tuples containing (identifier, url) pairs
-/// # Returns
+/// Implementations are shared between concurrent fetches, so they use interior mutability.
+/// Within a Tokio runtime, [`FeedCache::get`] and [`FeedCache::set`] are called on its blocking threads,
+/// so they can read and write files.
+pub trait FeedCache: Send + Sync {
+    fn get(&self, url: &str) -> Option<CacheEntry>;
+    fn set(&self, url: &str, entry: CacheEntry);
+
+    /// Whether entries are stored with their body.
+    ///
+    /// A cache that outlives the feeds built with it, like [`DiskFeedCache`] across restarts, keeps
+    /// the bodies: an unchanged feed is then parsed again from its body, rather than reported as
+    /// [`FeedResult::NotModified`] to a caller that may not have its posts anymore.
+    fn keeps_bodies(&self) -> bool {
+        false
+    }
+}
+
+/// A [`FeedCache`] kept in memory, for the lifetime of the application.
+#[derive(Debug, Default)]
+pub struct MemoryFeedCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl MemoryFeedCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl FeedCache for MemoryFeedCache {
+    fn get(&self, url: &str) -> Option<CacheEntry> {
+        self.entries.lock().ok()?.get(url).cloned()
+    }
+
+    fn set(&self, url: &str, entry: CacheEntry) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(url.to_string(), entry);
+        }
+    }
+}
+
+/// A [`FeedCache`] storing one file per feed URL in a directory, with the feed's body.
 ///
-/// A vector of tuples containing (Profile, Vec\<Post\>, String), where the String is the URL, for successfully fetched feeds
-pub async fn get_feeds_with_timeout(followed_users: Vec<(String, String)>) -> Vec<(Profile, Vec<Post>, String)> {
-    get_feeds(followed_users, Some(Duration::from_secs(30))).await
+/// Read and write errors are ignored - they only cause feeds to be downloaded again.
+#[derive(Debug, Clone)]
+pub struct DiskFeedCache {
+    directory: PathBuf,
 }
 
-/// Fetches and parses org-social files from a profile's follow list with a default 30-second timeout.
+impl DiskFeedCache {
+    /// Use the given directory, it's created on the first write if needed.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into() }
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}.cache", fnv1a_hash(url.as_bytes())))
+    }
+}
+
+impl FeedCache for DiskFeedCache {
+    fn get(&self, url: &str) -> Option<CacheEntry> {
+        let (values, body) = read_entry_file(&self.entry_path(url), url)?;
+        Some(CacheEntry {
+            etag: values.get("etag").map(|etag| etag.to_string()),
+            last_modified: values.get("last_modified").map(|last_modified| last_modified.to_string()),
+            content_length: values.get("content_length").and_then(|length| length.parse().ok()),
+            body_hash: values.get("body_hash")?.parse().ok()?,
+            fetched_at: DateTime::parse_from_rfc3339(values.get("fetched_at")?).ok()?.with_timezone(&Utc),
+            head_unreliable: values.get("head_unreliable").map(String::as_str) == Some("true"),
+            body,
+        })
+    }
+
+    fn set(&self, url: &str, entry: CacheEntry) {
+        let mut header = vec![format!("url: {url}")];
+        if let Some(etag) = &entry.etag {
+            header.push(format!("etag: {etag}"));
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            header.push(format!("last_modified: {last_modified}"));
+        }
+        if let Some(content_length) = entry.content_length {
+            header.push(format!("content_length: {content_length}"));
+        }
+        header.push(format!("body_hash: {}", entry.body_hash));
+        header.push(format!("fetched_at: {}", entry.fetched_at.to_rfc3339()));
+        if entry.head_unreliable {
+            header.push("head_unreliable: true".to_string());
+        }
+
+        write_entry_file(&self.directory, &self.entry_path(url), &header, entry.body.as_deref());
+    }
+
+    fn keeps_bodies(&self) -> bool {
+        true
+    }
+}
+
+/// Reads a file written by [`write_entry_file`], as its header values and its body if it has one.
 ///
-/// This is a convenience function that applies a reasonable default timeout.
-/// # Arguments
-/// * `profile` - The profile containing the follow list
+/// Returns None if the file can't be read, or was written for another URL with the same hash.
+fn read_entry_file(path: &std::path::Path, url: &str) -> Option<(HashMap<String, String>, Option<String>)> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let (header, body) = match content.split_once("\n\n") {
+        Some((header, body)) => (header, Some(body.to_string())),
+        None => (content.as_str(), None),
+    };
+    let values: HashMap<String, String> = header
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    // Guard against hash collisions between URLs
+    if values.get("url").map(String::as_str) != Some(url) {
+        return None;
+    }
+    Some((values, body))
+}
+
+/// Writes `key: value` header lines, then a blank line and the body if there's one.
 ///
-/// # Returns
+/// Returns whether the file was written.
+fn write_entry_file(directory: &std::path::Path, path: &std::path::Path, header: &[String], body: Option<&str>) -> bool {
+    let content = match body {
+        Some(body) => format!("{}\n\n{body}", header.join("\n")),
+        None => header.join("\n") + "\n",
+    };
+    std::fs::create_dir_all(directory).is_ok() && std::fs::write(path, content).is_ok()
+}
+
+/// 64-bit FNV-1a hash. Unlike `DefaultHasher`, it's stable across Rust versions, so it can be stored on disk.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    fnv1a_update(0xcbf29ce484222325, bytes)
+}
+
+/// Continues a [`fnv1a_hash`] with the next bytes.
+fn fnv1a_update(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
+
+/// A response stored in a [`DiskCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+    pub body: String,
+    pub validators: Validators,
+    pub fetched_at: DateTime<Utc>,
+    /// URL the response was read from after permanent redirects, the URL it's stored for by default.
+    pub final_url: String,
+}
+
+/// A cache of whole responses, storing one file per URL in a directory, see [`fetch_with_disk_cache`].
 ///
-/// A vector of tuples containing (Profile, Vec<Post>, String), where the String is the URL, for successfully fetched feeds
-pub async fn get_feeds_from_profile_with_timeout(profile: &Profile) -> Vec<(Profile, Vec<Post>, String)> {
-    get_feeds_from_profile(profile, Some(Duration::from_secs(30))).await
+/// The body is kept with its validators, so it can be used again when the server answers
+/// `304 Not Modified`, or when it can't be reached (see [`FetchOptions::offline_fallback`]).
+/// When the files grow bigger than the size limit, the oldest ones are removed.
+/// Read and write errors are ignored - they only cause responses to be downloaded again.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    directory: PathBuf,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Use the given directory, it's created on the first write if needed.
+    pub fn new(directory: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self { directory: directory.into(), max_bytes }
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}.response", fnv1a_hash(url.as_bytes())))
+    }
+
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        let (values, body) = read_entry_file(&self.entry_path(url), url)?;
+        Some(CachedResponse {
+            body: body?,
+            validators: Validators {
+                etag: values.get("etag").map(|etag| etag.to_string()),
+                last_modified: values.get("last_modified").map(|last_modified| last_modified.to_string()),
+                content_length: values.get("content_length").and_then(|length| length.parse().ok()),
+            },
+            fetched_at: DateTime::parse_from_rfc3339(values.get("fetched_at")?).ok()?.with_timezone(&Utc),
+            final_url: values.get("final_url").map_or(url, String::as_str).to_string(),
+        })
+    }
+
+    /// [`DiskCache::get`] on a blocking thread, see [`run_blocking`].
+    async fn load(&self, url: &str) -> Option<CachedResponse> {
+        let (cache, url) = (self.clone(), url.to_string());
+        run_blocking(move || cache.get(&url)).await
+    }
+
+    /// [`DiskCache::put_moved`] on a blocking thread, see [`run_blocking`].
+    async fn save(&self, url: &str, final_url: &str, body: String, validators: Validators) {
+        let (cache, url, final_url) = (self.clone(), url.to_string(), final_url.to_string());
+        run_blocking(move || cache.put_moved(&url, &final_url, &body, &validators)).await
+    }
+
+    /// Store a response, then remove the oldest ones if the cache is over its size limit.
+    pub fn put(&self, url: &str, body: &str, validators: &Validators) {
+        self.put_moved(url, url, body, validators);
+    }
+
+    /// Like [`DiskCache::put`], for a response read from `final_url` after permanent redirects.
+    pub fn put_moved(&self, url: &str, final_url: &str, body: &str, validators: &Validators) {
+        let mut header = vec![format!("url: {url}")];
+        if final_url != url {
+            header.push(format!("final_url: {final_url}"));
+        }
+        if let Some(etag) = &validators.etag {
+            header.push(format!("etag: {etag}"));
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            header.push(format!("last_modified: {last_modified}"));
+        }
+        if let Some(content_length) = validators.content_length {
+            header.push(format!("content_length: {content_length}"));
+        }
+        header.push(format!("fetched_at: {}", Utc::now().to_rfc3339()));
+
+        if write_entry_file(&self.directory, &self.entry_path(url), &header, Some(body)) {
+            self.evict(&self.entry_path(url));
+        }
+    }
+
+    /// Total size of the stored responses, in bytes.
+    pub fn size(&self) -> u64 {
+        self.entries().iter().map(|(_, size, _)| size).sum()
+    }
+
+    /// The stored response files as (path, size, modification time).
+    fn entries(&self) -> Vec<(PathBuf, u64, std::time::SystemTime)> {
+        let Ok(directory) = std::fs::read_dir(&self.directory) else {
+            return Vec::new();
+        };
+        directory
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "response"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+            })
+            .collect()
+    }
+
+    /// Remove the oldest responses until the cache fits its size limit, keeping the one just written.
+    fn evict(&self, keep: &std::path::Path) {
+        let mut entries = self.entries();
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, entry_size, _) in entries {
+            if size <= self.max_bytes {
+                break;
+            }
+            if path != keep && std::fs::remove_file(&path).is_ok() {
+                size -= entry_size;
+            }
+        }
+    }
+}
+
+/// Outcome of fetching a feed with a [`FeedCache`].
+// Only one result per followed feed is built, boxing the profile isn't worth it
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum FeedResult {
+    /// The feed was downloaded and parsed.
+    Fetched(Profile, Vec<Post>),
+    /// The feed didn't change since it was cached (`304 Not Modified`, or an identical body).
+    NotModified,
+}
+
+/// Outcome of fetching one followed feed, see [`get_feeds_detailed`].
+#[derive(Debug)]
+pub struct FeedFetchResult {
+    /// Nick the feed is followed under, empty if none was given.
+    pub nick: String,
+    /// The followed URL.
+    pub url: String,
+    /// The feed's profile and posts. Their source is the URL the feed was read from, after permanent redirects.
+    pub outcome: Result<(Profile, Vec<Post>), NetworkError>,
+    /// Time taken by the requests, from the start of the first one to the parsed feed or the error.
+    pub elapsed: Duration,
+    /// Time spent waiting for the [`NetworkConfig::rate_limit`], not counted in the elapsed time.
+    pub queued: Duration,
+    /// Number of requests made, more than one if failed requests were retried.
+    /// Left at 0 for feeds cancelled at the deadline.
+    pub attempts: u32,
+    /// Problems with the feed that didn't prevent reading it.
+    pub warnings: Vec<FetchWarning>,
+    /// Whether the outcome is the feed as just fetched, or a stored copy.
+    pub freshness: Freshness,
+    /// The download stopped before the end of the feed, at its first post older than
+    /// [`FetchOptions::since`]: the posts after it weren't read.
+    pub early_stop: bool,
+}
+
+impl From<FeedFetch> for FeedFetchResult {
+    fn from(fetch: FeedFetch) -> Self {
+        let outcome = match fetch.result {
+            Ok(FeedResult::Fetched(profile, posts)) => Ok((profile, posts)),
+            // Only possible with a cache, reported like by fetch_feed
+            Ok(FeedResult::NotModified) => Err(NetworkError::Http { url: fetch.url.clone(), status: 304 }),
+            Err(error) => Err(error),
+        };
+        Self {
+            nick: fetch.identifier,
+            url: fetch.url,
+            outcome,
+            elapsed: fetch.elapsed,
+            queued: fetch.queued,
+            attempts: fetch.attempts,
+            warnings: fetch.warnings,
+            freshness: fetch.freshness,
+            early_stop: fetch.early_stop,
+        }
+    }
+}
+
+/// Fetches and parses org-social files from followed users concurrently, reporting the failures too.
+///
+/// The feeds are fetched within the calling task, without spawning, so any executor can drive this.
+/// HTTP requests still need a Tokio reactor (e.g. a runtime entered with `Runtime::enter`).
+/// Timeouts use the Tokio timer, unless the `futures-timer` feature is enabled.
+/// Local files are read on the runtime's blocking threads if there's one, in place otherwise.
+///
+/// # Arguments
+///
+/// * `followed_users` - A vector of tuples containing (identifier, url) pairs
+/// * `options` - The timeout of each feed, how failed requests are retried and how requests are made
+/// # Returns
+///
+/// The outcome of each feed, in the order of `followed_users`
+///
+/// With a [`FetchOptions::overall_deadline`], the feeds still loading when it's reached fail with
+/// [`NetworkError::DeadlineExceeded`], the others keep their outcome.
+pub async fn get_feeds_detailed(followed_users: Vec<(String, String)>, options: &FetchOptions) -> Vec<FeedFetchResult> {
+    fetch_feeds_with_hooks(followed_users, options, None, FetchHooks::default())
+        .await
+        .into_iter()
+        .map(FeedFetchResult::from)
+        .collect()
+}
+
+/// Fetches and parses org-social files from followed users concurrently, leaving out the failures.
+///
+/// # Arguments
+///
+/// * `followed_users` - A vector of tuples containing (identifier, url) pairs
+/// * `timeout` - Optional timeout duration for each feed request. If None, no timeout is applied.
+/// # Returns
+///
+/// A vector of tuples containing (Profile, Vec\<Post\>, String), where the String is the URL, for successfully fetched feeds
+#[deprecated(since = "0.5.0", note = "use get_feeds_detailed, which also reports the feeds that failed")]
+pub async fn get_feeds(followed_users: Vec<(String, String)>, timeout: Option<Duration>) -> Vec<(Profile, Vec<Post>, String)> {
+    let options = FetchOptions { per_feed_timeout: timeout, ..Default::default() };
+    get_feeds_detailed(followed_users, &options)
+        .await
+        .into_iter()
+        .filter_map(|result| result.outcome.ok())
+        .map(|(profile, posts)| {
+            let url = profile.source().cloned().unwrap_or_default();
+            (profile, posts, url)
+        })
+        .collect()
+}
+
+/// Like [`get_feeds`], making the requests as set in `config`, e.g. with a preconfigured client.
+pub async fn get_feeds_with_config(
+    followed_users: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    config: &NetworkConfig,
+) -> Vec<(Profile, Vec<Post>, String)> {
+    successful_feeds(fetch_feeds_with_errors(followed_users, timeout, None, None, config).await)
+}
+
+/// Like [`get_feeds`], retrying failed requests according to `retry`.
+///
+/// The timeout applies to each request, and a request that times out is retried like other transient failures.
+pub async fn get_feeds_with_retry(
+    followed_users: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+) -> Vec<(Profile, Vec<Post>, String)> {
+    successful_feeds(fetch_feeds_with_errors(followed_users, timeout, None, Some(retry), NetworkHandle::shared().config()).await)
+}
+
+/// Keep the feeds that were fetched, as (profile, posts, URL).
+fn successful_feeds(outcomes: Vec<FeedFetch>) -> Vec<(Profile, Vec<Post>, String)> {
+    outcomes
+        .into_iter()
+        .filter_map(|outcome| match outcome.result {
+            Ok(FeedResult::Fetched(profile, posts)) => Some((profile, posts, outcome.final_url)),
+            Ok(FeedResult::NotModified) | Err(_) => None,
+        })
+        .collect()
+}
+
+/// Like [`get_feeds`], but makes conditional requests using the validators stored in `cache`.
+///
+/// Feeds that didn't change since they were cached are returned as `FeedResult::NotModified`,
+/// and the cache is updated with the validators of the feeds that did.
+/// # Returns
+///
+/// A vector of (URL, FeedResult) pairs for successfully fetched feeds
+pub async fn get_feeds_cached(
+    followed_users: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    cache: Arc<dyn FeedCache>,
+) -> Vec<(String, FeedResult)> {
+    fetch_feeds(followed_users, timeout, Some(cache)).await
+}
+
+/// Like [`get_feeds_from_profile`], with a [`FeedCache`], see [`get_feeds_cached`].
+pub async fn get_feeds_from_profile_cached(
+    profile: &Profile,
+    timeout: Option<Duration>,
+    cache: Arc<dyn FeedCache>,
+) -> Vec<(String, FeedResult)> {
+    let followed_users = profile.follow_urls_normalized();
+    if followed_users.is_empty() {
+        return Vec::new();
+    }
+    get_feeds_cached(followed_users, timeout, cache).await
+}
+
+/// Fetches feeds concurrently, returning the successful results. Failures are left out.
+async fn fetch_feeds(
+    followed_users: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    cache: Option<Arc<dyn FeedCache>>,
+) -> Vec<(String, FeedResult)> {
+    fetch_feeds_with_errors(followed_users, timeout, cache, None, NetworkHandle::shared().config())
+        .await
+        .into_iter()
+        .filter_map(|outcome| outcome.result.ok().map(|result| (outcome.url, result)))
+        .collect()
+}
+
+/// Outcome of fetching one feed.
+pub(crate) struct FeedFetch {
+    pub identifier: String,
+    pub url: String,
+    /// URL the feed was read from, after permanent redirects, and the source of its posts.
+    ///
+    /// After a temporary redirect (`302`, `303`, `307`), the feed keeps its URL.
+    pub final_url: String,
+    pub result: Result<FeedResult, NetworkError>,
+    /// Time taken by the requests, from the start of the first one to the parsed feed or the error.
+    pub elapsed: Duration,
+    /// Number of requests made, more than one if failed requests were retried.
+    pub attempts: u32,
+    /// Problems with the feed that didn't prevent reading it.
+    pub warnings: Vec<FetchWarning>,
+    /// Time spent waiting for the [`NetworkConfig::rate_limit`], not counted in the elapsed time.
+    pub queued: Duration,
+    /// Whether the feed was fetched, or read from a [`DiskCache`] because it couldn't be.
+    pub freshness: Freshness,
+    /// The download stopped at the first post older than [`FetchOptions::since`].
+    pub early_stop: bool,
+}
+
+/// Whether a feed is up to date, see [`FetchOptions::offline_fallback`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Freshness {
+    /// The feed was just fetched.
+    #[default]
+    Fresh,
+    /// The feed couldn't be fetched, this is the copy stored at `fetched_at`.
+    Stale { fetched_at: DateTime<Utc> },
+}
+
+/// Progress of fetching several feeds, see [`get_feeds_with_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchEvent {
+    /// The request for the feed at this URL started.
+    Started(String),
+    /// The feed was fetched, with its number of posts (0 if it didn't change since it was cached).
+    Finished { url: String, posts: usize, elapsed: Duration },
+    /// The feed couldn't be fetched.
+    Failed { url: String, error: String },
+}
+
+/// Called with the URL of each feed when its request starts.
+pub(crate) type OnFetchStart = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Ways to follow the progress of [`spawn_feed_fetches`].
+#[derive(Clone, Default)]
+pub(crate) struct FetchHooks {
+    pub on_start: Option<OnFetchStart>,
+    /// Receives a [`FetchEvent`] when each request starts and ends. The events are queued while the channel
+    /// is full, so the requests don't wait for room in it, and are no longer sent once the receiver is dropped.
+    pub events: Option<tokio::sync::mpsc::Sender<FetchEvent>>,
+}
+
+/// Relays events to `events` from an unbounded channel, so the fetches don't wait for room in
+/// `events` while they hold a request slot (see [`NetworkConfig::max_concurrency`]).
+///
+/// The relay future completes once all the senders are dropped, or once `events` is closed.
+fn relay_events(
+    events: Option<tokio::sync::mpsc::Sender<FetchEvent>>,
+) -> (Option<tokio::sync::mpsc::UnboundedSender<FetchEvent>>, impl std::future::Future<Output = ()> + Send + 'static) {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let sender = events.is_some().then_some(sender);
+    let relay = async move {
+        let Some(events) = events else {
+            return;
+        };
+        while let Some(event) = receiver.recv().await {
+            if events.send(event).await.is_err() {
+                break;
+            }
+        }
+    };
+    (sender, relay)
+}
+
+/// Like [`get_feeds`], sending a [`FetchEvent`] to `events` as each feed starts and finishes, e.g. for a progress bar.
+///
+/// The requests don't wait for room in the channel, but the function returns once all the events were sent,
+/// so the receiver should be read while the feeds are fetched (e.g. from another task), or have room for
+/// two events per feed. Once the receiver is dropped, events are no longer sent and the feeds are still fetched.
+pub async fn get_feeds_with_progress(
+    followed_users: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    events: tokio::sync::mpsc::Sender<FetchEvent>,
+) -> Vec<(Profile, Vec<Post>, String)> {
+    let hooks = FetchHooks { events: Some(events), ..Default::default() };
+    let options = FetchOptions { per_feed_timeout: timeout, ..Default::default() };
+    successful_feeds(fetch_feeds_with_hooks(followed_users, &options, None, hooks).await)
+}
+
+/// Fetches feeds concurrently, returning the outcome of each one in the order of `followed_users`.
+pub(crate) async fn fetch_feeds_with_errors(
+    followed_users: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    cache: Option<Arc<dyn FeedCache>>,
+    retry: Option<RetryPolicy>,
+    config: &NetworkConfig,
+) -> Vec<FeedFetch> {
+    let options = FetchOptions { per_feed_timeout: timeout, retry, network: config.clone(), ..Default::default() };
+    fetch_feeds_with_hooks(followed_users, &options, cache, FetchHooks::default()).await
+}
+
+/// Like [`fetch_feeds_with_errors`], reporting progress to `hooks`.
+///
+/// The feeds are fetched concurrently within the calling task, nothing is spawned. Once the
+/// [`FetchOptions::overall_deadline`] is reached, the fetches still running are dropped and fail
+/// with [`NetworkError::DeadlineExceeded`].
+pub(crate) async fn fetch_feeds_with_hooks(
+    followed_users: Vec<(String, String)>,
+    options: &FetchOptions,
+    cache: Option<Arc<dyn FeedCache>>,
+    hooks: FetchHooks,
+) -> Vec<FeedFetch> {
+    let (events, relay) = relay_events(hooks.events);
+    let fetch_futures = feed_fetches(followed_users.clone(), options, cache, hooks.on_start, events.clone());
+    let Some(deadline) = options.overall_deadline else {
+        drop(events);
+        let fetches = futures_util::future::join_all(fetch_futures);
+        return futures_util::future::join(fetches, relay).await.0.into_iter().flatten().collect();
+    };
+
+    // The futures are all polled from the start, so each timeout ends at the deadline
+    let started = std::time::Instant::now();
+    let bounded = followed_users.into_iter().zip(fetch_futures).map(|((identifier, url), fetch)| {
+        let events = events.clone();
+        async move {
+            if let Some(outcome) = with_timeout(deadline, fetch).await {
+                return outcome;
+            }
+            let error = NetworkError::DeadlineExceeded { url: url.clone() };
+            if let Some(events) = &events {
+                let _ = events.send(FetchEvent::Failed { url: url.clone(), error: error.to_string() });
+            }
+            Some(FeedFetch {
+                identifier,
+                url: url.clone(),
+                final_url: url,
+                result: Err(error),
+                elapsed: started.elapsed(),
+                attempts: 0,
+                warnings: Vec::new(),
+                queued: Duration::ZERO,
+                freshness: Freshness::Fresh,
+                early_stop: false,
+            })
+        }
+    });
+    let fetches = futures_util::future::join_all(bounded.collect::<Vec<_>>());
+    drop(events);
+    futures_util::future::join(fetches, relay).await.0.into_iter().flatten().collect()
+}
+
+/// Spawns one Tokio task per feed, see [`feed_fetches`].
+pub(crate) fn spawn_feed_fetches(
+    followed_users: Vec<(String, String)>,
+    options: &FetchOptions,
+    cache: Option<Arc<dyn FeedCache>>,
+    hooks: FetchHooks,
+) -> Vec<tokio::task::JoinHandle<Option<FeedFetch>>> {
+    let (events, relay) = relay_events(hooks.events);
+    tokio::spawn(relay);
+    feed_fetches(followed_users, options, cache, hooks.on_start, events).into_iter().map(tokio::spawn).collect()
+}
+
+/// One future per feed, running at most [`NetworkConfig::max_concurrency`] requests at the same time,
+/// as allowed by the [`NetworkConfig::rate_limit`].
+///
+/// Each future resolves to the feed's outcome, or None if it couldn't start.
+/// Without a retry policy, each feed gets a single request. The overall deadline isn't applied.
+fn feed_fetches(
+    followed_users: Vec<(String, String)>,
+    options: &FetchOptions,
+    cache: Option<Arc<dyn FeedCache>>,
+    on_start: Option<OnFetchStart>,
+    events: Option<tokio::sync::mpsc::UnboundedSender<FetchEvent>>,
+) -> Vec<impl std::future::Future<Output = Option<FeedFetch>> + Send + 'static> {
+    let timeout = options.per_feed_timeout;
+    let retry = options.retry.unwrap_or_else(RetryPolicy::none);
+    let config = Arc::new(options.network.with_transport());
+    let semaphore = config.concurrency_limit();
+    let store = options.raw_store.clone();
+    let offline_fallback = options.offline_fallback;
+    let since = options.since;
+
+    followed_users
+        .into_iter()
+        .map(|(identifier, url)| {
+            let config = config.clone();
+            let semaphore = semaphore.clone();
+            let cache = cache.clone();
+            let on_start = on_start.clone();
+            let events = events.clone();
+            let store = store.clone();
+
+            async move {
+                let throttle = Throttle::new(&config, &url);
+                let first_wait = throttle.first_request(&url).await;
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                if let Some(on_start) = &on_start {
+                    on_start(&url);
+                }
+                if let Some(events) = &events {
+                    let _ = events.send(FetchEvent::Started(url.clone()));
+                }
+
+                let started = std::time::Instant::now();
+                let (result, attempts) = with_retries(&url, timeout, &retry, || {
+                    fetch_feed_once(&config, &url, cache.as_ref(), store.as_ref(), since, &throttle)
+                })
+                .await;
+                // The time waited for the rate limit between requests isn't part of the requests
+                let queued = throttle.waited();
+                let elapsed = started.elapsed().saturating_sub(queued - first_wait);
+
+                // Offline, the last copy of the feed is better than nothing
+                let mut freshness = Freshness::Fresh;
+                let stored = match (&result, &store) {
+                    (Err(error), Some(store)) if offline_fallback && error.is_transient() => store.load(&url).await,
+                    _ => None,
+                };
+                let result = match stored {
+                    Some(stored) => {
+                        freshness = Freshness::Stale { fetched_at: stored.fetched_at };
+                        let (profile, mut posts) = parse_file(&stored.body, Some(stored.final_url.clone()));
+                        if let Some(cutoff) = since {
+                            posts.retain(|post| post.time().is_none_or(|time| time >= cutoff));
+                        }
+                        let result = FeedResult::Fetched(profile, posts);
+                        Ok(DecodedFeed { result, final_url: stored.final_url, warnings: Vec::new(), early_stop: false })
+                    }
+                    None => result,
+                };
+                let (result, final_url, warnings, early_stop) = match result {
+                    Ok(DecodedFeed { result, final_url, warnings, early_stop }) => (Ok(result), final_url, warnings, early_stop),
+                    Err(error) => (Err(error), url.clone(), Vec::new(), false),
+                };
+
+                if let Some(events) = &events {
+                    let event = match &result {
+                        Ok(FeedResult::Fetched(_, posts)) => FetchEvent::Finished { url: url.clone(), posts: posts.len(), elapsed },
+                        Ok(FeedResult::NotModified) => FetchEvent::Finished { url: url.clone(), posts: 0, elapsed },
+                        Err(error) => FetchEvent::Failed { url: url.clone(), error: error.to_string() },
+                    };
+                    let _ = events.send(event);
+                }
+                Some(FeedFetch { identifier, url, final_url, result, elapsed, attempts, warnings, queued, freshness, early_stop })
+            }
+        })
+        .collect()
+}
+
+/// The path of a feed given as a `file://` URL or an absolute path, None for remote feeds.
+///
+/// `file://` URLs are percent-decoded, e.g. `file:///a%20b.org` is the path `/a b.org`.
+fn local_feed_path(url: &str) -> Option<PathBuf> {
+    if url.starts_with("file://") {
+        reqwest::Url::parse(url).ok()?.to_file_path().ok()
+    } else if !url.contains("://") && std::path::Path::new(url).is_absolute() {
+        Some(PathBuf::from(url))
+    } else {
+        None
+    }
+}
+
+/// Runs `attempt` until it succeeds or fails in a way `retry` doesn't retry, with `timeout` for each attempt.
+///
+/// An attempt that times out fails with [`NetworkError::Timeout`], retried like other failures.
+/// The attempts together are only bounded by the caller, e.g. with [`FetchOptions::overall_deadline`].
+///
+/// # Returns
+///
+/// The result of the last attempt, and the number of attempts made
+async fn with_retries<T, F>(
+    url: &str,
+    timeout: Option<Duration>,
+    retry: &RetryPolicy,
+    attempt: impl Fn() -> F,
+) -> (Result<T, NetworkError>, u32)
+where
+    F: std::future::Future<Output = Result<T, NetworkError>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = match timeout {
+            Some(duration) => with_timeout(duration, attempt())
+                .await
+                .unwrap_or_else(|| Err(NetworkError::Timeout { url: url.to_string(), after: duration })),
+            None => attempt().await,
+        };
+        match result {
+            Err(error) if attempts < retry.max_attempts && (retry.retry_on)(&error) => {
+                sleep(retry.delay(attempts)).await;
+            }
+            result => return (result, attempts),
+        }
+    }
+}
+
+/// Waits for `duration`, with the Tokio timer, or `futures-timer` with the `futures-timer` feature.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "futures-timer")]
+    futures_timer::Delay::new(duration).await;
+    #[cfg(not(feature = "futures-timer"))]
+    tokio::time::sleep(duration).await;
+}
+
+/// Runs blocking file operations on the blocking threads of the Tokio runtime, so they don't hold up
+/// the other fetches, or in place without a runtime, e.g. on another executor.
+async fn run_blocking<T: Send + 'static>(operation: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => runtime.spawn_blocking(operation).await.unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic())),
+        Err(_) => operation(),
+    }
+}
+
+/// [`FeedCache::get`] on a blocking thread, see [`run_blocking`].
+async fn cache_get(cache: &Arc<dyn FeedCache>, url: &str) -> Option<CacheEntry> {
+    let (cache, url) = (cache.clone(), url.to_string());
+    run_blocking(move || cache.get(&url)).await
+}
+
+/// [`FeedCache::set`] on a blocking thread, see [`run_blocking`].
+async fn cache_set(cache: &Arc<dyn FeedCache>, url: &str, entry: CacheEntry) {
+    let (cache, url) = (cache.clone(), url.to_string());
+    run_blocking(move || cache.set(&url, entry)).await
+}
+
+/// Runs `future` for at most `duration`, None if it didn't complete in time, see [`sleep`].
+async fn with_timeout<F: std::future::Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    let timer = std::pin::pin!(sleep(duration));
+    match futures_util::future::select(future, timer).await {
+        futures_util::future::Either::Left((output, _)) => Some(output),
+        futures_util::future::Either::Right(_) => None,
+    }
+}
+
+/// Fetches a single org-social file and parses it, with a HEAD request first if `config` asks for it.
+///
+/// # Arguments
+/// * `config` - How to make the requests
+/// * `url` - The URL of the org-social file to fetch
+/// * `cache` - Optional cache of validators, used to skip feeds that didn't change
+/// * `store` - Optional store the downloaded body is saved to, see [`FetchOptions::raw_store`]
+/// * `since` - Optional cutoff, older posts are left out, see [`parse_feed_stream`]
+/// * `throttle` - The rate limit each request waits for
+/// # Returns
+///
+/// A Result containing the parsed feed (or `NotModified`) with its final URL and warnings on success,
+/// or the reason it couldn't be fetched
+async fn fetch_feed_once(
+    config: &NetworkConfig,
+    url: &str,
+    cache: Option<&Arc<dyn FeedCache>>,
+    store: Option<&DiskCache>,
+    since: Option<DateTime<FixedOffset>>,
+    throttle: &Throttle,
+) -> Result<DecodedFeed, NetworkError> {
+    let cached = match cache {
+        Some(cache) => cache_get(cache, url).await,
+        None => None,
+    };
+    let validators = cached.as_ref().map(|cached| Validators {
+        etag: cached.etag.clone(),
+        last_modified: cached.last_modified.clone(),
+        content_length: cached.content_length,
+    });
+
+    // Without an ETag, a HEAD request may tell the feed didn't change, unless the server is known to lie
+    let mut head = None;
+    let http = url.starts_with("http://") || url.starts_with("https://");
+    let precheck = config.head_precheck && http && cached.as_ref().is_some_and(|cached| cached.etag.is_none() && !cached.head_unreliable);
+    if let Some(previous) = validators.as_ref().filter(|_| precheck) {
+        throttle.request(url).await;
+        if let Ok(Some((current, final_url))) = head_validators(config, url).await {
+            if compare_validators(previous, &current) == ChangeHint::ProbablyUnchanged {
+                return Ok(unchanged_feed(cached.as_ref(), final_url, Vec::new(), since));
+            }
+            head = Some(current);
+        }
+    }
+
+    let resolver = config.resolver(url)?;
+    throttle.request(url).await;
+    let (resolved, body) = resolver.resolve_streaming(url, validators.as_ref(), config).await?;
+    let Resolved { content_type, final_url, validators: fresh, not_modified, mut warnings, .. } = resolved;
+    if not_modified && validators.is_some() {
+        // The HEAD request told the feed changed, but the server says it didn't
+        if let (Some(cache), Some(cached), Some(head)) = (cache, &cached, &head) {
+            if validators.as_ref().is_some_and(|previous| compare_validators(previous, head) == ChangeHint::Changed) {
+                cache_set(cache, url, CacheEntry { head_unreliable: true, ..cached.clone() }).await;
+            }
+        }
+        return Ok(unchanged_feed(cached.as_ref(), final_url, warnings, since));
+    }
+
+    let source = final_url.clone();
+    let keeps_bodies = cache.is_some_and(|cache| cache.keeps_bodies());
+    let keep_content = store.is_some() || keeps_bodies;
+    let streamed = parse_feed_stream(body, content_type.as_deref(), url, &source, config.max_body_bytes, since, keep_content).await?;
+    let StreamedFeed { profile, posts, early_stop, body_hash, content, warnings: decode_warnings } = streamed;
+    warnings.extend(decode_warnings);
+
+    // Only whole feeds are stored, to be parsed again
+    let content = content.filter(|_| !early_stop);
+    if let (Some(store), Some(content)) = (store, &content) {
+        store.save(url, &final_url, content.clone(), fresh.clone()).await;
+    }
+    if let Some(cache) = cache {
+        let head_unreliable = cached.as_ref().is_some_and(|cached| cached.head_unreliable)
+            || head.is_some_and(|head| compare_validators(&head, &fresh) == ChangeHint::Changed);
+        let Validators { etag, last_modified, content_length } = fresh;
+        let body = content.filter(|_| keeps_bodies);
+        cache_set(cache, url, CacheEntry { etag, last_modified, content_length, body_hash, fetched_at: Utc::now(), head_unreliable, body }).await;
+    }
+    if !early_stop && !keeps_bodies && cached.is_some_and(|cached| cached.body_hash == body_hash) {
+        return Ok(DecodedFeed { result: FeedResult::NotModified, final_url, warnings, early_stop });
+    }
+    Ok(DecodedFeed { result: FeedResult::Fetched(profile, posts), final_url, warnings, early_stop })
+}
+
+/// The outcome for a feed that didn't change since it was cached: parsed from the cached body
+/// if the cache keeps it (see [`FeedCache::keeps_bodies`]), [`FeedResult::NotModified`] otherwise.
+fn unchanged_feed(
+    cached: Option<&CacheEntry>,
+    final_url: String,
+    warnings: Vec<FetchWarning>,
+    since: Option<DateTime<FixedOffset>>,
+) -> DecodedFeed {
+    let Some(body) = cached.and_then(|cached| cached.body.as_deref()) else {
+        return DecodedFeed { result: FeedResult::NotModified, final_url, warnings, early_stop: false };
+    };
+    let (profile, mut posts) = parse_file(body, Some(final_url.clone()));
+    if let Some(cutoff) = since {
+        posts.retain(|post| post.time().is_none_or(|time| time >= cutoff));
+    }
+    DecodedFeed { result: FeedResult::Fetched(profile, posts), final_url, warnings, early_stop: false }
+}
+
+/// A feed parsed as it was downloaded, see [`parse_feed_stream`].
+struct StreamedFeed {
+    profile: Profile,
+    posts: Vec<Post>,
+    /// The download stopped at the first post older than the cutoff.
+    early_stop: bool,
+    /// [`fnv1a_hash`] of the decoded content that was read.
+    body_hash: u64,
+    /// The decoded content, if it was kept.
+    content: Option<String>,
+    warnings: Vec<FetchWarning>,
+}
+
+/// Decodes a feed body as its chunks are received (see [`decode_body`]) and parses it with a [`StreamingParser`],
+/// so posts are produced as it's downloaded without holding it whole in memory.
+///
+/// Posts older than `since` are left out. Once two posts with timestamps show the feed is ordered
+/// newest first, the download stops at the first post older than `since`, dropping the rest of the body.
+///
+/// # Arguments
+///
+/// * `url` - The requested URL, errors are reported for it
+/// * `source` - The source of the profile and posts
+/// * `keep_content` - Whether to return the decoded content too
+async fn parse_feed_stream(
+    mut body: BodyStream,
+    content_type: Option<&str>,
+    url: &str,
+    source: &str,
+    max_bytes: usize,
+    since: Option<DateTime<FixedOffset>>,
+    keep_content: bool,
+) -> Result<StreamedFeed, NetworkError> {
+    let mut decoder = BodyDecoder::new(content_type);
+    let mut parser = StreamingParser::new(Some(source.to_string()));
+    let mut posts = Vec::new();
+    let mut content = keep_content.then(String::new);
+    let mut body_hash = fnv1a_hash(b"");
+    let mut read = 0;
+    // The start of the content, until it's long enough to tell whether it's HTML
+    let mut start = Some(String::new());
+    let mut order = PostOrder::default();
+    let mut early_stop = false;
+    let mut text = String::new();
+
+    'chunks: loop {
+        let chunk = futures_util::StreamExt::next(&mut body)
+            .await
+            .transpose()
+            .map_err(|source| NetworkError::Transport { url: url.to_string(), source })?;
+        let last = chunk.is_none();
+        let bytes = chunk.unwrap_or_default();
+        read += bytes.len();
+        if read > max_bytes {
+            return Err(NetworkError::TooLarge { url: url.to_string(), limit: max_bytes });
+        }
+
+        text.clear();
+        decoder.decode(&bytes, last, &mut text).map_err(|source| NetworkError::InvalidUtf8 { url: url.to_string(), source })?;
+        body_hash = fnv1a_update(body_hash, text.as_bytes());
+        if let Some(content) = &mut content {
+            content.push_str(&text);
+        }
+        if let Some(prefix) = &mut start {
+            prefix.push_str(&text);
+            if last || prefix.trim_start().len() >= 15 {
+                if looks_like_html(prefix) {
+                    return Err(NetworkError::Parse { url: url.to_string(), reason: "got an HTML document".to_string() });
+                }
+                start = None;
+            }
+        }
+
+        for post in parser.push_str(&text) {
+            match order.keep(&post, since) {
+                Some(true) => posts.push(post),
+                Some(false) => {}
+                None => {
+                    early_stop = true;
+                    break 'chunks;
+                }
+            }
+        }
+        if last {
+            break;
+        }
+    }
+
+    let (profile, last_posts) = parser.finish();
+    if !early_stop {
+        for post in last_posts {
+            match order.keep(&post, since) {
+                Some(true) => posts.push(post),
+                Some(false) => {}
+                None => {
+                    early_stop = true;
+                    break;
+                }
+            }
+        }
+    }
+    Ok(StreamedFeed { profile, posts, early_stop, body_hash, content, warnings: decoder.warnings() })
+}
+
+/// The order of the posts of a feed as it's read, to stop reading a newest first feed at the cutoff.
+#[derive(Default)]
+struct PostOrder {
+    /// Timestamp of the first post with one.
+    first: Option<DateTime<FixedOffset>>,
+    /// Whether the feed is newest first, from the timestamps of its first two posts with one.
+    newest_first: Option<bool>,
+}
+
+impl PostOrder {
+    /// Whether to keep the next post of the feed, None if the feed is known to only have older posts from there on.
+    /// Posts without a timestamp are kept.
+    fn keep(&mut self, post: &Post, since: Option<DateTime<FixedOffset>>) -> Option<bool> {
+        let (Some(cutoff), Some(time)) = (since, post.time()) else {
+            return Some(true);
+        };
+        match self.first {
+            None => self.first = Some(time),
+            Some(first) if self.newest_first.is_none() && first != time => self.newest_first = Some(time < first),
+            Some(_) => {}
+        }
+        match (time >= cutoff, self.newest_first) {
+            (true, _) => Some(true),
+            (false, Some(true)) => None,
+            (false, _) => Some(false),
+        }
+    }
+}
+
+/// Options for [`fetch_feed`], [`fetch_profile`] and [`get_feeds_detailed`].
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// Timeout for each request of a feed, a request that times out can be retried (see [`FetchOptions::retry`]).
+    /// If None, no timeout is applied. The [`FetchOptions::overall_deadline`] bounds all the requests together.
+    pub per_feed_timeout: Option<Duration>,
+    /// Time after which the feeds still loading are cancelled and fail with [`NetworkError::DeadlineExceeded`].
+    /// If None, there's no deadline.
+    pub overall_deadline: Option<Duration>,
+    /// Where the body of each fetched feed is saved, for [`FetchOptions::offline_fallback`].
+    pub raw_store: Option<DiskCache>,
+    /// When a feed can't be fetched because of a network failure (see [`NetworkError::is_transient`]),
+    /// use its copy in the [`FetchOptions::raw_store`], marked as [`Freshness::Stale`].
+    pub offline_fallback: bool,
+    /// Only keep the posts from this time on. Only used when fetching several feeds, like with [`get_feeds_detailed`].
+    ///
+    /// Feeds are parsed as they're downloaded. Once a feed is seen to be ordered newest first,
+    /// its download stops at the first older post, see [`FeedFetchResult::early_stop`].
+    pub since: Option<DateTime<FixedOffset>>,
+    /// How failed requests are retried. If None, a single request is made.
+    pub retry: Option<RetryPolicy>,
+    /// How requests are made.
+    pub network: NetworkConfig,
+}
+
+/// A single feed fetched with [`fetch_feed`].
+#[derive(Debug, Clone)]
+pub struct FetchedFeed {
+    pub profile: Profile,
+    pub posts: Vec<Post>,
+    /// URL the feed was read from after permanent redirects, the source of its profile and posts.
+    pub final_url: String,
+    /// Validators of the response, to make conditional requests later (empty for local files).
+    pub validators: Validators,
+    /// Problems with the feed that didn't prevent reading it.
+    pub warnings: Vec<FetchWarning>,
+    /// Whether the feed was fetched, or read from the [`FetchOptions::raw_store`] because it couldn't be.
+    pub freshness: Freshness,
+}
+
+/// Fetches and parses a single feed, e.g. to preview it before following it.
+///
+/// Like followed feeds, `url` can be a local file path, a `file://` URL, or a URL of a scheme
+/// registered with [`NetworkConfig::register_scheme`].
+///
+/// # Arguments
+/// * `url` - The URL of the org-social file to fetch
+/// * `options` - Timeout, retries and how the requests are made
+pub async fn fetch_feed(url: &str, options: &FetchOptions) -> Result<FetchedFeed, NetworkError> {
+    let FeedText { content, final_url, validators, warnings, freshness } = fetch_feed_text(url, options).await?;
+    let (profile, posts) = parse_file(&content, Some(final_url.clone()));
+    Ok(FetchedFeed { profile, posts, final_url, validators, warnings, freshness })
+}
+
+/// Fetches a single feed and parses only its profile, see [`fetch_feed`].
+///
+/// The whole file is still downloaded, but its posts aren't parsed.
+pub async fn fetch_profile(url: &str, options: &FetchOptions) -> Result<Profile, NetworkError> {
+    let FeedText { content, final_url, .. } = fetch_feed_text(url, options).await?;
+    Ok(parse_profile(&content, Some(final_url)))
+}
+
+/// The content of a feed, read from a file or fetched, before it's parsed.
+struct FeedText {
+    content: String,
+    final_url: String,
+    validators: Validators,
+    warnings: Vec<FetchWarning>,
+    freshness: Freshness,
+}
+
+/// Reads a feed from a file or fetches it without a cache within the overall deadline, see [`fetch_feed`].
+///
+/// The feed is saved to the [`FetchOptions::raw_store`], and read from it when it can't be fetched
+/// with [`FetchOptions::offline_fallback`].
+async fn fetch_feed_text(url: &str, options: &FetchOptions) -> Result<FeedText, NetworkError> {
+    let result = match options.overall_deadline {
+        Some(deadline) => with_timeout(deadline, read_or_fetch_feed_text(url, options))
+            .await
+            .unwrap_or_else(|| Err(NetworkError::DeadlineExceeded { url: url.to_string() })),
+        None => read_or_fetch_feed_text(url, options).await,
+    };
+    let Some(store) = &options.raw_store else {
+        return result;
+    };
+    match result {
+        Ok(text) => {
+            store.save(url, &text.final_url, text.content.clone(), text.validators.clone()).await;
+            Ok(text)
+        }
+        Err(error) if options.offline_fallback && error.is_transient() => match store.load(url).await {
+            Some(CachedResponse { body, validators, fetched_at, final_url }) => {
+                Ok(FeedText { content: body, final_url, validators, warnings: Vec::new(), freshness: Freshness::Stale { fetched_at } })
+            }
+            None => Err(error),
+        },
+        Err(error) => Err(error),
+    }
+}
+
+/// Reads a feed from a file or fetches it without a cache, see [`fetch_feed_text`].
+async fn read_or_fetch_feed_text(url: &str, options: &FetchOptions) -> Result<FeedText, NetworkError> {
+    let retry = options.retry.unwrap_or_else(RetryPolicy::none);
+    let (result, _) = with_retries(url, options.per_feed_timeout, &retry, || async {
+        let TextResponse { outcome, final_url, warnings } = get_conditional(&options.network, url, None).await?;
+        match outcome {
+            FetchOutcome::Fresh(content, validators) => Ok(FeedText { content, final_url, validators, warnings, freshness: Freshness::Fresh }),
+            // Without validators, a 304 is an unexpected status like any other
+            FetchOutcome::NotModified => Err(NetworkError::Http { url: url.to_string(), status: 304 }),
+        }
+    })
+    .await;
+
+    let text = result?;
+    if looks_like_html(&text.content) {
+        return Err(NetworkError::Parse { url: url.to_string(), reason: "got an HTML document".to_string() });
+    }
+    Ok(text)
+}
+
+/// Fetches a text resource, sending the validators of a previous response to skip it if it didn't change.
+///
+/// Unlike the feed functions, the content isn't parsed, so this works for any text resource.
+/// It's decoded as UTF-8, or with the charset of its `Content-Type` with the `charsets` feature.
+/// See [`fetch_with_disk_cache`] to keep the content of previous responses.
+///
+/// # Arguments
+/// * `client` - The HTTP client to use for the request
+/// * `url` - The URL to fetch
+/// * `validators` - Validators of the previous response, if any
+/// * `timeout` - Optional timeout duration for the request. If None, no timeout is applied.
+pub async fn fetch_conditional(
+    client: &reqwest::Client,
+    url: &str,
+    validators: Option<&Validators>,
+    timeout: Option<Duration>,
+) -> Result<FetchOutcome, NetworkError> {
+    fetch_conditional_with_config(url, validators, timeout, &NetworkConfig::with_client(client.clone())).await
+}
+
+/// Like [`fetch_conditional`], making the request as configured by `config`.
+pub async fn fetch_conditional_with_config(
+    url: &str,
+    validators: Option<&Validators>,
+    timeout: Option<Duration>,
+    config: &NetworkConfig,
+) -> Result<FetchOutcome, NetworkError> {
+    match timeout {
+        Some(duration) => with_timeout(duration, get_conditional(config, url, validators))
+            .await
+            .unwrap_or_else(|| Err(NetworkError::Timeout { url: url.to_string(), after: duration })),
+        None => get_conditional(config, url, validators).await,
+    }
+    .map(|response| response.outcome)
+}
+
+/// Fetches a text resource, using the response stored in `cache` if it didn't change.
+///
+/// # Returns
+///
+/// The fresh or cached content, along with whether it came from the cache
+pub async fn fetch_with_disk_cache(
+    client: &reqwest::Client,
+    url: &str,
+    cache: &DiskCache,
+    timeout: Option<Duration>,
+) -> Result<(String, bool), NetworkError> {
+    fetch_with_disk_cache_with_config(url, cache, timeout, &NetworkConfig::with_client(client.clone())).await
+}
+
+/// Like [`fetch_with_disk_cache`], making the request as configured by `config`.
+pub async fn fetch_with_disk_cache_with_config(
+    url: &str,
+    cache: &DiskCache,
+    timeout: Option<Duration>,
+    config: &NetworkConfig,
+) -> Result<(String, bool), NetworkError> {
+    let cached = cache.load(url).await;
+    match fetch_conditional_with_config(url, cached.as_ref().map(|cached| &cached.validators), timeout, config).await? {
+        FetchOutcome::Fresh(content, validators) => {
+            cache.save(url, url, content.clone(), validators).await;
+            Ok((content, false))
+        }
+        // A 304 is only possible when validators were sent, so there's a cached response
+        FetchOutcome::NotModified => Ok((cached.map(|cached| cached.body).unwrap_or_default(), true)),
+    }
+}
+
+/// Reads a response body, failing as soon as it's known to be bigger than `max_bytes`:
+/// from its `Content-Length` if given, while it's downloaded otherwise.
+async fn read_body(response: FetchResponse, url: &str, max_bytes: usize) -> Result<Vec<u8>, NetworkError> {
+    if response.content_length().is_some_and(|length| length > max_bytes as u64) {
+        return Err(NetworkError::TooLarge { url: url.to_string(), limit: max_bytes });
+    }
+    read_stream(response.into_body(), url, max_bytes).await
+}
+
+/// Reads a body, failing as soon as it's bigger than `max_bytes`.
+async fn read_stream(mut body: BodyStream, url: &str, max_bytes: usize) -> Result<Vec<u8>, NetworkError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = futures_util::StreamExt::next(&mut body).await {
+        let chunk = chunk.map_err(|source| NetworkError::Transport { url: url.to_string(), source })?;
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(NetworkError::TooLarge { url: url.to_string(), limit: max_bytes });
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// A response decoded by [`get_conditional`].
+struct TextResponse {
+    outcome: FetchOutcome,
+    /// URL the response came from, after permanent redirects.
+    final_url: String,
+    warnings: Vec<FetchWarning>,
+}
+
+/// Reads a resource with the resolver of its scheme, conditionally if validators are given,
+/// and decodes the body, see [`decode_body`].
+async fn get_conditional(config: &NetworkConfig, url: &str, validators: Option<&Validators>) -> Result<TextResponse, NetworkError> {
+    let config = &config.with_transport();
+    let Resolved { body, content_type, final_url, validators: fresh, not_modified, mut warnings } =
+        config.resolver(url)?.resolve(url, validators, config).await?;
+    if not_modified && validators.is_some() {
+        return Ok(TextResponse { outcome: FetchOutcome::NotModified, final_url, warnings });
+    }
+
+    let (content, decode_warnings) = decode_body(&body, content_type.as_deref(), url)?;
+    warnings.extend(decode_warnings);
+    Ok(TextResponse { outcome: FetchOutcome::Fresh(content, fresh), final_url, warnings })
+}
+
+/// What a [`SchemeResolver`] read for a URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resolved {
+    pub body: Vec<u8>,
+    /// Media type of the body, with the charset it's decoded with. If None, it's decoded as UTF-8.
+    pub content_type: Option<String>,
+    /// Where the body was read from, e.g. after permanent redirects - a temporary one doesn't
+    /// change the URL of the resource. It's the source of the posts of a feed.
+    pub final_url: String,
+    /// Validators to read the resource conditionally next time, see [`SchemeResolver::resolve`].
+    pub validators: Validators,
+    /// The resource didn't change since it was read with the validators given to the resolver, the body is empty.
+    pub not_modified: bool,
+    /// Problems that didn't prevent reading the resource.
+    pub warnings: Vec<FetchWarning>,
+}
+
+impl Resolved {
+    /// The body read from `final_url`, without validators.
+    pub fn new(final_url: &str, body: Vec<u8>) -> Self {
+        Self { body, final_url: final_url.to_string(), ..Self::default() }
+    }
+}
+
+/// The future of a [`SchemeResolver::resolve`].
+pub type ResolveFuture<'a> = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Resolved, NetworkError>> + Send + 'a>>;
+
+/// The future of a [`SchemeResolver::resolve_streaming`]: what was read, and the chunks of the body.
+pub type StreamingResolveFuture<'a> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<(Resolved, BodyStream), NetworkError>> + Send + 'a>>;
+
+/// Reads the feeds of a URL scheme, see [`NetworkConfig::register_scheme`].
+///
+/// The feed functions apply their timeouts, retries, caching and size limit checks the same way
+/// whatever the scheme, [`HttpResolver`] and [`FileResolver`] are the built-in resolvers.
+pub trait SchemeResolver: std::fmt::Debug + Send + Sync {
+    /// Read the resource at `url`.
+    ///
+    /// `validators` are those of the previous read, if any. A resolver supporting conditional reads
+    /// answers with [`Resolved::not_modified`] if the resource didn't change since.
+    /// Errors that may go away on their own should be reported as such (see [`NetworkError::is_transient`]),
+    /// so the read is retried.
+    fn resolve<'a>(&'a self, url: &'a str, validators: Option<&'a Validators>, config: &'a NetworkConfig) -> ResolveFuture<'a>;
+
+    /// Like [`SchemeResolver::resolve`], giving the body as it's read so feeds are parsed while they're downloaded.
+    /// The body of the returned [`Resolved`] is left empty.
+    ///
+    /// By default the body read by `resolve` is given in one chunk.
+    fn resolve_streaming<'a>(
+        &'a self,
+        url: &'a str,
+        validators: Option<&'a Validators>,
+        config: &'a NetworkConfig,
+    ) -> StreamingResolveFuture<'a> {
+        Box::pin(async move {
+            let mut resolved = self.resolve(url, validators, config).await?;
+            let body = std::mem::take(&mut resolved.body);
+            Ok((resolved, single_chunk(body)))
+        })
+    }
+}
+
+/// Resolver of `http://` and `https://` URLs, making the requests with the [`NetworkConfig::transport`].
+///
+/// Redirects are followed as set in [`NetworkConfig::max_redirects`], and bodies bigger than
+/// [`NetworkConfig::max_body_bytes`] fail with [`NetworkError::TooLarge`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpResolver;
+
+impl SchemeResolver for HttpResolver {
+    fn resolve<'a>(&'a self, url: &'a str, validators: Option<&'a Validators>, config: &'a NetworkConfig) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let (mut resolved, body) = self.resolve_streaming(url, validators, config).await?;
+            resolved.body = read_stream(body, url, config.max_body_bytes).await?;
+            Ok(resolved)
+        })
+    }
+
+    fn resolve_streaming<'a>(
+        &'a self,
+        url: &'a str,
+        validators: Option<&'a Validators>,
+        config: &'a NetworkConfig,
+    ) -> StreamingResolveFuture<'a> {
+        Box::pin(async move {
+            let mut headers = reqwest::header::HeaderMap::new();
+            if let Some(validators) = validators {
+                let mut insert = |name, value: &Option<String>| {
+                    if let Some(value) = value.as_deref().and_then(|value| reqwest::header::HeaderValue::from_str(value).ok()) {
+                        headers.insert(name, value);
+                    }
+                };
+                insert(reqwest::header::IF_NONE_MATCH, &validators.etag);
+                insert(reqwest::header::IF_MODIFIED_SINCE, &validators.last_modified);
+            }
+            let (response, moved_to) = config.send(url, headers).await?;
+            let final_url = moved_to.clone().unwrap_or_else(|| url.to_string());
+            let warnings = moved_to.into_iter().map(|to| FetchWarning::MovedPermanently { to }).collect();
+
+            if response.status == 304 && validators.is_some() {
+                return Ok((Resolved { final_url, not_modified: true, warnings, ..Resolved::default() }, single_chunk(Vec::new())));
+            }
+            if !(200..300).contains(&response.status) {
+                return Err(NetworkError::Http { url: url.to_string(), status: response.status });
+            }
+
+            let validators = response_validators(&response);
+            let content_type = response.header(reqwest::header::CONTENT_TYPE).map(str::to_string);
+            if response.content_length().is_some_and(|length| length > config.max_body_bytes as u64) {
+                return Err(NetworkError::TooLarge { url: url.to_string(), limit: config.max_body_bytes });
+            }
+            let resolved = Resolved { body: Vec::new(), content_type, final_url, validators, not_modified: false, warnings };
+            Ok((resolved, response.into_body()))
+        })
+    }
+}
+
+/// Resolver of `file://` URLs and absolute paths.
+///
+/// The file is read on a blocking thread of the Tokio runtime, or in place without one.
+/// Its final URL is the `file://` URL of its canonical path, so the full IDs of its posts
+/// don't depend on how the file was referred to. Files bigger than [`NetworkConfig::max_body_bytes`]
+/// fail with [`NetworkError::TooLarge`], and missing ones with [`NetworkError::NotFound`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileResolver;
+
+impl SchemeResolver for FileResolver {
+    fn resolve<'a>(&'a self, url: &'a str, _: Option<&'a Validators>, config: &'a NetworkConfig) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let path = local_feed_path(url).ok_or_else(|| NetworkError::InvalidUrl { url: url.to_string() })?;
+            let (requested, max_bytes) = (url.to_string(), config.max_body_bytes);
+            run_blocking(move || {
+                let io_error = |url: &str, source: std::io::Error| match source.kind() {
+                    std::io::ErrorKind::NotFound => NetworkError::NotFound { url: url.to_string() },
+                    _ => NetworkError::Io { url: url.to_string(), source },
+                };
+                let canonical = std::fs::canonicalize(path).map_err(|error| io_error(&requested, error))?;
+                let url = reqwest::Url::from_file_path(&canonical)
+                    .map(String::from)
+                    .unwrap_or_else(|_| format!("file://{}", canonical.to_string_lossy()));
+
+                let length = std::fs::metadata(&canonical).map_err(|error| io_error(&url, error))?.len();
+                if length > max_bytes as u64 {
+                    return Err(NetworkError::TooLarge { url, limit: max_bytes });
+                }
+                let body = std::fs::read(&canonical).map_err(|error| io_error(&url, error))?;
+                Ok(Resolved::new(&url, body))
+            })
+            .await
+        })
+    }
+}
+
+/// The validators sent with a response.
+fn response_validators(response: &FetchResponse) -> Validators {
+    let header = |name| response.header(name).map(str::to_string);
+    Validators {
+        etag: header(reqwest::header::ETAG),
+        last_modified: header(reqwest::header::LAST_MODIFIED),
+        content_length: header(reqwest::header::CONTENT_LENGTH).and_then(|length| length.parse().ok()),
+    }
+}
+
+/// Whether a resource changed, as told by a `HEAD` request, see [`check_modified`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeHint {
+    /// The validators of the resource are the same as before.
+    ProbablyUnchanged,
+    /// At least one of the validators changed.
+    Changed,
+    /// The server didn't answer with validators to compare, or the request failed.
+    Unknown,
+}
+
+/// Asks with a `HEAD` request whether a resource changed since a response with the `previous` validators.
+///
+/// This is much cheaper than downloading it again from servers that don't send `ETag`s, but some servers
+/// answer `HEAD` requests with stale or made up headers, so the result is only a hint.
+///
+/// The `ETag`s are compared if both responses have one, otherwise the `Last-Modified` and
+/// `Content-Length` headers that both have. A different `Content-Length` means the resource changed,
+/// but the same one alone doesn't tell it didn't: a matching `ETag` or `Last-Modified` is needed.
+///
+/// # Returns
+///
+/// [`ChangeHint::Unknown`] if there's nothing to compare or the server answered with an error status
+pub async fn check_modified(config: &NetworkConfig, url: &str, previous: &Validators) -> Result<ChangeHint, NetworkError> {
+    Ok(match head_validators(&config.with_transport(), url).await? {
+        Some((current, _)) => compare_validators(previous, &current),
+        None => ChangeHint::Unknown,
+    })
+}
+
+/// Makes a `HEAD` request, returning the validators and the URL of the resource after permanent redirects,
+/// None if it wasn't successful.
+async fn head_validators(config: &NetworkConfig, url: &str) -> Result<Option<(Validators, String)>, NetworkError> {
+    let (response, moved_to) = config.send_request(reqwest::Method::HEAD, url, reqwest::header::HeaderMap::new()).await?;
+    if !(200..300).contains(&response.status) {
+        return Ok(None);
+    }
+    Ok(Some((response_validators(&response), moved_to.unwrap_or_else(|| url.to_string()))))
+}
+
+/// Compares the validators of two responses for the same resource, see [`check_modified`].
+fn compare_validators(previous: &Validators, current: &Validators) -> ChangeHint {
+    if let (Some(previous), Some(current)) = (&previous.etag, &current.etag) {
+        return if previous == current { ChangeHint::ProbablyUnchanged } else { ChangeHint::Changed };
+    }
+    let last_modified = match (&previous.last_modified, &current.last_modified) {
+        (Some(previous), Some(current)) => Some(previous == current),
+        _ => None,
+    };
+    let content_length = match (previous.content_length, current.content_length) {
+        (Some(previous), Some(current)) => Some(previous == current),
+        _ => None,
+    };
+    match (last_modified, content_length) {
+        (Some(false), _) | (_, Some(false)) => ChangeHint::Changed,
+        (Some(true), _) => ChangeHint::ProbablyUnchanged,
+        // An edit can keep the same length
+        (None, _) => ChangeHint::Unknown,
+    }
+}
+
+/// A fetched feed, with where it was read from and the problems that didn't prevent reading it.
+struct DecodedFeed {
+    result: FeedResult,
+    final_url: String,
+    warnings: Vec<FetchWarning>,
+    /// The download stopped at the first post older than [`FetchOptions::since`].
+    early_stop: bool,
+}
+
+/// Decodes a whole body, see [`BodyDecoder`].
+fn decode_body(body: &[u8], content_type: Option<&str>, url: &str) -> Result<(String, Vec<FetchWarning>), NetworkError> {
+    let mut decoder = BodyDecoder::new(content_type);
+    let mut content = String::new();
+    decoder.decode(body, true, &mut content).map_err(|source| NetworkError::InvalidUtf8 { url: url.to_string(), source })?;
+    Ok((content, decoder.warnings()))
+}
+
+/// Decodes a body as its chunks are received, without its byte order mark.
+///
+/// Bodies are UTF-8, and invalid ones fail with [`NetworkError::InvalidUtf8`]. With the `charsets` feature,
+/// they're decoded with the charset of their `Content-Type` instead (UTF-8 if there's none or it's unknown,
+/// a byte order mark takes precedence), and invalid bytes are replaced with `U+FFFD` and reported
+/// as a [`FetchWarning::LossyDecode`].
+struct BodyDecoder {
+    #[cfg(feature = "charsets")]
+    decoder: encoding_rs::Decoder,
+    /// Whether invalid bytes were replaced.
+    #[cfg(feature = "charsets")]
+    lossy: bool,
+    /// The bytes of a character split between the last chunk and the next one.
+    #[cfg(not(feature = "charsets"))]
+    pending: Vec<u8>,
+    /// Whether some of the body was decoded, past its byte order mark.
+    #[cfg(not(feature = "charsets"))]
+    started: bool,
+}
+
+#[cfg(feature = "charsets")]
+impl BodyDecoder {
+    fn new(content_type: Option<&str>) -> Self {
+        let encoding = content_type
+            .and_then(|content_type| {
+                content_type.split(';').skip(1).find_map(|parameter| {
+                    let (name, value) = parameter.split_once('=')?;
+                    name.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches('"'))
+                })
+            })
+            .and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes()))
+            .unwrap_or(encoding_rs::UTF_8);
+        Self { decoder: encoding.new_decoder(), lossy: false }
+    }
+
+    /// Decodes the next chunk into `output`, `last` flushes the decoder.
+    fn decode(&mut self, chunk: &[u8], last: bool, output: &mut String) -> Result<(), std::str::Utf8Error> {
+        let mut rest = chunk;
+        loop {
+            output.reserve(self.decoder.max_utf8_buffer_length(rest.len()).unwrap_or(rest.len() * 3 + 16));
+            let (result, read, replaced) = self.decoder.decode_to_string(rest, output, last);
+            self.lossy |= replaced;
+            rest = &rest[read..];
+            if result == encoding_rs::CoderResult::InputEmpty {
+                return Ok(());
+            }
+        }
+    }
+
+    fn warnings(&self) -> Vec<FetchWarning> {
+        if self.lossy {
+            vec![FetchWarning::LossyDecode { encoding: self.decoder.encoding().name().to_string() }]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(not(feature = "charsets"))]
+impl BodyDecoder {
+    fn new(_content_type: Option<&str>) -> Self {
+        Self { pending: Vec::new(), started: false }
+    }
+
+    /// Decodes the next chunk into `output`, `last` tells an incomplete character at the end is invalid.
+    fn decode(&mut self, chunk: &[u8], last: bool, output: &mut String) -> Result<(), std::str::Utf8Error> {
+        self.pending.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(error) if error.error_len().is_none() && !last => error.valid_up_to(),
+            Err(error) => return Err(error),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid])?;
+        output.push_str(if self.started { text } else { text.strip_prefix('\u{FEFF}').unwrap_or(text) });
+        self.started |= valid > 0;
+        self.pending.drain(..valid);
+        Ok(())
+    }
+
+    fn warnings(&self) -> Vec<FetchWarning> {
+        Vec::new()
+    }
+}
+
+/// Whether a response body is an HTML document rather than an org file.
+fn looks_like_html(content: &str) -> bool {
+    let start: String = content.trim_start().chars().take(15).collect::<String>().to_lowercase();
+    start.starts_with("<!doctype html") || start.starts_with("<html")
+}
+
+/// Fetches and parses org-social files from a profile's follow list.
+///
+/// This is a convenient wrapper around `get_feeds` that extracts the follow list
+/// from a profile and fetches all followed users' feeds.
+/// Duplicate follows are dropped first (see [`Profile::follow_urls_normalized`]), so each feed is fetched once.
+/// # Arguments
+/// * `profile` - The profile containing the follow list
+/// * `timeout` - Optional timeout duration for each feed request. If None, no timeout is applied.
+///
+/// # Returns
+///
+/// A vector of tuples containing (Profile, Vec<Post>, String), where the String is the URL, for successfully fetched feeds
+pub async fn get_feeds_from_profile(profile: &Profile, timeout: Option<Duration>) -> Vec<(Profile, Vec<Post>, String)> {
+    get_feeds_from_profile_with_config(profile, timeout, NetworkHandle::shared().config()).await
+}
+
+/// Like [`get_feeds_from_profile`], making the requests as set in `config`, see [`get_feeds_with_config`].
+pub async fn get_feeds_from_profile_with_config(
+    profile: &Profile,
+    timeout: Option<Duration>,
+    config: &NetworkConfig,
+) -> Vec<(Profile, Vec<Post>, String)> {
+    let followed_users = profile.follow_urls_normalized();
+    if followed_users.is_empty() {
+        return Vec::new();
+    }
+    get_feeds_with_config(followed_users, timeout, config).await
+}
+
+/// Fetches and parses org-social files from followed users concurrently with a default 30-second timeout.
+/// 
+/// This is a convenience function that applies a reasonable default timeout.
+/// # Arguments
+///
+/// * `followed_users` - A vector of tuples containing (identifier, url) pairs
+/// # Returns
+///
+/// A vector of tuples containing (Profile, Vec\<Post\>, String), where the String is the URL, for successfully fetched feeds
+pub async fn get_feeds_with_timeout(followed_users: Vec<(String, String)>) -> Vec<(Profile, Vec<Post>, String)> {
+    get_feeds_with_config(followed_users, Some(Duration::from_secs(30)), NetworkHandle::shared().config()).await
+}
+
+/// Fetches and parses org-social files from a profile's follow list with a default 30-second timeout.
+///
+/// This is a convenience function that applies a reasonable default timeout.
+/// # Arguments
+/// * `profile` - The profile containing the follow list
+///
+/// # Returns
+///
+/// A vector of tuples containing (Profile, Vec<Post>, String), where the String is the URL, for successfully fetched feeds
+pub async fn get_feeds_from_profile_with_timeout(profile: &Profile) -> Vec<(Profile, Vec<Post>, String)> {
+    get_feeds_from_profile(profile, Some(Duration::from_secs(30))).await
+}
+
+/// Fetches candidate feeds and returns the profiles of those that follow a user.
+///
+/// Useful to discover followers outside of the user's own follows, e.g. among follows-of-follows.
+/// See `Feed::followers_of` for the matching rules.
+///
+/// # Arguments
+/// * `me` - The user's profile, its source is the URL followers must follow
+/// * `candidate_urls` - URLs of the feeds to check
+/// * `timeout` - Optional timeout duration for each feed request. If None, no timeout is applied.
+///
+/// # Returns
+///
+/// The profiles of the candidate feeds following the user
+pub async fn discover_followers(me: &Profile, candidate_urls: Vec<String>, timeout: Option<Duration>) -> Vec<Profile> {
+    let Some(my_source) = me.source() else {
+        return Vec::new();
+    };
+
+    let candidates = candidate_urls.into_iter().map(|url| (String::new(), url)).collect();
+    get_feeds_with_config(candidates, timeout, NetworkHandle::shared().config())
+        .await
+        .into_iter()
+        .map(|(profile, _, _)| profile)
+        .filter(|profile| profile.follows_feed(my_source))
+        .collect()
+}
+
+/// Options for [`fetch_bytes`].
+#[derive(Debug, Clone)]
+pub struct FetchBytesOptions {
+    /// Size limit, bigger responses fail with [`NetworkError::TooLarge`]. [`DEFAULT_MAX_AVATAR_BYTES`] by default.
+    pub max_bytes: usize,
+    /// Accepted content types, e.g. `image/png` or `image/*`, also sent in the `Accept` header.
+    /// If empty, the default, any content is accepted.
+    pub accept: Vec<String>,
+    /// Timeout for the request. If None, no timeout is applied.
+    pub timeout: Option<Duration>,
+    /// How the request is made.
+    pub network: NetworkConfig,
+}
+
+impl Default for FetchBytesOptions {
+    fn default() -> Self {
+        Self { max_bytes: DEFAULT_MAX_AVATAR_BYTES, accept: Vec::new(), timeout: None, network: NetworkConfig::default() }
+    }
+}
+
+/// A binary resource fetched with [`fetch_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    pub bytes: Vec<u8>,
+    /// Content type detected from the first bytes, or the one reported by the server if it isn't recognized.
+    /// Parameters like the charset are left out.
+    pub content_type: Option<String>,
+    /// URL the resource was read from, after redirects.
+    pub final_url: String,
+    /// The `ETag` response header.
+    pub etag: Option<String>,
+}
+
+/// Fetches a binary resource, like an avatar or a media attachment.
+///
+/// Servers often report wrong content types, so PNG, JPEG, GIF, WebP and SVG images, and HTML pages,
+/// are recognized by their first bytes, the reported type is only used for other content.
+///
+/// # Errors
+///
+/// [`NetworkError::UnexpectedType`] if the content type doesn't match [`FetchBytesOptions::accept`],
+/// [`NetworkError::TooLarge`] if the resource is bigger than [`FetchBytesOptions::max_bytes`]
+pub async fn fetch_bytes(url: &str, options: &FetchBytesOptions) -> Result<Resource, NetworkError> {
+    let request_future = async {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Ok(accept) = reqwest::header::HeaderValue::from_str(&options.accept.join(", ")) {
+            if !options.accept.is_empty() {
+                headers.insert(reqwest::header::ACCEPT, accept);
+            }
+        }
+        let (response, _) = options.network.with_transport().send(url, headers).await?;
+        if !(200..300).contains(&response.status) {
+            return Err(NetworkError::Http { url: url.to_string(), status: response.status });
+        }
+
+        let final_url = response.url.clone();
+        let header = |name| response.header(name).map(str::to_string);
+        let etag = header(reqwest::header::ETAG);
+        let reported_type = header(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.split(';').next().map(|essence| essence.trim().to_ascii_lowercase()))
+            .filter(|essence| !essence.is_empty());
+        let bytes = read_body(response, url, options.max_bytes).await?;
+
+        let content_type = sniff_content_type(&bytes).map(str::to_string).or(reported_type);
+        let accepted = options.accept.is_empty()
+            || content_type.as_deref().is_some_and(|content_type| {
+                options.accept.iter().any(|pattern| content_type_matches(content_type, pattern))
+            });
+        if !accepted {
+            return Err(NetworkError::UnexpectedType { url: url.to_string(), content_type });
+        }
+        Ok(Resource { bytes, content_type, final_url, etag })
+    };
+
+    match options.timeout {
+        Some(duration) => with_timeout(duration, request_future)
+            .await
+            .unwrap_or_else(|| Err(NetworkError::Timeout { url: url.to_string(), after: duration })),
+        None => request_future.await,
+    }
+}
+
+/// The content type of `bytes` from their signature, None if it isn't recognized.
+fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("image/png");
+    }
+    if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        return Some("image/jpeg");
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some("image/gif");
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+
+    // Markup may start with a byte order mark, whitespace, an XML declaration or comments
+    let start = &bytes[..bytes.len().min(1024)];
+    let text = String::from_utf8_lossy(start.strip_prefix(b"\xef\xbb\xbf").unwrap_or(start)).to_ascii_lowercase();
+    let text = text.trim_start();
+    if text.starts_with("<!doctype html") || text.starts_with("<html") {
+        return Some("text/html");
+    }
+    // Only a document whose root element is <svg> is an image, not one embedding it, e.g. an XHTML page
+    if skip_xml_prolog(text).starts_with("<svg") {
+        return Some("image/svg+xml");
+    }
+    None
+}
+
+/// Skips the XML declaration, doctype, comments and whitespace before the root element of a document.
+fn skip_xml_prolog(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        let end = if text.starts_with("<?") {
+            text.find("?>").map(|end| end + 2)
+        } else if text.starts_with("<!--") {
+            text.find("-->").map(|end| end + 3)
+        } else if text.starts_with("<!doctype") {
+            text.find('>').map(|end| end + 1)
+        } else {
+            return text;
+        };
+        match end {
+            Some(end) => text = &text[end..],
+            None => return "",
+        }
+    }
+}
+
+/// Whether a content type matches an accepted type like `image/png`, `image/*` or `*/*`.
+fn content_type_matches(content_type: &str, pattern: &str) -> bool {
+    let pattern = pattern.split(';').next().unwrap_or_default().trim();
+    match pattern.strip_suffix("/*") {
+        Some("*") => true,
+        Some(kind) => content_type.split('/').next().is_some_and(|own| own.eq_ignore_ascii_case(kind)),
+        None => content_type.eq_ignore_ascii_case(pattern),
+    }
+}
+
+/// Fetches the avatar of a profile.
+///
+/// Relative `#+AVATAR` URLs are resolved against the profile's source, redirects are followed.
+/// Only images are accepted, see [`fetch_bytes`].
+///
+/// # Arguments
+/// * `profile` - The profile whose avatar to fetch
+/// * `max_bytes` - Maximum size of the avatar, bigger responses fail with `NetworkError::TooLarge`
+/// * `timeout` - Optional timeout duration for the request. If None, no timeout is applied.
+///
+/// # Returns
+///
+/// The avatar bytes with their content type on success, or an error on failure
+pub async fn fetch_avatar(profile: &Profile, max_bytes: usize, timeout: Option<Duration>) -> Result<AvatarData, NetworkError> {
+    fetch_avatar_with_config(profile, max_bytes, timeout, NetworkHandle::shared().config()).await
+}
+
+/// Fetches the avatars of several profiles concurrently.
+///
+/// Profiles without an avatar are skipped. At most [`MAX_CONCURRENT_FETCHES`] requests run at the same time.
+///
+/// # Returns
+///
+/// A vector of (profile, result) pairs, in the order of the given profiles
+pub async fn fetch_avatars(
+    profiles: Vec<Arc<Profile>>,
+    max_bytes: usize,
+    timeout: Option<Duration>,
+) -> Vec<(Arc<Profile>, Result<AvatarData, NetworkError>)> {
+    fetch_avatars_with_config(profiles, max_bytes, timeout, NetworkHandle::shared().config()).await
+}
+
+/// Like [`fetch_avatars`], making the requests as set in `config`.
+pub async fn fetch_avatars_with_config(
+    profiles: Vec<Arc<Profile>>,
+    max_bytes: usize,
+    timeout: Option<Duration>,
+    config: &NetworkConfig,
+) -> Vec<(Arc<Profile>, Result<AvatarData, NetworkError>)> {
+    let config = Arc::new(config.clone());
+    let semaphore = config.concurrency_limit();
+
+    let fetch_futures: Vec<_> = profiles
+        .into_iter()
+        .filter(|profile| profile.avatar().is_some())
+        .map(|profile| {
+            let config = config.clone();
+            let semaphore = semaphore.clone();
+
+            async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let result = fetch_avatar_with_config(&profile, max_bytes, timeout, &config).await;
+                Some((profile, result))
+            }
+        })
+        .collect();
+
+    futures_util::future::join_all(fetch_futures).await.into_iter().flatten().collect()
+}
+
+/// Like [`fetch_avatar`], making the request as set in `config`.
+pub async fn fetch_avatar_with_config(
+    profile: &Profile,
+    max_bytes: usize,
+    timeout: Option<Duration>,
+    config: &NetworkConfig,
+) -> Result<AvatarData, NetworkError> {
+    let url = resolve_avatar_url(profile)?;
+    let options = FetchBytesOptions { max_bytes, accept: vec!["image/*".to_string()], timeout, network: config.clone() };
+    let Resource { bytes, content_type, .. } = fetch_bytes(&url, &options).await?;
+    Ok(AvatarData { bytes, content_type, source_url: url })
+}
+
+/// Credentials sent with [`publish`] requests.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum PublishAuth {
+    #[default]
+    None,
+    Basic { username: String, password: Option<String> },
+    Bearer(String),
+}
+
+impl std::fmt::Debug for PublishAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Keep secrets out of logs
+        match self {
+            PublishAuth::None => write!(f, "None"),
+            PublishAuth::Basic { username, .. } => f.debug_struct("Basic").field("username", username).finish_non_exhaustive(),
+            PublishAuth::Bearer(_) => write!(f, "Bearer(..)"),
+        }
+    }
+}
+
+/// Where [`publish`] uploads the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishTarget {
+    /// A server accepting a plain `PUT` of the file.
+    HttpPut { url: String, auth: PublishAuth },
+    /// A WebDAV server, e.g. Nextcloud. If the upload response has no `ETag`, it's read with a `PROPFIND`.
+    WebDav { url: String, auth: PublishAuth },
+}
+
+impl PublishTarget {
+    pub fn url(&self) -> &str {
+        match self {
+            PublishTarget::HttpPut { url, .. } | PublishTarget::WebDav { url, .. } => url,
+        }
+    }
+
+    pub fn auth(&self) -> &PublishAuth {
+        match self {
+            PublishTarget::HttpPut { auth, .. } | PublishTarget::WebDav { auth, .. } => auth,
+        }
+    }
+}
+
+/// Options for [`publish`] and [`publish_file`].
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// `ETag` of the file when it was last fetched. If given, the upload fails with [`NetworkError::Conflict`]
+    /// instead of overwriting the file if it changed on the server since.
+    pub if_match: Option<String>,
+    /// Timeout for all the requests together. If None, no timeout is applied.
+    pub timeout: Option<Duration>,
+    /// How requests are made.
+    pub network: NetworkConfig,
+}
+
+/// Result of a successful [`publish`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishReceipt {
+    /// `ETag` of the uploaded file, to pass as [`PublishOptions::if_match`] next time. None if the server didn't give one.
+    pub etag: Option<String>,
+    /// Whether the file didn't exist before (`201 Created`).
+    pub created: bool,
+}
+
+/// Uploads the content of the user's org-social file to the server.
+///
+/// Redirects aren't followed, the target URL must be the file itself.
+///
+/// # Arguments
+/// * `content` - The content of the file
+/// * `target` - Where to upload it and how to authenticate
+/// * `options` - The `ETag` to check, timeout and how requests are made
+pub async fn publish(content: &str, target: &PublishTarget, options: &PublishOptions) -> Result<PublishReceipt, NetworkError> {
+    let url = target.url();
+    let network = options.network.with_transport();
+    let publish_future = async {
+        let mut request = authenticated(network.request(reqwest::Method::PUT, url)?, target.auth())
+            .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(content.to_string());
+        if let Some(etag) = &options.if_match {
+            request = request.header(reqwest::header::IF_MATCH, etag);
+        }
+        let response = network.execute(request, url).await?;
+
+        let status = response.status;
+        if status == 412 {
+            return Err(NetworkError::Conflict { url: url.to_string() });
+        }
+        if !(200..300).contains(&status) {
+            return Err(NetworkError::Http { url: url.to_string(), status });
+        }
+
+        let mut etag = response.header(reqwest::header::ETAG).map(str::to_string);
+        if etag.is_none() && matches!(target, PublishTarget::WebDav { .. }) {
+            etag = webdav_etag(url, target.auth(), &network).await?;
+        }
+        Ok(PublishReceipt { etag, created: status == 201 })
+    };
+
+    match options.timeout {
+        Some(duration) => with_timeout(duration, publish_future)
+            .await
+            .unwrap_or_else(|| Err(NetworkError::Timeout { url: url.to_string(), after: duration })),
+        None => publish_future.await,
+    }
+}
+
+/// Serializes the user's profile and posts with [`serialize_file`] and uploads them, see [`publish`].
+pub async fn publish_file(
+    profile: &Profile,
+    posts: &[Post],
+    target: &PublishTarget,
+    options: &PublishOptions,
+) -> Result<PublishReceipt, NetworkError> {
+    publish(&serialize_file(profile, posts), target, options).await
+}
+
+/// Add the credentials to a request.
+fn authenticated(request: reqwest::RequestBuilder, auth: &PublishAuth) -> reqwest::RequestBuilder {
+    match auth {
+        PublishAuth::None => request,
+        PublishAuth::Basic { username, password } => request.basic_auth(username, password.as_ref()),
+        PublishAuth::Bearer(token) => request.bearer_auth(token),
+    }
+}
+
+/// Reads the `ETag` of a file on a WebDAV server with a `PROPFIND` request.
+async fn webdav_etag(url: &str, auth: &PublishAuth, config: &NetworkConfig) -> Result<Option<String>, NetworkError> {
+    const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><propfind xmlns="DAV:"><prop><getetag/></prop></propfind>"#;
+
+    let method = reqwest::Method::from_bytes(b"PROPFIND").expect("PROPFIND is a valid method");
+    let request = authenticated(config.request(method, url)?, auth)
+        .header("Depth", "0")
+        .header(reqwest::header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(PROPFIND_BODY);
+    let response = config.execute(request, url).await?;
+    if !(200..300).contains(&response.status) {
+        return Err(NetworkError::Http { url: url.to_string(), status: response.status });
+    }
+
+    let body = read_body(response, url, config.max_body_bytes).await?;
+    let multistatus = String::from_utf8_lossy(&body);
+    // The element can have any namespace prefix, e.g. `<d:getetag>"abc"</d:getetag>`
+    let etag = multistatus.split_once("getetag>").and_then(|(_, rest)| rest.split_once('<')).map(|(etag, _)| {
+        etag.trim().replace("&quot;", "\"").replace("&amp;", "&")
+    });
+    Ok(etag.filter(|etag| !etag.is_empty()))
+}
+
+/// Options for [`FeedWatcher`].
+#[derive(Clone)]
+pub struct WatchOptions {
+    /// Time between the polls of the feeds.
+    pub interval: Duration,
+    /// Up to this much is randomly added to each interval, so watchers started together don't poll together.
+    pub jitter: Duration,
+    /// Validators of the feeds, so unchanged feeds are skipped with a `304 Not Modified`.
+    /// If None, they're kept in memory for the lifetime of the watcher.
+    pub cache: Option<Arc<dyn FeedCache>>,
+    /// Timeout for each feed request. If None, no timeout is applied.
+    pub timeout: Option<Duration>,
+    /// How requests are made.
+    pub network: NetworkConfig,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5 * 60),
+            jitter: Duration::from_secs(30),
+            cache: None,
+            timeout: Some(Duration::from_secs(30)),
+            network: NetworkConfig::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for WatchOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchOptions")
+            .field("interval", &self.interval)
+            .field("jitter", &self.jitter)
+            .field("cache", &self.cache.as_ref().map(|_| "FeedCache"))
+            .field("timeout", &self.timeout)
+            .field("network", &self.network)
+            .finish()
+    }
+}
+
+/// A change found by a [`FeedWatcher`] in the feed at `url`.
+#[derive(Debug)]
+pub struct FeedChange {
+    pub url: String,
+    pub kind: FeedChangeKind,
+}
+
+/// What changed in a watched feed, see [`FeedChange`].
+#[derive(Debug)]
+pub enum FeedChangeKind {
+    /// Posts that weren't in the feed at the previous poll.
+    NewPosts(Vec<Post>),
+    /// The profile of the feed changed since the previous poll.
+    ProfileChanged(ProfileDiff),
+    /// The feed couldn't be fetched. Reported once, until it can be fetched again.
+    Unreachable(NetworkError),
+}
+
+/// Polls feeds periodically, reporting their new posts and profile changes.
+///
+/// Feeds are fetched with conditional requests, so unchanged ones only cost a `304 Not Modified`.
+/// The first poll of a feed gives the content changes are found against, it isn't reported.
+/// Changes are read with [`FeedWatcher::next_change`] or as a [`futures_core::Stream`].
+///
+/// The polling runs in a Tokio task, stopped by [`FeedWatcher::shutdown`] or when the watcher is dropped.
+pub struct FeedWatcher {
+    follows: Arc<Mutex<Vec<(String, String)>>>,
+    changes: tokio::sync::mpsc::UnboundedReceiver<FeedChange>,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl FeedWatcher {
+    /// Start watching feeds, given as (identifier, url) pairs. The first poll starts right away.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn new(follows: Vec<(String, String)>, options: WatchOptions) -> Self {
+        let follows = Arc::new(Mutex::new(follows));
+        let (sender, changes) = tokio::sync::mpsc::unbounded_channel();
+        let (shutdown, shutdown_receiver) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(watch_feeds(follows.clone(), options, sender, shutdown_receiver));
+        Self { follows, changes, shutdown: Some(shutdown), task: Some(task) }
+    }
+
+    /// Start watching the feeds followed in a profile, see [`FeedWatcher::new`].
+    pub fn from_profile(profile: &Profile, options: WatchOptions) -> Self {
+        Self::new(profile.follow_urls_normalized(), options)
+    }
+
+    /// Replace the watched feeds, from the next poll on.
+    ///
+    /// Feeds that were already watched keep their state, new ones start from their content at the next poll.
+    pub fn set_follows(&self, follows: Vec<(String, String)>) {
+        if let Ok(mut current) = self.follows.lock() {
+            *current = follows;
+        }
+    }
+
+    /// Wait for the next change. Returns None once the watcher is shut down and all changes were read.
+    pub async fn next_change(&mut self) -> Option<FeedChange> {
+        self.changes.recv().await
+    }
+
+    /// Stop polling, waiting for a poll in progress to finish.
+    ///
+    /// The changes found until then can still be read.
+    pub async fn shutdown(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl futures_core::Stream for FeedWatcher {
+    type Item = FeedChange;
+
+    fn poll_next(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<FeedChange>> {
+        self.changes.poll_recv(cx)
+    }
+}
+
+impl Drop for FeedWatcher {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+/// What a [`FeedWatcher`] knows about a feed.
+#[derive(Default)]
+struct WatchedFeed {
+    /// Profile and post IDs at the last poll that fetched the feed.
+    seen: Option<(Profile, HashSet<String>)>,
+    unreachable: bool,
+}
+
+/// The polling loop of a [`FeedWatcher`].
+async fn watch_feeds(
+    follows: Arc<Mutex<Vec<(String, String)>>>,
+    options: WatchOptions,
+    changes: tokio::sync::mpsc::UnboundedSender<FeedChange>,
+    mut shutdown: tokio::sync::oneshot::Receiver<()>,
+) {
+    let cache = options.cache.clone().unwrap_or_else(|| Arc::new(MemoryFeedCache::new()));
+    let mut feeds: HashMap<String, WatchedFeed> = HashMap::new();
+    // Every poll goes through the same transport
+    let network = NetworkHandle::new(options.network.clone());
+
+    loop {
+        let targets = follows.lock().map(|follows| follows.clone()).unwrap_or_default();
+        feeds.retain(|url, _| targets.iter().any(|(_, target)| target == url));
+
+        // Feeds seen for the first time are fetched in full, they're compared against later
+        let (known, new): (Vec<_>, Vec<_>) =
+            targets.into_iter().partition(|(_, url)| feeds.get(url).is_some_and(|feed| feed.seen.is_some()));
+        let mut outcomes = fetch_feeds_with_errors(known, options.timeout, Some(cache.clone()), None, network.config()).await;
+        outcomes.extend(fetch_feeds_with_errors(new, options.timeout, None, None, network.config()).await);
+
+        for outcome in outcomes {
+            let feed = feeds.entry(outcome.url.clone()).or_default();
+            let report = |kind| {
+                let _ = changes.send(FeedChange { url: outcome.url.clone(), kind });
+            };
+            match outcome.result {
+                Ok(FeedResult::Fetched(profile, posts)) => {
+                    feed.unreachable = false;
+                    let ids = posts.iter().map(|post| post.id().to_string()).collect();
+                    if let Some((seen_profile, seen_ids)) = &feed.seen {
+                        let new_posts: Vec<Post> = posts.into_iter().filter(|post| !seen_ids.contains(post.id())).collect();
+                        if !new_posts.is_empty() {
+                            report(FeedChangeKind::NewPosts(new_posts));
+                        }
+                        let diff = seen_profile.diff(&profile);
+                        if !diff.is_empty() {
+                            report(FeedChangeKind::ProfileChanged(diff));
+                        }
+                    }
+                    feed.seen = Some((profile, ids));
+                }
+                Ok(FeedResult::NotModified) => feed.unreachable = false,
+                Err(error) => {
+                    if !feed.unreachable {
+                        report(FeedChangeKind::Unreachable(error));
+                    }
+                    feed.unreachable = true;
+                }
+            }
+        }
+
+        if changes.is_closed() {
+            return;
+        }
+        let delay = options.interval + options.jitter.mul_f64(random_fraction());
+        if let futures_util::future::Either::Right(_) = futures_util::future::select(std::pin::pin!(sleep(delay)), &mut shutdown).await {
+            return;
+        }
+    }
+}
+
+/// Blocking versions of the fetch functions, for programs that don't otherwise need an async runtime.
+///
+/// They run the async functions on a runtime private to this module, so they behave the same
+/// (timeouts, limits, retries, reports). They panic if called from within an async runtime.
+///
+/// When [`NetworkConfig::client`] isn't set, a client shared by the blocking calls is used,
+/// separate from the one of the async functions. A client passed in must not be used by another runtime.
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    use super::*;
+
+    /// Runs a future to completion on the runtime of the blocking functions.
+    pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+        RUNTIME
+            .get_or_init(|| {
+                tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .thread_name("org-social-blocking")
+                    .build()
+                    .expect("the blocking runtime can be created")
+            })
+            .block_on(future)
+    }
+
+    /// Clients used when none is configured, one per proxy settings, created on first use so their connections are reused.
+    ///
+    /// Only for the requests made within the runtime of the blocking functions.
+    #[derive(Default)]
+    struct ClientPool(Mutex<HashMap<ProxySettings, reqwest::Client>>);
+
+    impl ClientPool {
+        /// The client for the proxy settings of `config`.
+        fn client(&self, config: &NetworkConfig) -> Result<reqwest::Client, NetworkError> {
+            let settings = config.proxy_settings();
+            let mut clients = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(client) = clients.get(&settings) {
+                return Ok(client.clone());
+            }
+            let client = build_client(&settings)?;
+            clients.insert(settings, client.clone());
+            Ok(client)
+        }
+    }
+
+    /// `config`, with the client of the blocking functions for its proxy settings if it has none.
+    ///
+    /// If that client can't be built, `config` is left without one so the requests fail with the error.
+    pub(crate) fn config(config: &NetworkConfig) -> NetworkConfig {
+        static CLIENTS: OnceLock<ClientPool> = OnceLock::new();
+        let client = config.client.clone().or_else(|| CLIENTS.get_or_init(ClientPool::default).client(config).ok());
+        NetworkConfig { client, ..config.clone() }
+    }
+
+    /// Blocking version of [`super::get_feeds_with_config`] with the default configuration.
+    pub fn get_feeds(followed_users: Vec<(String, String)>, timeout: Option<Duration>) -> Vec<(Profile, Vec<Post>, String)> {
+        get_feeds_with_config(followed_users, timeout, &NetworkConfig::default())
+    }
+
+    /// Blocking version of [`super::get_feeds_with_config`].
+    pub fn get_feeds_with_config(
+        followed_users: Vec<(String, String)>,
+        timeout: Option<Duration>,
+        config: &NetworkConfig,
+    ) -> Vec<(Profile, Vec<Post>, String)> {
+        block_on(super::get_feeds_with_config(followed_users, timeout, &self::config(config)))
+    }
+
+    /// Blocking version of [`super::fetch_feed`].
+    pub fn fetch_feed(url: &str, options: &FetchOptions) -> Result<FetchedFeed, NetworkError> {
+        block_on(super::fetch_feed(url, &FetchOptions { network: config(&options.network), ..options.clone() }))
+    }
+
+    /// Blocking version of [`super::fetch_profile`].
+    pub fn fetch_profile(url: &str, options: &FetchOptions) -> Result<Profile, NetworkError> {
+        block_on(super::fetch_profile(url, &FetchOptions { network: config(&options.network), ..options.clone() }))
+    }
+}
+
+/// Resolve the avatar URL of a profile, relative URLs are joined to the profile's source.
+fn resolve_avatar_url(profile: &Profile) -> Result<String, NetworkError> {
+    let avatar = profile.resolved_avatar().ok_or(NetworkError::NoAvatar)?;
+    match reqwest::Url::parse(&avatar) {
+        Ok(_) => Ok(avatar),
+        Err(_) => Err(NetworkError::InvalidUrl { url: avatar }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MockResponse, MockTransport};
+    use crate::test_support::{abortable_stub_server, delayed_stub_server, http_response, stub_server, stub_server_with_request};
+
+    fn avatar_handler(path: &str) -> Vec<u8> {
+        match path {
+            "/avatar.png" => http_response("200 OK", &[("Content-Type", "image/png")], b"PNGDATA"),
+            "/old-avatar.png" => http_response("301 Moved Permanently", &[("Location", "/avatar.png")], b""),
+            "/huge.png" => http_response("200 OK", &[("Content-Type", "image/png")], &[0; 64]),
+            _ => http_response("404 Not Found", &[], b""),
+        }
+    }
+
+    fn profile_with_avatar(avatar: &str, source: Option<String>) -> Profile {
+        let mut profile = Profile::from(vec!["#+NICK: someone".to_string(), format!("#+AVATAR: {avatar}")]);
+        profile.set_source(source);
+        profile
+    }
+
+    #[tokio::test]
+    async fn test_fetch_avatar() {
+        let base = stub_server(avatar_handler).await;
+
+        let profile = profile_with_avatar(&format!("{base}/avatar.png"), None);
+        let avatar = fetch_avatar(&profile, 1024, Some(Duration::from_secs(5))).await.unwrap();
+        assert_eq!(avatar.bytes, b"PNGDATA");
+        assert_eq!(avatar.content_type.as_deref(), Some("image/png"));
+        assert_eq!(avatar.source_url, format!("{base}/avatar.png"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_avatar_relative_and_redirected() {
+        let base = stub_server(avatar_handler).await;
+
+        let profile = profile_with_avatar("old-avatar.png", Some(format!("{base}/social.org")));
+        let avatar = fetch_avatar(&profile, 1024, None).await.unwrap();
+        assert_eq!(avatar.bytes, b"PNGDATA");
+        assert_eq!(avatar.source_url, format!("{base}/old-avatar.png"));
+
+        let without_source = profile_with_avatar("avatar.png", None);
+        assert!(matches!(fetch_avatar(&without_source, 1024, None).await, Err(NetworkError::InvalidUrl { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_avatar_errors() {
+        let base = stub_server(avatar_handler).await;
+
+        let huge = profile_with_avatar(&format!("{base}/huge.png"), None);
+        assert!(matches!(fetch_avatar(&huge, 32, None).await, Err(NetworkError::TooLarge { limit: 32, .. })));
+
+        let missing = profile_with_avatar(&format!("{base}/missing.png"), None);
+        assert!(matches!(fetch_avatar(&missing, 1024, None).await, Err(NetworkError::Http { status: 404, .. })));
+
+        assert!(matches!(fetch_avatar(&Profile::default(), 1024, None).await, Err(NetworkError::NoAvatar)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_avatars_skips_profiles_without_avatar() {
+        let base = stub_server(avatar_handler).await;
+
+        let profiles = vec![
+            Arc::new(profile_with_avatar(&format!("{base}/avatar.png"), None)),
+            Arc::new(Profile::default()),
+            Arc::new(profile_with_avatar(&format!("{base}/missing.png"), None)),
+        ];
+        let results = fetch_avatars(profiles, 1024, None).await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_discover_followers() {
+        let base = stub_server(|path| {
+            let body = match path {
+                "/fan.org" => "#+NICK: fan\n#+FOLLOW: me http://ME.example/social.org/\n",
+                "/stranger.org" => "#+NICK: stranger\n#+FOLLOW: other https://other.example/social.org\n",
+                _ => return http_response("404 Not Found", &[], b""),
+            };
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+
+        let mut me = Profile::from(vec!["#+NICK: me".to_string()]);
+        me.set_source(Some("https://me.example/social.org".to_string()));
+
+        let candidates = vec![format!("{base}/fan.org"), format!("{base}/stranger.org"), format!("{base}/missing.org")];
+        let followers = discover_followers(&me, candidates, Some(Duration::from_secs(5))).await;
+        assert_eq!(followers.len(), 1);
+        assert_eq!(followers[0].nick(), "fan");
+
+        assert!(discover_followers(&Profile::default(), vec![format!("{base}/fan.org")], None).await.is_empty());
+    }
+
+    const FEED: &str = "#+NICK: alice\n\n* Posts\n** 2025-01-01T12:00:00+0000\nHello\n";
+    const FEED_WITH_ID: &str = "#+NICK: alice\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n";
+
+    fn feed_url(base: &str) -> Vec<(String, String)> {
+        vec![("alice".to_string(), format!("{base}/social.org"))]
+    }
+
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_get_feeds_cached_etag() {
+        let base = stub_server_with_request(|_, request| {
+            if request.to_lowercase().contains("if-none-match: \"v1\"") {
+                http_response("304 Not Modified", &[], b"")
+            } else {
+                http_response("200 OK", &[("ETag", "\"v1\"")], FEED.as_bytes())
+            }
+        })
+        .await;
+        let cache = Arc::new(MemoryFeedCache::new());
+
+        let results = get_feeds_cached(feed_url(&base), None, cache.clone()).await;
+        assert!(matches!(&results[0].1, FeedResult::Fetched(profile, posts) if profile.nick() == "alice" && posts.len() == 1));
+        let entry = cache.get(&format!("{base}/social.org")).unwrap();
+        assert_eq!(entry.etag.as_deref(), Some("\"v1\""));
+
+        let results = get_feeds_cached(feed_url(&base), None, cache.clone()).await;
+        assert!(matches!(results[0].1, FeedResult::NotModified));
+
+        // Without a cache, the feed is always downloaded
+        assert_eq!(get_feeds(feed_url(&base), None).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_feeds_cached_last_modified() {
+        const LAST_MODIFIED: &str = "Wed, 01 Jan 2025 12:00:00 GMT";
+        let base = stub_server_with_request(|_, request| {
+            if request.to_lowercase().contains(&format!("if-modified-since: {}", LAST_MODIFIED.to_lowercase())) {
+                http_response("304 Not Modified", &[], b"")
+            } else {
+                http_response("200 OK", &[("Last-Modified", LAST_MODIFIED)], FEED.as_bytes())
+            }
+        })
+        .await;
+        let cache: Arc<dyn FeedCache> = Arc::new(MemoryFeedCache::new());
+
+        let results = get_feeds_cached(feed_url(&base), None, cache.clone()).await;
+        assert!(matches!(results[0].1, FeedResult::Fetched(..)));
+        let results = get_feeds_cached(feed_url(&base), None, cache).await;
+        assert!(matches!(results[0].1, FeedResult::NotModified));
+    }
+
+    #[tokio::test]
+    async fn test_get_feeds_cached_unchanged_body() {
+        let base = stub_server(|_| http_response("200 OK", &[], FEED.as_bytes())).await;
+        let cache: Arc<dyn FeedCache> = Arc::new(MemoryFeedCache::new());
+
+        let results = get_feeds_cached(feed_url(&base), None, cache.clone()).await;
+        assert!(matches!(results[0].1, FeedResult::Fetched(..)));
+        let results = get_feeds_cached(feed_url(&base), None, cache).await;
+        assert!(matches!(results[0].1, FeedResult::NotModified));
+    }
+
+    #[test]
+    fn test_disk_feed_cache() {
+        let directory = std::env::temp_dir().join(format!("org-social-cache-test-{}", std::process::id()));
+        let cache = DiskFeedCache::new(&directory);
+        let url = "https://alice.example/social.org";
+        assert_eq!(cache.get(url), None);
+
+        let entry = CacheEntry {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            content_length: Some(FEED.len() as u64),
+            body_hash: fnv1a_hash(FEED.as_bytes()),
+            fetched_at: DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z").unwrap().with_timezone(&Utc),
+            head_unreliable: true,
+            body: Some(FEED.to_string()),
+        };
+        cache.set(url, entry.clone());
+        assert_eq!(DiskFeedCache::new(&directory).get(url), Some(entry.clone()));
+        let entry = CacheEntry { body: None, ..entry };
+        cache.set(url, entry.clone());
+        assert_eq!(DiskFeedCache::new(&directory).get(url), Some(entry));
+        assert_eq!(cache.get("https://bob.example/social.org"), None);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_disk_feed_cache_after_restart() {
+        let base = stub_server_with_request(|_, request| {
+            if request.to_lowercase().contains("if-none-match: \"v1\"") {
+                http_response("304 Not Modified", &[], b"")
+            } else {
+                http_response("200 OK", &[("ETag", "\"v1\"")], FEED.as_bytes())
+            }
+        })
+        .await;
+        let directory = std::env::temp_dir().join(format!("org-social-cache-restart-test-{}", std::process::id()));
+
+        let results = get_feeds_cached(feed_url(&base), None, Arc::new(DiskFeedCache::new(&directory))).await;
+        assert!(matches!(&results[0].1, FeedResult::Fetched(_, posts) if posts.len() == 1));
+
+        // A new cache on the same directory answers the 304 with the stored body
+        let results = get_feeds_cached(feed_url(&base), None, Arc::new(DiskFeedCache::new(&directory))).await;
+        assert!(matches!(&results[0].1, FeedResult::Fetched(profile, posts) if profile.nick() == "alice" && posts.len() == 1));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_errors() {
+        let base = stub_server(|path| match path {
+            "/latin1.org" => http_response("200 OK", &[], b"#+NICK: caf\xe9\n"),
+            "/portal.org" => http_response("200 OK", &[("Content-Type", "text/html")], b"\n<!DOCTYPE html><html><body>Log in</body></html>"),
+            "/bom.org" => http_response("200 OK", &[], format!("\u{FEFF}{FEED}").as_bytes()),
+            "/down.org" => http_response("502 Bad Gateway", &[], b""),
+            _ => http_response("200 OK", &[], FEED.as_bytes()),
+        })
+        .await;
+
+        let urls = ["latin1", "portal", "bom", "down"].map(|name| (name.to_string(), format!("{base}/{name}.org")));
+        let outcomes = fetch_feeds_with_errors(urls.to_vec(), Some(Duration::from_secs(5)), None, None, &NetworkConfig::default()).await;
+
+        #[cfg(not(feature = "charsets"))]
+        assert!(matches!(&outcomes[0].result, Err(NetworkError::InvalidUtf8 { url, .. }) if url.ends_with("/latin1.org")));
+        #[cfg(feature = "charsets")]
+        assert_eq!(outcomes[0].warnings, vec![FetchWarning::LossyDecode { encoding: "UTF-8".to_string() }]);
+        assert!(matches!(&outcomes[1].result, Err(NetworkError::Parse { .. })));
+        assert!(matches!(&outcomes[2].result, Ok(FeedResult::Fetched(profile, posts)) if profile.nick() == "alice" && posts.len() == 1));
+        assert!(matches!(&outcomes[3].result, Err(NetworkError::Http { status: 502, .. })));
+        assert!(outcomes[2].warnings.is_empty());
+    }
+
+    #[cfg(feature = "charsets")]
+    #[tokio::test]
+    async fn test_charset_decoding() {
+        let base = stub_server(|path| match path {
+            "/latin1.org" => http_response("200 OK", &[("Content-Type", "text/plain; charset=\"ISO-8859-1\"")], b"#+NICK: caf\xe9\n"),
+            "/undeclared.org" => http_response("200 OK", &[("Content-Type", "text/plain")], b"#+NICK: caf\xe9\n"),
+            _ => http_response("200 OK", &[("Content-Type", "text/plain; charset=utf-8")], "#+NICK: café\n".as_bytes()),
+        })
+        .await;
+
+        let urls = ["latin1", "undeclared", "utf8"].map(|name| (name.to_string(), format!("{base}/{name}.org")));
+        let outcomes = fetch_feeds_with_errors(urls.to_vec(), Some(Duration::from_secs(5)), None, None, &NetworkConfig::default()).await;
+        let nick = |index: usize| match &outcomes[index].result {
+            Ok(FeedResult::Fetched(profile, _)) => profile.nick(),
+            _ => panic!("expected a parsed feed"),
+        };
+
+        assert_eq!(nick(0), "café");
+        assert!(outcomes[0].warnings.is_empty());
+        assert_eq!(nick(1), "caf\u{FFFD}");
+        assert_eq!(outcomes[1].warnings, vec![FetchWarning::LossyDecode { encoding: "UTF-8".to_string() }]);
+        assert_eq!(nick(2), "café");
+        assert!(outcomes[2].warnings.is_empty());
+    }
+
+    /// A zlib stream holding `data` in a single uncompressed block.
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let length = data.len() as u16;
+        let mut stream = vec![0x78, 0x01, 0x01];
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(data);
+        let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+            let a = (a + byte as u32) % 65521;
+            (a, (b + a) % 65521)
+        });
+        stream.extend_from_slice(&((b << 16) | a).to_be_bytes());
+        stream
+    }
+
+    #[tokio::test]
+    async fn test_compressed_response() {
+        let base = stub_server_with_request(|_, request| {
+            if request.to_lowercase().contains("accept-encoding: gzip, deflate") {
+                http_response("200 OK", &[("Content-Encoding", "deflate")], &zlib_stored(FEED.as_bytes()))
+            } else {
+                http_response("406 Not Acceptable", &[], b"")
+            }
+        })
+        .await;
+
+        let outcomes = fetch_feeds_with_errors(feed_url(&base), Some(Duration::from_secs(5)), None, None, &NetworkConfig::default()).await;
+        assert!(matches!(&outcomes[0].result, Ok(FeedResult::Fetched(profile, posts)) if profile.nick() == "alice" && posts.len() == 1));
+    }
+
+    fn quick_retry(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, base_delay: Duration::from_millis(10), max_delay: Duration::from_millis(20), ..Default::default() }
+    }
+
+    /// A configuration making the requests with `transport`.
+    fn mock_network(transport: &Arc<MockTransport>) -> NetworkConfig {
+        NetworkConfig { transport: Some(transport.clone()), ..Default::default() }
+    }
+
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_retry_transient_failures() {
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base = stub_server(move |path| match path {
+            "/missing.org" => http_response("404 Not Found", &[], b""),
+            _ if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 => http_response("502 Bad Gateway", &[], b""),
+            _ => http_response("200 OK", &[], FEED.as_bytes()),
+        })
+        .await;
+
+        let urls = vec![("alice".to_string(), format!("{base}/social.org")), ("bob".to_string(), format!("{base}/missing.org"))];
+        let outcomes = fetch_feeds_with_errors(urls, Some(Duration::from_secs(5)), None, Some(quick_retry(3)), &NetworkConfig::default()).await;
+
+        assert!(matches!(outcomes[0].result, Ok(FeedResult::Fetched(..))));
+        assert_eq!(outcomes[0].attempts, 3);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 3);
+        // Not found isn't retried
+        assert!(matches!(outcomes[1].result, Err(NetworkError::Http { status: 404, .. })));
+        assert_eq!(outcomes[1].attempts, 1);
+
+        // Without a retry policy, the first failure is final
+        requests.store(0, std::sync::atomic::Ordering::SeqCst);
+        assert!(get_feeds(feed_url(&base), None).await.is_empty());
+        requests.store(0, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(get_feeds_with_retry(feed_url(&base), None, quick_retry(2)).await.len(), 0);
+        requests.store(1, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(get_feeds_with_retry(feed_url(&base), None, quick_retry(2)).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_mock_transport() {
+        let transport = Arc::new(MockTransport::new());
+        let (alice, bob) = ("https://example.com/alice.org", "https://example.com/missing.org");
+        transport
+            .respond(alice, MockResponse::status(502))
+            .respond(alice, MockResponse::error("connection reset"))
+            .respond(alice, MockResponse::ok(FEED));
+        transport.respond(bob, MockResponse::status(404));
+
+        let urls = vec![("alice".to_string(), alice.to_string()), ("bob".to_string(), bob.to_string())];
+        let outcomes = fetch_feeds_with_errors(urls, Some(Duration::from_secs(5)), None, Some(quick_retry(3)), &mock_network(&transport)).await;
+
+        assert!(matches!(outcomes[0].result, Ok(FeedResult::Fetched(..))));
+        assert_eq!(outcomes[0].attempts, 3);
+        assert_eq!(transport.request_count(alice), 3);
+        // Not found isn't retried
+        assert!(matches!(outcomes[1].result, Err(NetworkError::Http { status: 404, .. })));
+        assert_eq!(outcomes[1].attempts, 1);
+        assert_eq!(transport.request_count(bob), 1);
+
+        // Without a retry policy, the first failure is final
+        let flaky = Arc::new(MockTransport::new());
+        flaky.respond(alice, MockResponse::error("connection refused")).respond(alice, MockResponse::ok(FEED));
+        let options = FetchOptions { network: mock_network(&flaky), ..Default::default() };
+        assert!(matches!(fetch_feed(alice, &options).await, Err(NetworkError::Transport { .. })));
+        assert_eq!(flaky.request_count(alice), 1);
+        let options = FetchOptions { retry: Some(quick_retry(2)), ..options };
+        assert_eq!(fetch_feed(alice, &options).await.unwrap().profile.nick(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_retry_respects_deadline() {
+        let transport = Arc::new(MockTransport::new());
+        transport.respond("https://example.com/social.org", MockResponse::status(503));
+        let options = FetchOptions {
+            overall_deadline: Some(Duration::from_millis(300)),
+            retry: Some(RetryPolicy { max_attempts: 100, base_delay: Duration::from_millis(100), ..Default::default() }),
+            network: mock_network(&transport),
+            ..Default::default()
+        };
+
+        let started = std::time::Instant::now();
+        let outcomes = fetch_feeds_with_hooks(feed_url("https://example.com"), &options, None, FetchHooks::default()).await;
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(matches!(outcomes[0].result, Err(NetworkError::DeadlineExceeded { .. })));
+        let requests = transport.request_count("https://example.com/social.org");
+        assert!(requests > 1 && requests < 100);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_attempt_is_retried() {
+        let transport = Arc::new(MockTransport::new());
+        let url = "https://example.com/social.org";
+        transport.respond(url, MockResponse::ok(FEED).with_delay(Duration::from_secs(30))).respond(url, MockResponse::ok(FEED));
+        let options = FetchOptions {
+            per_feed_timeout: Some(Duration::from_millis(100)),
+            retry: Some(quick_retry(2)),
+            network: mock_network(&transport),
+            ..Default::default()
+        };
+
+        let outcomes = fetch_feeds_with_hooks(feed_url("https://example.com"), &options, None, FetchHooks::default()).await;
+        assert!(matches!(outcomes[0].result, Ok(FeedResult::Fetched(..))));
+        assert_eq!(outcomes[0].attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_with_slow_transport() {
+        let transport = Arc::new(MockTransport::new());
+        let url = "https://example.com/social.org";
+        transport.respond(url, MockResponse::ok(FEED).with_delay(Duration::from_secs(30)));
+
+        let options = FetchOptions {
+            network: mock_network(&transport),
+            per_feed_timeout: Some(Duration::from_millis(100)),
+            retry: Some(RetryPolicy::none()),
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let error = fetch_feed(url, &options).await.unwrap_err();
+        assert!(matches!(error, NetworkError::Timeout { after, .. } if after == Duration::from_millis(100)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_transport_requests() {
+        let transport = Arc::new(MockTransport::new());
+        transport.respond("https://example.com/old.org", MockResponse::status(301).with_header("Location", "/social.org"));
+        transport.respond("https://example.com/social.org", MockResponse::ok(FEED).with_header("ETag", "\"v1\""));
+        transport.respond("https://example.com/dav/social.org", MockResponse::status(201).with_header("ETag", "\"v2\""));
+        let network = NetworkConfig { user_agent: Some("test-agent".to_string()), ..mock_network(&transport) };
+
+        // Redirects are followed on top of the transport
+        let feed = fetch_feed("https://example.com/old.org", &FetchOptions { network: network.clone(), ..Default::default() }).await.unwrap();
+        assert_eq!(feed.final_url, "https://example.com/social.org");
+        assert_eq!(feed.validators.etag.as_deref(), Some("\"v1\""));
+
+        let target = PublishTarget::WebDav {
+            url: "https://example.com/dav/social.org".to_string(),
+            auth: PublishAuth::Basic { username: "alice".to_string(), password: Some("secret".to_string()) },
+        };
+        let receipt = publish(FEED, &target, &PublishOptions { network, ..Default::default() }).await.unwrap();
+        assert_eq!(receipt, PublishReceipt { etag: Some("\"v2\"".to_string()), created: true });
+
+        let requests = transport.requests();
+        let urls: Vec<_> = requests.iter().map(|request| (request.method.as_str(), request.url.as_str())).collect();
+        assert_eq!(
+            urls,
+            [("GET", "https://example.com/old.org"), ("GET", "https://example.com/social.org"), ("PUT", "https://example.com/dav/social.org")]
+        );
+        assert!(requests.iter().all(|request| request.headers[reqwest::header::USER_AGENT] == "test-agent"));
+        assert_eq!(requests[2].body.as_deref(), Some(FEED.as_bytes()));
+        assert_eq!(requests[2].headers[reqwest::header::AUTHORIZATION], "Basic YWxpY2U6c2VjcmV0");
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = RetryPolicy { base_delay: Duration::from_millis(100), max_delay: Duration::from_millis(300), ..Default::default() };
+        for _ in 0..20 {
+            let first = retry.delay(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let second = retry.delay(2);
+            assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+            let capped = retry.delay(10);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+
+        assert!(NetworkError::Http { url: String::new(), status: 502 }.is_transient());
+        assert!(NetworkError::Http { url: String::new(), status: 429 }.is_transient());
+        assert!(!NetworkError::Http { url: String::new(), status: 404 }.is_transient());
+        assert!(!NetworkError::TooLarge { url: String::new(), limit: 1 }.is_transient());
+    }
+
+    fn etag_server_handler(_: &str, request: &str) -> Vec<u8> {
+        if request.to_lowercase().contains("if-none-match: \"v1\"") {
+            http_response("304 Not Modified", &[], b"")
+        } else {
+            http_response("200 OK", &[("ETag", "\"v1\""), ("Last-Modified", "Wed, 01 Jan 2025 12:00:00 GMT")], FEED.as_bytes())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_conditional() {
+        let base = stub_server_with_request(etag_server_handler).await;
+        let client = reqwest::Client::new();
+        let url = format!("{base}/social.org");
+
+        let FetchOutcome::Fresh(content, validators) = fetch_conditional(&client, &url, None, None).await.unwrap() else {
+            panic!("expected fresh content");
+        };
+        assert_eq!(content, FEED);
+        assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(validators.last_modified.as_deref(), Some("Wed, 01 Jan 2025 12:00:00 GMT"));
+
+        let outcome = fetch_conditional(&client, &url, Some(&validators), Some(Duration::from_secs(5))).await.unwrap();
+        assert_eq!(outcome, FetchOutcome::NotModified);
+
+        let stale = Validators { etag: Some("\"v0\"".to_string()), ..Validators::default() };
+        assert!(matches!(fetch_conditional(&client, &url, Some(&stale), None).await.unwrap(), FetchOutcome::Fresh(..)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_disk_cache() {
+        let base = stub_server_with_request(etag_server_handler).await;
+        let client = reqwest::Client::new();
+        let url = format!("{base}/social.org");
+        let directory = std::env::temp_dir().join(format!("org-social-disk-cache-test-{}", std::process::id()));
+        let cache = DiskCache::new(&directory, 1024 * 1024);
+
+        assert_eq!(fetch_with_disk_cache(&client, &url, &cache, None).await.unwrap(), (FEED.to_string(), false));
+        assert_eq!(cache.get(&url).unwrap().validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(fetch_with_disk_cache(&client, &url, &cache, None).await.unwrap(), (FEED.to_string(), true));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_disk_cache_eviction() {
+        let directory = std::env::temp_dir().join(format!("org-social-disk-cache-eviction-test-{}", std::process::id()));
+        let body = "x".repeat(400);
+        let cache = DiskCache::new(&directory, 1000);
+        let validators = Validators { etag: Some("\"1\"".to_string()), ..Validators::default() };
+
+        for url in ["https://a.example/social.org", "https://b.example/social.org"] {
+            cache.put(url, &body, &validators);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(cache.get("https://a.example/social.org").is_some());
+
+        // The third response doesn't fit, the oldest one is removed
+        cache.put("https://c.example/social.org", &body, &Validators::default());
+        assert!(cache.get("https://a.example/social.org").is_none());
+        assert_eq!(cache.get("https://b.example/social.org").unwrap().body, body);
+        assert!(cache.get("https://c.example/social.org").unwrap().validators.is_empty());
+        assert!(cache.size() <= 1000);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_injected_client() {
+        let base = stub_server_with_request(|_, request| {
+            if request.to_lowercase().contains("x-client-marker: injected") {
+                http_response("200 OK", &[], FEED.as_bytes())
+            } else {
+                http_response("403 Forbidden", &[], b"")
+            }
+        })
+        .await;
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-Client-Marker", reqwest::header::HeaderValue::from_static("injected"));
+        let config = NetworkConfig::with_client(reqwest::Client::builder().default_headers(headers).build().unwrap());
+
+        assert_eq!(get_feeds_with_config(feed_url(&base), None, &config).await.len(), 1);
+        assert!(get_feeds(feed_url(&base), None).await.is_empty());
+
+        let mut profile = Profile::default();
+        profile.add_follow("alice".to_string(), format!("{base}/social.org"));
+        assert_eq!(get_feeds_from_profile_with_config(&profile, None, &config).await.len(), 1);
+    }
+
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_user_agent() {
+        let user_agents = Arc::new(Mutex::new(Vec::new()));
+        let seen = user_agents.clone();
+        let base = stub_server_with_request(move |path, request| {
+            let user_agent = request
+                .lines()
+                .find_map(|line| line.strip_prefix("user-agent: ").or_else(|| line.strip_prefix("User-Agent: ")))
+                .unwrap_or_default();
+            seen.lock().unwrap().push(user_agent.to_string());
+            match path {
+                "/avatar.png" => http_response("200 OK", &[("Content-Type", "image/png")], b"PNGDATA"),
+                _ => http_response("200 OK", &[], FEED.as_bytes()),
+            }
+        })
+        .await;
+        let take = || std::mem::take(&mut *user_agents.lock().unwrap());
+
+        get_feeds(feed_url(&base), None).await;
+        fetch_avatar(&profile_with_avatar(&format!("{base}/avatar.png"), None), 1024, None).await.unwrap();
+        fetch_conditional(&reqwest::Client::new(), &format!("{base}/social.org"), None, None).await.unwrap();
+        assert_eq!(take(), vec![DEFAULT_USER_AGENT; 3]);
+        assert!(DEFAULT_USER_AGENT.starts_with(&format!("org-social-lib-rs/{} (+https://", env!("CARGO_PKG_VERSION"))));
+
+        let config = NetworkConfig::default().with_client_name("my-client/1.0");
+        get_feeds_with_config(feed_url(&base), None, &config).await;
+        fetch_conditional_with_config(&format!("{base}/social.org"), None, None, &config).await.unwrap();
+        fetch_avatar_with_config(&profile_with_avatar(&format!("{base}/avatar.png"), None), 1024, None, &config).await.unwrap();
+        assert_eq!(take(), vec![format!("{DEFAULT_USER_AGENT} my-client/1.0"); 3]);
+
+        // Without a user agent, the one of the injected client is kept
+        let client = reqwest::Client::builder().user_agent("custom/2.0").build().unwrap();
+        let config = NetworkConfig { user_agent: None, ..NetworkConfig::with_client(client) };
+        get_feeds_with_config(feed_url(&base), None, &config).await;
+        assert_eq!(take(), vec!["custom/2.0"]);
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Requests being answered, counted from their arrival until their response
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let (arrived, max) = (in_flight.clone(), max_in_flight.clone());
+        let base = delayed_stub_server(
+            move |_| {
+                let now = arrived.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(now, Ordering::SeqCst);
+                Duration::from_millis(50)
+            },
+            move |_, _| {
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                http_response("200 OK", &[], FEED.as_bytes())
+            },
+        )
+        .await;
+
+        let urls = (0..6).map(|index| (String::new(), format!("{base}/{index}.org"))).collect();
+        let config = NetworkConfig { max_concurrency: 2, ..Default::default() };
+        let outcomes = fetch_feeds_with_errors(urls, Some(Duration::from_secs(5)), None, None, &config).await;
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_ok()));
+        assert!((1..=2).contains(&max_in_flight.load(Ordering::SeqCst)));
+    }
+
+    #[tokio::test]
+    async fn test_get_feeds_with_progress() {
+        let base = stub_server(|path| match path {
+            "/social.org" => http_response("200 OK", &[], FEED.as_bytes()),
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let good = format!("{base}/social.org");
+        let missing = format!("{base}/missing.org");
+        let urls = vec![(String::new(), good.clone()), (String::new(), missing.clone())];
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let collector = tokio::spawn(async move {
+            let mut events = Vec::new();
+            while let Some(event) = receiver.recv().await {
+                events.push(event);
+            }
+            events
+        });
+        let feeds = get_feeds_with_progress(urls.clone(), None, sender).await;
+        assert_eq!(feeds.len(), 1);
+
+        let events = collector.await.unwrap();
+        assert_eq!(events.len(), 4);
+        for url in [&good, &missing] {
+            let started = events.iter().position(|event| event == &FetchEvent::Started(url.clone())).unwrap();
+            let ended = events
+                .iter()
+                .position(|event| matches!(event, FetchEvent::Finished { url: u, .. } | FetchEvent::Failed { url: u, .. } if u == url))
+                .unwrap();
+            assert!(started < ended);
+        }
+        assert!(events.iter().any(|event| matches!(event, FetchEvent::Finished { url, posts: 1, .. } if url == &good)));
+        assert!(events.iter().any(|event| matches!(event, FetchEvent::Failed { url, error } if url == &missing && error.contains("404"))));
+
+        // A dropped receiver doesn't stop the fetches
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        drop(receiver);
+        assert_eq!(get_feeds_with_progress(urls, None, sender).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_progress_reader_doesnt_hold_requests() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base = stub_server(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            http_response("200 OK", &[], FEED.as_bytes())
+        })
+        .await;
+        let urls: Vec<(String, String)> = (0..3).map(|index| (String::new(), format!("{base}/{index}.org"))).collect();
+        let options = FetchOptions { network: NetworkConfig { max_concurrency: 1, ..Default::default() }, ..Default::default() };
+
+        // The events aren't read until all the requests were made, with room for a single one
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let hooks = FetchHooks { events: Some(sender), ..Default::default() };
+        let fetches = tokio::spawn(async move { fetch_feeds_with_hooks(urls, &options, None, hooks).await });
+        let all_requested = async {
+            while requests.load(Ordering::SeqCst) < 3 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        assert!(tokio::time::timeout(Duration::from_secs(5), all_requested).await.is_ok());
+
+        let mut events = 0;
+        while receiver.recv().await.is_some() {
+            events += 1;
+        }
+        assert_eq!(events, 6);
+        assert_eq!(fetches.await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_body_bytes() {
+        let big = format!("{FEED}{}", "x".repeat(4096));
+        let base = stub_server(move |path| match path {
+            "/sized.org" => http_response("200 OK", &[], big.as_bytes()),
+            // Without Content-Length, the body ends when the connection is closed
+            "/streamed.org" => format!("HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{big}").into_bytes(),
+            _ => http_response("200 OK", &[], FEED.as_bytes()),
+        })
+        .await;
+
+        let config = NetworkConfig { max_body_bytes: 1024, ..Default::default() };
+        let urls = ["sized", "streamed", "small"].map(|name| (String::new(), format!("{base}/{name}.org")));
+        let outcomes = fetch_feeds_with_errors(urls.to_vec(), Some(Duration::from_secs(5)), None, None, &config).await;
+
+        assert!(matches!(&outcomes[0].result, Err(NetworkError::TooLarge { limit: 1024, url }) if url.ends_with("/sized.org")));
+        assert!(matches!(&outcomes[1].result, Err(NetworkError::TooLarge { limit: 1024, .. })));
+        assert!(matches!(&outcomes[2].result, Ok(FeedResult::Fetched(..))));
+
+        // The default limit is big enough for both
+        let outcomes = fetch_feeds_with_errors(urls.to_vec(), None, None, None, &NetworkConfig::default()).await;
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn test_local_feeds() {
+        let directory = std::env::temp_dir().join(format!("org-social-local-feed-test-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("sub")).unwrap();
+        std::fs::write(directory.join("social.org"), FEED_WITH_ID).unwrap();
+        let canonical = format!("file://{}", std::fs::canonicalize(directory.join("social.org")).unwrap().to_string_lossy());
+
+        let plain_path = directory.join("sub/../social.org").to_string_lossy().to_string();
+        let urls = vec![
+            ("alice".to_string(), format!("file://{}", directory.join("social.org").to_string_lossy())),
+            ("alice".to_string(), plain_path.clone()),
+            ("missing".to_string(), directory.join("missing.org").to_string_lossy().to_string()),
+        ];
+        let outcomes = fetch_feeds_with_errors(urls, Some(Duration::from_millis(1)), None, None, &NetworkConfig::default()).await;
+
+        for outcome in &outcomes[..2] {
+            assert_eq!(outcome.final_url, canonical);
+            let Ok(FeedResult::Fetched(profile, posts)) = &outcome.result else {
+                panic!("expected a parsed feed");
+            };
+            assert_eq!(profile.nick(), "alice");
+            assert_eq!(posts[0].full_id(), format!("{canonical}#2025-01-01T12:00:00+0000"));
+        }
+        assert!(matches!(&outcomes[2].result, Err(NetworkError::NotFound { url }) if url.ends_with("missing.org")));
+        let direct_path = directory.join("social.org").to_string_lossy().to_string();
+        assert!(crate::util::same_feed_url(&direct_path, &format!("file://{direct_path}")));
+
+        // file:// URLs are percent-decoded, and the final URL is encoded again
+        std::fs::write(directory.join("with space.org"), FEED).unwrap();
+        let spaced = format!("file://{}", directory.join("with%20space.org").to_string_lossy());
+        let outcomes = fetch_feeds_with_errors(vec![(String::new(), spaced)], None, None, None, &NetworkConfig::default()).await;
+        assert!(matches!(&outcomes[0].result, Ok(FeedResult::Fetched(..))));
+        assert!(outcomes[0].final_url.ends_with("/with%20space.org"));
+        let plain = directory.join("with space.org").to_string_lossy().to_string();
+        assert!(crate::util::same_feed_url(&plain, &outcomes[0].final_url));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_redirects() {
+        let base = stub_server(|path| match path {
+            "/old.org" => http_response("301 Moved Permanently", &[("Location", "/older.org")], b""),
+            "/older.org" => http_response("308 Permanent Redirect", &[("Location", "/social.org")], b""),
+            "/temporary.org" => http_response("302 Found", &[("Location", "/social.org")], b""),
+            "/loop.org" => http_response("301 Moved Permanently", &[("Location", "/loop.org")], b""),
+            _ => http_response("200 OK", &[], FEED.as_bytes()),
+        })
+        .await;
+
+        let urls = ["old", "temporary", "loop"].map(|name| (name.to_string(), format!("{base}/{name}.org")));
+        let config = NetworkConfig { max_redirects: 3, ..Default::default() };
+        let outcomes = fetch_feeds_with_errors(urls.to_vec(), Some(Duration::from_secs(5)), None, None, &config).await;
+        let new_url = format!("{base}/social.org");
+
+        assert_eq!(outcomes[0].url, format!("{base}/old.org"));
+        assert_eq!(outcomes[0].final_url, new_url);
+        assert_eq!(outcomes[0].warnings, vec![FetchWarning::MovedPermanently { to: new_url.clone() }]);
+        let Ok(FeedResult::Fetched(profile, posts)) = &outcomes[0].result else {
+            panic!("expected a parsed feed");
+        };
+        assert_eq!(profile.source(), Some(&new_url));
+        assert_eq!(posts[0].source().as_deref(), Some(new_url.as_str()));
+
+        // A temporary redirect is followed, but the feed didn't move
+        let temporary_url = format!("{base}/temporary.org");
+        assert_eq!(outcomes[1].final_url, temporary_url);
+        assert!(outcomes[1].warnings.is_empty());
+        let Ok(FeedResult::Fetched(profile, posts)) = &outcomes[1].result else {
+            panic!("expected a parsed feed");
+        };
+        assert_eq!(profile.source(), Some(&temporary_url));
+        assert_eq!(posts[0].source().as_deref(), Some(temporary_url.as_str()));
+
+        assert!(matches!(&outcomes[2].result, Err(NetworkError::TooManyRedirects { limit: 3, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_feed_and_profile() {
+        let base = stub_server(|path| match path {
+            "/social.org" => http_response("200 OK", &[("ETag", "\"v1\"")], FEED.as_bytes()),
+            "/moved.org" => http_response("301 Moved Permanently", &[("Location", "/social.org")], b""),
+            "/portal.org" => http_response("200 OK", &[], b"<html><body>Log in</body></html>"),
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let options = FetchOptions { per_feed_timeout: Some(Duration::from_secs(5)), ..Default::default() };
+
+        let feed = fetch_feed(&format!("{base}/moved.org"), &options).await.unwrap();
+        assert_eq!(feed.profile.nick(), "alice");
+        assert_eq!(feed.posts.len(), 1);
+        assert_eq!(feed.final_url, format!("{base}/social.org"));
+        assert_eq!(feed.validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(feed.warnings, vec![FetchWarning::MovedPermanently { to: feed.final_url.clone() }]);
+
+        let profile = fetch_profile(&format!("{base}/social.org"), &options).await.unwrap();
+        assert_eq!(profile.nick(), "alice");
+        assert_eq!(profile.source(), Some(&feed.final_url));
+
+        assert!(matches!(fetch_feed(&format!("{base}/portal.org"), &options).await, Err(NetworkError::Parse { .. })));
+        assert!(matches!(fetch_profile(&format!("{base}/gone.org"), &options).await, Err(NetworkError::Http { status: 404, .. })));
+
+        let path = std::env::temp_dir().join(format!("org-social-fetch-feed-test-{}.org", std::process::id()));
+        std::fs::write(&path, FEED).unwrap();
+        let local = fetch_feed(&path.to_string_lossy(), &options).await.unwrap();
+        assert_eq!(local.posts.len(), 1);
+        assert!(local.validators.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn publish_handler(path: &str, request: &str) -> Vec<u8> {
+        let method = request.split_whitespace().next().unwrap_or_default();
+        let headers = request.to_lowercase();
+        match (method, path) {
+            ("PUT", "/social.org") if !headers.contains("authorization: basic ywxpy2u6c2vjcmv0") => {
+                http_response("401 Unauthorized", &[], b"")
+            }
+            ("PUT", "/social.org") if headers.contains("if-match: \"v1\"") && request.trim_end().ends_with("Hello") => {
+                http_response("204 No Content", &[("ETag", "\"v2\"")], b"")
+            }
+            ("PUT", "/social.org") => http_response("412 Precondition Failed", &[], b""),
+            ("PUT", "/dav/social.org") if headers.contains("authorization: bearer token") => http_response("201 Created", &[], b""),
+            ("PROPFIND", "/dav/social.org") if headers.contains("depth: 0") => http_response(
+                "207 Multi-Status",
+                &[("Content-Type", "application/xml")],
+                b"<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\"><d:response><d:propstat><d:prop><d:getetag>&quot;dav1&quot;</d:getetag></d:prop></d:propstat></d:response></d:multistatus>",
+            ),
+            _ => http_response("405 Method Not Allowed", &[], b""),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        let base = stub_server_with_request(publish_handler).await;
+        let auth = PublishAuth::Basic { username: "alice".to_string(), password: Some("secret".to_string()) };
+        let target = PublishTarget::HttpPut { url: format!("{base}/social.org"), auth };
+        let options = PublishOptions { if_match: Some("\"v1\"".to_string()), timeout: Some(Duration::from_secs(5)), ..Default::default() };
+
+        let receipt = publish(FEED, &target, &options).await.unwrap();
+        assert_eq!(receipt, PublishReceipt { etag: Some("\"v2\"".to_string()), created: false });
+
+        // Someone else changed the file since it was fetched
+        let stale = PublishOptions { if_match: Some("\"v0\"".to_string()), ..options.clone() };
+        let error = publish(FEED, &target, &stale).await.unwrap_err();
+        assert!(matches!(&error, NetworkError::Conflict { url } if url.ends_with("/social.org")));
+
+        let unauthorized = PublishTarget::HttpPut { url: format!("{base}/social.org"), auth: PublishAuth::None };
+        assert!(matches!(publish(FEED, &unauthorized, &options).await, Err(NetworkError::Http { status: 401, .. })));
+
+        let (profile, posts) = parse_file(FEED_WITH_ID, None);
+        let receipt = publish_file(&profile, &posts, &target, &options).await.unwrap();
+        assert_eq!(receipt.etag.as_deref(), Some("\"v2\""));
+
+        let dav = PublishTarget::WebDav { url: format!("{base}/dav/social.org"), auth: PublishAuth::Bearer("token".to_string()) };
+        let receipt = publish(FEED, &dav, &PublishOptions::default()).await.unwrap();
+        assert_eq!(receipt, PublishReceipt { etag: Some("\"dav1\"".to_string()), created: true });
+        assert_eq!(format!("{:?}", dav.auth()), "Bearer(..)");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_fetches() {
+        // The stub server needs a runtime of its own, the blocking functions must be called outside of one
+        let server_runtime = tokio::runtime::Runtime::new().unwrap();
+        let base = server_runtime.block_on(stub_server(|path| match path {
+            "/social.org" => http_response("200 OK", &[], FEED.as_bytes()),
+            _ => http_response("404 Not Found", &[], b""),
+        }));
+
+        let feeds = blocking::get_feeds(feed_url(&base), Some(Duration::from_secs(5)));
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].0.nick(), "alice");
+
+        let options = FetchOptions { per_feed_timeout: Some(Duration::from_secs(5)), ..Default::default() };
+        let feed = blocking::fetch_feed(&format!("{base}/social.org"), &options).unwrap();
+        assert_eq!(feed.posts.len(), 1);
+        assert_eq!(blocking::fetch_profile(&format!("{base}/social.org"), &options).unwrap().nick(), "alice");
+        assert!(matches!(blocking::fetch_feed(&format!("{base}/gone.org"), &options), Err(NetworkError::Http { status: 404, .. })));
+    }
+
+    #[allow(deprecated)]
+    #[test]
+    fn test_get_feeds_outside_tokio() {
+        // Local feeds don't need a runtime at all
+        let path = std::env::temp_dir().join(format!("org-social-executor-test-{}.org", std::process::id()));
+        std::fs::write(&path, FEED).unwrap();
+        let local = vec![("alice".to_string(), path.to_string_lossy().to_string())];
+        assert_eq!(futures_executor::block_on(get_feeds(local.clone(), None)).len(), 1);
+        #[cfg(feature = "futures-timer")]
+        assert_eq!(futures_executor::block_on(get_feeds(local, Some(Duration::from_secs(5)))).len(), 1);
+        std::fs::remove_file(&path).unwrap();
+
+        // HTTP requests need a Tokio reactor for reqwest, but the fetches aren't spawned on it
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let base = runtime.block_on(stub_server(|_| http_response("200 OK", &[], FEED.as_bytes())));
+        let _guard = runtime.enter();
+        let feeds = futures_executor::block_on(get_feeds(feed_url(&base), Some(Duration::from_secs(5))));
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].0.nick(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_feed_watcher() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let version = Arc::new(AtomicUsize::new(0));
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let (current, counter) = (version.clone(), not_modified.clone());
+        let base = stub_server_with_request(move |path, request| {
+            let version = current.load(Ordering::SeqCst);
+            let etag = format!("\"v{version}\"");
+            match path {
+                "/social.org" if request.to_lowercase().contains(&format!("if-none-match: {etag}")) => {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    http_response("304 Not Modified", &[], b"")
+                }
+                "/social.org" if version == 0 => http_response("200 OK", &[("ETag", &etag)], FEED.as_bytes()),
+                "/social.org" => {
+                    let feed = FEED.replace("#+NICK: alice", "#+NICK: alice\n#+TITLE: Alice's feed")
+                        + "\n**\n:PROPERTIES:\n:ID: 2025-01-02T12:00:00+0000\n:END:\n\nSecond post\n";
+                    http_response("200 OK", &[("ETag", &etag)], feed.as_bytes())
+                }
+                _ => http_response("404 Not Found", &[], b""),
+            }
+        })
+        .await;
+
+        let options = WatchOptions { interval: Duration::from_millis(50), jitter: Duration::ZERO, ..Default::default() };
+        let follows = vec![("alice".to_string(), format!("{base}/social.org")), ("bob".to_string(), format!("{base}/gone.org"))];
+        let mut watcher = FeedWatcher::new(follows, options);
+        async fn next(watcher: &mut FeedWatcher) -> FeedChange {
+            tokio::time::timeout(Duration::from_secs(5), watcher.next_change()).await.unwrap().unwrap()
+        }
+
+        // The first content of a feed isn't a change, a feed failing is
+        let change = next(&mut watcher).await;
+        assert!(change.url.ends_with("/gone.org"));
+        assert!(matches!(change.kind, FeedChangeKind::Unreachable(NetworkError::Http { status: 404, .. })));
+
+        // Wait for the unchanged feed to be skipped with a 304
+        while not_modified.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        version.store(1, Ordering::SeqCst);
+        let change = next(&mut watcher).await;
+        assert!(change.url.ends_with("/social.org"));
+        let FeedChangeKind::NewPosts(posts) = change.kind else {
+            panic!("expected new posts, got {:?}", change.kind);
+        };
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].id(), "2025-01-02T12:00:00+0000");
+        assert!(matches!(next(&mut watcher).await.kind, FeedChangeKind::ProfileChanged(diff) if diff.changes.len() == 1));
+
+        // The failing feed isn't reported again
+        watcher.set_follows(vec![("alice".to_string(), format!("{base}/social.org"))]);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        watcher.shutdown().await;
+        assert!(watcher.next_change().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_head_precheck() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let base = stub_server_with_request(move |path, request| {
+            let method = request.split_whitespace().next().unwrap_or_default().to_string();
+            log.lock().unwrap().push(format!("{method} {path}"));
+            // The liar answers HEAD requests with a stale date
+            let last_modified =
+                if path == "/liar.org" && method == "HEAD" { "Mon, 01 Jan 2024 12:00:00 GMT" } else { "Wed, 01 Jan 2025 12:00:00 GMT" };
+            if method == "HEAD" {
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nLast-Modified: {last_modified}\r\nConnection: close\r\n\r\n",
+                    FEED.len()
+                );
+                return head.into_bytes();
+            }
+            http_response("200 OK", &[("Last-Modified", last_modified)], FEED.as_bytes())
+        })
+        .await;
+        let config = NetworkConfig { head_precheck: true, ..NetworkConfig::default() };
+        let cache: Arc<dyn FeedCache> = Arc::new(MemoryFeedCache::new());
+        let follows = vec![("honest".to_string(), format!("{base}/honest.org")), ("liar".to_string(), format!("{base}/liar.org"))];
+
+        for _ in 0..3 {
+            let outcomes = fetch_feeds_with_errors(follows.clone(), None, Some(cache.clone()), None, &config).await;
+            assert!(outcomes.iter().all(|outcome| outcome.result.is_ok()));
+        }
+        let requests = requests.lock().unwrap().clone();
+        let requests_for = |path: &str| requests.iter().filter(|request| request.ends_with(path)).cloned().collect::<Vec<_>>();
+        assert_eq!(requests_for("/honest.org"), ["GET /honest.org", "HEAD /honest.org", "HEAD /honest.org"]);
+        // Once the HEAD answer didn't match the GET, the feed is always downloaded
+        assert_eq!(requests_for("/liar.org"), ["GET /liar.org", "HEAD /liar.org", "GET /liar.org", "GET /liar.org"]);
+        assert!(cache.get(&format!("{base}/liar.org")).unwrap().head_unreliable);
+        assert!(!cache.get(&format!("{base}/honest.org")).unwrap().head_unreliable);
+
+        let previous = Validators {
+            last_modified: Some("Wed, 01 Jan 2025 12:00:00 GMT".to_string()),
+            content_length: Some(FEED.len() as u64),
+            ..Validators::default()
+        };
+        let url = format!("{base}/honest.org");
+        assert_eq!(check_modified(&config, &url, &previous).await.unwrap(), ChangeHint::ProbablyUnchanged);
+        let shorter = Validators { content_length: Some(1), ..previous.clone() };
+        assert_eq!(check_modified(&config, &url, &shorter).await.unwrap(), ChangeHint::Changed);
+        assert_eq!(check_modified(&config, &url, &Validators::default()).await.unwrap(), ChangeHint::Unknown);
+        let length_only = Validators { last_modified: None, ..previous };
+        assert_eq!(check_modified(&config, &url, &length_only).await.unwrap(), ChangeHint::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_proxies() {
+        // A proxy gets the full URL in the request line
+        let proxy = stub_server(|target| match target {
+            "http://feeds.invalid/social.org" => http_response("200 OK", &[], FEED.as_bytes()),
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let network = NetworkConfig { http_proxy: Some(proxy), ..NetworkConfig::default() };
+        let options = FetchOptions { per_feed_timeout: Some(Duration::from_secs(5)), network, ..Default::default() };
+        let feed = fetch_feed("http://feeds.invalid/social.org", &options).await.unwrap();
+        assert_eq!(feed.profile.nick(), "alice");
+
+        // Setting up the tunnel through the proxy counts toward the timeout
+        let slow_proxy = delayed_stub_server(|_| Duration::from_secs(2), |_, _| http_response("200 OK", &[], b"")).await;
+        let network = NetworkConfig { https_proxy: Some(slow_proxy), ..NetworkConfig::default() };
+        let options = FetchOptions { per_feed_timeout: Some(Duration::from_millis(100)), network, ..Default::default() };
+        let result = fetch_feed("https://feeds.invalid/social.org", &options).await;
+        assert!(matches!(result, Err(NetworkError::Timeout { .. })), "{result:?}");
+
+        let network = NetworkConfig { socks5: Some("socks5://[::1".to_string()), ..NetworkConfig::default() };
+        assert!(matches!(network.client(), Err(NetworkError::InvalidProxy { .. })));
+        let options = FetchOptions { network, ..Default::default() };
+        assert!(matches!(fetch_feed("http://feeds.invalid/social.org", &options).await, Err(NetworkError::InvalidProxy { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let base = stub_server(move |_| {
+            log.lock().unwrap().push(std::time::Instant::now());
+            http_response("200 OK", &[], FEED.as_bytes())
+        })
+        .await;
+        let config = NetworkConfig { rate_limit: Some(RateLimiter::new(1.0, 1)), ..NetworkConfig::default() };
+        let follows = (0..5).map(|index| (format!("user{index}"), format!("{base}/{index}.org"))).collect();
+
+        let outcomes = fetch_feeds_with_errors(follows, Some(Duration::from_secs(1)), None, None, &config).await;
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_ok()));
+        let mut requests = requests.lock().unwrap().clone();
+        requests.sort();
+        assert_eq!(requests.len(), 5);
+        for pair in requests.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(900), "{:?}", pair[1] - pair[0]);
+        }
+        // Waiting for the limit doesn't count toward the timeout, and is reported
+        let mut queued: Vec<_> = outcomes.iter().map(|outcome| outcome.queued).collect();
+        queued.sort();
+        assert_eq!(queued[0], Duration::ZERO);
+        assert!(queued[4] >= Duration::from_millis(3900));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_each_request() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let base = stub_server(move |_| {
+            let mut requests = log.lock().unwrap();
+            requests.push(std::time::Instant::now());
+            match requests.len() {
+                1 => http_response("503 Service Unavailable", &[], b""),
+                _ => http_response("200 OK", &[], FEED.as_bytes()),
+            }
+        })
+        .await;
+        let options = FetchOptions {
+            per_feed_timeout: Some(Duration::from_secs(5)),
+            retry: Some(quick_retry(2)),
+            network: NetworkConfig { rate_limit: Some(RateLimiter::new(1.0, 1)), ..NetworkConfig::default() },
+            ..Default::default()
+        };
+
+        // The retry waits for a token like the first request
+        let results = get_feeds_detailed(feed_url(&base), &options).await;
+        assert!(results[0].outcome.is_ok());
+        assert_eq!(results[0].attempts, 2);
+        let requests = requests.lock().unwrap().clone();
+        assert!(requests[1] - requests[0] >= Duration::from_millis(900), "{:?}", requests[1] - requests[0]);
+        assert!(results[0].queued >= Duration::from_millis(900));
+        assert!(results[0].elapsed < Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_bytes() {
+        const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let base = stub_server_with_request(|path, request| match path {
+            // A PNG the server says is text
+            "/mislabelled" => http_response("200 OK", &[("Content-Type", "text/plain; charset=utf-8"), ("ETag", "\"png\"")], PNG),
+            "/moved" => {
+                assert!(request.to_lowercase().contains("accept: image/png, image/*"));
+                http_response("302 Found", &[("Location", "/mislabelled")], b"")
+            }
+            "/login" => http_response("200 OK", &[("Content-Type", "image/png")], b"\n<!DOCTYPE html><html></html>"),
+            "/icon" => http_response("200 OK", &[], b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            "/data" => http_response("200 OK", &[("Content-Type", "Application/Octet-Stream")], b"\x01\x02"),
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let images = FetchBytesOptions {
+            accept: vec!["image/png".to_string(), "image/*".to_string()],
+            timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+
+        let resource = fetch_bytes(&format!("{base}/moved"), &images).await.unwrap();
+        assert_eq!(resource.bytes, PNG);
+        assert_eq!(resource.content_type.as_deref(), Some("image/png"));
+        assert_eq!(resource.final_url, format!("{base}/mislabelled"));
+        assert_eq!(resource.etag.as_deref(), Some("\"png\""));
+
+        let icon = fetch_bytes(&format!("{base}/icon"), &images).await.unwrap();
+        assert_eq!(icon.content_type.as_deref(), Some("image/svg+xml"));
+        let result = fetch_bytes(&format!("{base}/login"), &images).await;
+        assert!(matches!(result, Err(NetworkError::UnexpectedType { content_type: Some(ref kind), .. }) if kind == "text/html"));
+        assert!(matches!(fetch_bytes(&format!("{base}/data"), &images).await, Err(NetworkError::UnexpectedType { .. })));
+
+        // Without an accept list, the type reported by the server is used for unknown content
+        let data = fetch_bytes(&format!("{base}/data"), &FetchBytesOptions::default()).await.unwrap();
+        assert_eq!(data.content_type.as_deref(), Some("application/octet-stream"));
+        let small = FetchBytesOptions { max_bytes: 4, ..Default::default() };
+        assert!(matches!(fetch_bytes(&format!("{base}/mislabelled"), &small).await, Err(NetworkError::TooLarge { limit: 4, .. })));
+    }
+
+    #[test]
+    fn test_sniff_svg() {
+        let doctype = b"<!-- icon -->\n<!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \"svg11.dtd\">\n<svg/>";
+        assert_eq!(sniff_content_type(doctype), Some("image/svg+xml"));
+        // A page embedding an SVG isn't one
+        let page = b"<?xml version=\"1.0\"?>\n<page><svg/></page>";
+        assert_eq!(sniff_content_type(page), None);
+        assert_eq!(sniff_content_type(b"<?xml version=\"1.0\""), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_feeds_detailed() {
+        let base = stub_server(|path| match path {
+            "/social.org" => http_response("200 OK", &[], FEED.as_bytes()),
+            "/empty.org" => http_response("200 OK", &[], b"#+NICK: quiet\n"),
+            "/portal.org" => http_response("200 OK", &[], b"<html><body>Log in</body></html>"),
+            _ => http_response("500 Internal Server Error", &[], b""),
+        })
+        .await;
+        let follows = vec![
+            ("alice".to_string(), format!("{base}/social.org")),
+            ("quiet".to_string(), format!("{base}/empty.org")),
+            ("down".to_string(), format!("{base}/down.org")),
+            ("portal".to_string(), format!("{base}/portal.org")),
+            (String::new(), "not a url".to_string()),
+        ];
+        let options = FetchOptions { per_feed_timeout: Some(Duration::from_secs(5)), ..Default::default() };
+
+        let results = get_feeds_detailed(follows.clone(), &options).await;
+        assert_eq!(results.iter().map(|result| result.nick.as_str()).collect::<Vec<_>>(), ["alice", "quiet", "down", "portal", ""]);
+        let (alice, posts) = results[0].outcome.as_ref().unwrap();
+        assert_eq!((alice.nick(), posts.len()), ("alice", 1));
+        // Posting nothing isn't a failure
+        assert!(results[1].outcome.as_ref().is_ok_and(|(_, posts)| posts.is_empty()));
+        assert!(matches!(results[2].outcome, Err(NetworkError::Http { status: 500, .. })));
+        assert!(matches!(results[3].outcome, Err(NetworkError::Parse { .. })));
+        assert!(results[4].outcome.is_err());
+        assert_eq!(results[2].url, format!("{base}/down.org"));
+
+        #[allow(deprecated)]
+        let successes = get_feeds(follows, Some(Duration::from_secs(5))).await;
+        assert_eq!(successes.len(), 2);
+        assert_eq!(successes[0].2, format!("{base}/social.org"));
+    }
+
+    #[tokio::test]
+    async fn test_overall_deadline() {
+        // The slow feed never answers in the time of the test
+        let base = delayed_stub_server(
+            |path| if path == "/slow.org" { Duration::from_secs(3600) } else { Duration::ZERO },
+            |_, _| http_response("200 OK", &[], FEED.as_bytes()),
+        )
+        .await;
+        let follows = vec![("alice".to_string(), format!("{base}/social.org")), ("slow".to_string(), format!("{base}/slow.org"))];
+        let options = FetchOptions {
+            per_feed_timeout: Some(Duration::from_secs(30)),
+            overall_deadline: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+
+        let started = std::time::Instant::now();
+        let results = get_feeds_detailed(follows, &options).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(results[0].outcome.is_ok());
+        assert!(matches!(&results[1].outcome, Err(NetworkError::DeadlineExceeded { url }) if url.ends_with("/slow.org")));
+        assert_eq!(results[1].nick, "slow");
+
+        let result = fetch_feed(&format!("{base}/slow.org"), &options).await;
+        assert!(matches!(result, Err(NetworkError::DeadlineExceeded { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_offline_fallback() {
+        let directory = std::env::temp_dir().join(format!("org-social-raw-store-test-{}", std::process::id()));
+        let (base, server) = abortable_stub_server(|path| match path {
+            "/old.org" => http_response("301 Moved Permanently", &[("Location", "/social.org")], b""),
+            _ => http_response("200 OK", &[("Last-Modified", "Wed, 01 Jan 2025 12:00:00 GMT")], FEED.as_bytes()),
+        })
+        .await;
+        let url = format!("{base}/social.org");
+        let old_url = format!("{base}/old.org");
+        let store = DiskCache::new(&directory, u64::MAX);
+        let options = FetchOptions {
+            per_feed_timeout: Some(Duration::from_secs(5)),
+            raw_store: Some(store.clone()),
+            offline_fallback: true,
+            ..Default::default()
+        };
+
+        let results = get_feeds_detailed(feed_url(&base), &options).await;
+        assert_eq!(results[0].freshness, Freshness::Fresh);
+        let stored = store.get(&url).unwrap();
+        assert_eq!(stored.body, FEED);
+        assert_eq!(stored.validators.last_modified.as_deref(), Some("Wed, 01 Jan 2025 12:00:00 GMT"));
+        let fetched = fetch_feed(&old_url, &options).await.unwrap();
+        assert_eq!(fetched.freshness, Freshness::Fresh);
+        assert_eq!(store.get(&old_url).unwrap().final_url, url);
+
+        // With the server gone, the stored copy is served, with the same source as when it was fetched
+        server.abort();
+        let _ = server.await;
+        let results = get_feeds_detailed(feed_url(&base), &options).await;
+        let (profile, posts) = results[0].outcome.as_ref().unwrap();
+        assert_eq!((profile.nick(), posts.len()), ("alice", 1));
+        assert_eq!(posts[0].source().as_deref(), Some(url.as_str()));
+        assert_eq!(results[0].freshness, Freshness::Stale { fetched_at: stored.fetched_at });
+
+        let results = get_feeds_detailed(vec![("alice".to_string(), old_url.clone())], &options).await;
+        assert_eq!(results[0].outcome.as_ref().unwrap().1[0].source().as_deref(), Some(url.as_str()));
+        let fetched = fetch_feed(&old_url, &options).await.unwrap();
+        assert_eq!((fetched.final_url.as_str(), fetched.posts.len()), (url.as_str(), 1));
+        assert!(matches!(fetched.freshness, Freshness::Stale { .. }));
+        assert_eq!(fetch_profile(&old_url, &options).await.unwrap().source(), Some(&url));
+
+        let online_only = FetchOptions { offline_fallback: false, ..options };
+        let results = get_feeds_detailed(feed_url(&base), &online_only).await;
+        assert!(matches!(results[0].outcome, Err(NetworkError::Transport { .. })));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// Serves feeds of the `dummy` scheme from memory, with the ETag `"v1"`.
+    #[derive(Debug)]
+    struct DummyResolver;
+
+    impl SchemeResolver for DummyResolver {
+        fn resolve<'a>(&'a self, url: &'a str, validators: Option<&'a Validators>, _: &'a NetworkConfig) -> ResolveFuture<'a> {
+            Box::pin(async move {
+                let name = url.strip_prefix("dummy://").unwrap_or(url);
+                if name != "alice" {
+                    return Err(NetworkError::NotFound { url: url.to_string() });
+                }
+                let not_modified = validators.is_some_and(|validators| validators.etag.as_deref() == Some("\"v1\""));
+                let validators = Validators { etag: Some("\"v1\"".to_string()), ..Default::default() };
+                let body = if not_modified { Vec::new() } else { FEED_WITH_ID.as_bytes().to_vec() };
+                Ok(Resolved { validators, not_modified, ..Resolved::new(url, body) })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheme_resolvers() {
+        let url = "dummy://alice";
+        let unsupported = fetch_feed(url, &FetchOptions::default()).await.unwrap_err();
+        assert!(matches!(unsupported, NetworkError::UnsupportedScheme { scheme, .. } if scheme == "dummy"));
+
+        let mut network = NetworkConfig::default();
+        network.register_scheme("Dummy", Arc::new(DummyResolver));
+        let options = FetchOptions { network: network.clone(), ..Default::default() };
+        let feed = fetch_feed(url, &options).await.unwrap();
+        assert_eq!(feed.profile.nick(), "alice");
+        assert_eq!(feed.posts[0].full_id(), "dummy://alice#2025-01-01T12:00:00+0000");
+        assert_eq!(feed.validators.etag.as_deref(), Some("\"v1\""));
+        assert!(matches!(fetch_feed("dummy://bob", &options).await, Err(NetworkError::NotFound { .. })));
+
+        // Conditional reads and caching work like for HTTP feeds
+        let outcome = fetch_conditional_with_config(url, Some(&feed.validators), None, &network).await.unwrap();
+        assert!(matches!(outcome, FetchOutcome::NotModified));
+        let cache: Arc<dyn FeedCache> = Arc::new(MemoryFeedCache::new());
+        let follows = vec![("alice".to_string(), url.to_string())];
+        let first = fetch_feeds_with_errors(follows.clone(), None, Some(cache.clone()), None, &network).await;
+        assert!(matches!(&first[0].result, Ok(FeedResult::Fetched(_, posts)) if posts.len() == 1));
+        let second = fetch_feeds_with_errors(follows, None, Some(cache), None, &network).await;
+        assert!(matches!(second[0].result, Ok(FeedResult::NotModified)));
+
+        // Registered resolvers replace the built-in ones
+        network.register_scheme("https", Arc::new(DummyResolver));
+        let options = FetchOptions { network, ..Default::default() };
+        assert!(matches!(fetch_feed("https://example.com/social.org", &options).await, Err(NetworkError::NotFound { .. })));
+    }
+
+    /// A feed with one post a day from 2025-01-01, in order or newest first.
+    fn generated_feed(days: i64, newest_first: bool) -> String {
+        let start = DateTime::parse_from_rfc3339("2025-01-01T12:00:00+00:00").unwrap();
+        let mut days: Vec<_> = (0..days).collect();
+        if newest_first {
+            days.reverse();
+        }
+        let mut feed = "#+NICK: alice\n\n* Posts\n".to_string();
+        for day in days {
+            let id = (start + chrono::Duration::days(day)).format("%Y-%m-%dT%H:%M:%S%z");
+            feed.push_str(&format!("**\n:PROPERTIES:\n:ID: {id}\n:END:\n\nPost of day {day}, with some text to make it longer.\n\n"));
+        }
+        feed
+    }
+
+    #[tokio::test]
+    async fn test_streaming_since_cutoff() {
+        let newest_first = generated_feed(5000, true);
+        let oldest_first = generated_feed(5000, false);
+        let base = stub_server(move |path| match path {
+            "/newest.org" => http_response("200 OK", &[], newest_first.as_bytes()),
+            _ => http_response("200 OK", &[], oldest_first.as_bytes()),
+        })
+        .await;
+        let follows = vec![
+            ("newest".to_string(), format!("{base}/newest.org")),
+            ("oldest".to_string(), format!("{base}/oldest.org")),
+        ];
+        // The last 10 days
+        let since = DateTime::parse_from_rfc3339("2025-01-01T12:00:00+00:00").unwrap() + chrono::Duration::days(4990);
+        let options = FetchOptions { per_feed_timeout: Some(Duration::from_secs(5)), since: Some(since), ..Default::default() };
+
+        let results = get_feeds_detailed(follows, &options).await;
+        for result in &results {
+            let (profile, posts) = result.outcome.as_ref().unwrap();
+            assert_eq!((profile.nick(), posts.len()), ("alice", 10), "{}", result.url);
+            assert!(posts.iter().all(|post| post.time().unwrap() >= since));
+        }
+        // Only the newest first feed can stop at the cutoff
+        assert!(results[0].early_stop);
+        assert!(!results[1].early_stop);
+
+        let results = get_feeds_detailed(feed_url(&base), &FetchOptions { since: None, ..options }).await;
+        assert_eq!(results[0].outcome.as_ref().unwrap().1.len(), 5000);
+        assert!(!results[0].early_stop);
+    }
+
+    #[tokio::test]
+    async fn test_parse_feed_stream_chunks() {
+        let feed = "#+NICK: zoë\n\n* Posts\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nCafé\r\n";
+        // One byte per chunk, splitting the multi-byte characters and the line endings
+        let chunks: Vec<_> = feed.bytes().map(|byte| Ok(vec![byte])).collect();
+        let body: BodyStream = Box::pin(futures_util::stream::iter(chunks));
+        let streamed = parse_feed_stream(body, Some("text/plain; charset=utf-8"), "https://example.com/social.org", "https://example.com/social.org", 1024, None, true)
+            .await
+            .unwrap();
+        assert_eq!(streamed.profile.nick(), "zoë");
+        assert_eq!(streamed.posts.len(), 1);
+        assert_eq!(streamed.posts[0].content(), "Café");
+        assert_eq!(streamed.content.as_deref(), Some(feed));
+        assert_eq!(streamed.body_hash, fnv1a_hash(feed.as_bytes()));
+        assert!(streamed.warnings.is_empty());
+
+        // A byte order mark split between chunks is removed
+        let chunks: Vec<_> = format!("\u{FEFF}{feed}").bytes().map(|byte| Ok(vec![byte])).collect();
+        let body: BodyStream = Box::pin(futures_util::stream::iter(chunks));
+        let streamed = parse_feed_stream(body, None, "https://example.com/social.org", "https://example.com/social.org", 1024, None, true)
+            .await
+            .unwrap();
+        assert_eq!(streamed.content.as_deref(), Some(feed));
+
+        let html: BodyStream = single_chunk(b"<!DOCTYPE html><html></html>".to_vec());
+        let error = parse_feed_stream(html, None, "https://example.com/", "https://example.com/", 1024, None, false).await.err();
+        assert!(matches!(error, Some(NetworkError::Parse { .. })));
+
+        let large: BodyStream = single_chunk(feed.as_bytes().to_vec());
+        let error = parse_feed_stream(large, None, "https://example.com/", "https://example.com/", 16, None, false).await.err();
+        assert!(matches!(error, Some(NetworkError::TooLarge { limit: 16, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_network_handle() {
+        // A configuration from a handle keeps its transport
+        let transport = Arc::new(MockTransport::new());
+        transport.respond("https://example.com/social.org", MockResponse::ok(FEED));
+        let handle = NetworkHandle::new(NetworkConfig { transport: Some(transport.clone()), ..Default::default() });
+        let config = NetworkConfig::from(handle.clone());
+        let options = FetchOptions { network: config, ..Default::default() };
+        let fetched = fetch_feed("https://example.com/social.org", &options).await.unwrap();
+        assert_eq!(fetched.profile.nick(), "alice");
+        assert_eq!(transport.request_count("https://example.com/social.org"), 1);
+
+        // Without a transport, one is created once for the client
+        let handle = NetworkHandle::new(NetworkConfig::default());
+        let first = handle.config().transport.clone().unwrap();
+        assert!(Arc::ptr_eq(&first, handle.clone().config().transport.as_ref().unwrap()));
+        let shared = NetworkHandle::shared().config().transport.clone().unwrap();
+        assert!(Arc::ptr_eq(&shared, NetworkHandle::shared().config().transport.as_ref().unwrap()));
+
+        // The requests of an invalid proxy still fail with its error
+        let handle = NetworkHandle::new(NetworkConfig { http_proxy: Some("not a proxy".to_string()), ..Default::default() });
+        assert!(handle.config().transport.is_none());
+        let options = FetchOptions { network: handle.into(), ..Default::default() };
+        let error = fetch_feed("http://example.com/social.org", &options).await.unwrap_err();
+        assert!(matches!(error, NetworkError::InvalidProxy { .. }));
+    }
 }
@@ -6,6 +6,7 @@
 
 use crate::util;
 use crate::post::Post;
+use crate::profile::Profile;
 
 /// State of a new post being constructed.
 #[derive(Debug, Clone)]
@@ -13,12 +14,20 @@ pub struct NewPostState {
     pub lang: String,
     pub tags: Vec<String>,
     pub mood: String,
+    pub media: Vec<String>,
 
     pub content: String,
 
     pub reply_to: Option<String>,
     pub poll_end: Option<String>,
     pub poll_option: Option<String>,
+    pub reaction: Option<String>,
+}
+
+impl Default for NewPostState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl NewPostState {
@@ -27,10 +36,12 @@ impl NewPostState {
             lang: String::new(),
             tags: Vec::new(),
             mood: String::new(),
+            media: Vec::new(),
             content: String::new(),
             reply_to: None,
             poll_end: None,
             poll_option: None,
+            reaction: None,
         }
     }
 
@@ -39,13 +50,19 @@ impl NewPostState {
             lang: String::new(),
             tags: initial_tags.unwrap_or_default(),
             mood: String::new(),
+            media: Vec::new(),
             content: String::new(),
             reply_to: Some(reply_to),
             poll_end: None,
             poll_option: None,
+            reaction: None,
         }
     }
 
+    /// Reply to a post, using its `full_id` as the reply target.
+    ///
+    /// Posts without a source (e.g. from the user's own file) only have a bare ID, which doesn't
+    /// say whose post it is. Use `reply_to_profile_post` with the owner's profile for those.
     pub fn reply_to_post(target_post: Post) -> Self {
         Self::reply(
             target_post.full_id(),
@@ -53,15 +70,30 @@ impl NewPostState {
         )
     }
 
+    /// Reply to a post, using its owner's profile to build the reply target.
+    ///
+    /// Useful for replying to posts loaded from the user's own file, which have no source:
+    /// the target is then built with `Profile::full_id_for`.
+    pub fn reply_to_profile_post(target_post: Post, owner: &Profile) -> Self {
+        let reply_to = if target_post.source().is_none() {
+            owner.full_id_for(&target_post).unwrap_or_else(|| target_post.full_id())
+        } else {
+            target_post.full_id()
+        };
+        Self::reply(reply_to, target_post.tags().clone())
+    }
+
     pub fn vote(reply_to: String, initial_tags: Option<Vec<String>>, poll_option: String) -> Self {
         Self {
             lang: String::new(),
             tags: initial_tags.unwrap_or_default(),
             mood: String::new(),
+            media: Vec::new(),
             content: String::new(),
             reply_to: Some(reply_to),
             poll_end: None,
             poll_option: Some(poll_option),
+            reaction: None,
         }
     }
 
@@ -73,6 +105,17 @@ impl NewPostState {
         )
     }
 
+    pub fn reaction(reply_to: String, emoji: String) -> Self {
+        Self {
+            reaction: Some(emoji),
+            ..Self::reply(reply_to, None)
+        }
+    }
+
+    pub fn reaction_on_post(target_post: Post, emoji: String) -> Self {
+        Self::reaction(target_post.full_id(), emoji)
+    }
+
     pub fn is_empty(&self) -> bool {
         !self.content.trim().is_empty()
     }
@@ -81,6 +124,10 @@ impl NewPostState {
         self.reply_to.is_some()
     }
 
+    pub fn is_reaction(&self) -> bool {
+        self.reaction.is_some()
+    }
+
     pub fn is_vote(&self) -> bool {
         self.poll_option.is_some()
     }
@@ -98,9 +145,11 @@ impl NewPostState {
         post.set_lang(if self.lang.is_empty() { None } else { Some(self.lang.clone()) });
         post.set_tags(if self.tags.is_empty() { None } else { Some(self.tags.clone()) });
         post.set_mood(if self.mood.is_empty() { None } else { Some(self.mood.clone()) });
+        post.set_media(self.media.clone());
         post.set_reply_to(self.reply_to.clone());
         post.set_poll_end(self.poll_end.clone());
         post.set_poll_option(self.poll_option.clone());
+        post.set_reaction(self.reaction.clone());
         post.set_client(Some(client_name.to_string()));
 
         post
@@ -9,7 +9,7 @@ use crate::profile::Profile;
 use crate::post::Post;
 use crate::tokenizer::Token;
 use chrono::{DateTime, FixedOffset};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// Types of notifications that can occur
 #[derive(Debug, Clone, PartialEq)]
@@ -38,6 +38,71 @@ impl Notification {
     }
 }
 
+/// Index of the mentions in a set of posts, to find who mentioned whom without rescanning every post.
+///
+/// Maps the URLs and usernames of mention links to the full IDs of the posts mentioning them,
+/// matching them the same way as `NotificationFeed::create_notification_feed`.
+/// Build one with `Feed::build_mention_index`.
+#[derive(Debug, Clone, Default)]
+pub struct MentionIndex {
+    by_url: HashMap<String, Vec<String>>,
+    by_username: HashMap<String, Vec<String>>,
+    /// Posts also writing a mentioned username as plain `@username`, which notify anyone
+    plain_mentions: Vec<String>,
+    indexed_posts: usize,
+}
+
+impl MentionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the mentions of a post to the index.
+    pub fn add_post(&mut self, post: &Post) {
+        let full_id = post.full_id();
+        let mut plain_mention = false;
+        for token in post.tokens() {
+            if let Token::Mention { url, username } = token {
+                for posts in [self.by_url.entry(url.clone()).or_default(), self.by_username.entry(username.clone()).or_default()] {
+                    if !posts.contains(&full_id) {
+                        posts.push(full_id.clone());
+                    }
+                }
+                plain_mention |= post.content().contains(&format!("@{username}"));
+            }
+        }
+        if plain_mention {
+            self.plain_mentions.push(full_id);
+        }
+        self.indexed_posts += 1;
+    }
+
+    /// Full IDs of the posts mentioning a profile, by its source URL or its nick.
+    pub fn posts_mentioning_profile(&self, profile: &Profile) -> Vec<&str> {
+        let nick = profile.nick();
+        let by_url = profile.source().and_then(|source| self.by_url.get(source));
+        // Sometimes, users are mentioned with an "@" prefix
+        let by_username = self.by_username.get(nick);
+        let by_prefixed_username = nick.strip_prefix('@').and_then(|username| self.by_username.get(username));
+
+        let mut seen = HashSet::new();
+        by_url
+            .into_iter()
+            .chain(by_username)
+            .chain(by_prefixed_username)
+            .flatten()
+            .chain(&self.plain_mentions)
+            .map(String::as_str)
+            .filter(|full_id| seen.insert(*full_id))
+            .collect()
+    }
+
+    /// Number of posts added to the index.
+    pub fn indexed_posts(&self) -> usize {
+        self.indexed_posts
+    }
+}
+
 /// Represents a collection of notifications for a user
 pub struct NotificationFeed {
     pub notifications: Vec<Notification>,
@@ -51,6 +116,8 @@ impl NotificationFeed {
     /// 2. Posts that reply to the user's posts (by checking reply_to field)
     /// 3. Deduplicates posts that both mention and reply
     ///
+    /// Posts from feeds or authors muted in the user's profile are ignored.
+    ///
     /// # Arguments
     ///
     /// * `user_profile` - The user's profile containing their information
@@ -65,6 +132,21 @@ impl NotificationFeed {
         user_posts: &[Post],
         all_posts: Vec<Post>,
     ) -> NotificationFeed {
+        Self::create_notification_feed_with_index(user_profile, user_posts, all_posts, None)
+    }
+
+    /// Create a notification feed, see [`NotificationFeed::create_notification_feed`].
+    ///
+    /// With a prebuilt `mention_index` covering `all_posts`, mentions are looked up
+    /// in the index instead of scanning every post again.
+    pub fn create_notification_feed_with_index(
+        user_profile: &Profile,
+        user_posts: &[Post],
+        all_posts: Vec<Post>,
+        mention_index: Option<&MentionIndex>,
+    ) -> NotificationFeed {
+        let mentioning: Option<HashSet<&str>> =
+            mention_index.map(|index| index.posts_mentioning_profile(user_profile).into_iter().collect());
         let mut notifications = Vec::new();
         let mut processed_post_ids = HashSet::new();
 
@@ -74,12 +156,20 @@ impl NotificationFeed {
                 continue;
             }
 
+            // Skip posts from muted feeds and authors
+            if user_profile.is_muted(&post) {
+                continue;
+            }
+
             // Skip if we've already processed this post
             if processed_post_ids.contains(post.id()) {
                 continue;
             }
 
-            let is_mention = is_mention_to_user(&post, user_profile);
+            let is_mention = match &mentioning {
+                Some(mentioning) => mentioning.contains(post.full_id().as_str()),
+                None => is_mention_to_user(&post, user_profile),
+            };
             let is_reply = is_reply_to_user(&post, user_posts);
 
             let notification_type = match (is_mention, is_reply) {
@@ -96,14 +186,7 @@ impl NotificationFeed {
         }
 
         // Sort notifications chronologically (newest first)
-        notifications.sort_by(|a, b| {
-            match (a.post.time(), b.post.time()) {
-                (Some(time_a), Some(time_b)) => time_b.cmp(&time_a), // Reverse order for newest first
-                (Some(_), None) => std::cmp::Ordering::Less,         // Posts with time come before posts without
-                (None, Some(_)) => std::cmp::Ordering::Greater,      // Posts without time come after posts with time
-                (None, None) => std::cmp::Ordering::Equal,           // Equal if both don't have time
-            }
-        });
+        notifications.sort_by(|a, b| a.post.cmp_newest_first(&b.post));
 
         NotificationFeed { notifications }
     }
@@ -355,4 +438,18 @@ mod tests {
         assert_eq!(notification_feed.len(), 1);
         assert_eq!(notification_feed.notifications[0].notification_type, NotificationType::MentionAndReply);
     }
+
+    #[test]
+    fn test_muted_authors_are_ignored() {
+        let user_profile = Profile::from(vec!["#+NICK: testuser".to_string(), "#+MUTE: troll".to_string()]);
+
+        let mut mention_post = Post::new("mention_1".to_string(),
+            "Hello [[org-social:https://example.com/social.org][testuser]]!".to_string());
+        mention_post.set_author("troll".to_string());
+        mention_post.parse_content();
+
+        let notification_feed = NotificationFeed::create_notification_feed(&user_profile, &[], vec![mention_post]);
+        assert!(notification_feed.is_empty());
+    }
+
 }
@@ -17,26 +17,12 @@ pub use crate::post::Post;
 /// 
 /// A tuple containing the parsed profile and a vector of posts.
 pub fn parse_file(file_content: &str, source: Option<String>) -> (Profile, Vec<Post>) {
-    let lines = file_content
-        .lines()
-        .map(String::from)
-        .collect::<Vec<String>>();
     let mut posts = Vec::new();
-
-    // Find the start of the posts section
-    let posts_index = lines
-        .iter()
-        .position(|line| line.starts_with("* Posts"))
-        .unwrap_or(lines.len());
-
-    // Parse the profile section (everything before "* Posts")
-    let profile_section_lines = lines.split_at(posts_index).0.to_vec();
-    let mut profile = Profile::from(profile_section_lines);
-    profile.set_source(source.clone());
+    let (profile, posts_section) = split_file(file_content, source.clone());
 
     // Parse the posts section if it exists
-    if posts_index < lines.len() {
-        let posts_section_lines = lines.split_at(posts_index + 1).1.to_vec();
+    if let Some(posts_section) = posts_section {
+        let posts_section_lines = posts_section.map(String::from).collect::<Vec<String>>();
 
         // Find all post start indices (lines beginning with "**")
         let mut post_indices = Vec::new();
@@ -66,6 +52,262 @@ pub fn parse_file(file_content: &str, source: Option<String>) -> (Profile, Vec<P
     (profile, posts)
 }
 
+/// Parse the profile section of an org-social file (everything before `* Posts`),
+/// returning the lines of the posts section, if there's one, unread.
+fn split_file(file_content: &str, source: Option<String>) -> (Profile, Option<std::str::Lines<'_>>) {
+    let mut lines = file_content.lines();
+    let mut profile_section_lines = Vec::new();
+    let mut posts_section = None;
+    while let Some(line) = lines.next() {
+        if line.starts_with("* Posts") {
+            posts_section = Some(lines);
+            break;
+        }
+        profile_section_lines.push(line.to_string());
+    }
+
+    let mut profile = Profile::from(profile_section_lines);
+    profile.set_source(source);
+    (profile, posts_section)
+}
+
+/// Parses an org-social file as it's read, producing each post once the next one starts.
+///
+/// Gives the same profile and posts as [`parse_file`], without holding the whole file in memory,
+/// e.g. while it's downloaded.
+///
+/// ```
+/// use org_social_lib_rs::parser::StreamingParser;
+///
+/// let mut parser = StreamingParser::new(None);
+/// assert!(parser.push_str("#+NICK: alice\n* Posts\n** \n:PROPERTIES:\n:ID: 2025-01-").is_empty());
+/// assert_eq!(parser.profile().unwrap().nick(), "alice");
+/// let posts = parser.push_str("01T12:00:00+0000\n:END:\n\nHello\n** \n");
+/// assert_eq!(posts[0].content(), "Hello");
+/// ```
+#[derive(Debug, Default)]
+pub struct StreamingParser {
+    source: Option<String>,
+    /// The profile, once the `* Posts` heading was read.
+    profile: Option<Profile>,
+    profile_lines: Vec<String>,
+    post_lines: Vec<String>,
+    /// The end of the text pushed so far, after its last line break.
+    partial_line: String,
+}
+
+impl StreamingParser {
+    /// A parser for a file whose profile and posts are associated with `source`.
+    pub fn new(source: Option<String>) -> Self {
+        Self { source, ..Self::default() }
+    }
+
+    /// Parse the next part of the file, which can end in the middle of a line.
+    ///
+    /// # Returns
+    ///
+    /// The posts completed by it, in file order.
+    pub fn push_str(&mut self, text: &str) -> Vec<Post> {
+        let mut posts = Vec::new();
+        let mut rest = text;
+        while let Some((line, after)) = rest.split_once('\n') {
+            self.partial_line.push_str(line);
+            let line = std::mem::take(&mut self.partial_line);
+            posts.extend(self.push_line(line.strip_suffix('\r').unwrap_or(&line)));
+            rest = after;
+        }
+        self.partial_line.push_str(rest);
+        posts
+    }
+
+    /// Parse the next line of the file, without its line break.
+    ///
+    /// # Returns
+    ///
+    /// The post completed by it, if it starts another one.
+    pub fn push_line(&mut self, line: &str) -> Option<Post> {
+        if self.profile.is_none() {
+            if line.starts_with("* Posts") {
+                let mut profile = Profile::from(std::mem::take(&mut self.profile_lines));
+                profile.set_source(self.source.clone());
+                self.profile = Some(profile);
+            } else {
+                self.profile_lines.push(line.to_string());
+            }
+            return None;
+        }
+
+        // Lines between the heading and the first post aren't part of any post
+        if line.starts_with("**") {
+            let completed = self.take_post();
+            self.post_lines.push(line.to_string());
+            completed
+        } else {
+            if !self.post_lines.is_empty() {
+                self.post_lines.push(line.to_string());
+            }
+            None
+        }
+    }
+
+    /// The profile of the file, once the `* Posts` heading was read.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// End the file.
+    ///
+    /// # Returns
+    ///
+    /// The profile, and the posts not returned yet, in file order. There can be two of them
+    /// when the file ends with a post header without a line break.
+    pub fn finish(mut self) -> (Profile, Vec<Post>) {
+        let mut last_posts = Vec::new();
+        if !self.partial_line.is_empty() {
+            let line = std::mem::take(&mut self.partial_line);
+            last_posts.extend(self.push_line(line.strip_suffix('\r').unwrap_or(&line)));
+        }
+        last_posts.extend(self.take_post());
+        let profile = match self.profile {
+            Some(profile) => profile,
+            None => {
+                let mut profile = Profile::from(self.profile_lines);
+                profile.set_source(self.source);
+                profile
+            }
+        };
+        (profile, last_posts)
+    }
+
+    /// The post read so far, if any.
+    fn take_post(&mut self) -> Option<Post> {
+        if self.post_lines.is_empty() {
+            return None;
+        }
+        let mut post = Post::from(std::mem::take(&mut self.post_lines));
+        post.set_source(self.source.clone());
+        Some(post)
+    }
+}
+
+/// Parse an org-social file from a reader, line by line, see [`StreamingParser`].
+///
+/// # Errors
+///
+/// The error of the reader, or [`std::io::ErrorKind::InvalidData`] if the file isn't valid UTF-8.
+pub fn parse_reader(reader: impl std::io::BufRead, source: Option<String>) -> std::io::Result<(Profile, Vec<Post>)> {
+    let mut parser = StreamingParser::new(source);
+    let mut posts = Vec::new();
+    for line in reader.lines() {
+        posts.extend(parser.push_line(&line?));
+    }
+    let (profile, last_posts) = parser.finish();
+    posts.extend(last_posts);
+    Ok((profile, posts))
+}
+
+/// Parse only the profile of an org-social file, stopping at the `* Posts` section.
+///
+/// # Arguments
+///
+/// * `file_content` - The raw content of the org-social file
+/// * `source` - Optional source identifier to be associated with the profile
+pub fn parse_profile(file_content: &str, source: Option<String>) -> Profile {
+    split_file(file_content, source).0
+}
+
+/// Remove a post from a list of posts by its ID, either the bare ID or the full ID (`url#id`).
+///
+/// Replies in other feeds will no longer find the post - use `tombstone_post`
+/// to keep the ID around as a deletion marker instead.
+///
+/// # Returns
+///
+/// The removed post, or None if no post with the given ID exists.
+pub fn remove_post(posts: &mut Vec<Post>, id: &str) -> Option<Post> {
+    let index = posts.iter().position(|post| has_id(post, id))?;
+    Some(posts.remove(index))
+}
+
+/// Replace a post with a tombstone, keeping its ID, source, author and reply target.
+///
+/// The post is found by its bare or full ID, like in `remove_post`. This is the alternative to `remove_post` that does not break threads.
+///
+/// # Returns
+///
+/// `true` if a post with the given ID was found and replaced.
+pub fn tombstone_post(posts: &mut [Post], id: &str) -> bool {
+    if let Some(post) = posts.iter_mut().find(|post| has_id(post, id)) {
+        let mut tombstone = Post::tombstone(post.id().to_string());
+        tombstone.set_source(post.source().clone());
+        tombstone.set_reply_to(post.reply_to().clone());
+        if let Some(author) = post.author() {
+            tombstone.set_author(author.clone());
+        }
+        *post = tombstone;
+        true
+    } else {
+        false
+    }
+}
+
+/// Whether a post has the given bare or full ID.
+fn has_id(post: &Post, id: &str) -> bool {
+    post.id() == id || (id.contains('#') && post.full_id() == id)
+}
+
+/// Update the `#+FOLLOW` lines of an org-social file to match a profile's follow list.
+///
+/// The rest of the file is left untouched. The follow lines are written where the first
+/// existing `#+FOLLOW` line was, or at the end of the profile section if there was none.
+///
+/// # Returns
+///
+/// The updated file content.
+pub fn update_follows(file_content: &str, profile: &Profile) -> String {
+    let follow_lines: Vec<String> = profile.follows_detailed().iter().map(|follow| follow.to_org_line()).collect();
+
+    let is_follow_line = |line: &str| line.trim_start().to_uppercase().starts_with("#+FOLLOW:");
+    let posts_index = file_content.lines().position(|line| line.starts_with("* Posts"));
+
+    let mut output: Vec<String> = Vec::new();
+    let mut inserted = false;
+    for (i, line) in file_content.lines().enumerate() {
+        let in_profile_section = posts_index.map(|index| i < index).unwrap_or(true);
+
+        if in_profile_section && is_follow_line(line) {
+            if !inserted {
+                output.extend(follow_lines.iter().cloned());
+                inserted = true;
+            }
+            continue;
+        }
+
+        if !inserted && Some(i) == posts_index {
+            // Keep a blank line between the profile and the posts, if there was one
+            let insert_at = if output.last().map(|last| last.trim().is_empty()).unwrap_or(false) {
+                output.len() - 1
+            } else {
+                output.len()
+            };
+            output.splice(insert_at..insert_at, follow_lines.iter().cloned());
+            inserted = true;
+        }
+
+        output.push(line.to_string());
+    }
+
+    if !inserted {
+        output.extend(follow_lines);
+    }
+
+    let mut result = output.join("\n");
+    if file_content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
 /// Serialize a profile and posts back to org-social format.
 /// 
 /// Creates a complete org-social file with profile metadata and posts section.
@@ -104,4 +346,87 @@ pub fn serialize_file(profile: &Profile, posts: &[Post]) -> String {
     }
 
     output.join("\n")
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "#+TITLE: Alice\r\n#+NICK: alice\n\n* Posts\nignored\n**\n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nFirst\n\n** \n:PROPERTIES:\n:ID: 2025-01-02T12:00:00+0000\n:END:\n\nSecond";
+
+    #[test]
+    fn test_streaming_parser_matches_parse_file() {
+        let source = Some("https://alice.example/social.org".to_string());
+        let expected = parse_file(FILE, source.clone());
+
+        // However the file is split, the result is the same
+        for chunk_size in [1, 7, FILE.len()] {
+            let mut parser = StreamingParser::new(source.clone());
+            let mut posts = Vec::new();
+            for chunk in FILE.as_bytes().chunks(chunk_size) {
+                posts.extend(parser.push_str(std::str::from_utf8(chunk).unwrap()));
+            }
+            let (profile, last_posts) = parser.finish();
+            posts.extend(last_posts);
+            assert_eq!((&profile, &posts), (&expected.0, &expected.1));
+        }
+
+        // A stream ending in the middle of a post header keeps the post before it
+        let truncated = "#+NICK: alice\n* Posts\n** \n:PROPERTIES:\n:ID: 2025-01-01T12:00:00+0000\n:END:\n\nHello\n** ";
+        let mut parser = StreamingParser::new(None);
+        let mut posts = parser.push_str(truncated);
+        let (_, last_posts) = parser.finish();
+        posts.extend(last_posts);
+        assert_eq!(posts, parse_file(truncated, None).1);
+        assert_eq!(posts.len(), 2);
+        assert_eq!(posts[0].content(), "Hello");
+        assert_eq!(parse_reader(FILE.as_bytes(), source.clone()).unwrap(), expected);
+
+        // Without posts, the whole file is the profile
+        let (profile, posts) = parse_reader("#+NICK: bob\n".as_bytes(), None).unwrap();
+        assert_eq!((profile.nick(), posts.len()), ("bob", 0));
+        assert!(parse_reader(&b"#+NICK: \xff\n"[..], None).is_err());
+    }
+
+    #[test]
+    fn test_remove_post() {
+        let source = "https://alice.example/social.org";
+        let (_, mut posts) = parse_file(FILE, Some(source.to_string()));
+
+        assert!(remove_post(&mut posts, "2025-01-03T12:00:00+0000").is_none());
+        assert!(remove_post(&mut posts, "https://bob.example/social.org#2025-01-01T12:00:00+0000").is_none());
+        assert_eq!(posts.len(), 2);
+
+        let removed = remove_post(&mut posts, "2025-01-01T12:00:00+0000").unwrap();
+        assert_eq!(removed.content().trim(), "First");
+        let removed = remove_post(&mut posts, &format!("{source}#2025-01-02T12:00:00+0000")).unwrap();
+        assert_eq!(removed.content().trim(), "Second");
+        assert!(posts.is_empty());
+    }
+
+    #[test]
+    fn test_tombstone_post() {
+        let source = "https://alice.example/social.org";
+        let (profile, mut posts) = parse_file(FILE, Some(source.to_string()));
+        posts[1].set_reply_to(Some(format!("{source}#2025-01-01T12:00:00+0000")));
+
+        assert!(!tombstone_post(&mut posts, "2025-01-03T12:00:00+0000"));
+        assert!(!tombstone_post(&mut posts, "https://bob.example/social.org#2025-01-01T12:00:00+0000"));
+        assert!(posts.iter().all(|post| !post.is_deleted()));
+
+        assert!(tombstone_post(&mut posts, "2025-01-01T12:00:00+0000"));
+        assert!(tombstone_post(&mut posts, &format!("{source}#2025-01-02T12:00:00+0000")));
+        assert!(posts.iter().all(|post| post.is_deleted() && post.content().is_empty()));
+        assert_eq!(posts[1].reply_to().as_deref(), Some("https://alice.example/social.org#2025-01-01T12:00:00+0000"));
+        assert_eq!(posts[1].source().as_deref(), Some(source));
+
+        // The tombstones are written with their IDs and the deletion marker, and read back as deleted
+        let serialized = serialize_file(&profile, &posts);
+        assert!(serialized.contains(":ID: 2025-01-01T12:00:00+0000\n:DELETED: t\n"));
+        assert!(serialized.contains(":REPLY_TO: https://alice.example/social.org#2025-01-01T12:00:00+0000\n"));
+        assert!(!serialized.contains("First") && !serialized.contains("Second"));
+        let (_, reparsed) = parse_file(&serialized, Some(source.to_string()));
+        assert_eq!(reparsed.len(), 2);
+        assert!(reparsed.iter().all(Post::is_deleted));
+    }
+}
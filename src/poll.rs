@@ -194,14 +194,14 @@ pub fn parse_poll_from_content(content: &str, poll_end: Option<String>) -> Optio
     for (line_idx, line) in lines.iter().enumerate() {
         let trimmed = line.trim();
         
-        if trimmed.starts_with("- [ ]") {
+        if let Some(rest) = trimmed.strip_prefix("- [ ]") {
             if !in_poll_section {
                 in_poll_section = true;
                 start_line = Some(line_idx);
             }
             
             // Extract option text after "- [ ]"
-            let option_text = trimmed[5..].trim().to_string();
+            let option_text = rest.trim().to_string();
             if !option_text.is_empty() {
                 poll_options.push(option_text);
             }
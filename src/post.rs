@@ -3,9 +3,12 @@
 //! This module contains the Post struct and its implementations
 //! for parsing and serializing org-social posts.
 
+use std::cmp::Ordering;
 use std::fmt::Display;
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicUsize};
 
 use chrono::{DateTime, FixedOffset};
 
@@ -14,11 +17,16 @@ use crate::util;
 use crate::tokenizer::{Token, Tokenizer};
 use crate::blocks::{ActivatableElement, parse_blocks_with_poll_end};
 
+/// Author name used for posts and profiles whose author can't be determined,
+/// e.g. placeholders for reply targets that were not fetched.
+pub const UNKNOWN_AUTHOR: &str = "unknown";
+
 /// Represents the type of a post based on its properties.
 /// Used for categorizing posts as regular posts, polls, replies, or votes.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum PostType {
     /// A non-reply post that is not a poll, a standalone standard post.
+    #[default]
     Regular,
     /// A post that contains a poll.
     /// Could in theory be a reply.
@@ -33,17 +41,18 @@ pub enum PostType {
     SimplePollVote,
 }
 
-impl Default for PostType {
-    fn default() -> Self {
-        PostType::Regular
-    }
+/// Options controlling how `Post::format_for_display_with_options` renders a post.
+#[derive(Clone, Debug, Default)]
+pub struct DisplayOptions {
+    /// Show the content of posts with a content warning instead of hiding it behind the warning.
+    pub expand_content_warnings: bool,
 }
 
 /// Represents a post parsed from an org-social file.
 /// 
 /// Contains post metadata, it's content, author and source information,
 /// as well as parsed tokens and blocks from the content.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[derive(Default)]
 pub struct Post {
     id: String,
@@ -54,6 +63,12 @@ pub struct Post {
     poll_end: Option<String>,
     poll_option: Option<String>,
     mood: Option<String>,
+    share: Option<String>,
+    reaction: Option<String>,
+    content_warning: Option<String>,
+    media: Vec<String>,
+    deleted: bool,
+    signature: Option<String>,
     pub(crate) content: String,
     source: Option<String>,
     author: Option<String>,
@@ -73,6 +88,12 @@ impl From<&Post> for Post {
             poll_end: post.poll_end.clone(),
             poll_option: post.poll_option.clone(),
             mood: post.mood.clone(),
+            share: post.share.clone(),
+            reaction: post.reaction.clone(),
+            content_warning: post.content_warning.clone(),
+            media: post.media.clone(),
+            deleted: post.deleted,
+            signature: post.signature.clone(),
             content: post.content.clone(),
             source: post.source.clone(),
             author: post.author.clone(),
@@ -95,6 +116,12 @@ impl From<Vec<String>> for Post {
         let mut poll_end: Option<String> = None;
         let mut poll_option: Option<String> = None;
         let mut mood: Option<String> = None;
+        let mut share: Option<String> = None;
+        let mut reaction: Option<String> = None;
+        let mut content_warning: Option<String> = None;
+        let mut media: Vec<String> = Vec::new();
+        let mut deleted = false;
+        let mut signature: Option<String> = None;
         let mut content = String::new();
 
         let mut in_properties = false;
@@ -138,6 +165,13 @@ impl From<Vec<String>> for Post {
                         ":POLL_END" => poll_end = Some(parts[1].trim().to_string()),
                         ":POLL_OPTION" => poll_option = Some(parts[1].trim().to_string()),
                         ":MOOD" => mood = Some(parts[1].trim().to_string()),
+                        ":SHARE" => share = Some(parts[1].trim().to_string()),
+                        ":REACTION" => reaction = Some(parts[1].trim().to_string()),
+                        ":CW" | ":CONTENT_WARNING" => content_warning = Some(parts[1].trim().to_string()),
+                        // Multiple :MEDIA: lines and whitespace-separated URLs are both accepted
+                        ":MEDIA" => media.extend(parts[1].split_whitespace().map(String::from)),
+                        ":DELETED" => deleted = is_org_true(parts[1]),
+                        ":SIGNATURE" => signature = Some(parts[1].trim().to_string()),
                         _ => {}
                     }
                 }
@@ -193,6 +227,26 @@ impl From<Vec<String>> for Post {
                 poll_option = None;
             }
         }
+        if let Some(share_string) = &share {
+            if share_string.trim().is_empty() {
+                share = None;
+            }
+        }
+        if let Some(reaction_string) = &reaction {
+            if reaction_string.trim().is_empty() {
+                reaction = None;
+            }
+        }
+        if let Some(content_warning_string) = &content_warning {
+            if content_warning_string.trim().is_empty() {
+                content_warning = None;
+            }
+        }
+        if let Some(signature_string) = &signature {
+            if signature_string.trim().is_empty() {
+                signature = None;
+            }
+        }
 
         let mut post = Post {
             id,
@@ -203,6 +257,12 @@ impl From<Vec<String>> for Post {
             poll_end,
             poll_option,
             mood,
+            share,
+            reaction,
+            content_warning,
+            media,
+            deleted,
+            signature,
             content,
             source: None,
             author: None,
@@ -220,8 +280,8 @@ impl Display for Post {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Post:\nID: {}\nLang: {:?}\nTags: {:?}\nClient: {:?}\nReply To: {:?}\nPoll End: {:?}\nPoll Option: {:?}\nMood: {:?}\nSource: {:?}\nAuthor: {:?}\nTokens: {} parsed\nBlocks: {} parsed\nContent:\n{}",
-            self.id, self.lang, self.tags, self.client, self.reply_to, self.poll_end, self.poll_option, self.mood, self.source, self.author, self.tokens.len(), self.blocks.len(), self.content
+            "Post:\nID: {}\nLang: {:?}\nTags: {:?}\nClient: {:?}\nReply To: {:?}\nPoll End: {:?}\nPoll Option: {:?}\nMood: {:?}\nShare: {:?}\nReaction: {:?}\nContent Warning: {:?}\nMedia: {:?}\nDeleted: {}\nSource: {:?}\nAuthor: {:?}\nTokens: {} parsed\nBlocks: {} parsed\nContent:\n{}",
+            self.id, self.lang, self.tags, self.client, self.reply_to, self.poll_end, self.poll_option, self.mood, self.share, self.reaction, self.content_warning, self.media, self.deleted, self.source, self.author, self.tokens.len(), self.blocks.len(), self.content
         )
     }
 }
@@ -231,26 +291,50 @@ impl Post {
     /// If the `autotokenize` feature is enabled, the content will be automatically parsed.
     /// Otherwise, tokens and blocks will be empty until manual parsing is invoked.
     pub fn new(id: String, content: String) -> Self {
-        #[cfg(feature = "autotokenize")] {
-            let mut post = Post {
-                id,
-                content,
-                tokens: Vec::new(),
-                blocks: Vec::new(),
-                ..Default::default()
-            };
-        
-            post.parse_content();
-            return post;
-        }
-        #[cfg(not(feature = "autotokenize"))] {
-            Post {
-                id,
-                content,
-                tokens: Vec::new(),
-                blocks: Vec::new(),
-                ..Default::default()
-            }
+        #[cfg_attr(not(feature = "autotokenize"), allow(unused_mut))]
+        let mut post = Post {
+            id,
+            content,
+            tokens: Vec::new(),
+            blocks: Vec::new(),
+            ..Default::default()
+        };
+
+        #[cfg(feature = "autotokenize")]
+        post.parse_content();
+        post
+    }
+
+    /// Create a repost (share) of another post.
+    ///
+    /// The new post gets the current timestamp as its ID, references the original
+    /// through the `:SHARE:` property and uses the optional commentary as content.
+    pub fn repost_of(original: &Post, commentary: Option<String>) -> Self {
+        let mut post = Post::new(util::get_current_timestamp(), commentary.unwrap_or_default());
+        post.share = Some(original.full_id());
+        post
+    }
+
+    /// Create a lightweight reaction (e.g. an emoji) to another post.
+    ///
+    /// The reaction is an empty reply to the target carrying the `:REACTION:` property.
+    pub fn reaction_to(target_full_id: &str, emoji: &str) -> Self {
+        let mut post = Post::new(util::get_current_timestamp(), String::new());
+        post.reply_to = Some(target_full_id.to_string());
+        post.reaction = Some(emoji.to_string());
+        post
+    }
+
+    /// Create a tombstone for a deleted post.
+    ///
+    /// The tombstone keeps the original ID, so replies from other feeds still
+    /// have something to point at, but carries no content and is marked with
+    /// the `:DELETED: t` property.
+    pub fn tombstone(id: String) -> Self {
+        Post {
+            id,
+            deleted: true,
+            ..Default::default()
         }
     }
 
@@ -278,9 +362,28 @@ impl Post {
         }
     }
 
+    /// Compare two posts chronologically, oldest first.
+    ///
+    /// Posts without a valid timestamp sort last. Posts sharing a timestamp
+    /// are ordered by their `full_id`, so sorting is deterministic.
+    pub fn cmp_by_time(&self, other: &Post) -> Ordering {
+        util::cmp_optional_times(self.time(), other.time(), false)
+            .then_with(|| self.full_id().cmp(&other.full_id()))
+    }
+
+    /// Compare two posts chronologically, newest first.
+    ///
+    /// Posts without a valid timestamp sort last. Posts sharing a timestamp
+    /// are ordered by their `full_id`, so sorting is deterministic.
+    pub fn cmp_newest_first(&self, other: &Post) -> Ordering {
+        util::cmp_optional_times(self.time(), other.time(), true)
+            .then_with(|| self.full_id().cmp(&other.full_id()))
+    }
+
     pub fn post_type(&self) -> PostType {
         match (self.is_poll(), self.is_poll_vote(), self.is_reply(), self.is_empty()) {
             (true, _, _, _) => PostType::Poll,
+            (_, false, true, _) if self.is_reaction() => PostType::Reaction,
             (_, true, _, true) => PostType::SimplePollVote,
             (_, true, _, false) => PostType::PollVote,
             (_, _, true, true) => PostType::Reaction,
@@ -297,10 +400,87 @@ impl Post {
         &self.lang
     }
 
+    /// Languages of the post, lowercased. `:LANG:` may list several, separated by spaces or commas.
+    pub fn langs(&self) -> Vec<String> {
+        self.lang
+            .iter()
+            .flat_map(|lang| lang.split([' ', ',']))
+            .filter(|lang| !lang.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+
     pub fn tags(&self) -> &Option<Vec<String>> {
         &self.tags
     }
 
+    /// Hashtags written inline in the content (`#tag`), without the leading `#`.
+    ///
+    /// Uses the parsed tokens if available, tokenizing the content otherwise.
+    pub fn inline_tags(&self) -> Vec<String> {
+        let tokens = if self.tokens.is_empty() && !self.content.is_empty() {
+            Tokenizer::new(self.content.clone()).tokenize()
+        } else {
+            self.tokens.clone()
+        };
+
+        tokens
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Hashtag(tag) => Some(tag),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The content without org markup: link descriptions (or URLs), mention names and the text of styled spans.
+    ///
+    /// Uses the parsed tokens if available, tokenizing the content otherwise.
+    pub fn plain_text(&self) -> String {
+        let tokens = if self.tokens.is_empty() && !self.content.is_empty() {
+            Tokenizer::new(self.content.clone()).tokenize()
+        } else {
+            self.tokens.clone()
+        };
+
+        let mut text = String::new();
+        for token in tokens {
+            match token {
+                Token::PlainText(part)
+                | Token::Bold(part)
+                | Token::Italic(part)
+                | Token::BoldItalic(part)
+                | Token::Strikethrough(part)
+                | Token::Underline(part)
+                | Token::InlineCode(part) => text.push_str(&part),
+                Token::Link { url, description } => text.push_str(&description.unwrap_or(url)),
+                Token::Mention { username, .. } => text.push_str(&username),
+                Token::Hashtag(tag) => {
+                    text.push('#');
+                    text.push_str(&tag);
+                }
+            }
+        }
+        text
+    }
+
+    /// Property tags followed by inline hashtags, de-duplicated case-insensitively.
+    ///
+    /// The first spelling of a tag is kept.
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        let mut all_tags = Vec::new();
+
+        let property_tags = self.tags.clone().unwrap_or_default();
+        for tag in property_tags.into_iter().chain(self.inline_tags()) {
+            if seen.insert(tag.to_lowercase()) {
+                all_tags.push(tag);
+            }
+        }
+
+        all_tags
+    }
+
     pub fn client(&self) -> &Option<String> {
         &self.client
     }
@@ -321,6 +501,26 @@ impl Post {
         &self.mood
     }
 
+    /// The reaction (usually an emoji) from the `:REACTION:` property.
+    pub fn reaction(&self) -> &Option<String> {
+        &self.reaction
+    }
+
+    /// Content warning text from the `:CW:` (or `:CONTENT_WARNING:`) property.
+    pub fn content_warning(&self) -> Option<&str> {
+        self.content_warning.as_deref()
+    }
+
+    /// Full ID of the post this post shares, from the `:SHARE:` property.
+    pub fn shared_post_id(&self) -> &Option<String> {
+        &self.share
+    }
+
+    /// URLs of media attached to the post (images, audio, etc.)
+    pub fn media(&self) -> &[String] {
+        &self.media
+    }
+
     pub fn content(&self) -> &str {
         &self.content
     }
@@ -329,6 +529,11 @@ impl Post {
         &self.id
     }
 
+    /// The raw `:SIGNATURE:` property, in the `algorithm:base64` format.
+    pub fn signature(&self) -> &Option<String> {
+        &self.signature
+    }
+
     pub fn author(&self) -> &Option<String> {
         &self.author
     }
@@ -373,8 +578,9 @@ impl Post {
         self.reply_to = reply_to;
     }
 
+    /// Set the mood. An empty or blank mood is stored as no mood, like when parsing.
     pub fn set_mood(&mut self, mood: Option<String>) {
-        self.mood = mood;
+        self.mood = mood.filter(|mood| !mood.trim().is_empty());
     }
 
     pub fn set_lang(&mut self, lang: Option<String>) {
@@ -389,6 +595,46 @@ impl Post {
         self.poll_option = poll_option;
     }
 
+    pub fn set_content_warning(&mut self, content_warning: Option<String>) {
+        self.content_warning = content_warning;
+    }
+
+    pub fn set_reaction(&mut self, reaction: Option<String>) {
+        self.reaction = reaction;
+    }
+
+    pub fn set_shared_post_id(&mut self, share: Option<String>) {
+        self.share = share;
+    }
+
+    pub fn set_signature(&mut self, signature: Option<String>) {
+        self.signature = signature;
+    }
+
+    /// Sign the post, storing the signature in the `:SIGNATURE:` property.
+    ///
+    /// The signature covers the canonical form described in the `signing` module,
+    /// so any later change to the signed fields invalidates it.
+    #[cfg(feature = "signing")]
+    pub fn sign(&mut self, signer: &dyn crate::signing::Signer) {
+        self.signature = Some(crate::signing::sign_post(self, signer));
+    }
+
+    /// Verify the post's `:SIGNATURE:` property.
+    #[cfg(feature = "signing")]
+    pub fn verify(&self, verifier: &dyn crate::signing::Verifier) -> crate::signing::VerifyResult {
+        crate::signing::verify_post(self, verifier)
+    }
+
+    pub fn set_media(&mut self, media: Vec<String>) {
+        self.media = media;
+    }
+
+    /// Attach a media URL to the post.
+    pub fn add_media(&mut self, url: String) {
+        self.media.push(url);
+    }
+
     pub fn is_poll(&self) -> bool {
         crate::poll::is_poll_post(self)
     }
@@ -401,10 +647,24 @@ impl Post {
         self.poll_option.is_some() && self.reply_to.is_some()
     }
 
+    /// Whether the post is a reaction reply, see `Post::reaction_to`.
+    pub fn is_reaction(&self) -> bool {
+        self.reaction.is_some() && self.reply_to.is_some()
+    }
+
+    pub fn is_share(&self) -> bool {
+        self.share.is_some()
+    }
+
     pub fn is_reply(&self) -> bool {
         self.reply_to.is_some()
     }
 
+    /// Whether the post is a tombstone left behind by a deletion.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
     pub fn is_empty(&self) -> bool {
         self.content.trim().is_empty()
     }
@@ -417,6 +677,20 @@ impl Post {
         }
     }
 
+    /// Full IDs of the conversation this post belongs to, from the root down to this post.
+    ///
+    /// Only ancestors present in the feed are included, see `Feed::thread_root_of` for the stopping rules.
+    pub fn reply_chain_ids(&self, feed: &crate::feed::Feed) -> Vec<String> {
+        let mut chain: Vec<String> = feed
+            .ancestors_of(self, crate::feed::DEFAULT_MAX_THREAD_DEPTH)
+            .iter()
+            .rev()
+            .map(|post| post.full_id())
+            .collect();
+        chain.push(self.full_id());
+        chain
+    }
+
     /// Truncates the content to the specified length, adding "..." if truncated.
     pub fn summary(&self, len: usize) -> String {
         let chars: Vec<char> = self.content.chars().collect();
@@ -430,13 +704,21 @@ impl Post {
     }
 
     pub fn format_for_display(&self, profile: Option<&Profile>) -> String {
+        self.format_for_display_with_options(profile, &DisplayOptions::default())
+    }
+
+    /// Format the post for display, see `format_for_display`.
+    ///
+    /// Posts with a content warning have their body replaced by the warning,
+    /// unless `DisplayOptions::expand_content_warnings` is set.
+    pub fn format_for_display_with_options(&self, profile: Option<&Profile>, options: &DisplayOptions) -> String {
         let mut output = String::new();
 
         // Build header line with username, tags, and time
         let mut header = if let Some(author) = &self.author {
             author.to_string()
         } else {
-            "unknown".to_string()
+            UNKNOWN_AUTHOR.to_string()
         };
 
         // Add language as first tag if present
@@ -466,57 +748,29 @@ impl Post {
         }
 
         if let Some(reply_to) = self.reply_to() {
-            // Extract the post ID from the reply_to URL 
-            let reply_id = if let Some(hash_pos) = reply_to.rfind('#') {
-                &reply_to[hash_pos + 1..]
-            } else {
-                reply_to
+            // Split the reply_to URL into the feed URL and the post ID
+            let (base_url, reply_id) = match reply_to.rfind('#') {
+                Some(hash_pos) => (&reply_to[..hash_pos], &reply_to[hash_pos + 1..]),
+                None => (reply_to.as_str(), reply_to.as_str()),
             };
-            
-            // Try to map the URL to a nickname from the profile's follow list
-            let reply_display = if let Some(profile) = profile {
-                if let Some(follows) = profile.follow() {
-                    // Extract the base URL (without the fragment)
-                    let base_url = if let Some(hash_pos) = reply_to.rfind('#') {
-                        &reply_to[..hash_pos]
-                    } else {
-                        reply_to
-                    };
-                    
-                    // Normalize URLs by removing trailing slashes for comparison - they might be included by mistake
-                    let normalized_base = base_url.trim_end_matches('/');
-                    
-                    // Find the nickname for this URL
-                    if let Some((nick, _)) = follows.iter().find(|(_, url)| url.trim_end_matches('/') == normalized_base) {
-                        format!("{nick}#{reply_id}")
-                    } else {
-                        // No nickname found, use url#ID format
-                        format!("{base_url}#{reply_id}")
-                    }
-                } else {
-                    // No follow list, use url#ID format
-                    format!("{}#{}", 
-                        if let Some(hash_pos) = reply_to.rfind('#') {
-                            &reply_to[..hash_pos]
-                        } else {
-                            reply_to
-                        }, 
-                        reply_id)
-                }
-            } else {
-                // No profile, use url#ID format
-                format!("{}#{}", 
-                    if let Some(hash_pos) = reply_to.rfind('#') {
-                        &reply_to[..hash_pos]
-                    } else {
-                        reply_to
-                    }, 
-                    reply_id)
+
+            // Show the feed as the followed user's nickname if known, url#ID otherwise
+            let reply_display = match profile.and_then(|profile| profile.nick_for_url(base_url)) {
+                Some(nick) => format!("{nick}#{reply_id}"),
+                None => format!("{base_url}#{reply_id}"),
             };
             
             metadata.push(format!("Reply to: {}", reply_display));
         }
 
+        if let Some(share) = self.shared_post_id() {
+            metadata.push(format!("Shared: {}", share));
+        }
+
+        if let Some(reaction) = self.reaction() {
+            metadata.push(format!("Reaction: {}", reaction));
+        }
+
         if let Some(mood) = self.mood() {
             metadata.push(format!("Mood: {}", mood));
         }
@@ -534,8 +788,26 @@ impl Post {
             output.push_str(&format!("{}\n", metadata.join(" | ")));
         }
 
-        // Add post content
-        output.push_str(self.content());
+        // Media and content are hidden behind the content warning if there is one
+        let collapsed = self.content_warning().is_some() && !options.expand_content_warnings;
+
+        // List media attachments, one per line
+        if !collapsed {
+            for url in self.media() {
+                output.push_str(&format!("Attachment: {}\n", url));
+            }
+        }
+
+        if let Some(warning) = self.content_warning() {
+            if collapsed {
+                output.push_str(&format!("CW: {} [...]", warning));
+            } else {
+                output.push_str(&format!("CW: {}\n", warning));
+                output.push_str(self.content());
+            }
+        } else {
+            output.push_str(self.content());
+        }
 
         output
     }
@@ -585,6 +857,30 @@ impl Post {
             lines.push(format!(":MOOD: {mood}"));
         }
 
+        if let Some(share) = &self.share {
+            lines.push(format!(":SHARE: {share}"));
+        }
+
+        if let Some(reaction) = &self.reaction {
+            lines.push(format!(":REACTION: {reaction}"));
+        }
+
+        if let Some(content_warning) = &self.content_warning {
+            lines.push(format!(":CW: {content_warning}"));
+        }
+
+        for url in &self.media {
+            lines.push(format!(":MEDIA: {url}"));
+        }
+
+        if self.deleted {
+            lines.push(":DELETED: t".to_string());
+        }
+
+        if let Some(signature) = &self.signature {
+            lines.push(format!(":SIGNATURE: {signature}"));
+        }
+
         lines.push(":END:".to_string());
 
         // Empty line before content - for better readability in text mode.
@@ -596,20 +892,106 @@ impl Post {
     }
 
     /// Save the post to the specified file in org-social format.
+    ///
+    /// The post is appended to the existing content (a `* Posts` heading is added if missing),
+    /// written to a temporary file in the same directory and then atomically renamed over the target,
+    /// so an interrupted save never leaves a half-written file behind. If the target is a symlink,
+    /// the file it points to is replaced and the link is kept.
+    ///
+    /// Returns an error of kind `io::ErrorKind::AlreadyExists` if a post with the same ID is already in the file.
     pub fn save_post(&self, target_file: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let post_text = self.to_org_social();
+        let mut file_content = match fs::read_to_string(target_file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
 
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(target_file)?;
-            
-        writeln!(file, "{post_text}")?;
+        if !self.id.is_empty() {
+            let (_, existing_posts) = crate::parser::parse_file(&file_content, None);
+            if existing_posts.iter().any(|post| post.id() == self.id) {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("A post with ID {} already exists in {}", self.id, target_file),
+                ).into());
+            }
+        }
+
+        // Without the heading the parser would not find any posts in the file
+        if !file_content.lines().any(|line| line.starts_with("* Posts")) {
+            if !file_content.is_empty() && !file_content.ends_with('\n') {
+                file_content.push('\n');
+            }
+            file_content.push_str("* Posts\n");
+        } else if !file_content.ends_with('\n') {
+            file_content.push('\n');
+        }
+
+        file_content.push_str(&self.to_org_social());
+        file_content.push('\n');
+
+        // Renaming over a symlink would replace the link itself
+        let target_path = match fs::canonicalize(target_file) {
+            Ok(path) => path,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => PathBuf::from(target_file),
+            Err(e) => return Err(e.into()),
+        };
+        let (temp_path, temp_file) = create_temp_file(&target_path)?;
+        if let Err(e) = replace_with_temp_file(&target_path, &temp_path, temp_file, &file_content) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
 
         Ok(format!("New post saved to {}: {}", target_file, self.summary(50)))
     }
 }
 
+/// Create a temporary file next to `path`, named after it, the process and a counter,
+/// so concurrent saves to the same file don't write to the same temporary file.
+fn create_temp_file(path: &Path) -> io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "social.org".to_string());
+    loop {
+        let count = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+        let temp_path = path.with_file_name(format!(".{file_name}.{}.{count}.tmp", std::process::id()));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            // Left behind by a process that had the same ID
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Write `content` to the temporary file and rename it over `target`, see `Post::save_post`.
+fn replace_with_temp_file(target: &Path, temp_path: &Path, mut temp_file: File, content: &str) -> io::Result<()> {
+    // Keep the permissions of the existing file, e.g. a private 0600 file stays private
+    if let Ok(metadata) = fs::metadata(target) {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+    temp_file.write_all(content.as_bytes())?;
+    temp_file.sync_all()?;
+    drop(temp_file);
+    fs::rename(temp_path, target)
+}
+
+/// Sort posts chronologically with the newest first, see `Post::cmp_newest_first`.
+pub fn sort_newest_first(posts: &mut [Post]) {
+    posts.sort_by(Post::cmp_newest_first);
+}
+
+/// Sort posts chronologically with the oldest first, see `Post::cmp_by_time`.
+pub fn sort_oldest_first(posts: &mut [Post]) {
+    posts.sort_by(Post::cmp_by_time);
+}
+
+/// Interpret an org-mode style boolean property value ("t", "true", "yes").
+fn is_org_true(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "t" | "true" | "yes")
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -687,4 +1069,264 @@ mod tests {
         }
         
     }
+
+    #[test]
+    fn test_media_round_trip() {
+        let post_lines = vec![
+            "**".to_string(),
+            ":PROPERTIES:".to_string(),
+            ":ID: 2025-05-01T12:00:00+0100".to_string(),
+            ":MEDIA: https://example.com/cat.png".to_string(),
+            ":MEDIA: https://example.com/meow.ogg".to_string(),
+            ":END:".to_string(),
+            "".to_string(),
+            "Look at this cat".to_string(),
+        ];
+
+        let post = Post::from(post_lines);
+        assert_eq!(post.media(), &["https://example.com/cat.png".to_string(), "https://example.com/meow.ogg".to_string()]);
+
+        let serialized = post.to_org_social();
+        assert!(serialized.contains(":MEDIA: https://example.com/cat.png\n:MEDIA: https://example.com/meow.ogg"));
+
+        let reparsed = Post::from(serialized.lines().map(String::from).collect::<Vec<String>>());
+        assert_eq!(reparsed.media(), post.media());
+        assert_eq!(reparsed.content(), "Look at this cat");
+    }
+
+    #[test]
+    fn test_media_whitespace_separated() {
+        let post_lines = vec![
+            "**".to_string(),
+            ":PROPERTIES:".to_string(),
+            ":ID: 2025-05-01T12:00:00+0100".to_string(),
+            ":MEDIA: https://example.com/a.png https://example.com/b.png".to_string(),
+            ":END:".to_string(),
+        ];
+
+        let mut post = Post::from(post_lines);
+        assert_eq!(post.media().len(), 2);
+
+        post.add_media("https://example.com/c.png".to_string());
+        assert_eq!(post.media()[2], "https://example.com/c.png");
+        assert!(post.format_for_display(None).contains("Attachment: https://example.com/b.png"));
+    }
+
+    #[test]
+    fn test_save_post_creates_parseable_file() {
+        let path = std::env::temp_dir().join(format!("org-social-save-{}.org", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let first = Post::new("2025-05-01T12:00:00+0100".to_string(), "First post".to_string());
+        let second = Post::new("2025-05-02T12:00:00+0100".to_string(), "Second post".to_string());
+        first.save_post(path_str).unwrap();
+        second.save_post(path_str).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let (_, posts) = crate::parser::parse_file(&content, None);
+        assert_eq!(posts.len(), 2);
+        assert_eq!(posts[0].content(), "First post");
+        assert_eq!(posts[1].content(), "Second post");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_save_post_refuses_duplicate_id() {
+        let path = std::env::temp_dir().join(format!("org-social-duplicate-{}.org", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Only once".to_string());
+        post.save_post(path_str).unwrap();
+
+        let err = post.save_post(path_str).unwrap_err();
+        let io_err = err.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_err.kind(), io::ErrorKind::AlreadyExists);
+
+        let content = fs::read_to_string(&path).unwrap();
+        let (_, posts) = crate::parser::parse_file(&content, None);
+        assert_eq!(posts.len(), 1);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_post_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("org-social-permissions-{}.org", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, "#+NICK: me\n* Posts\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Private".to_string());
+        post.save_post(path_str).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        let _ = fs::remove_file(&path);
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_post_through_symlink() {
+        let directory = std::env::temp_dir().join(format!("org-social-symlink-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("synced")).unwrap();
+        let target = directory.join("synced/social.org");
+        let link = directory.join("social.org");
+        fs::write(&target, "#+NICK: me\n* Posts\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Through the link".to_string());
+        post.save_post(link.to_str().unwrap()).unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        let (_, posts) = crate::parser::parse_file(&fs::read_to_string(&target).unwrap(), None);
+        assert_eq!(posts.len(), 1);
+        // No temporary file is left behind
+        let names: Vec<_> = fs::read_dir(directory.join("synced")).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("social.org")]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_sort_is_deterministic_for_same_timestamp() {
+        let mut a = Post::new("2025-05-01T12:00:00+0100".to_string(), "A".to_string());
+        a.set_source(Some("https://a.example/social.org".to_string()));
+        let mut b = Post::new("2025-05-01T12:00:00+0100".to_string(), "B".to_string());
+        b.set_source(Some("https://b.example/social.org".to_string()));
+        let newer = Post::new("2025-05-02T12:00:00+0100".to_string(), "Newer".to_string());
+        let untimed = Post::new("not-a-timestamp".to_string(), "Untimed".to_string());
+
+        let mut first = vec![untimed.clone(), b.clone(), newer.clone(), a.clone()];
+        let mut second = vec![a.clone(), newer.clone(), untimed.clone(), b.clone()];
+        sort_newest_first(&mut first);
+        sort_newest_first(&mut second);
+
+        let order: Vec<&str> = first.iter().map(|p| p.content()).collect();
+        assert_eq!(order, vec!["Newer", "A", "B", "Untimed"]);
+        assert_eq!(order, second.iter().map(|p| p.content()).collect::<Vec<&str>>());
+
+        sort_oldest_first(&mut first);
+        let order: Vec<&str> = first.iter().map(|p| p.content()).collect();
+        assert_eq!(order, vec!["A", "B", "Newer", "Untimed"]);
+    }
+
+    #[test]
+    fn test_all_tags_merges_inline_and_property_tags() {
+        let mut post = Post::new("test-id".to_string(), "Writing #Rust in #emacs, see #+begin_src".to_string());
+        post.set_tags(Some(vec!["rust".to_string(), "programming".to_string()]));
+
+        assert_eq!(post.inline_tags(), vec!["Rust".to_string(), "emacs".to_string()]);
+        assert_eq!(post.all_tags(), vec!["rust".to_string(), "programming".to_string(), "emacs".to_string()]);
+    }
+
+    #[test]
+    fn test_repost_round_trip() {
+        let mut original = Post::new("2025-05-01T12:00:00+0100".to_string(), "Original".to_string());
+        original.set_source(Some("https://example.com/social.org".to_string()));
+
+        let repost = Post::repost_of(&original, Some("Worth reading".to_string()));
+        assert!(repost.is_share());
+        assert!(!repost.is_reply());
+        assert_eq!(repost.shared_post_id().as_deref(), Some("https://example.com/social.org#2025-05-01T12:00:00+0100"));
+        assert_eq!(repost.content(), "Worth reading");
+
+        let reparsed = Post::from(repost.to_org_social().lines().map(String::from).collect::<Vec<String>>());
+        assert_eq!(reparsed.shared_post_id(), repost.shared_post_id());
+    }
+
+    #[test]
+    fn test_reaction_round_trip() {
+        let reaction = Post::reaction_to("https://example.com/social.org#2025-05-01T12:00:00+0100", "❤️");
+        assert!(reaction.is_reaction());
+        assert!(reaction.is_empty());
+        assert_eq!(reaction.post_type(), PostType::Reaction);
+
+        let serialized = reaction.to_org_social();
+        assert!(serialized.contains(":REACTION: ❤️"));
+
+        let reparsed = Post::from(serialized.lines().map(String::from).collect::<Vec<String>>());
+        assert_eq!(reparsed.reaction().as_deref(), Some("❤️"));
+        assert_eq!(reparsed.reply_to(), reaction.reply_to());
+    }
+
+    #[test]
+    fn test_content_warning_round_trip_and_display() {
+        let post_lines = vec![
+            "**".to_string(),
+            ":PROPERTIES:".to_string(),
+            ":ID: 2025-05-01T12:00:00+0100".to_string(),
+            ":CONTENT_WARNING: Spoilers".to_string(),
+            ":END:".to_string(),
+            "".to_string(),
+            "The butler did it".to_string(),
+        ];
+
+        let mut post = Post::from(post_lines);
+        post.add_media("https://example.com/butler.png".to_string());
+        assert_eq!(post.content_warning(), Some("Spoilers"));
+
+        let reparsed = Post::from(post.to_org_social().lines().map(String::from).collect::<Vec<String>>());
+        assert_eq!(reparsed.content_warning(), Some("Spoilers"));
+        assert_eq!(reparsed.content(), "The butler did it");
+
+        let collapsed = post.format_for_display(None);
+        assert!(collapsed.contains("CW: Spoilers [...]"));
+        assert!(!collapsed.contains("The butler did it"));
+        assert!(!collapsed.contains("butler.png"));
+
+        let options = DisplayOptions { expand_content_warnings: true };
+        let expanded = post.format_for_display_with_options(None, &options);
+        assert!(expanded.contains("CW: Spoilers\nThe butler did it"));
+        assert!(expanded.contains("Attachment: https://example.com/butler.png"));
+    }
+
+    #[test]
+    fn test_tombstone_round_trip() {
+        let tombstone = Post::tombstone("2025-05-01T12:00:00+0100".to_string());
+        assert!(tombstone.is_deleted());
+        assert!(tombstone.is_empty());
+
+        let serialized = tombstone.to_org_social();
+        assert!(serialized.contains(":DELETED: t"));
+
+        let reparsed = Post::from(serialized.lines().map(String::from).collect::<Vec<String>>());
+        assert!(reparsed.is_deleted());
+        assert_eq!(reparsed.id(), "2025-05-01T12:00:00+0100");
+    }
+
+    #[test]
+    fn test_reply_display_uses_follow_nick() {
+        let profile = Profile::from(vec![
+            "#+NICK: me".to_string(),
+            "#+FOLLOW: alice http://alice.example/social.org/".to_string(),
+        ]);
+
+        let mut post = Post::new("2025-05-02T12:00:00+0100".to_string(), "Reply".to_string());
+        post.set_reply_to(Some("https://alice.example/social.org#2025-05-01T12:00:00+0100".to_string()));
+        assert!(post.format_for_display(Some(&profile)).contains("Reply to: alice#2025-05-01T12:00:00+0100"));
+
+        post.set_reply_to(Some("https://bob.example/social.org#2025-05-01T12:00:00+0100".to_string()));
+        assert!(post
+            .format_for_display(Some(&profile))
+            .contains("Reply to: https://bob.example/social.org#2025-05-01T12:00:00+0100"));
+    }
+
+    #[test]
+    fn test_set_empty_mood() {
+        let mut post = Post::new("2025-01-01T12:00:00+0000".to_string(), String::new());
+        post.set_mood(Some("😀".to_string()));
+        assert_eq!(post.mood().as_deref(), Some("😀"));
+
+        post.set_mood(Some(String::new()));
+        assert_eq!(post.mood(), &None);
+
+        post.set_mood(Some("  ".to_string()));
+        assert_eq!(post.mood(), &None);
+    }
 }
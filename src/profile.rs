@@ -3,21 +3,491 @@
 //! This module contains the Profile struct and its implementations
 //! for parsing and serializing user profile metadata.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::post::{self, Post};
+use crate::tokenizer::{Token, Tokenizer};
+use crate::util;
+
+/// URL schemes accepted in `#+CONTACT` entries.
+const KNOWN_CONTACT_SCHEMES: &[&str] = &["mailto", "xmpp", "matrix", "tel", "sms", "irc", "ircs", "http", "https"];
+
+/// How serious a profile issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueSeverity {
+    /// Other clients will likely mishandle the profile.
+    Error,
+    /// The profile works, but something is probably not intended.
+    Warning,
+}
+
+/// Kinds of problems found by [`Profile::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileIssueKind {
+    /// `#+NICK` is missing or empty.
+    EmptyNick,
+    /// `#+NICK` contains whitespace, which breaks mentions.
+    NickContainsWhitespace,
+    /// `#+AVATAR` is not an absolute URL.
+    AvatarNotAbsoluteUrl,
+    /// A `#+FOLLOW` URL is not an http(s) URL.
+    ///
+    /// A warning for local files (`file://` URLs, absolute paths) and other schemes, which this
+    /// library reads with its resolvers but other clients may not, an error if it isn't a URL at all.
+    FollowNotHttp(String),
+    /// The same feed is followed more than once.
+    DuplicateFollow(String),
+    /// A `#+CONTACT` entry uses an unknown URL scheme.
+    UnknownContactScheme(String),
+}
+
+/// A problem found in a profile, with a human-readable message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileIssue {
+    pub kind: ProfileIssueKind,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+impl ProfileIssue {
+    fn new(kind: ProfileIssueKind, severity: IssueSeverity, message: String) -> Self {
+        Self { kind, severity, message }
+    }
+}
+
+impl std::fmt::Display for ProfileIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            IssueSeverity::Error => "error",
+            IssueSeverity::Warning => "warning",
+        };
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+/// How a single profile field is merged by [`Profile::merge_from`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the cached value.
+    KeepLocal,
+    /// Replace the cached value with the remote one.
+    TakeRemote,
+    /// Keep the cached entries and add the new remote ones.
+    /// Only meaningful for lists (links, follows, contacts) - single values behave like `TakeRemote`.
+    Union,
+}
+
+/// Per-field strategies for [`Profile::merge_from`].
+///
+/// The default keeps the locally assigned nick and takes everything else from the remote profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergePolicy {
+    pub title: MergeStrategy,
+    pub nick: MergeStrategy,
+    pub description: MergeStrategy,
+    pub avatar: MergeStrategy,
+    pub link: MergeStrategy,
+    /// Also used for the groups of the follows.
+    pub follow: MergeStrategy,
+    pub contact: MergeStrategy,
+    pub public_key: MergeStrategy,
+    pub mute: MergeStrategy,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        Self {
+            title: MergeStrategy::TakeRemote,
+            nick: MergeStrategy::KeepLocal,
+            description: MergeStrategy::TakeRemote,
+            avatar: MergeStrategy::TakeRemote,
+            link: MergeStrategy::TakeRemote,
+            follow: MergeStrategy::TakeRemote,
+            contact: MergeStrategy::TakeRemote,
+            public_key: MergeStrategy::TakeRemote,
+            mute: MergeStrategy::TakeRemote,
+        }
+    }
+}
+
+/// Profile fields, as reported in [`ProfileChanges`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileField {
+    Title,
+    Nick,
+    Description,
+    Avatar,
+    Link,
+    Follow,
+    Contact,
+    PublicKey,
+    Mute,
+}
+
+/// What [`Profile::merge_from`] changed in the cached profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileChanges {
+    /// Fields whose value changed, in declaration order.
+    pub changed_fields: Vec<ProfileField>,
+    /// Follows (nick, url) that were added.
+    pub added_follows: Vec<(String, String)>,
+    /// Follows (nick, url) that were removed.
+    pub removed_follows: Vec<(String, String)>,
+}
+
+impl ProfileChanges {
+    /// Check whether the merge changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.changed_fields.is_empty()
+    }
+
+    /// Check whether a specific field changed.
+    pub fn changed(&self, field: ProfileField) -> bool {
+        self.changed_fields.contains(&field)
+    }
+}
+
+/// A single difference between two versions of a profile, see [`Profile::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileChange {
+    TitleChanged { from: String, to: String },
+    NickChanged { from: String, to: String },
+    DescriptionChanged { from: String, to: String },
+    AvatarChanged { from: Option<String>, to: Option<String> },
+    LinkAdded(String),
+    LinkRemoved(String),
+    /// A feed was followed, as (nick, url).
+    FollowAdded(String, String),
+    /// A feed was unfollowed, as (nick, url).
+    FollowRemoved(String, String),
+    /// A followed feed got another nick.
+    FollowRenamed { url: String, from: String, to: String },
+    ContactAdded(String),
+    ContactRemoved(String),
+    PublicKeyChanged { from: Option<String>, to: Option<String> },
+}
+
+impl std::fmt::Display for ProfileChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileChange::TitleChanged { from, to } => write!(f, "Title changed from '{from}' to '{to}'"),
+            ProfileChange::NickChanged { from, to } => write!(f, "Nick changed from '{from}' to '{to}'"),
+            ProfileChange::DescriptionChanged { .. } => write!(f, "Description changed"),
+            ProfileChange::AvatarChanged { to: Some(_), .. } => write!(f, "Avatar changed"),
+            ProfileChange::AvatarChanged { to: None, .. } => write!(f, "Avatar removed"),
+            ProfileChange::LinkAdded(link) => write!(f, "Added link {link}"),
+            ProfileChange::LinkRemoved(link) => write!(f, "Removed link {link}"),
+            ProfileChange::FollowAdded(nick, url) => write!(f, "Followed {nick} ({url})"),
+            ProfileChange::FollowRemoved(nick, url) => write!(f, "Unfollowed {nick} ({url})"),
+            ProfileChange::FollowRenamed { url, from, to } => write!(f, "Renamed {from} to {to} ({url})"),
+            ProfileChange::ContactAdded(contact) => write!(f, "Added contact {contact}"),
+            ProfileChange::ContactRemoved(contact) => write!(f, "Removed contact {contact}"),
+            ProfileChange::PublicKeyChanged { to: Some(_), .. } => write!(f, "Public key changed"),
+            ProfileChange::PublicKeyChanged { to: None, .. } => write!(f, "Public key removed"),
+        }
+    }
+}
+
+/// Structured differences between two versions of a profile, see [`Profile::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileDiff {
+    pub changes: Vec<ProfileChange>,
+}
+
+impl ProfileDiff {
+    /// Check whether the profiles are the same (ignoring their source).
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl std::fmt::Display for ProfileDiff {
+    /// One change per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines: Vec<String> = self.changes.iter().map(ToString::to_string).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Collect the entries of `to` missing from `from`, and the entries of `from` missing from `to`.
+fn list_differences<'a, T: PartialEq>(from: &'a Option<Vec<T>>, to: &'a Option<Vec<T>>) -> (Vec<&'a T>, Vec<&'a T>) {
+    let from: Vec<&T> = from.iter().flatten().collect();
+    let to: Vec<&T> = to.iter().flatten().collect();
+    let added = to.iter().filter(|item| !from.contains(item)).copied().collect();
+    let removed = from.iter().filter(|item| !to.contains(item)).copied().collect();
+    (added, removed)
+}
+
+/// Merge a single value, returning whether it changed.
+fn merge_value<T: Clone + PartialEq>(local: &mut T, remote: &T, strategy: MergeStrategy) -> bool {
+    if strategy == MergeStrategy::KeepLocal || local == remote {
+        return false;
+    }
+    *local = remote.clone();
+    true
+}
+
+/// Merge an optional list, deduplicating unions by `key`. Returns whether the list changed.
+fn merge_list<T: Clone + PartialEq, K: PartialEq>(
+    local: &mut Option<Vec<T>>,
+    remote: &Option<Vec<T>>,
+    strategy: MergeStrategy,
+    key: impl Fn(&T) -> K,
+) -> bool {
+    match strategy {
+        MergeStrategy::KeepLocal => false,
+        MergeStrategy::TakeRemote => merge_value(local, remote, strategy),
+        MergeStrategy::Union => {
+            let mut merged = local.clone().unwrap_or_default();
+            for item in remote.iter().flatten() {
+                let item_key = key(item);
+                if !merged.iter().any(|existing| key(existing) == item_key) {
+                    merged.push(item.clone());
+                }
+            }
+            let merged = if merged.is_empty() { None } else { Some(merged) };
+            merge_value(local, &merged, strategy)
+        }
+    }
+}
+
+/// Merge follow groups by URL, see [`MergePolicy::follow`]. Returns whether the groups changed.
+fn merge_follow_groups(local: &mut HashMap<String, Vec<String>>, remote: &HashMap<String, Vec<String>>, strategy: MergeStrategy) -> bool {
+    match strategy {
+        MergeStrategy::KeepLocal => false,
+        MergeStrategy::TakeRemote => merge_value(local, remote, strategy),
+        MergeStrategy::Union => {
+            let mut merged = local.clone();
+            for (url, groups) in remote {
+                let merged_groups = merged.entry(url.clone()).or_default();
+                for group in groups {
+                    if !merged_groups.contains(group) {
+                        merged_groups.push(group.clone());
+                    }
+                }
+            }
+            merge_value(local, &merged, strategy)
+        }
+    }
+}
+
+/// A `#+CONTACT` entry, classified by kind so clients can render the right icon or action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Contact {
+    /// `mailto:` address, stored without the scheme.
+    Email(String),
+    /// `xmpp:` address, stored without the scheme.
+    Xmpp(String),
+    /// Matrix handle (`@user:server`), `matrix:` URI or `matrix.to` link, stored as written.
+    Matrix(String),
+    /// Fediverse handle (`@user@instance`) or profile URL (`https://instance/@user`), stored as written.
+    Fediverse(String),
+    /// Any other http(s) URL.
+    Url(String),
+    /// Anything else, including free-form text.
+    Other(String),
+}
+
+impl Contact {
+    /// Classify a contact value. Never fails - unrecognized values become `Other`.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+
+        if let Some(address) = value.strip_prefix("mailto:") {
+            return Contact::Email(address.to_string());
+        }
+        if let Some(address) = value.strip_prefix("xmpp:") {
+            return Contact::Xmpp(address.to_string());
+        }
+        if value.starts_with("matrix:") || value.starts_with("https://matrix.to/") {
+            return Contact::Matrix(value.to_string());
+        }
+
+        if let Some(handle) = value.strip_prefix('@') {
+            if !handle.contains(char::is_whitespace) {
+                if let Some((user, instance)) = handle.split_once('@') {
+                    if !user.is_empty() && instance.contains('.') {
+                        return Contact::Fediverse(value.to_string());
+                    }
+                } else if let Some((user, server)) = handle.split_once(':') {
+                    if !user.is_empty() && !server.is_empty() {
+                        return Contact::Matrix(value.to_string());
+                    }
+                }
+            }
+        }
+
+        if value.starts_with("http://") || value.starts_with("https://") {
+            let path = value.split_once("://").map(|(_, rest)| rest).and_then(|rest| rest.find('/').map(|index| &rest[index..]));
+            if path.map(|path| path.starts_with("/@") && path.len() > 2).unwrap_or(false) {
+                return Contact::Fediverse(value.to_string());
+            }
+            return Contact::Url(value.to_string());
+        }
+
+        Contact::Other(value.to_string())
+    }
+
+    /// The value as written in a `#+CONTACT` line. Parsing it again gives the same contact.
+    pub fn to_org_value(&self) -> String {
+        match self {
+            Contact::Email(address) => format!("mailto:{address}"),
+            Contact::Xmpp(address) => format!("xmpp:{address}"),
+            Contact::Matrix(value) | Contact::Fediverse(value) | Contact::Url(value) | Contact::Other(value) => {
+                value.clone()
+            }
+        }
+    }
+}
+
+/// A followed feed with its nickname and the groups (lists) it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Follow {
+    /// The nickname the user gave the feed, empty if the `#+FOLLOW` line has none.
+    pub nick: String,
+    pub url: String,
+    pub groups: Vec<String>,
+}
+
+impl Follow {
+    /// Name to show for the follow: its nick, or the host of its URL if it has none, or the URL itself.
+    ///
+    /// The host is only used for display and is never stored or written back as the nick.
+    pub fn display_nick(&self) -> &str {
+        if !self.nick.is_empty() {
+            return &self.nick;
+        }
+        util::url_host(&self.url).unwrap_or(&self.url)
+    }
+
+    /// Format the follow as a `#+FOLLOW` line.
+    ///
+    /// The format is `nick url group1,group2`; an empty nick and empty group list are left out.
+    pub fn to_org_line(&self) -> String {
+        let mut line = String::from("#+FOLLOW:");
+        if !self.nick.is_empty() {
+            line.push_str(&format!(" {}", self.nick));
+        }
+        line.push_str(&format!(" {}", self.url));
+        if !self.groups.is_empty() {
+            line.push_str(&format!(" {}", self.groups.join(",")));
+        }
+        line
+    }
+}
+
+/// Parse the value of a `#+FOLLOW` line: `url`, `nick url`, `url groups` or `nick url groups`.
+///
+/// With two values, the first one is the URL if it's a feed location (see [`util::is_feed_location`]),
+/// e.g. a local file followed as `/home/me/social.org friends`, and the second one isn't.
+///
+/// A missing nick is left empty, as per
+/// https://github.com/tanrax/org-social/issues/18#issuecomment-3245769906, so the
+/// feed's own `#+NICK` is used for its posts, see [`Follow::display_nick`] for a name to show.
+fn parse_follow(value: &str) -> Option<Follow> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (nick, url, groups) = match parts.as_slice() {
+        [] => return None,
+        [url] => (None, *url, None),
+        [first, second] if util::is_feed_location(first) && !util::is_feed_location(second) => (None, *first, Some(*second)),
+        [nick, url] => (Some(*nick), *url, None),
+        [nick, url, groups, ..] => (Some(*nick), *url, Some(*groups)),
+    };
+
+    let nick = nick.unwrap_or_default().to_string();
+    let groups = groups
+        .map(|groups| groups.split(',').map(str::trim).filter(|group| !group.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    Some(Follow { nick, url: url.to_string(), groups })
+}
+
+/// Check whether a mute entry matches a post.
+///
+/// Entries containing `://` are feed URLs, matched against the post's source with [`util::same_feed_url`].
+/// Other entries are nicks (a leading `@` is ignored), matched case-insensitively against the post's author.
+pub fn mute_matches(mute: &str, post: &Post) -> bool {
+    let mute = mute.trim();
+    if mute.contains("://") {
+        post.source().as_ref().map(|source| util::same_feed_url(source, mute)).unwrap_or(false)
+    } else {
+        let nick = mute.trim_start_matches('@');
+        post.author().as_ref().map(|author| author.to_lowercase() == nick.to_lowercase()).unwrap_or(false)
+    }
+}
+
+/// Get the scheme of a URL, if it has a valid one.
+fn url_scheme(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if valid && !rest.is_empty() {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+/// A `#+LINK` or `#+CONTACT` value, optionally written as an org link with a label.
+///
+/// `[[https://example.com][My blog]]` is parsed into the URL and the label `My blog`,
+/// any other value is kept verbatim as the URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileLink {
+    pub url: String,
+    pub label: Option<String>,
+}
+
+impl ProfileLink {
+    /// Parse a profile field value, see [`ProfileLink`].
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with("[[") && value.ends_with("]]") {
+            if let [Token::Link { url, description }] = Tokenizer::new(value.to_string()).tokenize().as_slice() {
+                return ProfileLink {
+                    url: url.clone(),
+                    label: description.clone().filter(|label| !label.is_empty()),
+                };
+            }
+        }
+        ProfileLink { url: value.to_string(), label: None }
+    }
+
+    /// The value as written in the profile - an org link if there's a label, the bare URL otherwise.
+    pub fn to_org_value(&self) -> String {
+        match &self.label {
+            Some(label) => format!("[[{}][{}]]", self.url, label),
+            None => self.url.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for ProfileLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", label, self.url),
+            None => write!(f, "{}", self.url),
+        }
+    }
+}
 
 /// Represents a user profile parsed from an org-social file.
 /// 
 /// Contains metadata about the user.
-#[derive(Clone)]
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Profile {
     title: String,
     nick: String,
     description: String,
     avatar: Option<String>,
-    link: Option<Vec<String>>,
+    link: Option<Vec<ProfileLink>>,
     follow: Option<Vec<(String, String)>>,
-    contact: Option<Vec<String>>,
+    /// Groups of followed feeds, keyed by the normalized follow URL.
+    follow_groups: HashMap<String, Vec<String>>,
+    contact: Option<Vec<ProfileLink>>,
+    public_key: Option<String>,
+    /// Muted feed URLs or author nicks.
+    mute: Option<Vec<String>>,
     source: Option<String>,
 }
 
@@ -31,7 +501,10 @@ impl From<&Profile> for Profile {
             avatar: profile.avatar.clone(),
             link: profile.link.clone(),
             follow: profile.follow.clone(),
+            follow_groups: profile.follow_groups.clone(),
             contact: profile.contact.clone(),
+            public_key: profile.public_key.clone(),
+            mute: profile.mute.clone(),
             source: profile.source.clone(),
         }
     }
@@ -46,9 +519,12 @@ impl From<Vec<String>> for Profile {
         let mut nick = String::new();
         let mut description = String::new();
         let mut avatar: Option<String> = None;
-        let mut link: Option<Vec<String>> = None;
+        let mut link: Option<Vec<ProfileLink>> = None;
         let mut follow: Option<Vec<(String, String)>> = None;
-        let mut contact: Option<Vec<String>> = None;
+        let mut follow_groups: HashMap<String, Vec<String>> = HashMap::new();
+        let mut contact: Option<Vec<ProfileLink>> = None;
+        let mut public_key: Option<String> = None;
+        let mut mute: Option<Vec<String>> = None;
 
         for line in profile_section_lines {
             let parts: Vec<&str> = line.splitn(2, ':').collect();
@@ -62,33 +538,31 @@ impl From<Vec<String>> for Profile {
                         if link.is_none() {
                             link = Some(Vec::new());
                         }
-                        link.as_mut().unwrap().push(parts[1].trim().to_string());
+                        link.as_mut().unwrap().push(ProfileLink::parse(parts[1]));
                     }
                     "#+FOLLOW" => {
                         if follow.is_none() {
                             follow = Some(Vec::new());
                         }
-                        // Parse "nick url" format or "url"
-                        let follow_parts: Vec<&str> = parts[1].trim().splitn(2, ' ').collect();
-                        if follow_parts.len() == 2 {
-                            follow.as_mut().unwrap().push((
-                                follow_parts[0].to_string(),
-                                follow_parts[1].to_string(),
-                            ));
-                        } else if follow_parts.len() == 1 {
-                            // If only url is provided, use empty string for nick, as per:
-                            // https://github.com/tanrax/org-social/issues/18#issuecomment-3245769906
-                            follow.as_mut().unwrap().push((
-                                String::new(),
-                                follow_parts[0].to_string(),
-                            ));
+                        if let Some(parsed) = parse_follow(parts[1]) {
+                            if !parsed.groups.is_empty() {
+                                follow_groups.insert(util::normalize_url(&parsed.url), parsed.groups);
+                            }
+                            follow.as_mut().unwrap().push((parsed.nick, parsed.url));
                         }
                     }
                     "#+CONTACT" => {
                         if contact.is_none() {
                             contact = Some(Vec::new());
                         }
-                        contact.as_mut().unwrap().push(parts[1].trim().to_string());
+                        contact.as_mut().unwrap().push(ProfileLink::parse(parts[1]));
+                    }
+                    "#+PUBLIC_KEY" => public_key = Some(parts[1].trim().to_string()),
+                    "#+MUTE" => {
+                        let value = parts[1].trim();
+                        if !value.is_empty() {
+                            mute.get_or_insert_with(Vec::new).push(value.to_string());
+                        }
                     }
                     _ => {}
                 }
@@ -102,7 +576,10 @@ impl From<Vec<String>> for Profile {
             avatar,
             link,
             follow,
+            follow_groups,
             contact,
+            public_key,
+            mute,
             source: None,
         }
     }
@@ -129,7 +606,7 @@ impl std::fmt::Display for Profile {
                 if links.len() == 1 {
                     output.push(format!("Link: {}", links[0]));
                 } else {
-                    output.push(format!("Links:"));
+                    output.push("Links:".to_string());
                     for (i, link) in links.iter().enumerate() {
                         output.push(format!("  {}. {}", i + 1, link));
                     }
@@ -143,11 +620,17 @@ impl std::fmt::Display for Profile {
                     follows.len(),
                     if follows.len() == 1 { "user" } else { "users" }
                 ));
-                for (i, (name, url)) in follows.iter().enumerate() {
-                    output.push(format!("  {}. {} - {}", 
+                for (i, follow) in self.follows_detailed().iter().enumerate() {
+                    let groups = if follow.groups.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" [{}]", follow.groups.join(", "))
+                    };
+                    output.push(format!("  {}. {} - {}{}", 
                         i + 1,
-                        name, 
-                        url
+                        follow.nick, 
+                        follow.url,
+                        groups
                     ));
                 }
             }
@@ -158,7 +641,7 @@ impl std::fmt::Display for Profile {
                 if contacts.len() == 1 {
                     output.push(format!("Contact: {}", contacts[0]));
                 } else {
-                    output.push(format!("Contact:"));
+                    output.push("Contact:".to_string());
                     for (i, contact) in contacts.iter().enumerate() {
                         output.push(format!("  {}. {}", i + 1, contact));
                     }
@@ -166,6 +649,14 @@ impl std::fmt::Display for Profile {
             }
         }
         
+        if let Some(mutes) = &self.mute {
+            output.push(format!("Muted: {}", mutes.join(", ")));
+        }
+
+        if let Some(public_key) = &self.public_key {
+            output.push(format!("Public key: {}", public_key));
+        }
+
         if let Some(source) = &self.source {
             output.push(format!("Source: {}", source));
         }
@@ -187,6 +678,19 @@ impl Profile {
         &self.nick
     }
 
+    /// Name to show for the profile.
+    ///
+    /// Falls back from the nick to the title, then to the host of the source URL, and finally to [`post::UNKNOWN_AUTHOR`].
+    pub fn display_name(&self) -> &str {
+        if !self.nick.is_empty() {
+            &self.nick
+        } else if !self.title.is_empty() {
+            &self.title
+        } else {
+            self.source.as_deref().and_then(util::url_host).unwrap_or(post::UNKNOWN_AUTHOR)
+        }
+    }
+
     pub fn description(&self) -> &str {
         &self.description
     }
@@ -195,18 +699,136 @@ impl Profile {
         self.avatar.as_ref()
     }
 
-    pub fn link(&self) -> Option<&Vec<String>> {
-        self.link.as_ref()
+    /// Get the avatar URL, resolving a relative value against the profile's source.
+    ///
+    /// See [`util::resolve_url`] - a relative avatar is returned unchanged if the source is unknown.
+    pub fn resolved_avatar(&self) -> Option<String> {
+        let avatar = self.avatar.as_ref()?;
+        Some(util::resolve_url(self.source.as_deref(), avatar))
+    }
+
+    /// Get the links, resolving relative values against the profile's source, see [`Profile::resolved_avatar`].
+    pub fn resolved_links(&self) -> Vec<String> {
+        self.link
+            .iter()
+            .flatten()
+            .map(|link| util::resolve_url(self.source.as_deref(), &link.url))
+            .collect()
+    }
+
+    /// Get the link URLs, without labels. See [`Profile::links`].
+    pub fn link(&self) -> impl Iterator<Item = &str> {
+        self.links().iter().map(|link| link.url.as_str())
+    }
+
+    /// Get the links with their labels.
+    pub fn links(&self) -> &[ProfileLink] {
+        self.link.as_deref().unwrap_or_default()
+    }
+
+    /// Get the contact values, without labels. See [`Profile::contacts`].
+    pub fn contact(&self) -> impl Iterator<Item = &str> {
+        self.contacts().iter().map(|contact| contact.url.as_str())
+    }
+
+    /// Get the contacts with their labels.
+    pub fn contacts(&self) -> &[ProfileLink] {
+        self.contact.as_deref().unwrap_or_default()
+    }
+
+    /// Get the contacts classified by kind, see [`Contact`].
+    pub fn contacts_parsed(&self) -> Vec<Contact> {
+        self.contact.iter().flatten().map(|contact| Contact::parse(&contact.url)).collect()
+    }
+
+    /// Public key used to verify the user's post signatures, in the `algorithm:base64` format.
+    pub fn public_key(&self) -> Option<&String> {
+        self.public_key.as_ref()
+    }
+
+    pub fn set_public_key(&mut self, public_key: Option<String>) {
+        self.public_key = public_key;
+    }
+
+    /// Muted feed URLs and author nicks, from the `#+MUTE` keyword.
+    pub fn mutes(&self) -> &[String] {
+        self.mute.as_deref().unwrap_or_default()
+    }
+
+    /// Mute a feed URL or an author nick.
+    ///
+    /// # Returns
+    ///
+    /// `false` if it was already muted.
+    pub fn add_mute(&mut self, mute: String) -> bool {
+        let mute = mute.trim().to_string();
+        if self.mutes().contains(&mute) {
+            return false;
+        }
+        self.mute.get_or_insert_with(Vec::new).push(mute);
+        true
+    }
+
+    /// Unmute a feed URL or an author nick.
+    ///
+    /// # Returns
+    ///
+    /// `true` if it was muted.
+    pub fn remove_mute(&mut self, mute: &str) -> bool {
+        let Some(mutes) = &mut self.mute else {
+            return false;
+        };
+        let original_len = mutes.len();
+        mutes.retain(|existing| existing != mute.trim());
+        let removed = mutes.len() != original_len;
+        if mutes.is_empty() {
+            self.mute = None;
+        }
+        removed
     }
 
-    pub fn contact(&self) -> Option<&Vec<String>> {
-        self.contact.as_ref()
+    /// Check whether a post comes from a muted feed or author, see [`mute_matches`].
+    pub fn is_muted(&self, post: &Post) -> bool {
+        self.mutes().iter().any(|mute| mute_matches(mute, post))
     }
 
     pub fn source(&self) -> Option<&String> {
         self.source.as_ref()
     }
 
+    /// Canonical URL of the profile's feed, used to build full post IDs.
+    ///
+    /// The source is normalized with [`util::normalize_url`], and any `#fragment` is removed.
+    pub fn source_base(&self) -> Option<String> {
+        let source = self.source.as_deref()?;
+        let without_fragment = source.split('#').next().unwrap_or(source);
+        let base = util::normalize_url(without_fragment);
+        if base.is_empty() {
+            None
+        } else {
+            Some(base)
+        }
+    }
+
+    /// Full ID (`url#id`) of a post from this profile's feed, e.g. for a `:REPLY_TO:` property.
+    ///
+    /// Returns None if the profile has no source.
+    pub fn full_id_for(&self, post: &Post) -> Option<String> {
+        Some(format!("{}#{}", self.source_base()?, post.id()))
+    }
+
+    /// Key identifying the person behind the profile, to deduplicate profiles fetched under different URLs.
+    ///
+    /// The canonical source URL (see [`util::canonical_feed_url`]), so `http://host/social.org/`
+    /// and `https://host/social.org` give the same key. Profiles without a source are keyed
+    /// by their lowercased nick.
+    pub fn identity_key(&self) -> String {
+        match self.source_base() {
+            Some(base) => util::canonical_feed_url(&base),
+            None => format!("nick:{}", self.nick.to_lowercase()),
+        }
+    }
+
     pub fn set_source(&mut self, source: Option<String>) {
         self.source = source;
     }
@@ -236,23 +858,403 @@ impl Profile {
 
         if let Some(links) = &self.link {
             for link in links {
-                lines.push(format!("#+LINK: {link}"));
+                lines.push(format!("#+LINK: {}", link.to_org_value()));
             }
         }
 
-        if let Some(follows) = &self.follow {
-            for (nick, url) in follows {
-                lines.push(format!("#+FOLLOW: {nick} {url}"));
+        for follow in self.follows_detailed() {
+            lines.push(follow.to_org_line());
+        }
+
+        for contact in self.contacts() {
+            let contact = ProfileLink {
+                url: Contact::parse(&contact.url).to_org_value(),
+                label: contact.label.clone(),
+            };
+            lines.push(format!("#+CONTACT: {}", contact.to_org_value()));
+        }
+
+        if let Some(public_key) = &self.public_key {
+            lines.push(format!("#+PUBLIC_KEY: {public_key}"));
+        }
+
+        for mute in self.mutes() {
+            lines.push(format!("#+MUTE: {mute}"));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Follow a user.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the URL is already followed (compared after normalization), `true` otherwise.
+    pub fn add_follow(&mut self, nick: String, url: String) -> bool {
+        if self.is_following(&url) {
+            return false;
+        }
+        self.follow.get_or_insert_with(Vec::new).push((nick, url));
+        true
+    }
+
+    /// Unfollow a user by their feed URL.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a follow was removed.
+    pub fn remove_follow_by_url(&mut self, url: &str) -> bool {
+        let normalized = util::normalize_url(url);
+        self.remove_follows_where(|(_, follow_url)| util::normalize_url(follow_url) == normalized)
+    }
+
+    /// Unfollow a user by their nickname.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a follow was removed.
+    pub fn remove_follow_by_nick(&mut self, nick: &str) -> bool {
+        self.remove_follows_where(|(follow_nick, _)| follow_nick == nick)
+    }
+
+    fn remove_follows_where(&mut self, predicate: impl Fn(&(String, String)) -> bool) -> bool {
+        if let Some(follows) = &mut self.follow {
+            let original_len = follows.len();
+            follows.retain(|follow| !predicate(follow));
+            let removed = follows.len() != original_len;
+            if follows.is_empty() {
+                self.follow = None;
             }
+            self.prune_follow_groups();
+            removed
+        } else {
+            false
         }
+    }
 
-        if let Some(contacts) = &self.contact {
-            for contact in contacts {
-                lines.push(format!("#+CONTACT: {contact}"));
+    /// Drop the groups of feeds that are no longer followed.
+    fn prune_follow_groups(&mut self) {
+        let followed: HashSet<String> = self.follow.iter().flatten().map(|(_, url)| util::normalize_url(url)).collect();
+        self.follow_groups.retain(|url, _| followed.contains(url));
+    }
+
+    /// Get the follow list including the groups of each follow.
+    pub fn follows_detailed(&self) -> Vec<Follow> {
+        self.follow
+            .iter()
+            .flatten()
+            .map(|(nick, url)| Follow {
+                nick: nick.clone(),
+                url: url.clone(),
+                groups: self.follow_groups.get(&util::normalize_url(url)).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Set the groups of a followed feed.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the URL isn't followed.
+    pub fn set_follow_groups(&mut self, url: &str, groups: Vec<String>) -> bool {
+        if !self.is_following(url) {
+            return false;
+        }
+        let key = util::normalize_url(url);
+        if groups.is_empty() {
+            self.follow_groups.remove(&key);
+        } else {
+            self.follow_groups.insert(key, groups);
+        }
+        true
+    }
+
+    /// Get the follows belonging to a group.
+    pub fn follows_in_group(&self, group: &str) -> Vec<Follow> {
+        self.follows_detailed()
+            .into_iter()
+            .filter(|follow| follow.groups.iter().any(|follow_group| follow_group == group))
+            .collect()
+    }
+
+    /// Check whether a feed URL is followed, ignoring trailing slashes and host case.
+    pub fn is_following(&self, url: &str) -> bool {
+        let normalized = util::normalize_url(url);
+        self.follow
+            .as_ref()
+            .map(|follows| follows.iter().any(|(_, follow_url)| util::normalize_url(follow_url) == normalized))
+            .unwrap_or(false)
+    }
+
+    /// Check whether one of the follows points to a feed, treating `http` and `https` as the same.
+    ///
+    /// Unlike [`Profile::is_following`], this is meant for reading other people's follow lists,
+    /// which are written by hand and often use either scheme.
+    pub fn follows_feed(&self, url: &str) -> bool {
+        self.follow
+            .iter()
+            .flatten()
+            .any(|(_, follow_url)| util::same_feed_url(follow_url, url))
+    }
+
+    /// Get the follow list without duplicates.
+    ///
+    /// URLs are compared in their canonical form (see [`util::canonical_feed_url`]), so trailing slashes,
+    /// case in the host and `http` vs `https` don't matter. When the same feed is listed more than once,
+    /// the first entry (and its nick) is kept. URLs are kept as written, since they are the source
+    /// of the fetched posts and so part of their full IDs.
+    ///
+    /// # Returns
+    ///
+    /// (nick, url) pairs, in the order of the follow list.
+    pub fn follow_urls_normalized(&self) -> Vec<(String, String)> {
+        let mut seen = HashSet::new();
+        self.follow
+            .iter()
+            .flatten()
+            .filter(|(_, url)| seen.insert(util::canonical_feed_url(url)))
+            .map(|(nick, url)| (nick.clone(), url.trim().to_string()))
+            .collect()
+    }
+
+    /// Remove the duplicates from the follow list, see [`Profile::follow_urls_normalized`].
+    pub fn normalize_follows(&mut self) {
+        if self.follow.is_some() {
+            self.follow = Some(self.follow_urls_normalized());
+        }
+    }
+
+    /// Get the feed URL of a followed user by their nickname, see [`Profile::url_for_nick`].
+    #[deprecated(since = "0.5.0", note = "use url_for_nick")]
+    pub fn follow_url_for_nick(&self, nick: &str) -> Option<&str> {
+        self.url_for_nick(nick)
+    }
+
+    /// Get the feed URL of a followed user by their nickname. The nickname must match exactly.
+    ///
+    /// Follows without a nickname are never matched.
+    pub fn url_for_nick(&self, nick: &str) -> Option<&str> {
+        self.follow
+            .as_ref()?
+            .iter()
+            .find(|(follow_nick, _)| !follow_nick.is_empty() && follow_nick == nick)
+            .map(|(_, url)| url.as_str())
+    }
+
+    /// Compare two profiles, ignoring where they were fetched from.
+    ///
+    /// Useful to check whether a locally loaded profile matches its published copy.
+    pub fn eq_ignoring_source(&self, other: &Profile) -> bool {
+        Profile {
+            source: other.source.clone(),
+            ..self.clone()
+        } == *other
+    }
+
+    /// Update a cached profile with a newer copy, e.g. after re-fetching a followed feed.
+    ///
+    /// Each field is merged according to the policy. Union of follows compares URLs
+    /// after normalization and keeps the local nick for feeds that are already followed.
+    /// The source is only taken from the newer profile if the cached one has none.
+    ///
+    /// Follow groups are merged with the follows, and a change to them is reported as a change of
+    /// [`ProfileField::Follow`]. Groups of feeds that are no longer followed after the merge are dropped.
+    ///
+    /// # Returns
+    ///
+    /// A description of what changed, so clients can notify about profile updates.
+    pub fn merge_from(&mut self, newer: &Profile, policy: MergePolicy) -> ProfileChanges {
+        let mut changes = ProfileChanges::default();
+        let old_follows = self.follow.clone().unwrap_or_default();
+        let old_groups = self.follow_groups.clone();
+
+        let follows_changed = merge_list(&mut self.follow, &newer.follow, policy.follow, |(_, url)| util::normalize_url(url));
+        merge_follow_groups(&mut self.follow_groups, &newer.follow_groups, policy.follow);
+        self.prune_follow_groups();
+        let follow_changed = follows_changed || self.follow_groups != old_groups;
+
+        let changed = [
+            (ProfileField::Title, merge_value(&mut self.title, &newer.title, policy.title)),
+            (ProfileField::Nick, merge_value(&mut self.nick, &newer.nick, policy.nick)),
+            (ProfileField::Description, merge_value(&mut self.description, &newer.description, policy.description)),
+            (ProfileField::Avatar, merge_value(&mut self.avatar, &newer.avatar, policy.avatar)),
+            (ProfileField::Link, merge_list(&mut self.link, &newer.link, policy.link, |link| link.url.clone())),
+            (ProfileField::Follow, follow_changed),
+            (ProfileField::Contact, merge_list(&mut self.contact, &newer.contact, policy.contact, |contact| contact.url.clone())),
+            (ProfileField::PublicKey, merge_value(&mut self.public_key, &newer.public_key, policy.public_key)),
+            (ProfileField::Mute, merge_list(&mut self.mute, &newer.mute, policy.mute, |mute| mute.clone())),
+        ];
+        changes.changed_fields = changed.into_iter().filter(|(_, changed)| *changed).map(|(field, _)| field).collect();
+
+        if changes.changed(ProfileField::Follow) {
+            let new_follows = self.follow.clone().unwrap_or_default();
+            let contains = |follows: &[(String, String)], url: &str| {
+                let normalized = util::normalize_url(url);
+                follows.iter().any(|(_, other)| util::normalize_url(other) == normalized)
+            };
+            changes.added_follows =
+                new_follows.iter().filter(|(_, url)| !contains(&old_follows, url)).cloned().collect();
+            changes.removed_follows =
+                old_follows.iter().filter(|(_, url)| !contains(&new_follows, url)).cloned().collect();
+        }
+
+        if self.source.is_none() {
+            self.source = newer.source.clone();
+        }
+
+        changes
+    }
+
+    /// Compute what changed from this profile to another version of it, e.g. between two fetches.
+    ///
+    /// The source, mutes and follow groups are ignored. Follows are matched by normalized URL,
+    /// so a follow whose nick changed is reported as `FollowRenamed`.
+    pub fn diff(&self, other: &Profile) -> ProfileDiff {
+        let mut changes = Vec::new();
+
+        if self.title != other.title {
+            changes.push(ProfileChange::TitleChanged { from: self.title.clone(), to: other.title.clone() });
+        }
+        if self.nick != other.nick {
+            changes.push(ProfileChange::NickChanged { from: self.nick.clone(), to: other.nick.clone() });
+        }
+        if self.description != other.description {
+            changes.push(ProfileChange::DescriptionChanged {
+                from: self.description.clone(),
+                to: other.description.clone(),
+            });
+        }
+        if self.avatar != other.avatar {
+            changes.push(ProfileChange::AvatarChanged { from: self.avatar.clone(), to: other.avatar.clone() });
+        }
+
+        let (added, removed) = list_differences(&self.link, &other.link);
+        changes.extend(added.into_iter().map(|link| ProfileChange::LinkAdded(link.to_org_value())));
+        changes.extend(removed.into_iter().map(|link| ProfileChange::LinkRemoved(link.to_org_value())));
+
+        let find_follow = |follows: &Option<Vec<(String, String)>>, url: &str| {
+            let normalized = util::normalize_url(url);
+            follows.iter().flatten().find(|(_, other_url)| util::normalize_url(other_url) == normalized).cloned()
+        };
+        for (nick, url) in other.follow.iter().flatten() {
+            match find_follow(&self.follow, url) {
+                None => changes.push(ProfileChange::FollowAdded(nick.clone(), url.clone())),
+                Some((old_nick, _)) if &old_nick != nick => changes.push(ProfileChange::FollowRenamed {
+                    url: url.clone(),
+                    from: old_nick,
+                    to: nick.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (nick, url) in self.follow.iter().flatten() {
+            if find_follow(&other.follow, url).is_none() {
+                changes.push(ProfileChange::FollowRemoved(nick.clone(), url.clone()));
             }
         }
 
-        lines.join("\n")
+        let (added, removed) = list_differences(&self.contact, &other.contact);
+        changes.extend(added.into_iter().map(|contact| ProfileChange::ContactAdded(contact.to_org_value())));
+        changes.extend(removed.into_iter().map(|contact| ProfileChange::ContactRemoved(contact.to_org_value())));
+
+        if self.public_key != other.public_key {
+            changes.push(ProfileChange::PublicKeyChanged {
+                from: self.public_key.clone(),
+                to: other.public_key.clone(),
+            });
+        }
+
+        ProfileDiff { changes }
+    }
+
+    /// Check the profile for problems that other clients handle badly.
+    ///
+    /// The validation is advisory - [`Profile::to_org_social`] serializes the profile either way,
+    /// so clients can show the issues as warnings before saving.
+    pub fn validate(&self) -> Vec<ProfileIssue> {
+        let mut issues = Vec::new();
+
+        if self.nick.trim().is_empty() {
+            issues.push(ProfileIssue::new(
+                ProfileIssueKind::EmptyNick,
+                IssueSeverity::Error,
+                "The nick is empty".to_string(),
+            ));
+        } else if self.nick.chars().any(char::is_whitespace) {
+            issues.push(ProfileIssue::new(
+                ProfileIssueKind::NickContainsWhitespace,
+                IssueSeverity::Error,
+                format!("The nick '{}' contains whitespace", self.nick),
+            ));
+        }
+
+        if let Some(avatar) = &self.avatar {
+            let is_absolute = url_scheme(avatar).is_some() && avatar.contains("://");
+            if !is_absolute {
+                issues.push(ProfileIssue::new(
+                    ProfileIssueKind::AvatarNotAbsoluteUrl,
+                    IssueSeverity::Warning,
+                    format!("The avatar '{avatar}' is not an absolute URL"),
+                ));
+            }
+        }
+
+        let mut seen_follows = HashSet::new();
+        for (_, url) in self.follow.iter().flatten() {
+            let is_http = matches!(url_scheme(url).map(str::to_lowercase).as_deref(), Some("http") | Some("https"));
+            if !is_http {
+                let (severity, message) = if util::is_feed_location(url) {
+                    (IssueSeverity::Warning, format!("The followed feed '{url}' is not an http(s) URL, other clients may not read it"))
+                } else {
+                    (IssueSeverity::Error, format!("The followed feed '{url}' is not a URL"))
+                };
+                issues.push(ProfileIssue::new(ProfileIssueKind::FollowNotHttp(url.clone()), severity, message));
+            }
+
+            if !seen_follows.insert(util::normalize_url(url)) {
+                issues.push(ProfileIssue::new(
+                    ProfileIssueKind::DuplicateFollow(url.clone()),
+                    IssueSeverity::Warning,
+                    format!("The feed '{url}' is followed more than once"),
+                ));
+            }
+        }
+
+        for contact in self.contact.iter().flatten().map(|contact| &contact.url) {
+            if matches!(Contact::parse(contact), Contact::Matrix(_) | Contact::Fediverse(_)) {
+                continue;
+            }
+            let is_known = url_scheme(contact)
+                .map(|scheme| KNOWN_CONTACT_SCHEMES.contains(&scheme.to_lowercase().as_str()))
+                .unwrap_or(false);
+            if !is_known {
+                issues.push(ProfileIssue::new(
+                    ProfileIssueKind::UnknownContactScheme(contact.clone()),
+                    IssueSeverity::Warning,
+                    format!("The contact '{contact}' uses an unknown scheme"),
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// Get the nickname of a followed user by their feed URL.
+    ///
+    /// Matching rules:
+    /// - A `#post-id` fragment on the URL is ignored, so reply targets can be passed directly.
+    /// - Trailing slashes, surrounding whitespace and host case are ignored.
+    /// - `http` and `https` URLs are considered the same feed.
+    ///
+    /// Returns None if the URL isn't followed, or is followed without a nickname.
+    pub fn nick_for_url(&self, url: &str) -> Option<&str> {
+        let base_url = url.split('#').next().unwrap_or(url);
+        self.follow
+            .as_ref()?
+            .iter()
+            .find(|(_, follow_url)| util::same_feed_url(follow_url, base_url))
+            .map(|(nick, _)| nick.as_str())
+            .filter(|nick| !nick.is_empty())
     }
 
     pub fn create_follow_map(&self) -> HashMap<String, String> {
@@ -267,18 +1269,765 @@ impl Profile {
 
     /// Parses a followed user's nickname to an org-social mention syntax
     pub fn parse_followed_nickname_to_mention(&self, text: &str) -> Option<String> {
-        let follow_map = self.create_follow_map();
-        let mut result = text.to_string();
-        if result.starts_with('@') {
-            result = result.trim_start_matches('@').to_string();
+        let nick = text.trim_start_matches('@');
+        let url = self.url_for_nick(nick)?;
+        Some(format!("[[org-social:{}][@{}]]", url, nick))
+    }
+}
+
+impl Profile {
+    /// Export the profile as a minimal vCard 4.0, e.g. to share contact information.
+    ///
+    /// Contains `FN` (the title, or the nick if there's no title), `NICKNAME`, `NOTE` (the description),
+    /// `PHOTO` (the avatar URL), a `URL` for each link, and `EMAIL`/`IMPP`/`URL` entries
+    /// derived from the parsed contacts. Free-form contacts are left out.
+    pub fn to_vcard(&self) -> String {
+        let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:4.0".to_string()];
+
+        let full_name = if self.title.is_empty() { &self.nick } else { &self.title };
+        lines.push(format!("FN:{}", escape_vcard_text(full_name)));
+        if !self.nick.is_empty() {
+            lines.push(format!("NICKNAME:{}", escape_vcard_text(&self.nick)));
+        }
+        if !self.description.is_empty() {
+            lines.push(format!("NOTE:{}", escape_vcard_text(&self.description)));
+        }
+        if let Some(avatar) = &self.avatar {
+            lines.push(format!("PHOTO:{avatar}"));
+        }
+        for link in self.link.iter().flatten() {
+            lines.push(format!("URL:{}", link.url));
         }
 
-        if let Some(url) = follow_map.get(&result) {
-            result = format!("[[org-social:{}][@{}]]", url, result);
-        } else {
-            return None;
+        for contact in self.contacts_parsed() {
+            match contact {
+                Contact::Email(address) => lines.push(format!("EMAIL:{}", escape_vcard_text(&address))),
+                Contact::Xmpp(address) => lines.push(format!("IMPP:xmpp:{address}")),
+                Contact::Matrix(handle) if handle.starts_with('@') => {
+                    lines.push(format!("IMPP:matrix:u/{}", &handle[1..]));
+                }
+                Contact::Matrix(uri) => lines.push(format!("IMPP:{uri}")),
+                Contact::Fediverse(url) | Contact::Url(url) if url.contains("://") => lines.push(format!("URL:{url}")),
+                Contact::Fediverse(handle) => {
+                    // @user@instance -> https://instance/@user
+                    if let Some((user, instance)) = handle.trim_start_matches('@').split_once('@') {
+                        lines.push(format!("URL:https://{instance}/@{user}"));
+                    }
+                }
+                Contact::Url(_) | Contact::Other(_) => {}
+            }
         }
 
-        Some(result)
+        lines.push("END:VCARD".to_string());
+
+        let mut vcard: String = lines.iter().map(|line| fold_vcard_line(line)).collect::<Vec<String>>().join("\r\n");
+        vcard.push_str("\r\n");
+        vcard
+    }
+}
+
+/// Escape a vCard text value - backslashes, commas, semicolons and newlines, as per RFC 6350 section 3.4.
+fn escape_vcard_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a vCard line to at most 75 octets per line, continuation lines start with a space.
+fn fold_vcard_line(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+
+    let mut folded = String::with_capacity(line.len());
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(feature = "json")]
+impl Profile {
+    /// Serialize the profile to a JSON object. Available with the `json` feature.
+    ///
+    /// Missing optional fields are left out, follows are written as `{"nick": ..., "url": ...}` objects.
+    pub fn to_json(&self) -> String {
+        use serde_json::{json, Map, Value};
+
+        let mut object = Map::new();
+        object.insert("title".to_string(), json!(self.title));
+        object.insert("nick".to_string(), json!(self.nick));
+        object.insert("description".to_string(), json!(self.description));
+        if let Some(avatar) = &self.avatar {
+            object.insert("avatar".to_string(), json!(avatar));
+        }
+        if let Some(links) = &self.link {
+            let links: Vec<String> = links.iter().map(ProfileLink::to_org_value).collect();
+            object.insert("links".to_string(), json!(links));
+        }
+        if self.follow.is_some() {
+            let follows: Vec<Value> = self
+                .follows_detailed()
+                .iter()
+                .map(|follow| {
+                    let mut object = json!({ "nick": follow.nick, "url": follow.url });
+                    if !follow.groups.is_empty() {
+                        object["groups"] = json!(follow.groups);
+                    }
+                    object
+                })
+                .collect();
+            object.insert("follows".to_string(), json!(follows));
+        }
+        if let Some(contacts) = &self.contact {
+            let contacts: Vec<String> = contacts.iter().map(ProfileLink::to_org_value).collect();
+            object.insert("contacts".to_string(), json!(contacts));
+        }
+        if let Some(public_key) = &self.public_key {
+            object.insert("public_key".to_string(), json!(public_key));
+        }
+        if let Some(mutes) = &self.mute {
+            object.insert("mutes".to_string(), json!(mutes));
+        }
+        if let Some(source) = &self.source {
+            object.insert("source".to_string(), json!(source));
+        }
+
+        Value::Object(object).to_string()
+    }
+
+    /// Parse a profile from JSON produced by [`Profile::to_json`]. Available with the `json` feature.
+    ///
+    /// Unknown keys are ignored, missing ones are left empty.
+    pub fn from_json(json: &str) -> Result<Profile, serde_json::Error> {
+        use serde_json::{Map, Value};
+
+        let object: Map<String, Value> = serde_json::from_str(json)?;
+
+        let string = |key: &str| object.get(key).and_then(Value::as_str).map(str::to_string);
+        let strings = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|values| values.iter().filter_map(Value::as_str).map(str::to_string).collect::<Vec<String>>())
+        };
+
+        let mut follow_groups = HashMap::new();
+        let follow = object.get("follows").and_then(Value::as_array).map(|follows| {
+            follows
+                .iter()
+                .filter_map(|follow| {
+                    let url = follow.get("url")?.as_str()?.to_string();
+                    let nick = follow.get("nick").and_then(Value::as_str).unwrap_or_default().to_string();
+                    let groups: Vec<String> = follow
+                        .get("groups")
+                        .and_then(Value::as_array)
+                        .map(|groups| groups.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                        .unwrap_or_default();
+                    if !groups.is_empty() {
+                        follow_groups.insert(util::normalize_url(&url), groups);
+                    }
+                    Some((nick, url))
+                })
+                .collect()
+        });
+
+        Ok(Profile {
+            title: string("title").unwrap_or_default(),
+            nick: string("nick").unwrap_or_default(),
+            description: string("description").unwrap_or_default(),
+            avatar: string("avatar"),
+            link: strings("links").map(|links| links.iter().map(|link| ProfileLink::parse(link)).collect()),
+            follow,
+            follow_groups,
+            contact: strings("contacts").map(|contacts| contacts.iter().map(|contact| ProfileLink::parse(contact)).collect()),
+            public_key: string("public_key"),
+            mute: strings("mutes"),
+            source: string("source"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_management() {
+        let mut profile = Profile::default();
+
+        assert!(profile.add_follow("alice".to_string(), "https://Alice.example/social.org".to_string()));
+        assert!(profile.add_follow("bob".to_string(), "https://bob.example/social.org".to_string()));
+        assert!(!profile.add_follow("alice2".to_string(), "https://alice.example/social.org/".to_string()));
+
+        assert!(profile.is_following("https://ALICE.example/social.org"));
+        assert_eq!(profile.url_for_nick("bob"), Some("https://bob.example/social.org"));
+        assert_eq!(profile.url_for_nick("carol"), None);
+
+        assert!(profile.remove_follow_by_url("https://alice.example/social.org/"));
+        assert!(!profile.is_following("https://alice.example/social.org"));
+        assert!(profile.remove_follow_by_nick("bob"));
+        assert!(!profile.remove_follow_by_nick("bob"));
+        assert!(profile.follow().is_none());
+    }
+
+    #[test]
+    fn test_profile_equality() {
+        let file = "#+TITLE: Test\n#+NICK: me\n#+FOLLOW: alice https://alice.example/social.org\n#+FOLLOW: bob https://bob.example/social.org\n";
+        let profile = Profile::from(file.lines().map(String::from).collect::<Vec<String>>());
+
+        let reparsed = Profile::from(profile.to_org_social().lines().map(String::from).collect::<Vec<String>>());
+        assert_eq!(profile, reparsed);
+
+        let mut fetched = profile.clone();
+        fetched.set_source(Some("https://me.example/social.org".to_string()));
+        assert_ne!(profile, fetched);
+        assert!(profile.eq_ignoring_source(&fetched));
+
+        // Follow order matters
+        let mut reordered = profile.clone();
+        reordered.remove_follow_by_nick("alice");
+        reordered.add_follow("alice".to_string(), "https://alice.example/social.org".to_string());
+        assert_ne!(profile, reordered);
+        assert!(!profile.eq_ignoring_source(&reordered));
+    }
+
+    #[test]
+    fn test_follow_lines_updated_in_place() {
+        let file = "#+TITLE: Test\n#+NICK: me\n#+FOLLOW: alice https://alice.example/social.org\n#+CONTACT: mailto:me@example.com\n\n* Posts\n";
+        let mut profile = Profile::from(file.lines().map(String::from).collect::<Vec<String>>());
+
+        profile.add_follow("bob".to_string(), "https://bob.example/social.org".to_string());
+        let updated = crate::parser::update_follows(file, &profile);
+        assert_eq!(
+            updated,
+            "#+TITLE: Test\n#+NICK: me\n#+FOLLOW: alice https://alice.example/social.org\n#+FOLLOW: bob https://bob.example/social.org\n#+CONTACT: mailto:me@example.com\n\n* Posts\n"
+        );
+
+        profile.remove_follow_by_nick("alice");
+        profile.remove_follow_by_nick("bob");
+        let updated = crate::parser::update_follows(&updated, &profile);
+        assert_eq!(updated, "#+TITLE: Test\n#+NICK: me\n#+CONTACT: mailto:me@example.com\n\n* Posts\n");
+
+        profile.add_follow("carol".to_string(), "https://carol.example/social.org".to_string());
+        let updated = crate::parser::update_follows(&updated, &profile);
+        assert_eq!(
+            updated,
+            "#+TITLE: Test\n#+NICK: me\n#+CONTACT: mailto:me@example.com\n#+FOLLOW: carol https://carol.example/social.org\n\n* Posts\n"
+        );
+    }
+
+    fn profile_from(text: &str) -> Profile {
+        Profile::from(text.lines().map(String::from).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn test_validate_valid_profile() {
+        let profile = profile_from(
+            "#+TITLE: Test\n#+NICK: me\n#+AVATAR: https://me.example/avatar.png\n\
+             #+FOLLOW: alice https://alice.example/social.org\n#+CONTACT: mailto:me@example.com\n#+CONTACT: xmpp:me@example.com",
+        );
+        assert!(profile.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_each_issue() {
+        let profile = profile_from(
+            "#+NICK: \n#+AVATAR: avatar.png\n#+FOLLOW: alice gemini://alice.example/social.org\n\
+             #+FOLLOW: bob https://bob.example/social.org\n#+FOLLOW: bob2 https://BOB.example/social.org/\n\
+             #+CONTACT: carrier-pigeon:me",
+        );
+        let kinds: Vec<ProfileIssueKind> = profile.validate().into_iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ProfileIssueKind::EmptyNick,
+                ProfileIssueKind::AvatarNotAbsoluteUrl,
+                ProfileIssueKind::FollowNotHttp("gemini://alice.example/social.org".to_string()),
+                ProfileIssueKind::DuplicateFollow("https://BOB.example/social.org/".to_string()),
+                ProfileIssueKind::UnknownContactScheme("carrier-pigeon:me".to_string()),
+            ]
+        );
+
+        let mut profile = Profile::default();
+        profile.set_nick("two words".to_string());
+        let issues = profile.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, ProfileIssueKind::NickContainsWhitespace);
+        assert_eq!(issues[0].severity, IssueSeverity::Error);
+        assert_eq!(issues[0].to_string(), "error: The nick 'two words' contains whitespace");
+
+        // Local files and other schemes can be read with a resolver, only a warning
+        let profile = profile_from("#+NICK: me\n#+FOLLOW: file:///home/me/alice.org\n#+FOLLOW: /srv/bob.org\n#+FOLLOW: carol.org");
+        let severities: Vec<(ProfileIssueKind, IssueSeverity)> =
+            profile.validate().into_iter().map(|issue| (issue.kind, issue.severity)).collect();
+        assert_eq!(
+            severities,
+            vec![
+                (ProfileIssueKind::FollowNotHttp("file:///home/me/alice.org".to_string()), IssueSeverity::Warning),
+                (ProfileIssueKind::FollowNotHttp("/srv/bob.org".to_string()), IssueSeverity::Warning),
+                (ProfileIssueKind::FollowNotHttp("carol.org".to_string()), IssueSeverity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_from_default_policy() {
+        let mut cached = profile_from("#+TITLE: Alice\n#+NICK: ally\n#+AVATAR: https://alice.example/old.png");
+        let newer = profile_from("#+TITLE: Alice\n#+NICK: alice\n#+AVATAR: https://alice.example/new.png");
+
+        let changes = cached.merge_from(&newer, MergePolicy::default());
+        assert_eq!(changes.changed_fields, vec![ProfileField::Avatar]);
+        assert_eq!(cached.nick(), "ally");
+        assert_eq!(cached.avatar().map(String::as_str), Some("https://alice.example/new.png"));
+
+        assert!(cached.merge_from(&newer, MergePolicy::default()).is_empty());
+    }
+
+    #[test]
+    fn test_merge_from_follow_union() {
+        let mut cached = profile_from(
+            "#+NICK: alice\n#+FOLLOW: bobby https://bob.example/social.org\n#+FOLLOW: carol https://carol.example/social.org",
+        );
+        let newer = profile_from(
+            "#+NICK: alice\n#+FOLLOW: bob https://BOB.example/social.org/\n#+FOLLOW: dave https://dave.example/social.org",
+        );
+        let policy = MergePolicy {
+            follow: MergeStrategy::Union,
+            ..MergePolicy::default()
+        };
+
+        let changes = cached.merge_from(&newer, policy);
+        assert_eq!(changes.changed_fields, vec![ProfileField::Follow]);
+        assert_eq!(changes.added_follows, vec![("dave".to_string(), "https://dave.example/social.org".to_string())]);
+        assert!(changes.removed_follows.is_empty());
+        assert_eq!(
+            cached.follow().clone().unwrap(),
+            vec![
+                ("bobby".to_string(), "https://bob.example/social.org".to_string()),
+                ("carol".to_string(), "https://carol.example/social.org".to_string()),
+                ("dave".to_string(), "https://dave.example/social.org".to_string()),
+            ]
+        );
+
+        // Taking the remote list reports the dropped follows
+        let changes = cached.merge_from(&newer, MergePolicy::default());
+        assert_eq!(changes.removed_follows.len(), 1);
+        assert_eq!(changes.removed_follows[0].0, "carol");
+        assert!(changes.added_follows.is_empty());
+    }
+
+    #[test]
+    fn test_merge_from_mutes_and_groups() {
+        let cached = profile_from(
+            "#+NICK: alice\n#+FOLLOW: bob https://bob.example/social.org friends\n#+FOLLOW: carol https://carol.example/social.org work\n#+MUTE: @troll",
+        );
+        let newer = profile_from(
+            "#+NICK: alice\n#+FOLLOW: bob https://bob.example/social.org family\n#+MUTE: https://spam.example/social.org",
+        );
+
+        let mut merged = cached.clone();
+        let changes = merged.merge_from(&newer, MergePolicy::default());
+        assert_eq!(changes.changed_fields, vec![ProfileField::Follow, ProfileField::Mute]);
+        assert_eq!(merged.mutes(), ["https://spam.example/social.org".to_string()]);
+        assert!(merged.follows_in_group("friends").is_empty());
+        assert_eq!(merged.follows_in_group("family").len(), 1);
+        // Carol is no longer followed, so her group is gone
+        assert!(merged.follows_in_group("work").is_empty());
+
+        // Only the groups changed
+        let mut regrouped = newer.clone();
+        regrouped.set_follow_groups("https://bob.example/social.org", vec!["friends".to_string()]);
+        assert_eq!(regrouped.merge_from(&newer, MergePolicy::default()).changed_fields, vec![ProfileField::Follow]);
+
+        let policy = MergePolicy { follow: MergeStrategy::Union, mute: MergeStrategy::Union, ..MergePolicy::default() };
+        let mut merged = cached.clone();
+        merged.merge_from(&newer, policy);
+        assert_eq!(merged.mutes().len(), 2);
+        assert_eq!(merged.follows_in_group("friends").len(), 1);
+        assert_eq!(merged.follows_in_group("family").len(), 1);
+        assert_eq!(merged.follows_in_group("work").len(), 1);
+
+        let policy = MergePolicy { follow: MergeStrategy::KeepLocal, mute: MergeStrategy::KeepLocal, ..MergePolicy::default() };
+        let mut merged = cached.clone();
+        assert!(merged.merge_from(&newer, policy).is_empty());
+        assert_eq!(merged, cached);
+    }
+
+    #[test]
+    fn test_follow_urls_normalized() {
+        let mut profile = profile_from(
+            "#+NICK: me\n#+FOLLOW: a https://a.com/s.org\n#+FOLLOW: a-again https://a.com/s.org/\n#+FOLLOW: b  https://B.com/s.org/ \n\
+             #+FOLLOW: a-http http://A.com/s.org",
+        );
+
+        // Duplicates are dropped, the URLs kept are as written
+        let expected = vec![
+            ("a".to_string(), "https://a.com/s.org".to_string()),
+            ("b".to_string(), "https://B.com/s.org/".to_string()),
+        ];
+        assert_eq!(profile.follow_urls_normalized(), expected);
+
+        profile.normalize_follows();
+        assert_eq!(profile.follow().clone().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_follow_without_nick() {
+        let profile = profile_from(
+            "#+NICK: me\n#+FOLLOW: alice https://alice.example/social.org\n#+FOLLOW: https://bob.example:8080/social.org\n\
+             #+FOLLOW: carol\thttps://carol.example/social.org\n#+FOLLOW: social.org",
+        );
+
+        assert_eq!(
+            profile.follow().clone().unwrap(),
+            vec![
+                ("alice".to_string(), "https://alice.example/social.org".to_string()),
+                (String::new(), "https://bob.example:8080/social.org".to_string()),
+                ("carol".to_string(), "https://carol.example/social.org".to_string()),
+                (String::new(), "social.org".to_string()),
+            ]
+        );
+        let names: Vec<String> = profile.follows_detailed().iter().map(|follow| follow.display_nick().to_string()).collect();
+        assert_eq!(names, ["alice", "bob.example", "carol", "social.org"]);
+
+        // The stored nicks are written back, without inventing any or losing any URL
+        let serialized = profile.to_org_social();
+        assert!(serialized.contains("#+FOLLOW: https://bob.example:8080/social.org\n"));
+        let reparsed = profile_from(&serialized);
+        assert_eq!(reparsed.follow(), profile.follow());
+        let updated = crate::parser::update_follows("#+NICK: me\n", &profile);
+        assert!(updated.contains("#+FOLLOW: https://bob.example:8080/social.org\n"));
+    }
+
+    #[test]
+    fn test_org_link_values() {
+        let profile = profile_from(
+            "#+NICK: me\n#+LINK: [[https://me.example/blog][My blog]]\n#+LINK: https://me.example\n\
+             #+CONTACT: [[mailto:me@example.com][Email me]]",
+        );
+
+        assert_eq!(
+            profile.links(),
+            &[
+                ProfileLink { url: "https://me.example/blog".to_string(), label: Some("My blog".to_string()) },
+                ProfileLink { url: "https://me.example".to_string(), label: None },
+            ]
+        );
+        assert_eq!(profile.link().collect::<Vec<_>>(), vec!["https://me.example/blog", "https://me.example"]);
+        assert_eq!(profile.contact().collect::<Vec<_>>(), vec!["mailto:me@example.com"]);
+        assert_eq!(profile.contacts_parsed(), vec![Contact::Email("me@example.com".to_string())]);
+
+        let org = profile.to_org_social();
+        assert!(org.contains("#+LINK: [[https://me.example/blog][My blog]]"));
+        assert!(org.contains("#+LINK: https://me.example\n"));
+        assert!(org.contains("#+CONTACT: [[mailto:me@example.com][Email me]]"));
+        assert_eq!(profile_from(&org), profile);
+    }
+
+    #[test]
+    fn test_contacts_parsed() {
+        let profile = profile_from(
+            "#+NICK: me\n#+CONTACT: mailto:me@example.com\n#+CONTACT: xmpp:me@jabber.example\n\
+             #+CONTACT: @me:matrix.org\n#+CONTACT: https://matrix.to/#/@me:matrix.org\n#+CONTACT: @me@mastodon.social\n\
+             #+CONTACT: https://mastodon.social/@me\n#+CONTACT: https://me.example/contact\n#+CONTACT: ring me, maybe",
+        );
+
+        assert_eq!(
+            profile.contacts_parsed(),
+            vec![
+                Contact::Email("me@example.com".to_string()),
+                Contact::Xmpp("me@jabber.example".to_string()),
+                Contact::Matrix("@me:matrix.org".to_string()),
+                Contact::Matrix("https://matrix.to/#/@me:matrix.org".to_string()),
+                Contact::Fediverse("@me@mastodon.social".to_string()),
+                Contact::Fediverse("https://mastodon.social/@me".to_string()),
+                Contact::Url("https://me.example/contact".to_string()),
+                Contact::Other("ring me, maybe".to_string()),
+            ]
+        );
+
+        for contact in profile.contacts_parsed() {
+            assert_eq!(Contact::parse(&contact.to_org_value()), contact);
+        }
+        assert_eq!(profile_from(&profile.to_org_social()), profile);
+
+        // Handles have no scheme, but are valid contacts
+        let kinds: Vec<ProfileIssueKind> = profile.validate().into_iter().map(|issue| issue.kind).collect();
+        assert_eq!(kinds, vec![ProfileIssueKind::UnknownContactScheme("ring me, maybe".to_string())]);
+    }
+
+    #[test]
+    fn test_nick_and_url_lookup() {
+        let profile = profile_from(
+            "#+NICK: me\n#+FOLLOW: alice http://Alice.example/social.org/\n#+FOLLOW: https://anon.example/social.org",
+        );
+
+        assert_eq!(profile.nick_for_url("https://alice.example/social.org"), Some("alice"));
+        assert_eq!(profile.nick_for_url("https://alice.example/social.org#2025-01-01T00:00:00+0000"), Some("alice"));
+        assert_eq!(profile.nick_for_url("https://alice.example/other.org"), None);
+        assert_eq!(profile.nick_for_url("https://anon.example/social.org"), None);
+        assert_eq!(profile.url_for_nick(""), None);
+
+        assert_eq!(profile.url_for_nick("alice"), Some("http://Alice.example/social.org/"));
+        assert_eq!(profile.url_for_nick("Alice"), None);
+
+        assert_eq!(
+            profile.parse_followed_nickname_to_mention("@alice"),
+            Some("[[org-social:http://Alice.example/social.org/][@alice]]".to_string())
+        );
+        assert_eq!(profile.parse_followed_nickname_to_mention("@bob"), None);
+    }
+
+    #[test]
+    fn test_vcard_export() {
+        let profile = profile_from(
+            "#+TITLE: Alice's place\n#+NICK: alice\n#+DESCRIPTION: Rust, org-mode; coffee\n\
+             #+AVATAR: https://alice.example/avatar.png\n#+LINK: https://alice.example\n\
+             #+CONTACT: mailto:alice@example.com\n#+CONTACT: xmpp:alice@jabber.example\n#+CONTACT: @alice:matrix.org\n\
+             #+CONTACT: @alice@mastodon.social\n#+CONTACT: call me",
+        );
+
+        assert_eq!(
+            profile.to_vcard(),
+            "BEGIN:VCARD\r\n\
+             VERSION:4.0\r\n\
+             FN:Alice's place\r\n\
+             NICKNAME:alice\r\n\
+             NOTE:Rust\\, org-mode\\; coffee\r\n\
+             PHOTO:https://alice.example/avatar.png\r\n\
+             URL:https://alice.example\r\n\
+             EMAIL:alice@example.com\r\n\
+             IMPP:xmpp:alice@jabber.example\r\n\
+             IMPP:matrix:u/alice:matrix.org\r\n\
+             URL:https://mastodon.social/@alice\r\n\
+             END:VCARD\r\n"
+        );
+    }
+
+    #[test]
+    fn test_vcard_text_escaping() {
+        assert_eq!(escape_vcard_text("a,b;c"), "a\\,b\\;c");
+        assert_eq!(escape_vcard_text("back\\slash"), "back\\\\slash");
+        assert_eq!(escape_vcard_text("two\r\nlines"), "two\\nlines");
+        assert_eq!(escape_vcard_text("plain"), "plain");
+
+        let mut profile = Profile::default();
+        profile.set_nick("semi;colon,comma".to_string());
+        assert!(profile.to_vcard().contains("\r\nFN:semi\\;colon\\,comma\r\nNICKNAME:semi\\;colon\\,comma\r\n"));
+    }
+
+    #[test]
+    fn test_vcard_line_folding() {
+        let line = format!("NOTE:{}", "é".repeat(50));
+        let folded = fold_vcard_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+        assert_eq!(fold_vcard_line("FN:short"), "FN:short");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_round_trip() {
+        let mut profile = profile_from(
+            "#+TITLE: Test\n#+NICK: me\n#+DESCRIPTION: \"Quoted\" text\n#+AVATAR: https://me.example/a.png\n\
+             #+LINK: https://me.example\n#+FOLLOW: alice https://alice.example/social.org rust,friends\n#+FOLLOW: https://anon.example/social.org\n\
+             #+CONTACT: mailto:me@example.com\n#+PUBLIC_KEY: ed25519:AAAA\n#+MUTE: @troll",
+        );
+        profile.set_source(Some("https://me.example/social.org".to_string()));
+
+        let json = profile.to_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["follows"][0]["nick"], "alice");
+        assert_eq!(value["description"], "\"Quoted\" text");
+
+        assert_eq!(Profile::from_json(&json).unwrap(), profile);
+        assert_eq!(Profile::from_json(&Profile::default().to_json()).unwrap(), Profile::default());
+        assert!(Profile::from_json("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_display_name_fallbacks() {
+        let mut profile = Profile::default();
+        assert_eq!(profile.display_name(), "unknown");
+
+        profile.set_source(Some("https://alice.example/social.org".to_string()));
+        assert_eq!(profile.display_name(), "alice.example");
+
+        profile.title = "Alice's feed".to_string();
+        assert_eq!(profile.display_name(), "Alice's feed");
+
+        profile.set_nick("alice".to_string());
+        assert_eq!(profile.display_name(), "alice");
+    }
+
+    #[test]
+    fn test_follow_groups() {
+        let file = "#+NICK: me\n\
+            #+FOLLOW: alice https://alice.example/social.org rust,friends\n\
+            #+FOLLOW: https://bob.example/social.org rust\n\
+            #+FOLLOW: carol https://carol.example/social.org";
+        let mut profile = profile_from(file);
+
+        // The two-tuple follow list is unchanged
+        assert_eq!(profile.follow().as_ref().unwrap().len(), 3);
+        assert_eq!(profile.follow().as_ref().unwrap()[1], (String::new(), "https://bob.example/social.org".to_string()));
+
+        let detailed = profile.follows_detailed();
+        assert_eq!(detailed[0].groups, vec!["rust".to_string(), "friends".to_string()]);
+        assert_eq!(detailed[1].groups, vec!["rust".to_string()]);
+        assert!(detailed[2].groups.is_empty());
+
+        let rust: Vec<String> = profile.follows_in_group("rust").iter().map(|follow| follow.display_nick().to_string()).collect();
+        assert_eq!(rust, vec!["alice".to_string(), "bob.example".to_string()]);
+
+        // Lossless round-trip
+        let serialized = profile.to_org_social();
+        assert!(serialized.contains("#+FOLLOW: alice https://alice.example/social.org rust,friends\n"));
+        assert_eq!(profile_from(&serialized), profile);
+
+        assert!(profile.set_follow_groups("https://carol.example/social.org/", vec!["friends".to_string()]));
+        assert!(!profile.set_follow_groups("https://dave.example/social.org", vec!["friends".to_string()]));
+        assert_eq!(profile.follows_in_group("friends").len(), 2);
+
+        // Local files followed with groups
+        for url in ["/home/me/social.org", "file:///home/me/social.org", "file:/home/me/social.org"] {
+            let profile = profile_from(&format!("#+NICK: me\n#+FOLLOW: {url} friends"));
+            let follow = &profile.follows_detailed()[0];
+            assert_eq!((follow.nick.as_str(), follow.url.as_str()), ("", url));
+            assert_eq!(follow.groups, vec!["friends".to_string()]);
+        }
+
+        // Groups of removed follows are dropped
+        profile.remove_follow_by_nick("alice");
+        profile.add_follow("alice".to_string(), "https://alice.example/social.org".to_string());
+        assert!(profile.follows_detailed()[2].groups.is_empty());
+    }
+
+    #[test]
+    fn test_mutes() {
+        let mut profile = profile_from("#+NICK: me\n#+MUTE: https://spam.example/social.org\n#+MUTE: @Troll");
+        assert_eq!(profile.mutes(), ["https://spam.example/social.org".to_string(), "@Troll".to_string()]);
+
+        let mut spam = Post::new("2025-05-01T12:00:00+0100".to_string(), "Buy now".to_string());
+        spam.set_source(Some("http://spam.example/social.org/".to_string()));
+        assert!(profile.is_muted(&spam));
+
+        let mut troll = Post::new("2025-05-01T12:00:00+0100".to_string(), "Hmm".to_string());
+        troll.set_author("troll".to_string());
+        assert!(profile.is_muted(&troll));
+
+        assert!(!profile.add_mute("@Troll".to_string()));
+        assert!(profile.remove_mute("@Troll"));
+        assert!(!profile.is_muted(&troll));
+        assert!(profile.add_mute("troll".to_string()));
+        assert!(profile.is_muted(&troll));
+
+        let reparsed = profile_from(&profile.to_org_social());
+        assert_eq!(reparsed, profile);
+    }
+
+    #[test]
+    fn test_profile_diff() {
+        let old = profile_from(
+            "#+TITLE: Alice\n#+NICK: alice\n#+AVATAR: https://alice.example/old.png\n#+LINK: https://alice.example\n\
+             #+FOLLOW: bob https://bob.example/social.org\n#+FOLLOW: carol https://carol.example/social.org",
+        );
+        let mut new = profile_from(
+            "#+TITLE: Alice\n#+NICK: alice\n#+AVATAR: https://alice.example/new.png\n#+LINK: https://alice.example\n\
+             #+FOLLOW: bobby https://bob.example/social.org/\n#+FOLLOW: dave https://dave.example/social.org",
+        );
+        new.set_source(Some("https://alice.example/social.org".to_string()));
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.changes,
+            vec![
+                ProfileChange::AvatarChanged {
+                    from: Some("https://alice.example/old.png".to_string()),
+                    to: Some("https://alice.example/new.png".to_string()),
+                },
+                ProfileChange::FollowRenamed {
+                    url: "https://bob.example/social.org/".to_string(),
+                    from: "bob".to_string(),
+                    to: "bobby".to_string(),
+                },
+                ProfileChange::FollowAdded("dave".to_string(), "https://dave.example/social.org".to_string()),
+                ProfileChange::FollowRemoved("carol".to_string(), "https://carol.example/social.org".to_string()),
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "Avatar changed\n\
+             Renamed bob to bobby (https://bob.example/social.org/)\n\
+             Followed dave (https://dave.example/social.org)\n\
+             Unfollowed carol (https://carol.example/social.org)"
+        );
+
+        // The source is ignored
+        assert_eq!(new.diff(&old).changes.len(), 4);
+        let mut same = old.clone();
+        same.set_source(Some("https://elsewhere.example/social.org".to_string()));
+        assert!(old.diff(&same).is_empty());
+    }
+
+    #[test]
+    fn test_resolved_urls() {
+        let mut profile = profile_from(
+            "#+AVATAR: ../img/avatar.png?size=64\n#+LINK: https://other.example/page\n#+LINK: /blog\n#+LINK: ./about.html\n#+LINK: notes/",
+        );
+        assert_eq!(profile.resolved_avatar().as_deref(), Some("../img/avatar.png?size=64"));
+
+        profile.set_source(Some("https://alice.example/social/social.org".to_string()));
+        assert_eq!(profile.resolved_avatar().as_deref(), Some("https://alice.example/img/avatar.png?size=64"));
+        assert_eq!(
+            profile.resolved_links(),
+            vec![
+                "https://other.example/page".to_string(),
+                "https://alice.example/blog".to_string(),
+                "https://alice.example/social/about.html".to_string(),
+                "https://alice.example/social/notes/".to_string(),
+            ]
+        );
+
+        // Absolute values are passed through unchanged, not re-serialized
+        let absolute = profile_from("#+AVATAR: HTTPS://Alice.example/a.png");
+        assert_eq!(absolute.resolved_avatar().as_deref(), Some("HTTPS://Alice.example/a.png"));
+        assert_eq!(Profile::default().resolved_avatar(), None);
+    }
+
+    #[test]
+    fn test_source_base_and_full_ids() {
+        let post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Mine".to_string());
+
+        let mut profile = Profile::default();
+        assert_eq!(profile.source_base(), None);
+        assert_eq!(profile.full_id_for(&post), None);
+
+        profile.set_source(Some("https://Me.example/social.org/#top".to_string()));
+        assert_eq!(profile.source_base().as_deref(), Some("https://me.example/social.org"));
+        assert_eq!(profile.full_id_for(&post).as_deref(), Some("https://me.example/social.org#2025-05-01T12:00:00+0100"));
+
+        // Replying to a post without a source uses the owner's profile
+        let reply = crate::new_post::NewPostState::reply_to_profile_post(post.clone(), &profile);
+        assert_eq!(reply.reply_to.as_deref(), Some("https://me.example/social.org#2025-05-01T12:00:00+0100"));
+
+        let mut sourced = post;
+        sourced.set_source(Some("https://other.example/social.org".to_string()));
+        let reply = crate::new_post::NewPostState::reply_to_profile_post(sourced, &profile);
+        assert_eq!(reply.reply_to.as_deref(), Some("https://other.example/social.org#2025-05-01T12:00:00+0100"));
     }
 }
@@ -0,0 +1,306 @@
+//! Signing module for org-social posts.
+//!
+//! This module provides optional signing and verification of posts, available with the `signing` feature.
+//! Signatures are stored in the `:SIGNATURE:` post property as `algorithm:base64`, and the public key
+//! used to verify them is advertised in the profile with `#+PUBLIC_KEY: algorithm:base64`.
+//!
+//! ## Canonical form
+//!
+//! The signature is computed over a canonical serialization of the post, so that
+//! independent implementations can produce and check the same signatures:
+//!
+//! 1. The first line is the version marker `org-social-signature-v1`.
+//! 2. Then one `NAME: value` line for each of `ID`, `LANG`, `TAGS`, `REPLY_TO`, `POLL_END`,
+//!    `POLL_OPTION`, `MOOD`, `SHARE`, `REACTION`, `CW`, `MEDIA` and `DELETED`, in this order.
+//!    - Values are trimmed, a missing value is written as empty (`LANG: `).
+//!    - `TAGS` and `MEDIA` are joined with single spaces, in their original order.
+//!    - `DELETED` is `t` for tombstones and empty otherwise.
+//! 3. An empty line, followed by the content with `\r\n` converted to `\n` and trailing whitespace removed.
+//!
+//! Lines are joined with `\n` (no trailing newline) and the result is encoded as UTF-8.
+//! `CLIENT`, `SIGNATURE` and any unknown properties are not covered by the signature.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+use crate::post::Post;
+use crate::profile::Profile;
+
+/// Version marker starting the canonical form.
+const CANONICAL_VERSION: &str = "org-social-signature-v1";
+
+/// Something that can sign messages, such as a private key.
+pub trait Signer {
+    /// Algorithm identifier written in front of the signature, e.g. `ed25519`.
+    fn algorithm(&self) -> &str;
+    /// Sign the message, returning the raw signature bytes.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// Something that can verify signatures, such as a public key.
+pub trait Verifier {
+    /// Algorithm identifier this verifier accepts, e.g. `ed25519`.
+    fn algorithm(&self) -> &str;
+    /// Check the raw signature bytes against the message.
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Result of verifying a post's signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyResult {
+    /// The signature matches the post.
+    Valid,
+    /// The signature does not match - the post was modified or signed with another key.
+    Invalid,
+    /// The post has no `:SIGNATURE:` property.
+    Unsigned,
+    /// The `:SIGNATURE:` property is not in the `algorithm:base64` format.
+    Malformed,
+    /// The post was signed with an algorithm the verifier doesn't handle.
+    UnsupportedAlgorithm(String),
+}
+
+/// Build the canonical form of a post, as described in the module documentation.
+pub fn canonical_form(post: &Post) -> String {
+    fn value(field: &Option<String>) -> &str {
+        field.as_deref().map(str::trim).unwrap_or("")
+    }
+
+    let tags = post.tags().as_ref().map(|tags| tags.join(" ")).unwrap_or_default();
+    let media = post.media().join(" ");
+    let content = post.content().replace("\r\n", "\n");
+
+    let lines = [
+        CANONICAL_VERSION.to_string(),
+        format!("ID: {}", post.id().trim()),
+        format!("LANG: {}", value(post.lang())),
+        format!("TAGS: {}", tags.trim()),
+        format!("REPLY_TO: {}", value(post.reply_to())),
+        format!("POLL_END: {}", value(post.poll_end())),
+        format!("POLL_OPTION: {}", value(post.poll_option())),
+        format!("MOOD: {}", value(post.mood())),
+        format!("SHARE: {}", value(post.shared_post_id())),
+        format!("REACTION: {}", value(post.reaction())),
+        format!("CW: {}", post.content_warning().unwrap_or("").trim()),
+        format!("MEDIA: {}", media.trim()),
+        format!("DELETED: {}", if post.is_deleted() { "t" } else { "" }),
+        String::new(),
+        content.trim_end().to_string(),
+    ];
+
+    lines.join("\n")
+}
+
+/// Sign a post, returning the value for its `:SIGNATURE:` property.
+pub fn sign_post(post: &Post, signer: &dyn Signer) -> String {
+    let signature = signer.sign(canonical_form(post).as_bytes());
+    format!("{}:{}", signer.algorithm(), BASE64.encode(signature))
+}
+
+/// Verify the `:SIGNATURE:` property of a post.
+pub fn verify_post(post: &Post, verifier: &dyn Verifier) -> VerifyResult {
+    let signature = match post.signature() {
+        Some(signature) => signature,
+        None => return VerifyResult::Unsigned,
+    };
+
+    let (algorithm, encoded) = match split_algorithm(signature) {
+        Some(parts) => parts,
+        None => return VerifyResult::Malformed,
+    };
+
+    if algorithm != verifier.algorithm() {
+        return VerifyResult::UnsupportedAlgorithm(algorithm.to_string());
+    }
+
+    let signature_bytes = match BASE64.decode(encoded) {
+        Ok(bytes) => bytes,
+        Err(_) => return VerifyResult::Malformed,
+    };
+
+    if verifier.verify(canonical_form(post).as_bytes(), &signature_bytes) {
+        VerifyResult::Valid
+    } else {
+        VerifyResult::Invalid
+    }
+}
+
+/// Split an `algorithm:base64` value into its parts.
+fn split_algorithm(value: &str) -> Option<(&str, &str)> {
+    let (algorithm, encoded) = value.trim().split_once(':')?;
+    if algorithm.is_empty() || encoded.is_empty() {
+        None
+    } else {
+        Some((algorithm, encoded))
+    }
+}
+
+/// Ed25519 private key signer.
+pub struct Ed25519Signer {
+    key: SigningKey,
+}
+
+impl Ed25519Signer {
+    pub const ALGORITHM: &'static str = "ed25519";
+
+    /// Create a signer from a 32 byte secret key seed.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(seed),
+        }
+    }
+
+    /// The matching public key, in the format used by the `#+PUBLIC_KEY` profile keyword.
+    pub fn public_key(&self) -> String {
+        format!("{}:{}", Self::ALGORITHM, BASE64.encode(self.key.verifying_key().as_bytes()))
+    }
+}
+
+impl Signer for Ed25519Signer {
+    fn algorithm(&self) -> &str {
+        Self::ALGORITHM
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        use ed25519_dalek::Signer as _;
+        self.key.sign(message).to_bytes().to_vec()
+    }
+}
+
+/// Ed25519 public key verifier.
+pub struct Ed25519Verifier {
+    key: VerifyingKey,
+}
+
+impl Ed25519Verifier {
+    /// Create a verifier from an `ed25519:base64` public key.
+    ///
+    /// Returns None if the key is for another algorithm or can't be decoded.
+    pub fn from_public_key(public_key: &str) -> Option<Self> {
+        let (algorithm, encoded) = split_algorithm(public_key)?;
+        if algorithm != Ed25519Signer::ALGORITHM {
+            return None;
+        }
+
+        let bytes: [u8; 32] = BASE64.decode(encoded).ok()?.try_into().ok()?;
+        let key = VerifyingKey::from_bytes(&bytes).ok()?;
+        Some(Self { key })
+    }
+
+    /// Create a verifier from the `#+PUBLIC_KEY` advertised in a profile.
+    pub fn from_profile(profile: &Profile) -> Option<Self> {
+        Self::from_public_key(profile.public_key()?)
+    }
+}
+
+impl Verifier for Ed25519Verifier {
+    fn algorithm(&self) -> &str {
+        Ed25519Signer::ALGORITHM
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        let signature = match Signature::from_slice(signature) {
+            Ok(signature) => signature,
+            Err(_) => return false,
+        };
+        self.key.verify_strict(message, &signature).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_post() -> Post {
+        let mut post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Signed content\r\nSecond line\n\n".to_string());
+        post.set_lang(Some("en".to_string()));
+        post.set_tags(Some(vec!["rust".to_string(), "org".to_string()]));
+        post.set_client(Some("org-social-rs".to_string()));
+        post
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let expected = "org-social-signature-v1\n\
+            ID: 2025-05-01T12:00:00+0100\n\
+            LANG: en\n\
+            TAGS: rust org\n\
+            REPLY_TO: \n\
+            POLL_END: \n\
+            POLL_OPTION: \n\
+            MOOD: \n\
+            SHARE: \n\
+            REACTION: \n\
+            CW: \n\
+            MEDIA: \n\
+            DELETED: \n\
+            \n\
+            Signed content\n\
+            Second line";
+        assert_eq!(canonical_form(&sample_post()), expected);
+    }
+
+    #[test]
+    fn test_canonical_form_ignores_client_and_signature() {
+        let post = sample_post();
+        let mut other = sample_post();
+        other.set_client(Some("another-client".to_string()));
+        other.set_signature(Some("ed25519:AAAA".to_string()));
+        assert_eq!(canonical_form(&post), canonical_form(&other));
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = Ed25519Signer::from_seed(&[7; 32]);
+        let verifier = Ed25519Verifier::from_public_key(&signer.public_key()).unwrap();
+
+        let mut post = sample_post();
+        assert_eq!(post.verify(&verifier), VerifyResult::Unsigned);
+
+        post.sign(&signer);
+        assert!(post.signature().as_ref().unwrap().starts_with("ed25519:"));
+        assert_eq!(post.verify(&verifier), VerifyResult::Valid);
+
+        // Signature survives serialization
+        let reparsed = Post::from(post.to_org_social().lines().map(String::from).collect::<Vec<String>>());
+        assert_eq!(reparsed.verify(&verifier), VerifyResult::Valid);
+
+        post.set_content("Tampered content".to_string());
+        assert_eq!(post.verify(&verifier), VerifyResult::Invalid);
+    }
+
+    #[test]
+    fn test_verify_with_profile_key() {
+        let signer = Ed25519Signer::from_seed(&[1; 32]);
+        let profile_lines = vec![
+            "#+TITLE: Signed".to_string(),
+            "#+NICK: signer".to_string(),
+            format!("#+PUBLIC_KEY: {}", signer.public_key()),
+        ];
+        let profile = Profile::from(profile_lines);
+        let verifier = Ed25519Verifier::from_profile(&profile).unwrap();
+
+        let mut post = sample_post();
+        post.sign(&signer);
+        assert_eq!(post.verify(&verifier), VerifyResult::Valid);
+
+        let other_verifier = Ed25519Verifier::from_public_key(&Ed25519Signer::from_seed(&[2; 32]).public_key()).unwrap();
+        assert_eq!(post.verify(&other_verifier), VerifyResult::Invalid);
+    }
+
+    #[test]
+    fn test_malformed_and_unsupported_signatures() {
+        let verifier = Ed25519Verifier::from_public_key(&Ed25519Signer::from_seed(&[7; 32]).public_key()).unwrap();
+
+        let mut post = sample_post();
+        post.set_signature(Some("no-algorithm".to_string()));
+        assert_eq!(post.verify(&verifier), VerifyResult::Malformed);
+
+        post.set_signature(Some("ed25519:not base64!".to_string()));
+        assert_eq!(post.verify(&verifier), VerifyResult::Malformed);
+
+        post.set_signature(Some("rsa:AAAA".to_string()));
+        assert_eq!(post.verify(&verifier), VerifyResult::UnsupportedAlgorithm("rsa".to_string()));
+    }
+}
@@ -0,0 +1,127 @@
+//! Helpers shared by the tests of several modules.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Start a minimal HTTP server answering each request with the raw response returned by `handler` for its path.
+///
+/// Returns the base URL of the server, e.g. `http://127.0.0.1:12345`.
+pub async fn stub_server(handler: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static) -> String {
+    stub_server_with_request(move |path, _| handler(path)).await
+}
+
+/// Like [`stub_server`], but the handler also gets the raw request, e.g. to check request headers or the body.
+pub async fn stub_server_with_request(handler: impl Fn(&str, &str) -> Vec<u8> + Send + Sync + 'static) -> String {
+    delayed_stub_server(|_| Duration::ZERO, handler).await
+}
+
+/// Like [`stub_server_with_request`], but each response is sent after the delay returned by `delay` for its path.
+pub async fn delayed_stub_server(
+    delay: impl Fn(&str) -> Duration + Send + Sync + 'static,
+    handler: impl Fn(&str, &str) -> Vec<u8> + Send + Sync + 'static,
+) -> String {
+    serve(delay, handler, None).await.0
+}
+
+/// Like [`stub_server`], also returning the task accepting connections: once it's aborted, connections are refused.
+pub async fn abortable_stub_server(handler: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static) -> (String, JoinHandle<()>) {
+    serve(|_| Duration::ZERO, move |path, _| handler(path), None).await
+}
+
+/// Like [`stub_server`], keeping connections open for the following requests, as long as the responses
+/// are built with [`keep_alive_response`]. Also returns the number of connections accepted so far.
+pub async fn keep_alive_stub_server(handler: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static) -> (String, Arc<AtomicUsize>) {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let (base, _) = serve(|_| Duration::ZERO, move |path, _| handler(path), Some(connections.clone())).await;
+    (base, connections)
+}
+
+async fn serve(
+    delay: impl Fn(&str) -> Duration + Send + Sync + 'static,
+    handler: impl Fn(&str, &str) -> Vec<u8> + Send + Sync + 'static,
+    keep_alive: Option<Arc<AtomicUsize>>,
+) -> (String, JoinHandle<()>) {
+    let delay = Arc::new(delay);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    let server = tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            let delay = delay.clone();
+            if let Some(connections) = &keep_alive {
+                connections.fetch_add(1, Ordering::SeqCst);
+            }
+            let keep_alive = keep_alive.is_some();
+            tokio::spawn(async move {
+                let mut received = Vec::new();
+                while let Some(request) = read_request(&mut stream, &mut received).await {
+                    let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                    tokio::time::sleep(delay(&path)).await;
+                    if stream.write_all(&handler(&path, &request)).await.is_err() || !keep_alive {
+                        break;
+                    }
+                }
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    (format!("http://{address}"), server)
+}
+
+/// Read the next request of a connection, with its body so the connection isn't reset when it's closed.
+/// `received` keeps what was read past it. None once the connection is closed.
+async fn read_request(stream: &mut tokio::net::TcpStream, received: &mut Vec<u8>) -> Option<String> {
+    let mut buffer = [0; 1024];
+    let head_length = loop {
+        if let Some(index) = received.windows(4).position(|window| window == b"\r\n\r\n") {
+            break index + 4;
+        }
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => received.extend_from_slice(&buffer[..read]),
+        }
+    };
+    let body_length = String::from_utf8_lossy(&received[..head_length])
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length").then(|| value.trim().parse::<usize>().ok())?
+        })
+        .unwrap_or(0);
+    while received.len() < head_length + body_length {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => received.extend_from_slice(&buffer[..read]),
+        }
+    }
+    let rest = received.split_off(head_length + body_length);
+    let request = String::from_utf8_lossy(received).into_owned();
+    *received = rest;
+    Some(request)
+}
+
+/// Build a raw HTTP response.
+pub fn http_response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut headers = headers.to_vec();
+    headers.push(("Connection", "close"));
+    keep_alive_response(status, &headers, body)
+}
+
+/// Like [`http_response`], letting the client send more requests on the connection.
+pub fn keep_alive_response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut response = format!("HTTP/1.1 {status}\r\nContent-Length: {}\r\n", body.len());
+    for (name, value) in headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str("\r\n");
+    let mut response = response.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
@@ -3,9 +3,9 @@
 //! This module provides functionality to organize posts into threaded conversations
 //! based on reply relationships, creating hierarchical tree structures for display.
 
-use crate::{poll::Poll, post::Post};
+use crate::{feed::{Feed, FeedFilterSet}, poll::Poll, post::{self, Post}, util};
 use chrono::{DateTime, FixedOffset};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Represents a node in a threaded conversation tree.
 #[derive(Clone)]
@@ -18,16 +18,107 @@ pub struct ThreadNode {
     pub depth: usize,
     /// Latest activity time in this node's subtree (including this post and all replies)
     pub latest_activity_time: Option<DateTime<FixedOffset>>,
+    /// Reaction replies to this post, kept out of `replies` when `ThreadOptions::separate_reactions` is set
+    pub reaction_posts: Vec<Post>,
+    /// Full ID of the parent node in the tree, see `parent_id`
+    parent_id: Option<String>,
 }
 
+/// Options controlling how a `ThreadView` is built.
+#[derive(Debug, Clone)]
+pub struct ThreadOptions {
+    /// Move reaction replies (see `Post::reaction_to`) out of `ThreadNode::replies`
+    /// into `ThreadNode::reaction_posts`. Enabled by default.
+    pub separate_reactions: bool,
+    /// Order of the threads and of the replies in them, kept as posts are added.
+    pub sort_order: ThreadSortOrder,
+}
+
+impl Default for ThreadOptions {
+    fn default() -> Self {
+        Self {
+            separate_reactions: true,
+            sort_order: ThreadSortOrder::default(),
+        }
+    }
+}
+
+/// Order of the threads in a `ThreadView`, and of the replies within them.
+///
+/// Posts that compare equal are ordered by full ID, so the order doesn't depend on the order of the feed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadSortOrder {
+    pub roots: RootOrder,
+    pub replies: ReplyOrder,
+}
+
+/// Order of the root posts of a `ThreadView`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RootOrder {
+    /// Most recent activity anywhere in the thread first.
+    #[default]
+    LatestActivity,
+    /// Newest root post first.
+    CreationTime,
+    /// Most replies first, nested replies included.
+    ReplyCount,
+}
+
+/// Order of the replies to a post.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplyOrder {
+    /// Oldest first, by the latest activity in each reply's subtree.
+    #[default]
+    Chronological,
+    /// Newest first, by the latest activity in each reply's subtree.
+    ReverseChronological,
+}
+
+/// How `ThreadView::remove_post` deals with the replies of the removed post.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovePolicy {
+    /// The replies move up to the parent of the removed post, or become threads of their own.
+    PromoteChildren,
+    /// The replies are removed along with the post.
+    RemoveSubtree,
+    /// The post stays in the tree with its content blanked out, like a deleted post, so its replies keep their place.
+    Tombstone,
+}
+
+/// Error returned by `ThreadView::remove_post`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoveError {
+    /// No post with this full ID is in the threads.
+    NotFound(String),
+    /// The post is a placeholder for a missing post that still has replies, which can only be removed with them.
+    PlaceholderHasReplies(String),
+}
+
+impl std::fmt::Display for RemoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoveError::NotFound(id) => write!(f, "Post not found in the threads: {}", id),
+            RemoveError::PlaceholderHasReplies(id) => write!(f, "Placeholder still has replies: {}", id),
+        }
+    }
+}
+
+impl std::error::Error for RemoveError {}
+
 /// Represents a collection of threaded conversations.
+///
+/// The view owns copies of its posts and does not borrow the feed it was built from, so it can be
+/// kept next to a feed that gets refreshed, and brought up to date with `merge_posts`.
+#[derive(Clone)]
 pub struct ThreadView {
     /// Root posts (posts that are not replies to anything)
     pub roots: Vec<ThreadNode>,
     /// Map of post IDs to their full identifiers for quick lookup
     id_map: HashMap<String, String>,
-    /// Temporary map for placeholder posts during construction
-    placeholder_map: HashMap<String, ThreadNode>,
+    /// Full IDs of the placeholder roots standing for missing posts, replaced once the posts are added
+    placeholders: HashSet<String>,
+    /// Options the view was built with
+    options: ThreadOptions,
 }
 
 impl ThreadNode {
@@ -38,13 +129,23 @@ impl ThreadNode {
             replies: Vec::new(),
             depth,
             latest_activity_time,
+            reaction_posts: Vec::new(),
+            parent_id: None,
         }
     }
 
-    pub fn add_reply(&mut self, reply_node: ThreadNode) {
+    pub fn add_reply(&mut self, mut reply_node: ThreadNode) {
+        reply_node.parent_id = Some(self.post.full_id());
         self.replies.push(reply_node);
     }
 
+    /// Full ID of the post this node hangs under in the tree, None for a root.
+    ///
+    /// This is the resolved parent, which may be a placeholder, rather than the `reply_to` of the post.
+    pub fn parent_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+
     /// Calculate and update the latest activity time for this node and all its descendants.
     /// This should be called after the tree structure is complete.
     pub fn update_latest_activity_time(&mut self) {
@@ -59,10 +160,8 @@ impl ThreadNode {
         // Check all replies for later times
         for reply in &self.replies {
             match (latest_time, reply.latest_activity_time) {
-                (Some(current), Some(reply_time)) => {
-                    if reply_time > current {
-                        latest_time = Some(reply_time);
-                    }
+                (Some(current), Some(reply_time)) if reply_time > current => {
+                    latest_time = Some(reply_time);
                 }
                 (None, Some(reply_time)) => {
                     latest_time = Some(reply_time);
@@ -75,18 +174,62 @@ impl ThreadNode {
     }
 
     pub fn sort_replies(&mut self) {
+        self.sort_replies_by(ReplyOrder::Chronological);
+    }
+
+    /// Sort the replies to this post, and the replies below them, in the given order.
+    pub fn sort_replies_by(&mut self, order: ReplyOrder) {
+        let newest_first = order == ReplyOrder::ReverseChronological;
         self.replies.sort_by(|a, b| {
-            match (a.latest_activity_time, b.latest_activity_time) {
-                (Some(time_a), Some(time_b)) => time_a.cmp(&time_b), // Chronological order
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
+            util::cmp_optional_times(a.latest_activity_time, b.latest_activity_time, newest_first)
+                .then_with(|| a.post.full_id().cmp(&b.post.full_id()))
         });
         
         // Recursively sort replies of replies
         for reply in &mut self.replies {
-            reply.sort_replies();
+            reply.sort_replies_by(order);
+        }
+    }
+
+    /// Count reactions to this post per reaction (emoji).
+    ///
+    /// Includes reactions that were separated into `reaction_posts` as well as any left in `replies`.
+    pub fn reactions(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        let reaction_replies = self.replies.iter().map(|reply| &reply.post);
+        for post in self.reaction_posts.iter().chain(reaction_replies) {
+            if let (true, Some(reaction)) = (post.is_reaction(), post.reaction()) {
+                *counts.entry(reaction.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Set the depth of this node, and the depths and parent IDs of its replies below it.
+    ///
+    /// Called once the tree is assembled, so the depths follow the final structure.
+    fn assign_depths(&mut self, depth: usize) {
+        let mut stack = vec![(self, depth)];
+        while let Some((node, depth)) = stack.pop() {
+            node.depth = depth;
+            let full_id = node.post.full_id();
+            for reply in &mut node.replies {
+                reply.parent_id = Some(full_id.clone());
+            }
+            stack.extend(node.replies.iter_mut().map(|reply| (reply, depth + 1)));
+        }
+    }
+
+    /// Recursively move reaction replies without replies of their own into `reaction_posts`.
+    fn separate_reactions(&mut self) {
+        let replies = std::mem::take(&mut self.replies);
+        for mut reply in replies {
+            if reply.post.is_reaction() && reply.replies.is_empty() {
+                self.reaction_posts.push(reply.post);
+            } else {
+                reply.separate_reactions();
+                self.replies.push(reply);
+            }
         }
     }
 
@@ -108,7 +251,8 @@ impl ThreadView {
         Self {
             roots: Vec::new(),
             id_map: HashMap::new(),
-            placeholder_map: HashMap::new(),
+            placeholders: HashSet::new(),
+            options: ThreadOptions::default(),
         }
     }
 
@@ -125,72 +269,134 @@ impl ThreadView {
     /// # Returns
     /// A ThreadView containing the organized conversation trees.
     pub fn from_posts(posts: Vec<Post>) -> Self {
+        Self::from_posts_with_options(posts, ThreadOptions::default())
+    }
+
+    /// Create a threaded view from a collection of posts, see `from_posts`.
+    ///
+    /// # Arguments
+    /// * `posts` - Vector of posts to organize into threads
+    /// * `options` - Options controlling how the threads are built
+    pub fn from_posts_with_options(posts: Vec<Post>, options: ThreadOptions) -> Self {
         let mut thread_view = Self::new();
-        let mut post_map: HashMap<String, ThreadNode> = HashMap::new();
-        let mut reply_map: HashMap<String, Vec<ThreadNode>> = HashMap::new();
+        thread_view.options = options;
 
-        // Build ID mapping for quick lookups
-        for post in &posts {
-            let full_id = post.full_id();
-            thread_view.id_map.insert(post.id().to_string(), full_id);
-        }
+        // Build ID mapping for quick lookups, and index the posts by full ID
+        let (id_map, index_of) = Self::index_posts(&posts);
+        thread_view.id_map = id_map;
 
-        // First pass: create nodes for all posts
+        // First pass: find the parent of each post, creating placeholders for missing parents.
+        // Nodes are kept in a single arena, placeholders after the posts. Deleted posts become placeholders.
+        let mut nodes: Vec<Option<ThreadNode>> = Vec::with_capacity(posts.len());
+        let mut reply_targets = Vec::with_capacity(posts.len());
         for post in posts {
-            let node = ThreadNode::new(post.clone(), 0);
-            let full_id = post.full_id();
-            post_map.insert(full_id, node);
-        }
-
-        // Second pass: organize into threads and create placeholders for missing parents
-        let post_map_clone = post_map.clone();
-        for (_post_id, mut node) in post_map {
-            if let Some(reply_to) = node.post.reply_to() {
-                // This is a reply to another post
-                let reply_target = Self::resolve_reply_target(reply_to, &thread_view.id_map);
-                
-                if let Some(parent_node) = post_map_clone.get(&reply_target) {
-                    // Parent exists, add to reply map
-                    node.depth = parent_node.depth + 1;
-                    reply_map.entry(reply_target).or_default().push(node);
-                } else {
-                    // Parent doesn't exist, try timestamp-based fallback
-                    if let Some(fallback_target) = Self::find_by_timestamp_fallback(&reply_target, &post_map_clone) {
-                        // Found a post with matching timestamp, use it
-                        node.depth = post_map_clone.get(&fallback_target).unwrap().depth + 1;
-                        reply_map.entry(fallback_target).or_default().push(node);
-                    } else {
-                        // No match found even by timestamp, create a placeholder
-                        let placeholder_post = Self::create_placeholder_post(&reply_target);
-                        let placeholder_node = ThreadNode::new(placeholder_post.clone(), 0);
-                        node.depth = 1; // Reply to placeholder at depth 0
-                        
-                        // Add placeholder to placeholder_map and this node as its reply
-                        thread_view.placeholder_map.insert(reply_target.clone(), placeholder_node);
-                        reply_map.entry(reply_target).or_default().push(node);
-                    }
-                }
-            } else {
+            let post = if post.is_deleted() { Self::create_deleted_placeholder(&post) } else { post };
+            reply_targets.push(post.reply_to().clone());
+            nodes.push(Some(ThreadNode::new(post, 0)));
+        }
+
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        let mut roots = Vec::new();
+        let mut placeholders: HashMap<String, usize> = HashMap::new();
+        for (index, reply_to) in reply_targets.into_iter().enumerate() {
+            let Some(reply_to) = reply_to else {
                 // This is a root post
-                thread_view.roots.push(node);
-            }
+                roots.push(index);
+                continue;
+            };
+
+            let parent = Self::resolve_parent(&reply_to, &thread_view.id_map, &index_of).unwrap_or_else(|reply_target| {
+                // No match found even by timestamp, the reply goes under a placeholder
+                *placeholders.entry(reply_target).or_insert_with_key(|reply_target| {
+                    let placeholder = Self::create_placeholder_post(reply_target);
+                    thread_view.placeholders.insert(placeholder.full_id());
+                    nodes.push(Some(ThreadNode::new(placeholder, 0)));
+                    children.push(Vec::new());
+                    roots.push(nodes.len() - 1);
+                    nodes.len() - 1
+                })
+            });
+            children[parent].push(index);
         }
 
-        // Third pass: attach replies to their parents (including placeholders)
-        thread_view.attach_replies(&reply_map);
+        // Second pass: move the replies into their parents, from the deepest up.
+        // Replies that loop aren't reachable from a root, and are left out.
+        let mut order = Vec::with_capacity(nodes.len());
+        let mut stack = roots.clone();
+        while let Some(index) = stack.pop() {
+            order.push(index);
+            stack.extend(&children[index]);
+        }
+        for &index in order.iter().rev() {
+            let replies = children[index].iter().filter_map(|&child| nodes[child].take()).collect();
+            if let Some(node) = &mut nodes[index] {
+                node.replies = replies;
+            }
+        }
+        thread_view.roots = roots.into_iter().filter_map(|root| nodes[root].take()).collect();
 
-        // Fourth pass: attach replies to placeholder nodes and move them to roots
-        for (_, mut placeholder_node) in thread_view.placeholder_map.clone() {
-            Self::attach_replies_to_node(&mut placeholder_node, &reply_map);
-            thread_view.roots.push(placeholder_node);
+        if thread_view.options.separate_reactions {
+            for root in &mut thread_view.roots {
+                root.separate_reactions();
+            }
         }
 
-        // Sort all threads
+        // Sort all threads, setting the depths of the assembled trees
         thread_view.sort_threads();
 
         thread_view
     }
 
+    /// Create a threaded view of a feed's posts, copying each post once.
+    ///
+    /// To build the view without copying the posts, use `ThreadView::from_posts(feed.into_posts())`.
+    pub fn from_feed(feed: &Feed) -> Self {
+        Self::from_posts(feed.posts.clone())
+    }
+
+    /// Create a threaded view of a feed's posts, see `from_feed` and `from_posts_with_options`.
+    ///
+    /// # Arguments
+    /// * `feed` - The feed whose posts are threaded
+    /// * `options` - Options controlling how the threads are built and sorted
+    pub fn from_feed_with_options(feed: &Feed, options: ThreadOptions) -> Self {
+        Self::from_posts_with_options(feed.posts.clone(), options)
+    }
+
+    /// Create a threaded view of the feed's posts not hidden by `filters`, see [`Feed::filtered_view`].
+    ///
+    /// Muting a thread root makes the whole thread disappear, instead of leaving its replies behind a placeholder.
+    pub fn from_feed_filtered(feed: &Feed, filters: &FeedFilterSet) -> Self {
+        Self::from_posts(feed.filtered_view(filters).into_iter().cloned().collect())
+    }
+
+    pub fn options(&self) -> &ThreadOptions {
+        &self.options
+    }
+
+    /// Map the bare IDs of posts to their full IDs, and index the posts by full ID (the first one for duplicates).
+    fn index_posts(posts: &[Post]) -> (HashMap<String, String>, HashMap<String, usize>) {
+        let mut id_map = HashMap::with_capacity(posts.len());
+        let mut index_of = HashMap::with_capacity(posts.len());
+        for (index, post) in posts.iter().enumerate() {
+            let full_id = post.full_id();
+            id_map.insert(post.id().to_string(), full_id.clone());
+            index_of.entry(full_id).or_insert(index);
+        }
+        (id_map, index_of)
+    }
+
+    /// Find the index of the post a reply_to target refers to: the post with the full ID, or one with a matching timestamp.
+    ///
+    /// Returns the resolved target if no post matches, for a placeholder to stand for it.
+    fn resolve_parent(reply_to: &str, id_map: &HashMap<String, String>, index_of: &HashMap<String, usize>) -> Result<usize, String> {
+        let reply_target = Self::resolve_reply_target(reply_to, id_map);
+        index_of.get(&reply_target).copied().or_else(|| {
+            let fallback_target = Self::find_by_timestamp_fallback(&reply_target, id_map)?;
+            index_of.get(&fallback_target).copied()
+        }).ok_or(reply_target)
+    }
+
     /// Resolve a reply_to target to a full post identifier.
     fn resolve_reply_target(reply_to: &str, id_map: &HashMap<String, String>) -> String {
         if reply_to.contains('#') {
@@ -209,11 +415,11 @@ impl ThreadView {
     ///
     /// # Arguments
     /// * `reply_target` - The full reply target that couldn't be found
-    /// * `post_map` - Map of all available posts
+    /// * `id_map` - Map of the available post IDs to their full identifiers
     ///
     /// # Returns
     /// The full ID of a matching post if found, None otherwise
-    fn find_by_timestamp_fallback(reply_target: &str, post_map: &HashMap<String, ThreadNode>) -> Option<String> {
+    fn find_by_timestamp_fallback(reply_target: &str, id_map: &HashMap<String, String>) -> Option<String> {
         // Extract timestamp from reply target
         let timestamp = if reply_target.contains('#') {
             // Extract the part after the last '#' which should be the timestamp
@@ -223,14 +429,8 @@ impl ThreadView {
             reply_target
         };
 
-        // Search through all posts for one with this timestamp as the ID
-        for (full_id, node) in post_map {
-            if node.post.id() == timestamp {
-                return Some(full_id.clone());
-            }
-        }
-
-        None
+        // Look for a post with this timestamp as the ID
+        id_map.get(timestamp).cloned()
     }
 
     /// Create a placeholder post for missing reply targets.
@@ -243,7 +443,7 @@ impl ThreadView {
         };
         
         let mut placeholder = Post::new(placeholder_id, "[Post not available]".to_string());
-        placeholder.set_author("unknown".to_string());
+        placeholder.set_author(post::UNKNOWN_AUTHOR.to_string());
         
         // If the reply_target has a source part (before #), set it
         if let Some(hash_pos) = reply_target.find('#') {
@@ -256,48 +456,62 @@ impl ThreadView {
         placeholder
     }
 
-    /// Attach replies to their parent nodes recursively.
-    fn attach_replies(&mut self, reply_map: &HashMap<String, Vec<ThreadNode>>) {
-        // Attach replies to root nodes
-        for root in &mut self.roots {
-            Self::attach_replies_to_node(root, reply_map);
-        }
-    }
-
-    /// Recursively attach replies to a specific node.
-    fn attach_replies_to_node(node: &mut ThreadNode, reply_map: &HashMap<String, Vec<ThreadNode>>) {
-        let node_id = node.post.full_id();
-        if let Some(replies) = reply_map.get(&node_id) {
-            for mut reply in replies.clone() {
-                Self::attach_replies_to_node(&mut reply, reply_map);
-                node.add_reply(reply);
-            }
+    /// Create a placeholder for a deleted (tombstoned) post.
+    ///
+    /// The ID, source and reply target are kept so that the thread structure is preserved.
+    fn create_deleted_placeholder(tombstone: &Post) -> Post {
+        let mut placeholder = tombstone.clone();
+        placeholder.set_content("[Post deleted]".to_string());
+        if placeholder.author().is_none() {
+            placeholder.set_author(post::UNKNOWN_AUTHOR.to_string());
         }
+        placeholder
     }
 
-    /// Sort all threads and their replies chronologically.
+    /// Sort all threads and their replies in the order of the options, see `ThreadOptions::sort_order`.
+    ///
+    /// The depths and latest activity times of the nodes are updated first, so they match the threads as they are.
     pub fn sort_threads(&mut self) {
-        // First, update latest activity times for all threads
+        // First, update depths and latest activity times for all threads
         for root in &mut self.roots {
+            // A promoted reply of a removed post is a root now
+            root.parent_id = None;
+            root.assign_depths(0);
             root.update_latest_activity_time();
         }
         
-        // Sort root posts (latest activity first)
-        self.roots.sort_by(|a, b| {
-            match (a.latest_activity_time, b.latest_activity_time) {
-                (Some(time_a), Some(time_b)) => time_b.cmp(&time_a), // Reverse for latest activity first
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
-        });
+        // Sort root posts
+        let order = self.options.sort_order;
+        match order.roots {
+            RootOrder::LatestActivity => self.roots.sort_by(|a, b| {
+                util::cmp_optional_times(a.latest_activity_time, b.latest_activity_time, true)
+                    .then_with(|| a.post.full_id().cmp(&b.post.full_id()))
+            }),
+            RootOrder::CreationTime => self.roots.sort_by(|a, b| {
+                util::cmp_optional_times(a.post.time(), b.post.time(), true)
+                    .then_with(|| a.post.full_id().cmp(&b.post.full_id()))
+            }),
+            RootOrder::ReplyCount => self.roots.sort_by(|a, b| {
+                b.count_posts().cmp(&a.count_posts())
+                    .then_with(|| a.post.full_id().cmp(&b.post.full_id()))
+            }),
+        }
 
         // Sort replies within each thread
         for root in &mut self.roots {
-            root.sort_replies();
+            root.sort_replies_by(order.replies);
         }
     }
 
+    /// Sort the threads in another order, kept as posts are added afterwards, see `sort_threads`.
+    ///
+    /// # Arguments
+    /// * `order` - Order of the threads and of their replies
+    pub fn sort_threads_with(&mut self, order: ThreadSortOrder) {
+        self.options.sort_order = order;
+        self.sort_threads();
+    }
+
     pub fn thread_count(&self) -> usize {
         self.roots.len()
     }
@@ -330,85 +544,295 @@ impl ThreadView {
     /// If the post is a reply, it will be added to the appropriate parent node.
     /// If the parent doesn't exist, a placeholder will be created.
     /// If it's not a reply, it will be added as a new root thread.
+    /// If a placeholder stands for the post (by full ID, or by timestamp like `from_posts`), the post
+    /// replaces it with its replies, and moves under its own parent if it's a reply.
     /// 
     /// After adding the post, latest activity times will be updated and threads will be re-sorted.
     ///
     /// # Arguments
     /// * `post` - The new post to add to the thread tree
     pub fn add_post(&mut self, post: Post) {
-        if let Some(reply_to) = post.reply_to() {
-            let reply_target = Self::resolve_reply_target(reply_to, &self.id_map);
-            
-            // Try to find the parent in existing threads
-            if self.find_and_add_reply(&reply_target, post.clone()).is_some() {
-                self.id_map.insert(post.id().to_string(), post.full_id());
-                
-                self.sort_threads();
+        self.insert_post(post);
+        self.finish_insertions();
+    }
+
+    /// Add posts to the thread tree, like `add_post`, e.g. the posts of a feed loaded after others or of a refresh.
+    ///
+    /// Posts already in the tree replace the copy there, keeping its replies. The threads are sorted once, at the end.
+    ///
+    /// # Arguments
+    /// * `posts` - The posts to add to the thread tree
+    pub fn merge_posts(&mut self, posts: Vec<Post>) {
+        // Index the full IDs of the tree once, rather than searching it for every post
+        let mut known = HashSet::new();
+        let mut stack: Vec<&ThreadNode> = self.roots.iter().collect();
+        while let Some(node) = stack.pop() {
+            let full_id = node.post.full_id();
+            if !self.placeholders.contains(&full_id) {
+                known.insert(full_id);
+            }
+            stack.extend(&node.replies);
+        }
+
+        let mut updates = HashMap::new();
+        for post in posts {
+            let full_id = post.full_id();
+            if known.contains(&full_id) {
+                let post = if post.is_deleted() { Self::create_deleted_placeholder(&post) } else { post };
+                updates.insert(full_id, post);
             } else {
-                // Parent not found - create placeholder and add as new root thread
-                let placeholder_post = Self::create_placeholder_post(&reply_target);
-                let mut placeholder_node = ThreadNode::new(placeholder_post, 0);
-                
-                let reply_node = ThreadNode::new(post.clone(), 1);
-                placeholder_node.add_reply(reply_node);
-                
-                placeholder_node.update_latest_activity_time();
-                
-                self.roots.push(placeholder_node);
-                
-                self.id_map.insert(post.id().to_string(), post.full_id());
-                
-                // Resort threads
-                self.sort_threads();
+                known.insert(full_id);
+                self.insert_post(post);
             }
-        } else {
+        }
+
+        if !updates.is_empty() {
+            let mut stack: Vec<&mut ThreadNode> = self.roots.iter_mut().collect();
+            while let Some(node) = stack.pop() {
+                if let Some(post) = updates.remove(&node.post.full_id()) {
+                    node.post = post;
+                }
+                stack.extend(&mut node.replies);
+            }
+        }
+        self.finish_insertions();
+    }
+
+    /// Insert a post into the trees, without sorting them, see `add_post`.
+    fn insert_post(&mut self, post: Post) {
+        let post = if post.is_deleted() { Self::create_deleted_placeholder(&post) } else { post };
+        self.id_map.insert(post.id().to_string(), post.full_id());
+        let node = match self.take_placeholder_for(&post) {
+            Some(mut placeholder) => {
+                placeholder.post = post;
+                placeholder
+            }
+            None => ThreadNode::new(post, 0),
+        };
+
+        let Some(reply_to) = node.post.reply_to() else {
             // This is a root post
-            let new_root = ThreadNode::new(post.clone(), 0);
-            self.roots.push(new_root);
-            
-            self.id_map.insert(post.id().to_string(), post.full_id());
-            
-            // Resort threads
-            self.sort_threads();
+            self.roots.push(node);
+            return;
+        };
+        let reply_target = Self::resolve_reply_target(reply_to, &self.id_map);
+
+        // Try to find the parent in existing threads
+        if let Some(node) = self.find_and_add_reply(&reply_target, node) {
+            // Parent not found - the post goes under a placeholder, as a new root thread
+            let mut placeholder_node = ThreadNode::new(Self::create_placeholder_post(&reply_target), 0);
+            self.placeholders.insert(placeholder_node.post.full_id());
+            placeholder_node.add_reply(node);
+            self.roots.push(placeholder_node);
+        }
+    }
+
+    /// Remove a post from the threads, e.g. after it was deleted or muted.
+    ///
+    /// The depths, latest activity times and order of the threads are updated afterwards.
+    /// A placeholder standing for a missing post can't be removed while it has replies, unless they go with it.
+    ///
+    /// # Arguments
+    /// * `full_id` - Full ID of the post (`url#id` or `nick#id`)
+    /// * `policy` - What happens to the replies of the post
+    pub fn remove_post(&mut self, full_id: &str, policy: RemovePolicy) -> Result<(), RemoveError> {
+        let placeholder_with_replies = self.placeholders.contains(full_id)
+            && self.roots.iter().any(|root| root.post.full_id() == full_id && !root.replies.is_empty());
+        if placeholder_with_replies && policy != RemovePolicy::RemoveSubtree {
+            return Err(RemoveError::PlaceholderHasReplies(full_id.to_string()));
         }
+
+        if policy == RemovePolicy::Tombstone {
+            let node = self.roots.iter_mut().find_map(|root| Self::find_in_node_mut(root, full_id))
+                .ok_or_else(|| RemoveError::NotFound(full_id.to_string()))?;
+            node.post = Self::create_deleted_placeholder(&node.post);
+        } else {
+            let promote = policy == RemovePolicy::PromoteChildren;
+            let removed = match self.roots.iter().position(|root| root.post.full_id() == full_id) {
+                Some(index) => {
+                    let mut removed = self.roots.remove(index);
+                    if promote {
+                        self.roots.append(&mut removed.replies);
+                    }
+                    Some(removed)
+                }
+                None => self.roots.iter_mut().find_map(|root| Self::detach_from_node(root, full_id, promote)),
+            };
+            let removed = removed.ok_or_else(|| RemoveError::NotFound(full_id.to_string()))?;
+            self.placeholders.remove(full_id);
+            self.forget_ids(&removed);
+        }
+
+        self.sort_threads();
+        Ok(())
+    }
+
+    /// Take the node of a post out of the replies below a node, handing its own replies to its parent if `promote`.
+    fn detach_from_node(node: &mut ThreadNode, full_id: &str, promote: bool) -> Option<ThreadNode> {
+        if let Some(index) = node.replies.iter().position(|reply| reply.post.full_id() == full_id) {
+            let mut removed = node.replies.remove(index);
+            if promote {
+                node.replies.append(&mut removed.replies);
+            }
+            return Some(removed);
+        }
+        node.replies.iter_mut().find_map(|reply| Self::detach_from_node(reply, full_id, promote))
+    }
+
+    /// Drop the ID map entries of the posts in a removed subtree, reactions included.
+    fn forget_ids(&mut self, removed: &ThreadNode) {
+        let mut stack = vec![removed];
+        while let Some(node) = stack.pop() {
+            for post in std::iter::once(&node.post).chain(&node.reaction_posts) {
+                if self.id_map.get(post.id()) == Some(&post.full_id()) {
+                    self.id_map.remove(post.id());
+                }
+            }
+            stack.extend(&node.replies);
+        }
+    }
+
+    /// Separate reactions and sort the threads after posts were inserted.
+    fn finish_insertions(&mut self) {
+        if self.options.separate_reactions {
+            for root in &mut self.roots {
+                root.separate_reactions();
+            }
+        }
+        self.sort_threads();
+    }
+
+    /// Remove the placeholder root standing for `post`, if any: same full ID, or same timestamp ID.
+    fn take_placeholder_for(&mut self, post: &Post) -> Option<ThreadNode> {
+        let full_id = post.full_id();
+        let index = self.roots.iter().position(|root| {
+            let root_id = root.post.full_id();
+            self.placeholders.contains(&root_id) && (root_id == full_id || root.post.id() == post.id())
+        })?;
+        let placeholder = self.roots.swap_remove(index);
+        self.placeholders.remove(&placeholder.post.full_id());
+        Some(placeholder)
     }
 
-    /// Recursively search for a target post ID and add a reply to it.
-    /// Returns Some(depth) if the reply was successfully added, None if target not found.
-    fn find_and_add_reply(&mut self, target_id: &str, reply_post: Post) -> Option<usize> {
+    /// Find the node of a post by full ID within a node and its descendants.
+    fn find_in_node<'a>(node: &'a ThreadNode, full_id: &str) -> Option<&'a ThreadNode> {
+        if node.post.full_id() == full_id {
+            return Some(node);
+        }
+        node.replies.iter().find_map(|reply| Self::find_in_node(reply, full_id))
+    }
+
+    /// Find the node of a post by full ID within a node and its descendants, mutably.
+    fn find_in_node_mut<'a>(node: &'a mut ThreadNode, full_id: &str) -> Option<&'a mut ThreadNode> {
+        if node.post.full_id() == full_id {
+            return Some(node);
+        }
+        node.replies.iter_mut().find_map(|reply| Self::find_in_node_mut(reply, full_id))
+    }
+
+    /// Find the node of a post by full ID in all threads.
+    fn find_by_full_id(&self, full_id: &str) -> Option<&ThreadNode> {
+        self.roots.iter().find_map(|root| Self::find_in_node(root, full_id))
+    }
+
+    /// Resolve a full or bare post ID to the full ID of a node in the tree, like reply targets are resolved.
+    fn resolve_node_id(&self, id: &str) -> Option<String> {
+        let full_id = Self::resolve_reply_target(id, &self.id_map);
+        if self.find_by_full_id(&full_id).is_some() {
+            return Some(full_id);
+        }
+        Self::find_by_timestamp_fallback(&full_id, &self.id_map).filter(|fallback| self.find_by_full_id(fallback).is_some())
+    }
+
+    /// Find the node of a post in the threads.
+    ///
+    /// The ID is resolved like a `reply_to` target: a full ID (`url#id` or `nick#id`), a bare timestamp ID,
+    /// or, when the full ID isn't found, the post with the same timestamp ID from any source.
+    ///
+    /// # Arguments
+    /// * `id` - Full or bare ID of the post
+    pub fn find_node(&self, id: &str) -> Option<&ThreadNode> {
+        let full_id = self.resolve_node_id(id)?;
+        self.find_by_full_id(&full_id)
+    }
+
+    /// Find the node of a post in the threads mutably, see `find_node`.
+    ///
+    /// # Arguments
+    /// * `id` - Full or bare ID of the post
+    pub fn find_node_mut(&mut self, id: &str) -> Option<&mut ThreadNode> {
+        let full_id = self.resolve_node_id(id)?;
+        self.roots.iter_mut().find_map(|root| Self::find_in_node_mut(root, &full_id))
+    }
+
+    /// The posts a post replies to in the threads, from the root down to its parent.
+    ///
+    /// Placeholders for missing posts are part of the chain, so the missing context can be shown.
+    /// Empty for roots and for posts not in the threads.
+    ///
+    /// # Arguments
+    /// * `full_id` - Full or bare ID of the post, see `find_node`
+    pub fn ancestors(&self, full_id: &str) -> Vec<&Post> {
+        let Some(full_id) = self.resolve_node_id(full_id) else {
+            return Vec::new();
+        };
+
+        // Depth-first search keeping the path to the current node
+        let mut path: Vec<&ThreadNode> = Vec::new();
+        let mut stack: Vec<(&ThreadNode, usize)> = self.roots.iter().map(|root| (root, 0)).collect();
+        while let Some((node, level)) = stack.pop() {
+            path.truncate(level);
+            if node.post.full_id() == full_id {
+                return path.into_iter().map(|ancestor| &ancestor.post).collect();
+            }
+            path.push(node);
+            stack.extend(node.replies.iter().map(|reply| (reply, level + 1)));
+        }
+        Vec::new()
+    }
+
+    /// Copy of the subtree of a post, e.g. for a detail view of a reply and its replies, see `find_node`.
+    ///
+    /// # Arguments
+    /// * `id` - Full or bare ID of the post
+    pub fn subtree(&self, id: &str) -> Option<ThreadNode> {
+        self.find_node(id).cloned()
+    }
+
+    /// Whether a post is in the threads, see `find_node`.
+    ///
+    /// # Arguments
+    /// * `id` - Full or bare ID of the post
+    pub fn contains(&self, id: &str) -> bool {
+        self.resolve_node_id(id).is_some()
+    }
+
+    /// Add a node as a reply to the post with the target ID.
+    /// Returns the node back if the target was not found.
+    fn find_and_add_reply(&mut self, target_id: &str, reply_node: ThreadNode) -> Option<ThreadNode> {
+        // The node is only taken by the parent it's added to
+        let mut reply_node = Some(reply_node);
         for root in &mut self.roots {
-            if let Some(depth) = Self::find_and_add_reply_to_node(root, target_id, reply_post.clone()) {
-                return Some(depth);
+            if Self::find_and_add_reply_to_node(root, target_id, &mut reply_node) {
+                break;
             }
         }
-        None
+        reply_node
     }
 
     /// Recursively search within a specific node and its descendants for the target ID.
-    /// Returns Some(depth) if the reply was successfully added, None if target not found.
-    fn find_and_add_reply_to_node(node: &mut ThreadNode, target_id: &str, reply_post: Post) -> Option<usize> {
+    /// Returns whether the reply was added.
+    fn find_and_add_reply_to_node(node: &mut ThreadNode, target_id: &str, reply_node: &mut Option<ThreadNode>) -> bool {
         // Check if this node is the target
         if node.post.full_id() == target_id {
-            let reply_depth = node.depth + 1;
-            let reply_node = ThreadNode::new(reply_post, reply_depth);
-            node.add_reply(reply_node);
-            
-            // Update latest activity time for this node and propagate upwards
-            node.update_latest_activity_time();
-            
-            return Some(reply_depth);
-        }
-        
-        // Search in replies
-        for reply in &mut node.replies {
-            if let Some(depth) = Self::find_and_add_reply_to_node(reply, target_id, reply_post.clone()) {
-                // Update our latest activity time
-                node.update_latest_activity_time();
-                return Some(depth);
+            if let Some(reply_node) = reply_node.take() {
+                node.add_reply(reply_node);
             }
+            return true;
         }
-        
-        None
+
+        // Search in replies
+        node.replies.iter_mut().any(|reply| Self::find_and_add_reply_to_node(reply, target_id, reply_node))
     }
 }
 
@@ -418,6 +842,89 @@ impl Default for ThreadView {
     }
 }
 
+/// Build the thread of the conversation a post belongs to, without threading the rest of the feed.
+///
+/// The `reply_to` chain is followed up from the post to its root the same way `ThreadView::from_posts` links
+/// replies, with a placeholder standing for the topmost parent if it's missing from the feed. Only the posts
+/// below that root are copied into the thread, and its depths, activity times and reply order match the
+/// thread in a `ThreadView` of the whole feed.
+///
+/// # Arguments
+/// * `feed` - The feed the conversation is in
+/// * `post` - A post of the conversation, normally from the feed
+/// * `options` - Options controlling how the thread is built
+pub fn build_thread(feed: &Feed, post: &Post, options: ThreadOptions) -> ThreadNode {
+    let posts = &feed.posts;
+    let (id_map, index_of) = ThreadView::index_posts(posts);
+    let parent_of = |post: &Post| {
+        post.reply_to().as_deref().map(|reply_to| ThreadView::resolve_parent(reply_to, &id_map, &index_of))
+    };
+
+    // Walk up to the root, stopping if the chain loops back on itself
+    let start = index_of.get(&post.full_id()).copied();
+    let mut visited: HashSet<usize> = start.into_iter().collect();
+    let mut top = start;
+    let mut missing_root = None;
+    loop {
+        let current = top.map_or(post, |index| &posts[index]);
+        match parent_of(current) {
+            Some(Ok(parent)) if visited.insert(parent) => top = Some(parent),
+            Some(Err(reply_target)) => {
+                missing_root = Some(reply_target);
+                break;
+            }
+            _ => break,
+        }
+    }
+    let node_for = |post: &Post| {
+        let post = if post.is_deleted() { ThreadView::create_deleted_placeholder(post) } else { post.clone() };
+        ThreadNode::new(post, 0)
+    };
+    let (root_key, root) = match (missing_root, top) {
+        (Some(reply_target), _) => {
+            let placeholder = ThreadView::create_placeholder_post(&reply_target);
+            (Err(reply_target), ThreadNode::new(placeholder, 0))
+        }
+        (None, Some(index)) => (Ok(index), node_for(&posts[index])),
+        (None, None) => (Err(post.full_id()), node_for(post)),
+    };
+
+    // Group the replies of the feed by parent, then collect the ones below the root
+    let mut children: HashMap<Result<usize, String>, Vec<usize>> = HashMap::new();
+    for (index, reply) in posts.iter().enumerate() {
+        if let Some(parent) = parent_of(reply) {
+            children.entry(parent).or_default().push(index);
+        }
+    }
+    let mut nodes = vec![Some(root)];
+    let mut parents = vec![0];
+    let mut reached: HashSet<usize> = root_key.iter().copied().collect();
+    let mut stack = vec![(root_key, 0)];
+    while let Some((key, position)) = stack.pop() {
+        for &child in children.get(&key).into_iter().flatten() {
+            if reached.insert(child) {
+                nodes.push(Some(node_for(&posts[child])));
+                parents.push(position);
+                stack.push((Ok(child), nodes.len() - 1));
+            }
+        }
+    }
+
+    // Move the replies into their parents, from the deepest up
+    for position in (1..nodes.len()).rev() {
+        if let Some(node) = nodes[position].take() {
+            if let Some(parent) = &mut nodes[parents[position]] {
+                parent.add_reply(node);
+            }
+        }
+    }
+
+    let mut thread_view = ThreadView { options, ..ThreadView::new() };
+    thread_view.roots.extend(nodes[0].take());
+    thread_view.finish_insertions();
+    thread_view.roots.swap_remove(0)
+}
+
 impl std::fmt::Display for ThreadView {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Thread View with {} conversations:", self.thread_count())?;
@@ -584,4 +1091,517 @@ mod tests {
         assert_eq!(reply_node.post.content(), "This is a reply");
         assert_eq!(reply_node.depth, 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_reactions_counted_and_separated() {
+        let root_post = Post::new("2025-05-01T12:00:00+0100".to_string(), "Root".to_string());
+        let mut reply = Post::new("2025-05-01T13:00:00+0100".to_string(), "A reply".to_string());
+        reply.set_reply_to(Some("2025-05-01T12:00:00+0100".to_string()));
+        let mut heart1 = Post::reaction_to("2025-05-01T12:00:00+0100", "❤️");
+        heart1.set_id("2025-05-01T14:00:00+0100".to_string());
+        let mut heart2 = Post::reaction_to("2025-05-01T12:00:00+0100", "❤️");
+        heart2.set_id("2025-05-01T15:00:00+0100".to_string());
+        let mut thumbs = Post::reaction_to("2025-05-01T12:00:00+0100", "👍");
+        thumbs.set_id("2025-05-01T16:00:00+0100".to_string());
+
+        let posts = vec![root_post, reply, heart1, heart2, thumbs];
+
+        let thread_view = ThreadView::from_posts(posts.clone());
+        let root = &thread_view.roots[0];
+        assert_eq!(root.replies.len(), 1);
+        assert_eq!(root.reaction_posts.len(), 3);
+        let reactions = root.reactions();
+        assert_eq!(reactions.get("❤️"), Some(&2));
+        assert_eq!(reactions.get("👍"), Some(&1));
+
+        // Reactions stay in replies if separation is disabled, but are still counted
+        let options = ThreadOptions { separate_reactions: false, ..Default::default() };
+        let thread_view = ThreadView::from_posts_with_options(posts, options);
+        let root = &thread_view.roots[0];
+        assert_eq!(root.replies.len(), 4);
+        assert_eq!(root.reactions().get("❤️"), Some(&2));
+    }
+
+    #[test]
+    fn test_deleted_post_keeps_replies() {
+        let tombstone = Post::tombstone("deleted_post".to_string());
+
+        let mut reply_post = Post::new("reply1".to_string(), "This is a reply".to_string());
+        reply_post.set_reply_to(Some("deleted_post".to_string()));
+
+        let thread_view = ThreadView::from_posts(vec![tombstone, reply_post]);
+
+        assert_eq!(thread_view.thread_count(), 1);
+
+        let root = &thread_view.roots[0];
+        assert_eq!(root.post.id(), "deleted_post");
+        assert_eq!(root.post.content(), "[Post deleted]");
+        assert!(root.post.is_deleted());
+        assert_eq!(root.replies.len(), 1);
+        assert_eq!(root.replies[0].post.id(), "reply1");
+    }
+
+    #[test]
+    fn test_from_feed_filtered_hides_muted_thread() {
+        let mut root = Post::new("2025-01-01T10:00:00+0000".to_string(), "Root".to_string());
+        root.set_source(Some("https://alice.example/social.org".to_string()));
+        let mut reply = Post::new("2025-01-01T11:00:00+0000".to_string(), "Reply".to_string());
+        reply.set_source(Some("https://bob.example/social.org".to_string()));
+        reply.set_reply_to(Some("https://alice.example/social.org#2025-01-01T10:00:00+0000".to_string()));
+        let mut other = Post::new("2025-01-01T12:00:00+0000".to_string(), "Other".to_string());
+        other.set_source(Some("https://bob.example/social.org".to_string()));
+        let feed = Feed { posts: vec![other, reply, root], ..Default::default() };
+
+        let mut filters = FeedFilterSet::new();
+        filters.mute_thread("https://alice.example/social.org#2025-01-01T10:00:00+0000");
+        let thread_view = ThreadView::from_feed_filtered(&feed, &filters);
+
+        assert_eq!(thread_view.thread_count(), 1);
+        assert_eq!(thread_view.roots[0].post.id(), "2025-01-01T12:00:00+0000");
+        assert_eq!(ThreadView::from_feed_filtered(&feed, &FeedFilterSet::new()).total_posts(), 3);
+    }
+
+    #[test]
+    fn test_from_posts_nested_depth() {
+        let root = Post::new("root".to_string(), "Root".to_string());
+        let mut reply = Post::new("reply".to_string(), "Reply".to_string());
+        reply.set_reply_to(Some("root".to_string()));
+        let mut nested = Post::new("nested".to_string(), "Nested".to_string());
+        nested.set_reply_to(Some("reply".to_string()));
+
+        let thread_view = ThreadView::from_posts(vec![nested, reply, root]);
+
+        assert_eq!(thread_view.thread_count(), 1);
+        let reply_node = &thread_view.roots[0].replies[0];
+        assert_eq!(reply_node.depth, 1);
+        assert_eq!(reply_node.replies[0].post.id(), "nested");
+        assert_eq!(reply_node.replies[0].depth, 2);
+    }
+
+    #[test]
+    fn test_from_feed() {
+        let root = Post::new("root".to_string(), "Root".to_string());
+        let mut reply = Post::new("reply".to_string(), "Reply".to_string());
+        reply.set_reply_to(Some("root".to_string()));
+        let feed = Feed { posts: vec![reply, root], ..Default::default() };
+
+        let thread_view = ThreadView::from_feed(&feed);
+        assert_eq!(thread_view.total_posts(), 2);
+        assert_eq!(ThreadView::from_posts(feed.into_posts()).flatten().len(), thread_view.flatten().len());
+    }
+
+    #[test]
+    fn test_from_feed_large() {
+        // 1000 threads of 10 posts, each replying to one of the earlier posts of its thread,
+        // from 10 sources, and 100 replies to posts missing from the feed
+        let start = DateTime::parse_from_rfc3339("2025-01-01T00:00:00+00:00").unwrap();
+        let id = |index: usize| (start + chrono::Duration::minutes(index as i64)).format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+        let source = |index: usize| format!("https://user{}.example/social.org", index % 10);
+        let mut posts = Vec::new();
+        for thread in 0..1000 {
+            for position in 0..10 {
+                let index = thread * 10 + position;
+                let mut post = Post::new(id(index), format!("Post {index}"));
+                post.set_source(Some(source(index)));
+                if position > 0 {
+                    let parent = thread * 10 + (position - 1) / 2;
+                    post.set_reply_to(Some(format!("{}#{}", source(parent), id(parent))));
+                }
+                posts.push(post);
+            }
+        }
+        for missing in 0..100 {
+            let mut post = Post::new(id(20_000 + missing), "Reply".to_string());
+            post.set_reply_to(Some(format!("https://gone.example/social.org#{}", id(30_000 + missing))));
+            posts.push(post);
+        }
+        let feed = Feed { posts, ..Default::default() };
+
+        let started = std::time::Instant::now();
+        let thread_view = ThreadView::from_feed(&feed);
+        let elapsed = started.elapsed();
+
+        assert_eq!(thread_view.thread_count(), 1100);
+        assert_eq!(thread_view.total_posts(), 10_200);
+        let first = thread_view.roots.iter().find(|root| root.post.id() == id(0)).unwrap();
+        assert_eq!((first.count_posts(), first.replies.len()), (10, 2));
+        fn max_depth(node: &ThreadNode) -> usize {
+            node.replies.iter().map(max_depth).max().unwrap_or(node.depth)
+        }
+        assert_eq!(max_depth(first), 3);
+        assert!(elapsed < std::time::Duration::from_secs(5), "building the threads took {elapsed:?}");
+    }
+
+    #[test]
+    fn test_nested_reply_depths() {
+        // A chain of 4 posts, each replying to the previous one
+        let chain: Vec<Post> = (0..4)
+            .map(|level| {
+                let mut post = Post::new(format!("2025-01-01T1{level}:00:00+0000"), format!("Level {level}"));
+                post.set_source(Some("https://alice.example/social.org".to_string()));
+                if level > 0 {
+                    post.set_reply_to(Some(format!("https://alice.example/social.org#2025-01-01T1{}:00:00+0000", level - 1)));
+                }
+                post
+            })
+            .collect();
+        let depths = |thread_view: &ThreadView| {
+            let mut depths = Vec::new();
+            let mut node = &thread_view.roots[0];
+            loop {
+                depths.push((node.post.content().to_string(), node.depth));
+                let Some(reply) = node.replies.first() else {
+                    break;
+                };
+                node = reply;
+            }
+            depths
+        };
+        let expected: Vec<(String, usize)> = (0..4).map(|level| (format!("Level {level}"), level)).collect();
+
+        // Replies listed before their parents
+        let feed = Feed { posts: chain.iter().rev().cloned().collect(), ..Default::default() };
+        let thread_view = ThreadView::from_feed(&feed);
+        assert_eq!(thread_view.thread_count(), 1);
+        assert_eq!(depths(&thread_view), expected);
+
+        let mut thread_view = ThreadView::new();
+        for post in chain {
+            thread_view.add_post(post);
+        }
+        assert_eq!(thread_view.thread_count(), 1);
+        assert_eq!(depths(&thread_view), expected);
+    }
+
+    #[test]
+    fn test_parent_added_after_reply_replaces_placeholder() {
+        let alice = "https://alice.example/social.org";
+        let mut parent = Post::new("2025-01-01T10:00:00+0000".to_string(), "Parent".to_string());
+        parent.set_source(Some(alice.to_string()));
+        parent.set_reply_to(Some("https://bob.example/social.org#2025-01-01T09:00:00+0000".to_string()));
+        let mut reply = Post::new("2025-01-01T11:00:00+0000".to_string(), "Reply".to_string());
+        reply.set_source(Some("https://carol.example/social.org".to_string()));
+        reply.set_reply_to(Some(format!("{alice}#2025-01-01T10:00:00+0000")));
+        let mut bob = Post::new("2025-01-01T09:00:00+0000".to_string(), "Original".to_string());
+        bob.set_source(Some("https://bob.example/social.org".to_string()));
+
+        let mut thread_view = ThreadView::new();
+        thread_view.add_post(reply.clone());
+        assert_eq!(thread_view.roots[0].post.content(), "[Post not available]");
+
+        // The parent takes the place of its placeholder, under a placeholder of its own parent
+        thread_view.add_post(parent.clone());
+        assert_eq!(thread_view.thread_count(), 1);
+        let root = &thread_view.roots[0];
+        assert_eq!(root.post.content(), "[Post not available]");
+        assert_eq!(root.replies[0].post.content(), "Parent");
+        assert_eq!((root.replies[0].replies[0].post.content(), root.replies[0].replies[0].depth), ("Reply", 2));
+
+        thread_view.add_post(bob);
+        assert_eq!(thread_view.thread_count(), 1);
+        assert_eq!(thread_view.roots[0].post.content(), "Original");
+        assert_eq!(thread_view.total_posts(), 3);
+
+        // Merging a refresh updates the posts in place, without duplicating them
+        let mut edited = parent;
+        edited.set_content("Parent, edited".to_string());
+        let mut thread_view = ThreadView::from_posts(vec![reply.clone()]);
+        thread_view.merge_posts(vec![edited.clone(), reply]);
+        assert_eq!(thread_view.total_posts(), 3);
+        thread_view.merge_posts(vec![edited]);
+        assert_eq!(thread_view.total_posts(), 3);
+        let parent_node = &thread_view.roots[0].replies[0];
+        assert_eq!((parent_node.post.content(), parent_node.replies.len()), ("Parent, edited", 1));
+
+        // The last copy of a post merged twice in the same batch wins
+        let mut other = Post::new("2025-01-02T10:00:00+0000".to_string(), "Other".to_string());
+        let first_copy = other.clone();
+        other.set_content("Other, edited".to_string());
+        thread_view.merge_posts(vec![first_copy, other]);
+        assert_eq!(thread_view.total_posts(), 4);
+        assert!(thread_view.roots.iter().any(|root| root.post.content() == "Other, edited"));
+    }
+
+    #[test]
+    fn test_thread_view_outlives_feed() {
+        fn assert_owned<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+        struct App {
+            feed: Feed,
+            threads: ThreadView,
+        }
+
+        let mut feed = Feed::default();
+        let mut root = Post::new("2025-01-01T10:00:00+00:00".to_string(), "root".to_string());
+        root.set_source(Some("https://a.example/social.org".to_string()));
+        feed.posts.push(root);
+        let threads = ThreadView::from_feed(&feed);
+        assert_owned(&threads);
+
+        let mut app = App { feed, threads };
+        app.feed.posts.clear();
+        let snapshot = app.threads.clone();
+        assert_eq!(app.threads.total_posts(), 1);
+        assert_eq!(snapshot.roots[0].post.content(), "root");
+    }
+
+    #[test]
+    fn test_find_node() {
+        let source = "https://a.example/social.org";
+        let post = |id: &str, reply_to: Option<&str>| {
+            let mut post = Post::new(id.to_string(), format!("post {id}"));
+            post.set_source(Some(source.to_string()));
+            post.set_reply_to(reply_to.map(str::to_string));
+            post
+        };
+        let root = post("2025-01-01T10:00:00+00:00", None);
+        let reply = post("2025-01-01T11:00:00+00:00", Some(&root.full_id()));
+        let nested = post("2025-01-01T12:00:00+00:00", Some(&reply.full_id()));
+        let nested_full_id = nested.full_id();
+        let mut thread_view = ThreadView::from_posts(vec![root, reply, nested]);
+
+        // Bare timestamp ID, full ID, and the same timestamp from another source
+        let node = thread_view.find_node("2025-01-01T12:00:00+00:00").unwrap();
+        assert_eq!(node.post.content(), "post 2025-01-01T12:00:00+00:00");
+        assert_eq!(node.depth, 2);
+        assert!(thread_view.find_node(&nested_full_id).is_some());
+        assert!(thread_view.contains("https://b.example/social.org#2025-01-01T12:00:00+00:00"));
+        assert!(!thread_view.contains("2025-01-01T13:00:00+00:00"));
+        assert!(thread_view.find_node("2025-01-01T13:00:00+00:00").is_none());
+
+        let subtree = thread_view.subtree("2025-01-01T11:00:00+00:00").unwrap();
+        assert_eq!(subtree.count_posts(), 2);
+        assert_eq!(subtree.replies[0].post.full_id(), nested_full_id);
+
+        thread_view.find_node_mut("2025-01-01T12:00:00+00:00").unwrap().post.set_content("edited".to_string());
+        assert_eq!(thread_view.roots[0].replies[0].replies[0].post.content(), "edited");
+        // The subtree is a copy
+        assert_eq!(subtree.replies[0].post.content(), "post 2025-01-01T12:00:00+00:00");
+    }
+
+    /// A root, a reply and a nested reply, plus a second reply to the root
+    fn three_level_thread() -> ThreadView {
+        let post = |id: &str, reply_to: Option<&str>| {
+            let mut post = Post::new(id.to_string(), format!("post {id}"));
+            post.set_source(Some("https://a.example/social.org".to_string()));
+            post.set_reply_to(reply_to.map(|id| format!("https://a.example/social.org#{id}")));
+            post
+        };
+        ThreadView::from_posts(vec![
+            post("2025-01-01T10:00:00+00:00", None),
+            post("2025-01-01T11:00:00+00:00", Some("2025-01-01T10:00:00+00:00")),
+            post("2025-01-01T12:00:00+00:00", Some("2025-01-01T11:00:00+00:00")),
+            post("2025-01-01T13:00:00+00:00", Some("2025-01-01T10:00:00+00:00")),
+        ])
+    }
+
+    const REPLY_ID: &str = "https://a.example/social.org#2025-01-01T11:00:00+00:00";
+
+    #[test]
+    fn test_remove_post_promote_children() {
+        let mut thread_view = three_level_thread();
+        thread_view.remove_post(REPLY_ID, RemovePolicy::PromoteChildren).unwrap();
+
+        assert_eq!(thread_view.total_posts(), 3);
+        assert!(!thread_view.contains("2025-01-01T11:00:00+00:00"));
+        let root = &thread_view.roots[0];
+        assert_eq!(root.replies.len(), 2);
+        let promoted = thread_view.find_node("2025-01-01T12:00:00+00:00").unwrap();
+        assert_eq!(promoted.depth, 1);
+
+        // Removing the root makes its replies threads of their own
+        thread_view.remove_post("https://a.example/social.org#2025-01-01T10:00:00+00:00", RemovePolicy::PromoteChildren).unwrap();
+        assert_eq!(thread_view.thread_count(), 2);
+        assert!(thread_view.roots.iter().all(|root| root.depth == 0));
+        assert!(thread_view.roots.iter().all(|root| root.parent_id().is_none()));
+        // Latest activity first
+        assert_eq!(thread_view.roots[0].post.id(), "2025-01-01T13:00:00+00:00");
+    }
+
+    #[test]
+    fn test_remove_post_subtree() {
+        let mut thread_view = three_level_thread();
+        thread_view.remove_post(REPLY_ID, RemovePolicy::RemoveSubtree).unwrap();
+
+        assert_eq!(thread_view.total_posts(), 2);
+        assert!(!thread_view.contains("2025-01-01T11:00:00+00:00"));
+        assert!(!thread_view.contains("2025-01-01T12:00:00+00:00"));
+        assert_eq!(
+            thread_view.remove_post(REPLY_ID, RemovePolicy::RemoveSubtree),
+            Err(RemoveError::NotFound(REPLY_ID.to_string()))
+        );
+
+        // Activity times follow the remaining replies
+        let mut thread_view = three_level_thread();
+        thread_view.remove_post("https://a.example/social.org#2025-01-01T13:00:00+00:00", RemovePolicy::RemoveSubtree).unwrap();
+        assert_eq!(thread_view.roots[0].latest_activity_time, thread_view.find_node("2025-01-01T12:00:00+00:00").unwrap().post.time());
+    }
+
+    #[test]
+    fn test_remove_post_tombstone() {
+        let mut thread_view = three_level_thread();
+        thread_view.remove_post(REPLY_ID, RemovePolicy::Tombstone).unwrap();
+
+        assert_eq!(thread_view.total_posts(), 4);
+        let node = thread_view.find_node(REPLY_ID).unwrap();
+        assert_eq!(node.post.content(), "[Post deleted]");
+        assert_eq!(node.depth, 1);
+        assert_eq!(node.replies[0].post.id(), "2025-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn test_remove_placeholder_with_replies() {
+        let mut reply = Post::new("reply1".to_string(), "reply".to_string());
+        reply.set_reply_to(Some("https://a.example/social.org#missing".to_string()));
+        let mut thread_view = ThreadView::from_posts(vec![reply]);
+        let placeholder_id = thread_view.roots[0].post.full_id();
+
+        for policy in [RemovePolicy::PromoteChildren, RemovePolicy::Tombstone] {
+            assert_eq!(
+                thread_view.remove_post(&placeholder_id, policy),
+                Err(RemoveError::PlaceholderHasReplies(placeholder_id.clone()))
+            );
+        }
+        thread_view.remove_post(&placeholder_id, RemovePolicy::RemoveSubtree).unwrap();
+        assert!(thread_view.is_empty());
+    }
+
+    #[test]
+    fn test_build_thread() {
+        let source = "https://a.example/social.org";
+        let post = |id: String, reply_to: Option<&str>| {
+            let mut post = Post::new(id.clone(), format!("post {id}"));
+            post.set_source(Some(source.to_string()));
+            post.set_reply_to(reply_to.map(|id| format!("{source}#{id}")));
+            post
+        };
+        let id = |thread: usize, reply: usize| format!("2025-01-01T{:02}:{:02}:{:02}+00:00", thread / 60, thread % 60, reply);
+
+        // 2000 threads of a root and a chain of 9 replies, and a thread whose root is missing
+        let mut feed = Feed::default();
+        for thread in 0..2000 {
+            feed.posts.push(post(id(thread, 0), None));
+            for reply in 1..10 {
+                feed.posts.push(post(id(thread, reply), Some(&id(thread, reply - 1))));
+            }
+        }
+        let orphan = post("2024-06-01T10:00:00+00:00".to_string(), Some("2024-06-01T09:00:00+00:00"));
+        feed.posts.push(orphan.clone());
+        // A second reply to the root of thread 1000, and its own reply by bare ID
+        feed.posts.push(post(id(1000, 30), Some(&id(1000, 0))));
+        let mut bare = post(id(1000, 31), None);
+        bare.set_reply_to(Some(id(1000, 30)));
+        feed.posts.push(bare);
+
+        let started = std::time::Instant::now();
+        let leaf = feed.posts.iter().find(|post| post.id() == id(1000, 5)).unwrap().clone();
+        let thread = build_thread(&feed, &leaf, ThreadOptions::default());
+        let elapsed = started.elapsed();
+
+        assert_eq!(thread.post.id(), id(1000, 0));
+        assert_eq!(thread.depth, 0);
+        assert_eq!(thread.count_posts(), 12);
+        let full_view = ThreadView::from_feed(&feed);
+        let expected = full_view.find_node(&id(1000, 0)).unwrap();
+        let ids = |node: &ThreadNode| node.flatten().iter().map(|post| post.full_id()).collect::<Vec<_>>();
+        assert_eq!(ids(&thread), ids(expected));
+        assert_eq!(thread.latest_activity_time, expected.latest_activity_time);
+        assert_eq!(thread.replies[0].replies[0].replies[0].depth, 3);
+        assert!(elapsed < std::time::Duration::from_secs(2), "took {:?}", elapsed);
+
+        // A missing root gets a placeholder
+        let thread = build_thread(&feed, &orphan, ThreadOptions::default());
+        assert_eq!(thread.post.content(), "[Post not available]");
+        assert_eq!(thread.replies[0].post.id(), orphan.id());
+        assert_eq!(thread.replies[0].depth, 1);
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let source = "https://a.example/social.org";
+        let ids = ["2025-01-01T10:00:00+00:00", "2025-01-01T11:00:00+00:00", "2025-01-01T12:00:00+00:00", "2025-01-01T13:00:00+00:00"];
+        let mut posts: Vec<Post> = ids.iter().zip([None, Some(ids[0]), Some(ids[1]), Some(ids[2])]).map(|(id, reply_to)| {
+            let mut post = Post::new(id.to_string(), format!("post {id}"));
+            post.set_source(Some(source.to_string()));
+            post.set_reply_to(reply_to.map(|id| format!("{source}#{id}")));
+            post
+        }).collect();
+        let mut orphan = Post::new("2025-02-01T10:00:00+00:00".to_string(), "orphan".to_string());
+        orphan.set_reply_to(Some(format!("{source}#2025-01-31T10:00:00+00:00")));
+        posts.push(orphan);
+        let thread_view = ThreadView::from_posts(posts);
+
+        let chain: Vec<&str> = thread_view.ancestors(&format!("{source}#{}", ids[3])).iter().map(|post| post.id()).collect();
+        assert_eq!(chain, ids[..3]);
+        assert_eq!(thread_view.ancestors(ids[1]).len(), 1);
+        assert!(thread_view.ancestors(ids[0]).is_empty());
+        assert!(thread_view.ancestors("2030-01-01T00:00:00+00:00").is_empty());
+
+        let leaf = thread_view.find_node(ids[3]).unwrap();
+        assert_eq!(leaf.parent_id(), Some(format!("{source}#{}", ids[2]).as_str()));
+        assert_eq!(thread_view.find_node(ids[0]).unwrap().parent_id(), None);
+
+        // The placeholder for a missing parent is part of the chain
+        let chain = thread_view.ancestors("2025-02-01T10:00:00+00:00");
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].content(), "[Post not available]");
+        let orphan = thread_view.find_node("2025-02-01T10:00:00+00:00").unwrap();
+        assert_eq!(orphan.parent_id(), Some(chain[0].full_id().as_str()));
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        let source = "https://a.example/social.org";
+        let post = |id: &str, reply_to: Option<&str>| {
+            let mut post = Post::new(id.to_string(), format!("post {id}"));
+            post.set_source(Some(source.to_string()));
+            post.set_reply_to(reply_to.map(|id| format!("{source}#{id}")));
+            post
+        };
+        // A: oldest root, two replies, one of them the latest activity of all
+        // B: newest root, no replies
+        // C: middle root, one reply; D: same time as C from another source, no replies
+        let mut d = post("2025-01-02T10:00:00+00:00", None);
+        d.set_source(Some("https://b.example/social.org".to_string()));
+        let posts = vec![
+            post("2025-01-01T10:00:00+00:00", None),
+            post("2025-01-01T11:00:00+00:00", Some("2025-01-01T10:00:00+00:00")),
+            post("2025-01-05T10:00:00+00:00", Some("2025-01-01T10:00:00+00:00")),
+            post("2025-01-03T10:00:00+00:00", None),
+            post("2025-01-02T10:00:00+00:00", None),
+            post("2025-01-02T11:00:00+00:00", Some("2025-01-02T10:00:00+00:00")),
+            d,
+        ];
+        let feed = Feed { posts, ..Default::default() };
+        let a = format!("{source}#2025-01-01T10:00:00+00:00");
+        let b = format!("{source}#2025-01-03T10:00:00+00:00");
+        let c = format!("{source}#2025-01-02T10:00:00+00:00");
+        let d = "https://b.example/social.org#2025-01-02T10:00:00+00:00".to_string();
+        let roots = |thread_view: &ThreadView| thread_view.roots.iter().map(|root| root.post.full_id()).collect::<Vec<_>>();
+        let replies = |thread_view: &ThreadView| {
+            let root = thread_view.find_node(&a).unwrap();
+            root.replies.iter().map(|reply| reply.post.id().to_string()).collect::<Vec<_>>()
+        };
+
+        let mut thread_view = ThreadView::from_feed(&feed);
+        assert_eq!(roots(&thread_view), [&*a, &*b, &*c, &*d]);
+        assert_eq!(replies(&thread_view), ["2025-01-01T11:00:00+00:00", "2025-01-05T10:00:00+00:00"]);
+
+        let order = ThreadSortOrder { roots: RootOrder::CreationTime, replies: ReplyOrder::ReverseChronological };
+        let options = ThreadOptions { sort_order: order, ..Default::default() };
+        let mut creation = ThreadView::from_feed_with_options(&feed, options);
+        assert_eq!(roots(&creation), [&*b, &*c, &*d, &*a]);
+        assert_eq!(replies(&creation), ["2025-01-05T10:00:00+00:00", "2025-01-01T11:00:00+00:00"]);
+
+        thread_view.sort_threads_with(ThreadSortOrder { roots: RootOrder::ReplyCount, ..Default::default() });
+        assert_eq!(roots(&thread_view), [&*a, &*c, &*b, &*d]);
+        assert_eq!(replies(&thread_view), ["2025-01-01T11:00:00+00:00", "2025-01-05T10:00:00+00:00"]);
+
+        // The order is kept as posts are added
+        creation.add_post(post("2025-01-04T10:00:00+00:00", None));
+        assert_eq!(creation.roots[0].post.id(), "2025-01-04T10:00:00+00:00");
+        // B now has as many replies as C, and the tie goes by full ID, instead of B's latest activity putting it first
+        thread_view.add_post(post("2025-01-06T10:00:00+00:00", Some("2025-01-03T10:00:00+00:00")));
+        assert_eq!(roots(&thread_view), [&*a, &*c, &*b, &*d]);
+    }
+}
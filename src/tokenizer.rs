@@ -25,6 +25,8 @@ pub enum Token {
     },
     /// Inline code token
     InlineCode(String),
+    /// Inline hashtag token, `#tag` - stored without the leading `#`
+    Hashtag(String),
 }
 
 pub struct Tokenizer {
@@ -71,6 +73,13 @@ impl Tokenizer {
             return Some(token);
         }
 
+        // Check for inline hashtags #tag
+        if self.peek_char() == '#' {
+            if let Some(token) = self.parse_hashtag() {
+                return Some(token);
+            }
+        }
+
         // Check for bold italic */text/*
         if self.peek_chars(2) == "*/" {
             return self.parse_bold_italic();
@@ -170,8 +179,8 @@ impl Tokenizer {
                     let username = content[bracket_pos + 2..].to_string();
                     
                     // Check if URL part starts with "org-social:"
-                    if url_part.starts_with("org-social:") {
-                        let url = url_part[11..].to_string(); // Remove "org-social:" prefix
+                    if let Some(stripped) = url_part.strip_prefix("org-social:") {
+                        let url = stripped.to_string(); // Remove "org-social:" prefix
                         self.advance(2); // Skip ]]
                         return Some(Token::Mention { url, username });
                     }
@@ -231,6 +240,46 @@ impl Tokenizer {
         })
     }
 
+    fn parse_hashtag(&mut self) -> Option<Token> {
+        let len = self.hashtag_len_at_position()?;
+        let tag: String = self.input[self.position + 1..self.position + len].iter().collect();
+        self.advance(len);
+        Some(Token::Hashtag(tag))
+    }
+
+    /// Length (including the `#`) of a hashtag starting at the current position, if there is one.
+    ///
+    /// A hashtag has to start a word and consists of alphanumerics, `_` and `-`, with at least one letter,
+    /// so org keywords (`#+TITLE`), anchors (`page#section`) and numbers (`#1`) are not matched.
+    fn hashtag_len_at_position(&self) -> Option<usize> {
+        if self.peek_char() != '#' {
+            return None;
+        }
+
+        if self.position > 0 {
+            let previous = self.input[self.position - 1];
+            if !(previous.is_whitespace() || matches!(previous, '(' | '[' | '{' | '"' | '\'' | ',' | ';')) {
+                return None;
+            }
+        }
+
+        let mut end = self.position + 1;
+        while end < self.input.len() {
+            let ch = self.input[end];
+            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        if self.input[self.position + 1..end].iter().any(|ch| ch.is_alphabetic()) {
+            Some(end - self.position)
+        } else {
+            None
+        }
+    }
+
     fn parse_bold_italic(&mut self) -> Option<Token> {
         self.parse_delimited_text_multi("*/", "/*", false).map(Token::BoldItalic)
     }
@@ -298,6 +347,11 @@ impl Tokenizer {
             if ch == '*' || ch == '/' || ch == '~' || ch == '[' || ch == '_' || ch == '+' {
                 break;
             }
+
+            // Stop before a hashtag, unless it's the first character (which wasn't a valid hashtag then)
+            if ch == '#' && self.position > start && self.hashtag_len_at_position().is_some() {
+                break;
+            }
             
             // If we hit a potential URL, check if it's actually a URL
             if ch.is_alphabetic() {
@@ -614,6 +668,32 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_hashtags() {
+        let mut tokenizer = Tokenizer::new("Learning #rust and #org-mode (#emacs_lisp)".to_string());
+        let tokens = tokenizer.tokenize();
+        assert_eq!(tokens, vec![
+            Token::PlainText("Learning ".to_string()),
+            Token::Hashtag("rust".to_string()),
+            Token::PlainText(" and ".to_string()),
+            Token::Hashtag("org-mode".to_string()),
+            Token::PlainText(" (".to_string()),
+            Token::Hashtag("emacs_lisp".to_string()),
+            Token::PlainText(")".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_hashtag_not_detected_in_keywords_urls_or_numbers() {
+        let mut tokenizer = Tokenizer::new("#+begin_src rust\nissue #42 at https://example.com/page#section".to_string());
+        let tokens = tokenizer.tokenize();
+        assert!(!tokens.iter().any(|t| matches!(t, Token::Hashtag(_))));
+        assert!(tokens.contains(&Token::Link {
+            url: "https://example.com/page#section".to_string(),
+            description: None,
+        }));
+    }
+
     #[test]
     fn test_mention_basic() {
         let mut tokenizer = Tokenizer::new("Contact [[org-social:http://example.org/social.org][username]]".to_string());
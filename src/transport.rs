@@ -0,0 +1,332 @@
+//! Transports making the HTTP requests of the [`crate::network`] functions.
+//!
+//! The network functions handle redirects, retries, timeouts, caching and decoding themselves, and only
+//! hand single requests to a [`Transport`]. [`ReqwestTransport`] is used by default; another one can be set
+//! in [`crate::network::NetworkConfig::transport`], e.g. a `MockTransport` (with the `testing` feature)
+//! to test code using the network functions without sockets.
+
+use std::future::Future;
+use std::pin::Pin;
+
+/// A request handed to a [`Transport`].
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub method: reqwest::Method,
+    pub url: String,
+    /// Headers of the request, including the `User-Agent` and the credentials.
+    pub headers: reqwest::header::HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
+impl FetchRequest {
+    /// A `GET` request for `url` without headers.
+    pub fn get(url: &str) -> Self {
+        Self { method: reqwest::Method::GET, url: url.to_string(), headers: reqwest::header::HeaderMap::new(), body: None }
+    }
+
+    /// The request built by reqwest, e.g. with credentials added by [`reqwest::RequestBuilder::basic_auth`].
+    ///
+    /// A streaming body isn't supported, it's left out.
+    pub fn from_reqwest(request: &reqwest::Request) -> Self {
+        Self {
+            method: request.method().clone(),
+            url: request.url().to_string(),
+            headers: request.headers().clone(),
+            body: request.body().and_then(|body| body.as_bytes()).map(<[u8]>::to_vec),
+        }
+    }
+}
+
+/// Chunks of a response body, as they're received.
+pub type BodyStream = Pin<Box<dyn futures_core::Stream<Item = Result<Vec<u8>, TransportError>> + Send>>;
+
+/// A response received by a [`Transport`]. Redirects aren't followed by the transport, they're returned as is.
+pub struct FetchResponse {
+    pub status: u16,
+    /// URL of the request the response is for.
+    pub url: String,
+    pub headers: reqwest::header::HeaderMap,
+    body: BodyStream,
+}
+
+impl FetchResponse {
+    /// A response with the whole body.
+    pub fn new(status: u16, url: &str, headers: reqwest::header::HeaderMap, body: Vec<u8>) -> Self {
+        Self::streaming(status, url, headers, single_chunk(body))
+    }
+
+    /// A response with a body read as it's received, so responses that are too large can be dropped
+    /// without being read to the end.
+    pub fn streaming(status: u16, url: &str, headers: reqwest::header::HeaderMap, body: BodyStream) -> Self {
+        Self { status, url: url.to_string(), headers, body }
+    }
+
+    /// The value of a header, None if it's missing or not text.
+    pub fn header(&self, name: reqwest::header::HeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// The size of the body from the `Content-Length` header, if given.
+    pub fn content_length(&self) -> Option<u64> {
+        self.header(reqwest::header::CONTENT_LENGTH).and_then(|length| length.trim().parse().ok())
+    }
+
+    /// The next chunk of the body, None once it was read to the end.
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, TransportError> {
+        futures_util::StreamExt::next(&mut self.body).await.transpose()
+    }
+
+    /// The chunks of the body not read yet.
+    pub fn into_body(self) -> BodyStream {
+        self.body
+    }
+}
+
+/// A body stream with `body` as its only chunk, or none if it's empty.
+pub fn single_chunk(body: Vec<u8>) -> BodyStream {
+    let chunks = if body.is_empty() { Vec::new() } else { vec![Ok(body)] };
+    Box::pin(futures_util::stream::iter(chunks))
+}
+
+impl std::fmt::Debug for FetchResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FetchResponse")
+            .field("status", &self.status)
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Errors of a [`Transport`]: the request failed before a response was received.
+#[derive(Debug)]
+pub enum TransportError {
+    /// The request made with reqwest failed (DNS, connection, TLS...), or couldn't be built.
+    Reqwest(reqwest::Error),
+    /// The request made by another transport failed. If `transient`, it's worth retrying.
+    Other { source: Box<dyn std::error::Error + Send + Sync>, transient: bool },
+}
+
+impl TransportError {
+    /// Whether the error may go away on its own, see [`crate::network::NetworkError::is_transient`].
+    pub fn is_transient(&self) -> bool {
+        match self {
+            TransportError::Reqwest(source) => !source.is_builder() && !source.is_redirect(),
+            TransportError::Other { transient, .. } => *transient,
+        }
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransportError::Reqwest(source) => write!(f, "{source}"),
+            TransportError::Other { source, .. } => write!(f, "{source}"),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransportError::Reqwest(source) => Some(source),
+            TransportError::Other { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(source: reqwest::Error) -> Self {
+        TransportError::Reqwest(source)
+    }
+}
+
+/// The future of a [`Transport::send`].
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<FetchResponse, TransportError>> + Send + 'a>>;
+
+/// Makes single requests for the network functions, see the [module documentation](self).
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    /// Make a request, without following redirects.
+    fn send(&self, request: FetchRequest) -> TransportFuture<'_>;
+}
+
+/// The default transport, making the requests with a reqwest client.
+///
+/// The client should be built with `reqwest::redirect::Policy::none()`, see [`crate::network::NetworkConfig::client`].
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: FetchRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut builder = self.client.request(request.method, &request.url).headers(request.headers);
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let response = builder.send().await?;
+
+            let status = response.status().as_u16();
+            let url = response.url().to_string();
+            let headers = response.headers().clone();
+            let body = futures_util::stream::unfold(Some(response), |response| async move {
+                let mut response = response?;
+                match response.chunk().await {
+                    Ok(Some(chunk)) => Some((Ok(chunk.to_vec()), Some(response))),
+                    Ok(None) => None,
+                    Err(error) => Some((Err(error.into()), None)),
+                }
+            });
+            Ok(FetchResponse::streaming(status, &url, headers, Box::pin(body)))
+        })
+    }
+}
+
+/// A transport serving canned responses, recording the requests it gets.
+///
+/// Responses are registered by URL with [`MockTransport::respond`], and served in order; the last one
+/// is served again for the following requests. Requests for other URLs get a `404 Not Found`.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: std::sync::Mutex<std::collections::HashMap<String, std::collections::VecDeque<MockResponse>>>,
+    requests: std::sync::Mutex<Vec<FetchRequest>>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a response to the ones served for `url`.
+    pub fn respond(&self, url: &str, response: MockResponse) -> &Self {
+        let mut responses = self.responses.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        responses.entry(url.to_string()).or_default().push_back(response);
+        self
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<FetchRequest> {
+        self.requests.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// The number of requests received so far for `url`.
+    pub fn request_count(&self, url: &str) -> usize {
+        self.requests.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().filter(|request| request.url == url).count()
+    }
+
+    /// The response to serve for `url`, keeping the last one.
+    fn next_response(&self, url: &str) -> MockResponse {
+        let mut responses = self.responses.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match responses.get_mut(url) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().expect("the queue isn't empty"),
+            Some(queue) if !queue.is_empty() => queue[0].clone(),
+            _ => MockResponse::status(404),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Transport for MockTransport {
+    fn send(&self, request: FetchRequest) -> TransportFuture<'_> {
+        let response = self.next_response(&request.url);
+        let url = request.url.clone();
+        self.requests.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(request);
+        Box::pin(async move {
+            if !response.delay.is_zero() {
+                crate::network::sleep(response.delay).await;
+            }
+            if let Some(message) = response.error {
+                return Err(TransportError::Other { source: message.into(), transient: true });
+            }
+            let mut headers = reqwest::header::HeaderMap::new();
+            for (name, value) in &response.headers {
+                if let (Ok(name), Ok(value)) =
+                    (reqwest::header::HeaderName::from_bytes(name.as_bytes()), reqwest::header::HeaderValue::from_str(value))
+                {
+                    headers.append(name, value);
+                }
+            }
+            Ok(FetchResponse::new(response.status, &url, headers, response.body))
+        })
+    }
+}
+
+/// A response served by a [`MockTransport`].
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: std::time::Duration,
+    /// If set, the request fails with a transient [`TransportError::Other`] with this message.
+    error: Option<String>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl MockResponse {
+    /// A `200 OK` response with `body`.
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self { body: body.into(), ..Self::status(200) }
+    }
+
+    /// A response with `status` and no body.
+    pub fn status(status: u16) -> Self {
+        Self { status, headers: Vec::new(), body: Vec::new(), delay: std::time::Duration::ZERO, error: None }
+    }
+
+    /// A failure before any response was received, like a refused connection.
+    pub fn error(message: &str) -> Self {
+        Self { error: Some(message.to_string()), ..Self::status(0) }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Serve the response, or fail, only after `delay`.
+    pub fn with_delay(mut self, delay: std::time::Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_transport() {
+        let transport = MockTransport::new();
+        let url = "https://example.com/social.org";
+        transport.respond(url, MockResponse::error("connection refused")).respond(url, MockResponse::ok("body").with_header("ETag", "\"v1\""));
+
+        let error = transport.send(FetchRequest::get(url)).await.unwrap_err();
+        assert!(error.is_transient());
+        assert_eq!(error.to_string(), "connection refused");
+
+        // The last response keeps being served
+        for _ in 0..2 {
+            let mut response = transport.send(FetchRequest::get(url)).await.unwrap();
+            assert_eq!((response.status, response.url.as_str()), (200, url));
+            assert_eq!(response.header(reqwest::header::ETAG), Some("\"v1\""));
+            assert_eq!(response.chunk().await.unwrap().as_deref(), Some(&b"body"[..]));
+            assert_eq!(response.chunk().await.unwrap(), None);
+        }
+
+        let response = transport.send(FetchRequest::get("https://example.com/other.org")).await.unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(transport.request_count(url), 3);
+        assert_eq!(transport.requests().len(), 4);
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 use chrono::{DateTime, FixedOffset, SecondsFormat};
 
 pub fn parse_timestamp(s: &str) -> Result<DateTime<FixedOffset>, chrono::ParseError> {
@@ -13,4 +15,113 @@ pub fn parse_timestamp(s: &str) -> Result<DateTime<FixedOffset>, chrono::ParseEr
 pub fn get_current_timestamp() -> String {
     chrono::Local::now()
         .to_rfc3339_opts(SecondsFormat::Secs, false)
-}
\ No newline at end of file
+}
+
+/// Compare two optional timestamps.
+///
+/// Times are ordered newest first if `newest_first` is set, oldest first otherwise.
+/// A missing time always sorts after a present one, regardless of direction.
+pub fn cmp_optional_times(
+    a: Option<DateTime<FixedOffset>>,
+    b: Option<DateTime<FixedOffset>>,
+    newest_first: bool,
+) -> Ordering {
+    match (a, b) {
+        (Some(time_a), Some(time_b)) => {
+            if newest_first {
+                time_b.cmp(&time_a)
+            } else {
+                time_a.cmp(&time_b)
+            }
+        }
+        (Some(_), None) => Ordering::Less,    // Posts with time come before posts without
+        (None, Some(_)) => Ordering::Greater, // Posts without time come after posts with time
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Normalize a feed URL for comparison.
+///
+/// Surrounding whitespace and trailing slashes are removed, the scheme and host are lowercased.
+/// The path is kept as-is, since it may be case-sensitive.
+pub fn normalize_url(url: &str) -> String {
+    let trimmed = url.trim().trim_end_matches('/');
+    match trimmed.split_once("://") {
+        Some((scheme, rest)) => {
+            let (host, path) = match rest.find('/') {
+                Some(index) => rest.split_at(index),
+                None => (rest, ""),
+            };
+            format!("{}://{}{}", scheme.to_lowercase(), host.to_lowercase(), path)
+        }
+        None => trimmed.to_string(),
+    }
+}
+
+/// Get the host of a URL, without port or user info.
+///
+/// Returns None if the URL has no `scheme://` prefix or an empty host.
+pub fn url_host(url: &str) -> Option<&str> {
+    let (_, rest) = url.trim().split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host_and_port = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host_and_port.split(':').next().unwrap_or(host_and_port);
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+/// Check whether a value refers to a feed the way the feed resolvers read it:
+/// a URL with a scheme (`scheme://…`), a `file:` URL or an absolute path of a local file.
+pub fn is_feed_location(value: &str) -> bool {
+    value.contains("://") || value.starts_with("file:") || std::path::Path::new(value).is_absolute()
+}
+
+/// Check whether two URLs point to the same feed.
+///
+/// The URLs are compared after [`normalize_url`], and `http` and `https` are considered equivalent.
+pub fn same_feed_url(a: &str, b: &str) -> bool {
+    canonical_feed_url(a) == canonical_feed_url(b)
+}
+
+/// Canonical form of a feed URL, used to tell whether two URLs point to the same feed.
+///
+/// On top of [`normalize_url`], `http` is folded into `https` and default ports (`:80`, `:443`) are dropped.
+/// Absolute file paths are turned into (percent-encoded) `file://` URLs.
+pub fn canonical_feed_url(url: &str) -> String {
+    let normalized = normalize_url(url);
+    let Some((scheme, rest)) = normalized.split_once("://") else {
+        if std::path::Path::new(&normalized).is_absolute() {
+            return reqwest::Url::from_file_path(&normalized)
+                .map(String::from)
+                .unwrap_or_else(|_| format!("file://{normalized}"));
+        }
+        return normalized;
+    };
+    let scheme = if scheme == "http" { "https" } else { scheme };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let authority = authority.strip_suffix(":80").or_else(|| authority.strip_suffix(":443")).unwrap_or(authority);
+    format!("{scheme}://{authority}{path}")
+}
+
+/// Resolve a possibly relative URL against a base URL.
+///
+/// Absolute URLs are returned unchanged. Relative ones (`avatar.png`, `/blog`, `../img/a.png?size=2`)
+/// are joined to the base following the usual URL rules. If there's no usable base,
+/// the value is returned unchanged.
+pub fn resolve_url(base: Option<&str>, value: &str) -> String {
+    let value = value.trim();
+    if reqwest::Url::parse(value).is_ok() {
+        return value.to_string();
+    }
+
+    base.and_then(|base| reqwest::Url::parse(base).ok())
+        .and_then(|base| base.join(value).ok())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| value.to_string())
+}